@@ -0,0 +1,52 @@
+//! Compares the `Rc` tree with the arena tree.
+//!
+//! Run with `cargo bench --bench arena`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use html::dom::arena::Arena;
+
+/// Returns a document with `rows` list items and table rows.
+fn document(rows: usize) -> String {
+    let mut doc = String::from("<html><head><title>bench</title></head><body><ul>");
+    for i in 0..rows {
+        doc.push_str(&format!(r#"<li class="item i{}"><a href="/{}">link {}</a></li>"#, i % 10, i, i));
+    }
+    doc.push_str("</ul><table>");
+    for i in 0..rows {
+        doc.push_str(&format!("<tr><td>{}</td><td><b>{}</b></td></tr>", i, i * 2));
+    }
+    doc.push_str("</table></body></html>");
+    doc
+}
+
+/// Runs `f` for about a second and prints the mean time per run.
+fn bench<T>(name: &str, mut f: impl FnMut() -> T) {
+    let mut runs = 0u32;
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(1) {
+        black_box(f());
+        runs += 1;
+    }
+    println!("{:<24} {:>10.1?} / run ({} runs)", name, start.elapsed() / runs, runs);
+}
+
+fn main() {
+    let doc = document(2_000);
+
+    bench("rc parse", || html::parse(&doc).unwrap());
+    bench("arena parse", || Arena::parse(&doc).unwrap());
+
+    let root = html::parse(&doc).unwrap().get_copy_of_internal_arc();
+    let arena = Arena::parse(&doc).unwrap();
+
+    bench("rc descendants", || root.descendants().count());
+    bench("arena descendants", || arena.descendants(arena.root()).count());
+
+    bench("rc get_nodes_by_class", || html::get_nodes_by_class(&root, "i3").len());
+    bench("arena get_nodes_by_class", || arena.get_nodes_by_class(arena.root(), "i3").len());
+
+    bench("rc get_nodes_by_name", || html::get_nodes_by_name(&root, "td").len());
+    bench("arena get_nodes_by_name", || arena.get_nodes_by_name(arena.root(), "td").len());
+}
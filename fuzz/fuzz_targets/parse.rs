@@ -0,0 +1,14 @@
+//! Parses the input with every entry point that takes a `&str`.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|doc: &str| {
+    let _ = html::parse(doc);
+    let _ = html::parse_lenient(doc);
+    let _ = html::parse_document(doc);
+    let _ = html::parse_borrowed(doc);
+    let _ = html::parse_parallel(doc, &Default::default());
+    let _ = html::parse_fragment(doc, "div");
+    let _ = html::tokenize(doc).count();
+});
@@ -0,0 +1,14 @@
+//! Parses arbitrary bytes, at once and fed to the streaming parser in chunks.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|bytes: &[u8]| {
+    let _ = html::parse_bytes(bytes);
+
+    let mut parser = html::Parser::new();
+    for chunk in bytes.chunks(7) {
+        parser.feed(chunk);
+    }
+    let _ = parser.finish();
+});
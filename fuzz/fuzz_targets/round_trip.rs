@@ -0,0 +1,21 @@
+//! Checks that a lossless parse is written back as the input, and that the
+//! serialized tree of a normal parse is parsed again into the same tree.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|doc: &str| {
+    let options = html::ParseOptions {
+        lossless: true,
+        ..Default::default()
+    };
+    if let Ok(root) = html::parse_with_options(doc, &options) {
+        assert_eq!(root.to_html(), doc);
+    }
+
+    if let Ok(root) = html::parse(doc) {
+        let html = root.to_html();
+        let reparsed = html::parse(&html).unwrap_or_else(|e| panic!("{} in {:?}", e, html));
+        assert!(root.eq_semantic(&reparsed), "{:?} is not parsed back into the same tree", html);
+    }
+});
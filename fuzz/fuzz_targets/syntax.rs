@@ -0,0 +1,11 @@
+//! Builds the syntax tree, which must cover every byte of the input.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|doc: &str| {
+    let tree = html::syntax::parse(doc);
+    assert_eq!(tree.root().text(), doc);
+    let _ = tree.errors();
+    let _ = tree.folding_ranges();
+});
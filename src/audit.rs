@@ -0,0 +1,184 @@
+//! Finding accessibility problems in a tree.
+//!
+//! # Examples
+//! ```rust
+//! use html::audit::{audit, AuditKind};
+//!
+//! let root = html::parse(r#"<html><h1>Title</h1><h3>Part</h3><img src="a.png"></html>"#).unwrap();
+//! let issues = audit(&root);
+//!
+//! assert_eq!(issues[0].kind, AuditKind::MissingLang);
+//! assert_eq!(issues[1].kind, AuditKind::HeadingLevelSkip { previous: 1, level: 3 });
+//! assert_eq!(issues[2].kind, AuditKind::ImageWithoutAlt);
+//! assert_eq!(issues[2].position.unwrap().column, 34);
+//! ```
+
+use std::collections::HashSet;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::dom::{NodeData, Tag};
+use crate::Position;
+
+/// Input types that are labelled by their own value or `alt`, or that are not shown.
+const SELF_LABELLED_TYPES: [&str; 5] = ["hidden", "submit", "reset", "button", "image"];
+
+/// A problem found by [`audit`].
+#[derive(Debug, Clone)]
+pub struct AuditIssue {
+    pub kind: AuditKind,
+    /// The element with the problem.
+    pub node: Rc<NodeData>,
+    /// Where the element starts, or `None` if it was not parsed.
+    pub position: Option<Position>,
+}
+
+/// The kinds of [`AuditIssue`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditKind {
+    /// An image without `alt`. `alt=""` marks an image as decorative and is allowed.
+    ImageWithoutAlt,
+    /// An `<input>`, `<select>` or `<textarea>` without a `<label>` around it,
+    /// a `<label for>` with its id, `aria-label` or `aria-labelledby`.
+    InputWithoutLabel,
+    /// `<html>` without `lang`.
+    MissingLang,
+    /// An id that an element before this one has already.
+    DuplicateId(String),
+    /// A heading more than one level below the heading before it. `<h1>` followed by `<h3>`
+    HeadingLevelSkip { previous: u8, level: u8 },
+}
+
+impl fmt::Display for AuditIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            AuditKind::ImageWithoutAlt => write!(f, "Image without alt")?,
+            AuditKind::InputWithoutLabel => write!(f, "Form field without a label")?,
+            AuditKind::MissingLang => write!(f, "<html> without lang")?,
+            AuditKind::DuplicateId(id) => write!(f, "Duplicate id {}", id)?,
+            AuditKind::HeadingLevelSkip { previous, level } => write!(f, "<h{}> follows <h{}>", level, previous)?,
+        }
+
+        match &self.position {
+            Some(position) => write!(f, " at {}.", position),
+            None => write!(f, "."),
+        }
+    }
+}
+
+/// Returns the problems of the descendants of `root` in document order.
+pub fn audit(root: &NodeData) -> Vec<AuditIssue> {
+    let label_targets: HashSet<String> = root
+        .descendants()
+        .filter_map(|node| {
+            let tag = node.get_tag()?;
+            if !is_named(&tag, "label") {
+                return None;
+            }
+            tag.get_attribute_value("for")
+        })
+        .collect();
+
+    let mut issues = Vec::new();
+    let mut ids = HashSet::new();
+    let mut previous_heading = None;
+    for node in root.descendants() {
+        let tag = match node.get_tag() {
+            Some(tag) => tag,
+            None => continue,
+        };
+        let mut report = |kind| {
+            issues.push(AuditIssue {
+                kind,
+                node: Rc::clone(&node),
+                position: node.get_span().map(|span| Position {
+                    offset: span.start,
+                    line: span.line,
+                    column: span.column,
+                }),
+            })
+        };
+
+        if let Some(id) = tag.get_attribute_value("id").filter(|id| !id.is_empty()) {
+            if !ids.insert(id.clone()) {
+                report(AuditKind::DuplicateId(id));
+            }
+        }
+
+        let name = tag.get_name().to_ascii_lowercase();
+        match name.as_str() {
+            "html" if tag.get_attribute("lang").is_none_or(|lang| lang.as_str().trim().is_empty()) => {
+                report(AuditKind::MissingLang)
+            }
+            "img" if !tag.has_attribute("alt") => report(AuditKind::ImageWithoutAlt),
+            "input" | "select" | "textarea" if !is_labelled(&node, &tag, &label_targets) => {
+                report(AuditKind::InputWithoutLabel)
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name.as_bytes()[1] - b'0';
+                if let Some(previous) = previous_heading.filter(|&previous| level > previous + 1) {
+                    report(AuditKind::HeadingLevelSkip { previous, level });
+                }
+                previous_heading = Some(level);
+            }
+            _ => {}
+        }
+    }
+
+    issues
+}
+
+fn is_labelled(node: &NodeData, tag: &Tag, label_targets: &HashSet<String>) -> bool {
+    let field_type = tag.get_attribute_value("type").unwrap_or_default().to_ascii_lowercase();
+    if is_named(tag, "input") && SELF_LABELLED_TYPES.contains(&field_type.as_str()) {
+        return true;
+    }
+
+    let has_value = |name: &str| tag.get_attribute(name).is_some_and(|value| !value.as_str().trim().is_empty());
+    has_value("aria-label")
+        || has_value("aria-labelledby")
+        || tag.get_attribute_value("id").is_some_and(|id| label_targets.contains(&id))
+        || node.ancestors().any(|ancestor| ancestor.get_tag().is_some_and(|tag| is_named(&tag, "label")))
+}
+
+fn is_named(tag: &Tag, name: &str) -> bool {
+    tag.get_name().eq_ignore_ascii_case(name)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::Payload;
+    use crate::parse;
+
+    #[test]
+    fn audit_test() {
+        let root = parse(
+            r#"<html lang="en"><h2 id="a">a</h2><h4>b</h4><h3 id="a">c</h3><h5>d</h5><img alt=""><label>Name <input name="n"></label><label for="e">Email</label><input id="e"><input type="hidden"><input aria-label="Search"><textarea></textarea><select aria-labelledby=" "></select></html>"#,
+        )
+        .unwrap();
+        let issues = audit(&root);
+        let kinds: Vec<&AuditKind> = issues.iter().map(|issue| &issue.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &AuditKind::HeadingLevelSkip { previous: 2, level: 4 },
+                &AuditKind::DuplicateId(String::from("a")),
+                &AuditKind::HeadingLevelSkip { previous: 3, level: 5 },
+                &AuditKind::InputWithoutLabel,
+                &AuditKind::InputWithoutLabel,
+            ]
+        );
+        assert_eq!(issues[3].node.tag_name().as_deref(), Some("textarea"));
+        assert_eq!(issues[1].to_string(), "Duplicate id a at line 1, column 44.");
+
+        // nodes that were not parsed have no position
+        let img = crate::dom::Node::new(Payload::Tag(Tag::new("img")));
+        let root = crate::dom::Node::new(Payload::Tag(Tag::new("div")));
+        root.add_child_and_update_parent(&img);
+        let issues = audit(&root);
+        assert_eq!(issues[0].position, None);
+        assert_eq!(issues[0].to_string(), "Image without alt.");
+    }
+}
@@ -0,0 +1,517 @@
+//! Converting a tree to Markdown or plain text.
+//!
+//! # Examples
+//! ```rust
+//! let root = html::parse(r#"
+//!     <h1>Title</h1>
+//!     <p>A <a href="/x">link</a>, in <b>bold</b>.</p>
+//!     <ul><li>one</li><li>two</li></ul>
+//! "#).unwrap();
+//!
+//! assert_eq!(
+//!     html::convert::to_markdown(&root),
+//!     "# Title\n\nA [link](/x), in **bold**.\n\n- one\n- two"
+//! );
+//! assert_eq!(
+//!     html::convert::to_text(&root, 0),
+//!     "Title\n\nA link, in bold.\n\n- one\n- two"
+//! );
+//! ```
+
+use std::rc::Rc;
+
+use crate::dom::{NodeData, Payload, Tag};
+
+/// Elements that are rendered as blocks separated by a blank line.
+const BLOCK_ELEMENTS: [&str; 34] = [
+    "address", "article", "aside", "blockquote", "body", "caption", "dd", "details",
+    "dialog", "div", "dl", "dt", "fieldset", "figcaption", "figure", "footer",
+    "form", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "html", "main",
+    "nav", "ol", "p", "pre", "section", "table", "ul",
+];
+
+/// Elements whose content is not text to show.
+const HIDDEN_ELEMENTS: [&str; 7] = ["head", "noscript", "script", "style", "template", "title", "iframe"];
+
+/// Converts `node` and its descendants to Markdown.
+///
+/// Headings, paragraphs, lists, links, images, emphasis, code, block
+/// quotes and tables are converted. Other elements are replaced with
+/// their content.
+pub fn to_markdown(node: &NodeData) -> String {
+    Converter { markdown: true }.convert(node, 0)
+}
+
+/// Converts `node` and its descendants to plain text, keeping the
+/// structure of headings, lists and tables.
+///
+/// If `width` is not 0, paragraphs are wrapped so that lines are not
+/// longer than `width` characters, where possible.
+pub fn to_text(node: &NodeData, width: usize) -> String {
+    Converter { markdown: false }.convert(node, width)
+}
+
+struct Converter {
+    // false for plain text
+    markdown: bool,
+}
+
+/// What [`Converter::convert`] does next. The nodes are converted with a
+/// stack, as converting them recursively overflows the call stack for
+/// deeply nested trees.
+enum Task {
+    /// Converts a node in the content of a block.
+    Block(Rc<NodeData>),
+    /// Writes a node as inline content.
+    Inline(Rc<NodeData>),
+    /// Converts an `<li>` that starts with the marker.
+    Item(Rc<NodeData>, String),
+    /// Starts a row of a table.
+    Row,
+    /// Converts a cell of a table.
+    Cell(Rc<NodeData>),
+    /// Pops the content of a node from the buffers and adds it to its parent.
+    End(End),
+}
+
+/// What the content of a node becomes once it is converted.
+enum End {
+    Heading(usize),
+    Blocks,
+    Blockquote,
+    List,
+    Item(String),
+    Table,
+    Cell,
+    Link(String),
+    Surround(&'static str),
+}
+
+/// The content of a node being converted.
+#[derive(Default)]
+struct Buffer {
+    /// The blocks so far, or the items of a list.
+    blocks: Vec<String>,
+    /// The inline content after the last block.
+    inline: String,
+    /// The width that paragraphs are wrapped at.
+    width: usize,
+    /// The rows of a table.
+    rows: Vec<Vec<String>>,
+}
+
+impl Converter {
+    fn convert(&self, node: &NodeData, width: usize) -> String {
+        let mut buffers = vec![Buffer { width, ..Buffer::default() }];
+        let mut tasks = Vec::new();
+        if node.is_document_root() {
+            push_children(&mut tasks, node, Task::Block);
+        } else {
+            self.start_block(&mut buffers, &mut tasks, node);
+        }
+
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Block(node) => self.start_block(&mut buffers, &mut tasks, &node),
+                Task::Inline(node) => self.start_inline(&mut buffers, &mut tasks, &node),
+                Task::Item(node, marker) => {
+                    let width = top(&mut buffers).width.saturating_sub(marker.len());
+                    buffers.push(Buffer { width, ..Buffer::default() });
+                    tasks.push(Task::End(End::Item(marker)));
+                    push_children(&mut tasks, &node, Task::Block);
+                }
+                Task::Row => top(&mut buffers).rows.push(Vec::new()),
+                Task::Cell(node) => {
+                    buffers.push(Buffer::default());
+                    tasks.push(Task::End(End::Cell));
+                    push_children(&mut tasks, &node, Task::Inline);
+                }
+                Task::End(end) => self.end(&mut buffers, end),
+            }
+        }
+
+        let mut buffer = buffers.pop().unwrap_or_default();
+        self.flush(&mut buffer);
+        buffer.blocks.join("\n\n")
+    }
+
+    /// Converts `node` in the content of a block, as a block if it is one.
+    fn start_block(&self, buffers: &mut Vec<Buffer>, tasks: &mut Vec<Task>, node: &NodeData) {
+        let name = match node.get_tag() {
+            Some(tag) if is_block(&tag) => tag.get_name().to_ascii_lowercase(),
+            _ => return self.start_inline(buffers, tasks, node),
+        };
+
+        // the inline content before the block is a paragraph
+        let parent = top(buffers);
+        self.flush(parent);
+        let width = parent.width;
+        match name.as_str() {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                buffers.push(Buffer::default());
+                tasks.push(Task::End(End::Heading(usize::from(name.as_bytes()[1] - b'0'))));
+                push_children(tasks, node, Task::Inline);
+            }
+            "ul" | "ol" => {
+                buffers.push(Buffer { width, ..Buffer::default() });
+                tasks.push(Task::End(End::List));
+                let items = node.children().into_iter().filter(|child| has_name(child, &["li"]));
+                let items: Vec<Task> = items
+                    .enumerate()
+                    .map(|(i, item)| match name == "ol" {
+                        true => Task::Item(item, format!("{}. ", i + 1)),
+                        false => Task::Item(item, String::from("- ")),
+                    })
+                    .collect();
+                tasks.extend(items.into_iter().rev());
+            }
+            "pre" => {
+                let code = raw_text(node);
+                let code = code.strip_suffix('\n').unwrap_or(&code);
+                let block = match self.markdown {
+                    true => {
+                        let language = code_language(node).unwrap_or_default();
+                        format!("```{}\n{}\n```", language, code)
+                    }
+                    false => indent(code, "    ", "    "),
+                };
+                push_block(parent, block);
+            }
+            "blockquote" => {
+                buffers.push(Buffer { width: width.saturating_sub(2), ..Buffer::default() });
+                tasks.push(Task::End(End::Blockquote));
+                push_children(tasks, node, Task::Block);
+            }
+            "table" => {
+                buffers.push(Buffer::default());
+                tasks.push(Task::End(End::Table));
+                let mut cells = Vec::new();
+                for row in node.descendants().filter(|row| has_name(row, &["tr"])) {
+                    cells.push(Task::Row);
+                    let row = row.children().into_iter().filter(|cell| has_name(cell, &["td", "th"]));
+                    cells.extend(row.map(Task::Cell));
+                }
+                tasks.extend(cells.into_iter().rev());
+            }
+            "hr" => push_block(parent, String::from("---")),
+            _ => {
+                buffers.push(Buffer { width, ..Buffer::default() });
+                tasks.push(Task::End(End::Blocks));
+                push_children(tasks, node, Task::Block);
+            }
+        }
+    }
+
+    /// Writes `node` as inline content to the buffer on top.
+    /// Whitespace is collapsed and '\n' is a line break.
+    fn start_inline(&self, buffers: &mut Vec<Buffer>, tasks: &mut Vec<Task>, node: &NodeData) {
+        let text = &mut top(buffers).inline;
+        let payload = node.get_payload();
+        let tag = match &*payload {
+            Payload::Tag(tag) => tag,
+            Payload::Text(t) => {
+                for c in t.chars() {
+                    if c.is_whitespace() {
+                        if !text.is_empty() && !text.ends_with([' ', '\n']) {
+                            text.push(' ');
+                        }
+                    } else {
+                        if self.markdown && "\\*_`[]".contains(c) {
+                            text.push('\\');
+                        }
+                        text.push(c);
+                    }
+                }
+                return;
+            }
+            Payload::RawText(_) | Payload::Comment(_) | Payload::ConditionalComment(_) | Payload::Doctype(_) => return,
+        };
+
+        let name = tag.get_name().to_ascii_lowercase();
+        if HIDDEN_ELEMENTS.contains(&name.as_str()) {
+            return;
+        }
+
+        let markdown = self.markdown;
+        let end = match name.as_str() {
+            "br" => {
+                let len = text.trim_end_matches(' ').len();
+                text.truncate(len);
+                text.push('\n');
+                return;
+            }
+            "img" if markdown => {
+                let alt = tag.get_attribute_value("alt").unwrap_or_default();
+                let src = tag.get_attribute_value("src").unwrap_or_default();
+                text.push_str(&format!("![{}]({})", alt, src));
+                return;
+            }
+            "img" => {
+                text.push_str(&tag.get_attribute_value("alt").unwrap_or_default());
+                return;
+            }
+            "code" | "kbd" | "samp" if markdown => {
+                text.push('`');
+                text.push_str(&raw_text(node));
+                text.push('`');
+                return;
+            }
+            "a" if markdown && tag.has_attribute("href") => {
+                End::Link(tag.get_attribute_value("href").unwrap_or_default())
+            }
+            "b" | "strong" if markdown => End::Surround("**"),
+            "i" | "em" if markdown => End::Surround("*"),
+            "del" | "s" | "strike" if markdown => End::Surround("~~"),
+            _ => {
+                push_children(tasks, node, Task::Inline);
+                return;
+            }
+        };
+
+        buffers.push(Buffer::default());
+        tasks.push(Task::End(end));
+        push_children(tasks, node, Task::Inline);
+    }
+
+    /// Pops the content of a node and adds what it converts to to the
+    /// buffer of its parent.
+    fn end(&self, buffers: &mut Vec<Buffer>, end: End) {
+        let mut buffer = buffers.pop().unwrap_or_default();
+        let parent = top(buffers);
+        match end {
+            End::Heading(level) => {
+                let text = one_line(&buffer.inline);
+                match self.markdown {
+                    true => push_block(parent, format!("{} {}", "#".repeat(level), text)),
+                    false => push_block(parent, text),
+                }
+            }
+            End::Blocks => {
+                self.flush(&mut buffer);
+                push_block(parent, buffer.blocks.join("\n\n"));
+            }
+            End::Blockquote => {
+                self.flush(&mut buffer);
+                push_block(parent, indent(&buffer.blocks.join("\n\n"), "> ", "> "));
+            }
+            End::List => push_block(parent, buffer.blocks.join("\n")),
+            End::Item(marker) => {
+                self.flush(&mut buffer);
+                let content = buffer.blocks.join("\n");
+                parent.blocks.push(indent(&content, &marker, &" ".repeat(marker.len())));
+            }
+            End::Table => push_block(parent, self.table(&buffer.rows)),
+            End::Cell => {
+                let cell = one_line(&buffer.inline);
+                let cell = match self.markdown {
+                    true => cell.replace('|', "\\|"),
+                    false => cell,
+                };
+                if let Some(row) = parent.rows.last_mut() {
+                    row.push(cell);
+                }
+            }
+            End::Link(href) => parent.inline.push_str(&format!("[{}]({})", one_line(&buffer.inline), href)),
+            End::Surround(mark) => {
+                let content = one_line(&buffer.inline);
+                if !content.is_empty() {
+                    parent.inline.push_str(mark);
+                    parent.inline.push_str(&content);
+                    parent.inline.push_str(mark);
+                }
+            }
+        }
+    }
+
+    /// Pushes the inline content collected so far as a paragraph.
+    fn flush(&self, buffer: &mut Buffer) {
+        let paragraph = std::mem::take(&mut buffer.inline);
+        let paragraph = paragraph
+            .split('\n')
+            .map(|line| line.trim_matches(' '))
+            .collect::<Vec<_>>()
+            .join(if self.markdown { "  \n" } else { "\n" });
+        let paragraph = paragraph.trim();
+
+        if !paragraph.is_empty() {
+            buffer.blocks.push(wrap(paragraph, buffer.width));
+        }
+    }
+
+    fn table(&self, rows: &[Vec<String>]) -> String {
+        if !self.markdown {
+            return rows.iter().map(|row| row.join("\t")).collect::<Vec<_>>().join("\n");
+        }
+
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut lines = Vec::new();
+        for (i, row) in rows.iter().enumerate() {
+            let mut cells = row.clone();
+            cells.resize(columns, String::new());
+            lines.push(format!("| {} |", cells.join(" | ")));
+            if i == 0 {
+                lines.push(format!("|{}", " --- |".repeat(columns)));
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// Returns the buffer on top, which the converted content goes to.
+fn top(buffers: &mut [Buffer]) -> &mut Buffer {
+    buffers.last_mut().expect("the buffer of the converted node")
+}
+
+/// Pushes a task for each child of `node`, in reverse so that they are
+/// done in order.
+fn push_children(tasks: &mut Vec<Task>, node: &NodeData, task: fn(Rc<NodeData>) -> Task) {
+    tasks.extend(node.get_children().iter().rev().map(|child| task(Rc::clone(child))));
+}
+
+/// Adds `block` to the blocks of `buffer` unless it is blank.
+fn push_block(buffer: &mut Buffer, block: String) {
+    if !block.trim().is_empty() {
+        buffer.blocks.push(block);
+    }
+}
+
+/// Returns `text` on one line, with each run of whitespace replaced with ' '.
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn is_block(tag: &Tag) -> bool {
+    BLOCK_ELEMENTS.iter().any(|name| name.eq_ignore_ascii_case(tag.get_name()))
+}
+
+/// Returns true if `node` is a tag named one of `names`, ignoring ASCII case.
+fn has_name(node: &NodeData, names: &[&str]) -> bool {
+    node.get_tag().is_some_and(|tag| names.iter().any(|name| name.eq_ignore_ascii_case(tag.get_name())))
+}
+
+/// Returns the text of `node` and its descendants as it is.
+fn raw_text(node: &NodeData) -> String {
+    node.descendants()
+        .filter_map(|node| match &*node.get_payload() {
+            Payload::Text(text) => Some(text.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the language of `<pre><code class="language-rust">`.
+fn code_language(pre: &NodeData) -> Option<String> {
+    let code = pre.first_child()?;
+    let class = code.get_tag().filter(|tag| tag.get_name().eq_ignore_ascii_case("code"))?.get_attribute_value("class")?;
+    class.split_ascii_whitespace().find_map(|class| class.strip_prefix("language-").map(String::from))
+}
+
+/// Prefixes the first line of `text` with `first` and the others with `rest`.
+/// Empty lines are not prefixed with trailing spaces.
+fn indent(text: &str, first: &str, rest: &str) -> String {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let prefix = if i == 0 { first } else { rest };
+            match line.is_empty() {
+                true => prefix.trim_end().to_string(),
+                false => format!("{}{}", prefix, line),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Wraps each line of `text` at spaces so that it is not longer than
+/// `width` characters. Words longer than `width` are not broken.
+fn wrap(text: &str, width: usize) -> String {
+    if width == 0 {
+        return String::from(text);
+    }
+
+    let mut res = Vec::new();
+    for line in text.split('\n') {
+        let mut current = String::new();
+        for word in line.split(' ') {
+            if !current.is_empty() && width < current.chars().count() + 1 + word.chars().count() {
+                res.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+        res.push(current);
+    }
+
+    res.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn markdown_test() {
+        let html = r#"
+        <h2>Install</h2>
+        <p>Run <code>cargo add</code>, or <em>see</em>: <a href="/docs">the docs</a>.<br>Next line_1</p>
+        <ol>
+          <li>one</li>
+          <li>two<ul><li>nested</li></ul></li>
+        </ol>
+        <pre><code class="language-rust">fn main() {}
+</code></pre>
+        <blockquote><p>quote</p></blockquote>
+        <table>
+          <tr><th>a</th><th>b</th></tr>
+          <tr><td>1</td><td>2|3</td></tr>
+        </table>
+        <script>x()</script>
+        "#;
+        let root = parse(html).unwrap();
+
+        assert_eq!(
+            to_markdown(&root),
+            "## Install\n\n\
+             Run `cargo add`, or *see*: [the docs](/docs).  \nNext line\\_1\n\n\
+             1. one\n2. two\n   - nested\n\n\
+             ```rust\nfn main() {}\n```\n\n\
+             > quote\n\n\
+             | a | b |\n| --- | --- |\n| 1 | 2\\|3 |"
+        );
+    }
+
+    #[test]
+    fn text_test() {
+        let html = "<h1>Title</h1><p>one two three four five</p><ul><li>a b c d</li></ul>";
+        let root = parse(html).unwrap();
+
+        assert_eq!(to_text(&root, 10), "Title\n\none two\nthree four\nfive\n\n- a b c d");
+        assert_eq!(to_text(&root, 0), "Title\n\none two three four five\n\n- a b c d");
+
+        // the text is not escaped as Markdown is
+        let root = parse("<table><tr><td>2|3</td><td>a_b</td></tr></table>").unwrap();
+        assert_eq!(to_text(&root, 0), "2|3\ta_b");
+    }
+
+    #[test]
+    fn names_case_test() {
+        let root = parse("<H1>Title</H1><UL><LI>a<BR>b</LI></UL><P><B>c</B></P><Script>d</Script>").unwrap();
+
+        assert_eq!(to_markdown(&root), "# Title\n\n- a  \n  b\n\n**c**");
+        assert_eq!(to_text(&root, 0), "Title\n\n- a\n  b\n\nc");
+    }
+
+    #[test]
+    fn deep_nesting_test() {
+        // deeply nested elements do not overflow the stack
+        let html = "<div>".repeat(50_000) + "<b>a</b>" + &"<span>".repeat(50_000) + "b";
+        let root = parse(&html).unwrap();
+
+        assert_eq!(to_markdown(&root), "**a**b");
+        assert_eq!(to_text(&root, 0), "ab");
+    }
+}
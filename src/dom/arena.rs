@@ -0,0 +1,492 @@
+//! A tree whose nodes live in one `Vec` and are addressed by [`NodeId`].
+//!
+//! [`Node`](super::Node) puts every node behind an `Rc` with `RefCell`s for
+//! its links. An [`Arena`] stores the nodes next to each other with their
+//! links as indexes, which is cheaper to build, to walk and to drop.
+//! Nodes are never freed until the arena is dropped: a detached node is
+//! only unlinked.
+//!
+//! # Examples
+//! ```rust
+//! use html::dom::arena::Arena;
+//!
+//! let arena = Arena::parse(r#"<ul><li class="a">one</li><li>two</li></ul>"#).unwrap();
+//! let items = arena.get_nodes_by_name(arena.root(), "li");
+//!
+//! assert_eq!(items.len(), 2);
+//! assert_eq!(arena.inner_text(items[1]), "two");
+//! assert_eq!(arena.get_parent(items[0]), arena.first_child(arena.root()));
+//! ```
+
+use crate::extent::AttrMatcher;
+use crate::parser::{is_end_implied, Event, ParseError, ParseOptions, Tokenizer};
+
+use super::{Atom, Node, NodeData, Payload, Span, Tag, ROOT_TAG_NAME};
+
+/// The index of a node in its [`Arena`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+#[derive(Debug, Clone)]
+struct ArenaNode {
+    payload: Payload,
+    span: Option<Span>,
+    parent: Option<NodeId>,
+    first_child: Option<NodeId>,
+    last_child: Option<NodeId>,
+    prev_sibling: Option<NodeId>,
+    next_sibling: Option<NodeId>,
+}
+
+/// The nodes of a tree. See the [module](self) documentation.
+#[derive(Debug, Clone)]
+pub struct Arena {
+    nodes: Vec<ArenaNode>,
+}
+
+impl Default for Arena {
+    fn default() -> Arena {
+        Arena::new()
+    }
+}
+
+impl Arena {
+    /// Creates an arena with only the `root` node.
+    pub fn new() -> Arena {
+        let mut arena = Arena { nodes: Vec::new() };
+        arena.create_node(Payload::Tag(Tag::new(ROOT_TAG_NAME)), None);
+        arena
+    }
+
+    /// Parses the tag document into an arena.
+    ///
+    /// # Errors
+    /// * [`ParseError`] as [`parse`](crate::parse) does.
+    pub fn parse(doc: &str) -> Result<Arena, ParseError> {
+        Arena::parse_with_options(doc, &ParseOptions::default())
+    }
+
+    /// Parses the tag document with `options` into an arena.
+    ///
+    /// The tree is built as [`parse_lenient`](crate::parse_lenient) builds
+    /// it: end tags close the nearest open tag with the same name and end
+    /// tags that match no open tag are discarded. Broken tags are errors
+    /// unless `options.recover` is true.
+    ///
+    /// # Errors
+    /// * [`ParseError`] as [`parse_with_options`](crate::parse_with_options) does.
+    pub fn parse_with_options(doc: &str, options: &ParseOptions) -> Result<Arena, ParseError> {
+        options.check_input_len(doc)?;
+        let mut arena = Arena::new();
+        if doc.trim_end().is_empty() {
+            return Ok(arena);
+        }
+
+        let mut tokenizer = Tokenizer::new(doc, options.clone());
+        let mut open_nodes = vec![arena.root()];
+        while let Some((event, span)) = tokenizer.next_event()? {
+            let id = NodeId(arena.len());
+            arena.push_event(&mut open_nodes, event, span);
+            if arena.len() == id.0 {
+                continue;
+            }
+
+            // the root is not counted
+            options.check_nodes(id.0, Some(span))?;
+            if let Payload::Tag(_) = arena.get_payload(id) {
+                let depth = if open_nodes.last() == Some(&id) { open_nodes.len() - 1 } else { open_nodes.len() };
+                options.check_depth(depth, Some(span))?;
+            }
+        }
+
+        let doc_end = arena.nodes.iter().filter_map(|node| node.span).map(|span| span.end).max().unwrap_or(0);
+        arena.close_open_nodes(open_nodes, doc_end);
+
+        Ok(arena)
+    }
+
+    /// Adds the node of `event` to the tree. `open_nodes` are the tags
+    /// whose end tag has not been seen yet, the outermost first.
+    pub(crate) fn push_event(&mut self, open_nodes: &mut Vec<NodeId>, event: Event, span: Span) {
+        let payload = match event {
+            Event::StartTag(tag) => {
+                // close the tags whose end tag is implied. `<li>one<li>two`
+                while open_nodes.len() > 1 {
+                    let open = *open_nodes.last().unwrap();
+                    let implied = match self.get_payload(open) {
+                        Payload::Tag(open_tag) => is_end_implied(open_tag.get_name(), tag.get_name()),
+                        _ => false,
+                    };
+                    if !implied {
+                        break;
+                    }
+                    self.extend_span(open, span.start);
+                    open_nodes.pop();
+                }
+
+                let is_open = !tag.is_self_closing() && !tag.is_void_element();
+                let id = self.append(*open_nodes.last().unwrap(), Payload::Tag(tag), Some(span));
+                if is_open {
+                    open_nodes.push(id);
+                }
+                return;
+            }
+            Event::EndTag(name) => {
+                let open_idx = open_nodes.iter().skip(1).rposition(|&open| {
+                    matches!(self.get_payload(open), Payload::Tag(tag) if tag.get_name().eq_ignore_ascii_case(&name))
+                });
+                if let Some(open_idx) = open_idx.map(|i| i + 1) {
+                    for open in open_nodes.drain(open_idx + 1..) {
+                        self.extend_span(open, span.start);
+                    }
+                    self.extend_span(open_nodes.pop().unwrap(), span.end);
+                }
+                return;
+            }
+            Event::Text(text) => Payload::Text(text),
+            Event::RawText(text) => Payload::RawText(text),
+            Event::Comment(text) => Payload::Comment(text),
+            Event::Doctype(doctype) => Payload::Doctype(doctype),
+        };
+
+        let parent = *open_nodes.last().unwrap();
+        self.append(parent, payload, Some(span));
+        self.extend_span(parent, span.end);
+    }
+
+    /// Extends the tags that were never closed to `end`.
+    pub(crate) fn close_open_nodes(&mut self, open_nodes: Vec<NodeId>, end: usize) {
+        for open in open_nodes.into_iter().skip(1) {
+            self.extend_span(open, end);
+        }
+    }
+
+    /// Returns the synthetic `root` node that wraps the tree.
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /// Returns the number of nodes, the detached ones included.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true if the arena has only the `root` node.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.len() == 1
+    }
+
+    pub fn get_payload(&self, id: NodeId) -> &Payload {
+        &self.nodes[id.0].payload
+    }
+
+    pub fn get_payload_mut(&mut self, id: NodeId) -> &mut Payload {
+        &mut self.nodes[id.0].payload
+    }
+
+    /// Returns where the node was in the parsed document.
+    pub fn get_span(&self, id: NodeId) -> Option<Span> {
+        self.nodes[id.0].span
+    }
+
+    pub fn get_parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].parent
+    }
+
+    pub fn first_child(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].first_child
+    }
+
+    pub fn last_child(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].last_child
+    }
+
+    pub fn next_sibling(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].next_sibling
+    }
+
+    pub fn prev_sibling(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].prev_sibling
+    }
+
+    /// Returns an iterator over the children of the node.
+    pub fn children(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(self.first_child(id), move |&child| self.next_sibling(child))
+    }
+
+    /// Returns an iterator over the ancestors of the node, from its parent
+    /// up to the root.
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(self.get_parent(id), move |&node| self.get_parent(node))
+    }
+
+    /// Returns an iterator over the descendants of the node in document
+    /// order. The node itself is not included.
+    pub fn descendants(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let mut next = self.first_child(id);
+        std::iter::from_fn(move || {
+            let node = next?;
+            // the first child, or the next sibling of the nearest node that has one
+            next = self.first_child(node).or_else(|| {
+                std::iter::once(node)
+                    .chain(self.ancestors(node))
+                    .take_while(|&ancestor| ancestor != id)
+                    .find_map(|ancestor| self.next_sibling(ancestor))
+            });
+            Some(node)
+        })
+    }
+
+    /// Creates a node with `payload` as the last child of `parent`.
+    pub fn append(&mut self, parent: NodeId, payload: Payload, span: Option<Span>) -> NodeId {
+        let id = self.create_node(payload, span);
+        self.append_child(parent, id);
+        id
+    }
+
+    /// Moves `child` to the end of the children of `parent`.
+    pub fn append_child(&mut self, parent: NodeId, child: NodeId) {
+        self.detach(child);
+
+        let prev = self.nodes[parent.0].last_child;
+        match prev {
+            Some(prev) => self.nodes[prev.0].next_sibling = Some(child),
+            None => self.nodes[parent.0].first_child = Some(child),
+        }
+        self.nodes[parent.0].last_child = Some(child);
+
+        let node = &mut self.nodes[child.0];
+        node.parent = Some(parent);
+        node.prev_sibling = prev;
+    }
+
+    /// Removes the node with its descendants from its parent.
+    pub fn detach(&mut self, id: NodeId) {
+        let ArenaNode { parent, prev_sibling, next_sibling, .. } = self.nodes[id.0];
+        let parent = match parent {
+            Some(parent) => parent,
+            None => return,
+        };
+
+        match prev_sibling {
+            Some(prev) => self.nodes[prev.0].next_sibling = next_sibling,
+            None => self.nodes[parent.0].first_child = next_sibling,
+        }
+        match next_sibling {
+            Some(next) => self.nodes[next.0].prev_sibling = prev_sibling,
+            None => self.nodes[parent.0].last_child = prev_sibling,
+        }
+
+        let node = &mut self.nodes[id.0];
+        node.parent = None;
+        node.prev_sibling = None;
+        node.next_sibling = None;
+    }
+
+    /// Returns the first node named `tag_name`, `id` included.
+    pub fn get_node_by_name(&self, id: NodeId, tag_name: &str) -> Option<NodeId> {
+        let tag_name = Atom::from(tag_name);
+        self.find(id, |payload| matches!(payload, Payload::Tag(tag) if *tag.get_name_atom() == tag_name))
+    }
+
+    /// Returns the first node whose `id` attribute is `value`, `id` included.
+    pub fn get_node_by_id(&self, id: NodeId, value: &str) -> Option<NodeId> {
+        self.get_node_by_attribute(id, AttrMatcher::Exact("id", value))
+    }
+
+    /// Returns the first node that has a matching attribute, `id` included.
+    /// `attribute` is an [`Attribute`](crate::Attribute) or an [`AttrMatcher`].
+    pub fn get_node_by_attribute<'a>(&self, id: NodeId, attribute: impl Into<AttrMatcher<'a>>) -> Option<NodeId> {
+        let matcher = attribute.into();
+        self.find(id, |payload| matcher.matches_payload(payload))
+    }
+
+    /// Returns all the nodes named `tag_name`, `id` included.
+    pub fn get_nodes_by_name(&self, id: NodeId, tag_name: &str) -> Vec<NodeId> {
+        let tag_name = Atom::from(tag_name);
+        self.find_all(id, |payload| matches!(payload, Payload::Tag(tag) if *tag.get_name_atom() == tag_name))
+    }
+
+    /// Returns all the nodes that have a matching attribute, `id` included.
+    pub fn get_nodes_by_attribute<'a>(&self, id: NodeId, attribute: impl Into<AttrMatcher<'a>>) -> Vec<NodeId> {
+        let matcher = attribute.into();
+        self.find_all(id, |payload| matcher.matches_payload(payload))
+    }
+
+    /// Returns the nodes whose `class` attribute contains `class_name`, `id` included.
+    pub fn get_nodes_by_class(&self, id: NodeId, class_name: &str) -> Vec<NodeId> {
+        self.get_nodes_by_attribute(id, AttrMatcher::WordInList("class", class_name))
+    }
+
+    /// Returns the first node whose payload `predicate` is true for, `id` included.
+    pub fn find<F>(&self, id: NodeId, predicate: F) -> Option<NodeId>
+    where
+        F: Fn(&Payload) -> bool,
+    {
+        self.self_and_descendants(id).find(|&node| predicate(self.get_payload(node)))
+    }
+
+    /// Returns all the nodes whose payload `predicate` is true for, `id` included.
+    pub fn find_all<F>(&self, id: NodeId, predicate: F) -> Vec<NodeId>
+    where
+        F: Fn(&Payload) -> bool,
+    {
+        self.self_and_descendants(id)
+            .filter(|&node| predicate(self.get_payload(node)))
+            .collect()
+    }
+
+    /// Copies the node and its descendants into a [`Node`].
+    pub fn to_node(&self, id: NodeId) -> Node {
+        let copy = |id| {
+            let node = Node::new(self.get_payload(id).clone());
+            if let Some(span) = self.get_span(id) {
+                node.set_span(span);
+            }
+            node
+        };
+
+        // copies the descendants with a stack, as copying them recursively
+        // overflows the call stack for deeply nested trees
+        let root = copy(id);
+        let mut stack = vec![(id, root.clone())];
+        while let Some((id, node)) = stack.pop() {
+            for child in self.children(id) {
+                let child_node = copy(child);
+                node.add_child_and_update_parent(&child_node);
+                stack.push((child, child_node));
+            }
+        }
+
+        root
+    }
+
+    /// Copies the node and its descendants into a [`sync::Node`](super::sync::Node).
+    pub fn to_sync_node(&self, id: NodeId) -> super::sync::Node {
+        let copy = |id| super::sync::Node::new_with_span(self.get_payload(id).clone(), self.get_span(id));
+        let root = copy(id);
+        let mut stack = vec![(id, root.clone())];
+        while let Some((id, node)) = stack.pop() {
+            for child in self.children(id) {
+                let child_node = copy(child);
+                node.add_child_and_update_parent(&child_node);
+                stack.push((child, child_node));
+            }
+        }
+
+        root
+    }
+
+    /// Serializes the node and its descendants. See [`NodeData::to_html`].
+    pub fn to_html(&self, id: NodeId) -> String {
+        self.to_node(id).to_html()
+    }
+
+    /// Returns the text of the node and its descendants. See [`NodeData::inner_text`].
+    pub fn inner_text(&self, id: NodeId) -> String {
+        self.to_node(id).inner_text()
+    }
+
+    fn self_and_descendants(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::once(id).chain(self.descendants(id))
+    }
+
+    fn create_node(&mut self, payload: Payload, span: Option<Span>) -> NodeId {
+        self.nodes.push(ArenaNode {
+            payload,
+            span,
+            parent: None,
+            first_child: None,
+            last_child: None,
+            prev_sibling: None,
+            next_sibling: None,
+        });
+        NodeId(self.nodes.len() - 1)
+    }
+
+    fn extend_span(&mut self, id: NodeId, end: usize) {
+        if let Some(span) = self.nodes[id.0].span.as_mut() {
+            span.end = span.end.max(end);
+        }
+    }
+}
+
+impl From<&NodeData> for Arena {
+    /// Copies `node` and its descendants into an arena. A document root is
+    /// the root of the arena, and any other node is its only child.
+    fn from(node: &NodeData) -> Arena {
+        fn copy(arena: &mut Arena, parent: NodeId, node: &NodeData) {
+            let id = arena.append(parent, node.get_payload().clone(), node.get_span());
+            for child in node.get_children().iter() {
+                copy(arena, id, child);
+            }
+        }
+
+        let mut arena = Arena::new();
+        let root = arena.root();
+        if node.is_document_root() {
+            for child in node.get_children().iter() {
+                copy(&mut arena, root, child);
+            }
+        } else {
+            copy(&mut arena, root, node);
+        }
+
+        arena
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Attribute};
+
+    #[test]
+    fn arena_test() {
+        let html = r#"<div id="main"><ul><li class="a b">one<li>two</ul><p>three <b>3</b></p></div><!-- c -->"#;
+        let arena = Arena::parse(html).unwrap();
+        let root = arena.root();
+
+        assert_eq!(arena.to_html(root), parse(html).unwrap().to_html());
+        assert_eq!(arena.to_node(root), parse(html).unwrap());
+        assert_eq!(Arena::from(&*parse(html).unwrap()).to_node(root), arena.to_node(root));
+
+        let main = arena.get_node_by_id(root, "main").unwrap();
+        let names: Vec<String> = arena
+            .descendants(main)
+            .filter_map(|id| match arena.get_payload(id) {
+                Payload::Tag(tag) => Some(tag.get_name().to_string()),
+                Payload::Text(text) => Some(text.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, ["ul", "li", "one", "li", "two", "p", "three ", "b", "3"]);
+
+        let li = arena.get_nodes_by_class(root, "b");
+        assert_eq!(li.len(), 1);
+        assert_eq!(arena.ancestors(li[0]).collect::<Vec<_>>().len(), 3);
+        assert_eq!(arena.get_nodes_by_attribute(root, Attribute("class", "a b")), li);
+        assert_eq!(arena.get_span(main).map(|span| span.end), Some(html.find("<!--").unwrap()));
+    }
+
+    #[test]
+    fn mutation_test() {
+        let mut arena = Arena::parse("<ul><li>a</li><li>b</li><li>c</li></ul>").unwrap();
+        let root = arena.root();
+        let ul = arena.get_node_by_name(root, "ul").unwrap();
+        let items: Vec<NodeId> = arena.children(ul).collect();
+
+        arena.detach(items[1]);
+        assert_eq!(arena.to_html(root), "<ul><li>a</li><li>c</li></ul>");
+        assert_eq!(arena.prev_sibling(items[2]), Some(items[0]));
+
+        arena.append_child(ul, items[0]);
+        let d = arena.append(ul, Payload::Tag(Tag::new("li")), None);
+        arena.append(d, Payload::Text(String::from("d")), None);
+        if let Payload::Tag(tag) = arena.get_payload_mut(ul) {
+            tag.set_name("ol");
+        }
+        assert_eq!(arena.to_html(root), "<ol><li>c</li><li>a</li><li>d</li></ol>");
+        assert_eq!(arena.len(), 10);
+    }
+}
@@ -0,0 +1,232 @@
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// The tag and attribute names of HTML, SVG and MathML that are stored
+/// once for the program, sorted.
+const KNOWN_NAMES: [&str; 271] = [
+    "a", "abbr", "accept", "accept-charset", "accesskey", "action", "address", "align", "alt",
+    "annotation-xml", "area", "aria-describedby", "aria-expanded", "aria-hidden", "aria-label",
+    "aria-labelledby", "article", "aside", "async", "audio", "autocomplete", "autofocus",
+    "autoplay", "b", "base", "bdi", "bdo", "bgcolor", "big", "blockquote", "body", "border", "br",
+    "button", "canvas", "caption", "cellpadding", "cellspacing", "center", "charset", "checked",
+    "circle", "cite", "class", "clipPath", "code", "col", "colgroup", "color", "cols", "colspan",
+    "content", "contenteditable", "controls", "coords", "crossorigin", "d", "data", "datalist",
+    "datetime", "dd", "decoding", "default", "defer", "defs", "del", "desc", "details", "dfn",
+    "dialog", "dir", "dirname", "disabled", "div", "dl", "download", "draggable", "dt", "ellipse",
+    "em", "embed", "enctype", "fieldset", "figcaption", "figure", "fill", "font", "footer", "for",
+    "foreignObject", "form", "formaction", "frame", "frameset", "g", "h1", "h2", "h3", "h4", "h5",
+    "h6", "head", "header", "headers", "height", "hgroup", "hidden", "high", "hr", "href",
+    "hreflang", "html", "http-equiv", "i", "id", "iframe", "img", "input", "inputmode", "ins",
+    "integrity", "is", "ismap", "itemprop", "kbd", "kind", "label", "lang", "legend", "li", "line",
+    "linearGradient", "link", "list", "loading", "loop", "low", "main", "map", "mark", "marquee",
+    "mask", "math", "max", "maxlength", "media", "menu", "meta", "meter", "method", "mi", "min",
+    "minlength", "mn", "mo", "ms", "mtext", "multiple", "muted", "name", "nav", "noframes", "nonce",
+    "noscript", "novalidate", "object", "ol", "onchange", "onclick", "onerror", "oninput", "onload",
+    "onmouseover", "onsubmit", "open", "optgroup", "optimum", "option", "output", "p", "param",
+    "path", "pattern", "picture", "ping", "placeholder", "polygon", "polyline", "poster", "pre",
+    "preload", "progress", "q", "radialGradient", "readonly", "rect", "referrerpolicy", "rel",
+    "required", "reversed", "role", "rows", "rowspan", "rp", "rt", "ruby", "s", "samp", "sandbox",
+    "scope", "script", "search", "section", "select", "selected", "shape", "size", "sizes", "slot",
+    "small", "source", "span", "spellcheck", "src", "srcdoc", "srclang", "srcset", "start", "step",
+    "stop", "strike", "stroke", "strong", "style", "sub", "summary", "sup", "svg", "symbol",
+    "tabindex", "table", "target", "tbody", "td", "template", "text", "textarea", "tfoot", "th",
+    "thead", "time", "title", "tr", "track", "translate", "tspan", "tt", "type", "u", "ul", "use",
+    "usemap", "valign", "value", "var", "video", "viewBox", "wbr", "width", "wrap", "xmlns",
+];
+
+/// A tag or attribute name.
+///
+/// The names known to HTML, SVG and MathML, such as `div` and `class`, are
+/// stored once for the program, so cloning one is a copy and comparing two
+/// compares their index. Other names are stored with a reference count and
+/// freed with their last atom, so a document with many made up names does
+/// not grow memory that is shared by every thread.
+///
+/// # Examples
+/// ```rust
+/// use html::dom::Atom;
+///
+/// let div = Atom::from("div");
+/// assert_eq!(div, Atom::from(String::from("div")));
+/// assert_eq!(div.as_str(), "div");
+/// assert_eq!(Atom::lookup("div"), Some(div));
+/// assert_eq!(Atom::from("my-widget"), "my-widget");
+/// ```
+#[derive(Clone)]
+pub struct Atom(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    /// The index in [`KNOWN_NAMES`].
+    Known(u16),
+    Owned(Arc<str>),
+}
+
+impl Atom {
+    /// Returns the atom for `name`.
+    pub fn new(name: &str) -> Atom {
+        Atom::lookup(name).unwrap_or_else(|| Atom(Repr::Owned(Arc::from(name))))
+    }
+
+    /// Returns the atom for `name` if it is a name known to HTML, SVG or
+    /// MathML, without allocating.
+    pub fn lookup(name: &str) -> Option<Atom> {
+        let index = KNOWN_NAMES.binary_search(&name).ok()?;
+        Some(Atom(Repr::Known(index as u16)))
+    }
+
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            Repr::Known(index) => KNOWN_NAMES[usize::from(*index)],
+            Repr::Owned(name) => name,
+        }
+    }
+}
+
+impl PartialEq for Atom {
+    fn eq(&self, other: &Atom) -> bool {
+        match (&self.0, &other.0) {
+            (Repr::Known(a), Repr::Known(b)) => a == b,
+            (Repr::Owned(a), Repr::Owned(b)) => Arc::ptr_eq(a, b) || a == b,
+            // a known name is never owned
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Atom {}
+
+impl Hash for Atom {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl PartialEq<str> for Atom {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Atom {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Atom {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<Atom> for &str {
+    fn eq(&self, other: &Atom) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl PartialOrd for Atom {
+    fn partial_cmp(&self, other: &Atom) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Atom {
+    fn cmp(&self, other: &Atom) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Deref for Atom {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Atom {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for Atom {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Atom {
+    fn from(name: &str) -> Atom {
+        Atom::new(name)
+    }
+}
+
+impl From<&String> for Atom {
+    fn from(name: &String) -> Atom {
+        Atom::new(name)
+    }
+}
+
+impl From<String> for Atom {
+    fn from(name: String) -> Atom {
+        Atom::new(&name)
+    }
+}
+
+impl fmt::Debug for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::Payload;
+
+    #[test]
+    fn atom_test() {
+        let root = crate::parse(r#"<div class="a"><div class="b"></div></div>"#).unwrap();
+        let names: Vec<(Atom, Atom)> = root
+            .descendants()
+            .filter_map(|node| match &*node.get_payload() {
+                Payload::Tag(tag) => {
+                    let class = tag.get_attributes()?.keys().next()?.clone();
+                    Some((tag.get_name_atom().clone(), class))
+                }
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(names.len(), 2);
+        assert!(matches!((&names[0].0 .0, &names[1].0 .0), (Repr::Known(a), Repr::Known(b)) if a == b));
+        assert!(matches!((&names[0].1 .0, &names[1].1 .0), (Repr::Known(a), Repr::Known(b)) if a == b));
+        assert_eq!(names[0].1, "class");
+        assert_ne!(Atom::from("div"), Atom::from("DIV"));
+        assert_eq!(Atom::lookup("no-such-name-anywhere"), None);
+
+        // other names are not kept once their atoms are dropped
+        let name = Atom::from("no-such-name-anywhere");
+        assert_eq!(name, Atom::from(String::from("no-such-name-anywhere")));
+        assert_ne!(name, Atom::from("div"));
+        assert!(matches!(&name.0, Repr::Owned(name) if Arc::strong_count(name) == 1));
+
+        let root = crate::parse("<my-widget>a</my-widget>").unwrap();
+        assert_eq!(root.query_name("my-widget").count(), 1);
+
+        assert!(KNOWN_NAMES.windows(2).all(|names| names[0] < names[1]));
+        assert!(u16::try_from(KNOWN_NAMES.len()).is_ok());
+    }
+}
@@ -0,0 +1,181 @@
+use std::fmt;
+
+use super::Atom;
+
+/// The value of an attribute, with how it was written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttrValue {
+    /// The attribute has no value. `<input disabled>`
+    Empty,
+    /// `<td width=10>`
+    Unquoted(String),
+    /// `<a href="/">` or `<a href='/'>`, which may be empty. `<img alt="">`
+    Quoted(String),
+}
+
+impl AttrValue {
+    /// Returns the value, or "" if the attribute has no value.
+    pub fn as_str(&self) -> &str {
+        match self {
+            AttrValue::Empty => "",
+            AttrValue::Unquoted(value) | AttrValue::Quoted(value) => value,
+        }
+    }
+
+    /// Returns true if the attribute has no value. `<input disabled>`
+    pub fn is_empty(&self) -> bool {
+        *self == AttrValue::Empty
+    }
+}
+
+/// "" becomes [`AttrValue::Empty`], anything else [`AttrValue::Quoted`].
+impl From<String> for AttrValue {
+    fn from(value: String) -> AttrValue {
+        if value.is_empty() {
+            AttrValue::Empty
+        } else {
+            AttrValue::Quoted(value)
+        }
+    }
+}
+
+impl From<&str> for AttrValue {
+    fn from(value: &str) -> AttrValue {
+        AttrValue::from(String::from(value))
+    }
+}
+
+impl PartialEq<str> for AttrValue {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for AttrValue {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Display for AttrValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The attributes of a tag, kept in the order they were inserted.
+///
+/// Inserting an existing name replaces its value and keeps its position.
+#[derive(Clone, PartialEq, Default)]
+pub struct Attributes {
+    entries: Vec<(Atom, AttrValue)>,
+}
+
+impl Attributes {
+    pub fn new() -> Attributes {
+        Attributes::default()
+    }
+
+    /// Sets the value of `name` and returns the previous value.
+    pub fn insert(&mut self, name: impl Into<Atom>, value: impl Into<AttrValue>) -> Option<AttrValue> {
+        let name = name.into();
+        let value = value.into();
+        match self.get_mut(&name) {
+            Some(v) => Some(std::mem::replace(v, value)),
+            None => {
+                self.entries.push((name, value));
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AttrValue> {
+        self.entries.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut AttrValue> {
+        self.entries.iter_mut().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Removes `name` and returns its value. The order of the others is kept.
+    pub fn remove(&mut self, name: &str) -> Option<AttrValue> {
+        let index = self.entries.iter().position(|(n, _)| n == name)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns an iterator over the names and values in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Atom, &AttrValue)> {
+        self.entries.iter().map(|(n, v)| (n, v))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Atom, &mut AttrValue)> {
+        self.entries.iter_mut().map(|(n, v)| (&*n, v))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Atom> {
+        self.entries.iter().map(|(n, _)| n)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &AttrValue> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut AttrValue> {
+        self.entries.iter_mut().map(|(_, v)| v)
+    }
+}
+
+impl fmt::Debug for Attributes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<N: Into<Atom>, V: Into<AttrValue>> FromIterator<(N, V)> for Attributes {
+    fn from_iter<I: IntoIterator<Item = (N, V)>>(iter: I) -> Attributes {
+        let mut attributes = Attributes::new();
+        for (name, value) in iter {
+            attributes.insert(name, value);
+        }
+        attributes
+    }
+}
+
+impl IntoIterator for Attributes {
+    type Item = (Atom, AttrValue);
+    type IntoIter = std::vec::IntoIter<(Atom, AttrValue)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order_test() {
+        let mut attributes = Attributes::new();
+        attributes.insert(String::from("src"), String::from("a.png"));
+        attributes.insert(String::from("alt"), String::from("a"));
+        attributes.insert(String::from("width"), String::from("10"));
+        assert_eq!(attributes.insert(String::from("src"), String::from("b.png")), Some(AttrValue::from("a.png")));
+
+        assert_eq!(attributes.keys().collect::<Vec<_>>(), ["src", "alt", "width"]);
+        assert_eq!(attributes.remove("alt"), Some(AttrValue::from("a")));
+        assert_eq!(attributes.values().map(AttrValue::as_str).collect::<Vec<_>>(), ["b.png", "10"]);
+    }
+}
@@ -0,0 +1,188 @@
+//! A tree that borrows its names and text from the document.
+//!
+//! Created by [`parse_borrowed`](crate::parse_borrowed). Tag names,
+//! attributes and text are slices of the document, and only the text with
+//! character references such as `&amp;` is copied to be decoded. The tree
+//! owns its children and has no parent links, so it is cheaper to build
+//! than [`Node`](super::Node) and can be converted to one with
+//! [`Node::to_node`].
+
+use std::borrow::Cow;
+
+use super::{drop_descendants, Attributes, Doctype, OwnsChildren};
+
+/// A node of the borrowed tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node<'a> {
+    payload: Payload<'a>,
+    children: Vec<Node<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Payload<'a> {
+    Tag(Tag<'a>),
+    Text(Cow<'a, str>),
+    /// The content of `<script>` or `<style>`.
+    RawText(&'a str),
+    Comment(&'a str),
+    Doctype(Doctype),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag<'a> {
+    name: &'a str,
+    attributes: Vec<(&'a str, AttrValue<'a>)>,
+    self_closing: bool,
+}
+
+/// The value of an attribute of a [`Tag`]. See [`super::AttrValue`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttrValue<'a> {
+    Empty,
+    Unquoted(Cow<'a, str>),
+    Quoted(Cow<'a, str>),
+}
+
+impl Drop for Node<'_> {
+    fn drop(&mut self) {
+        drop_descendants(self);
+    }
+}
+
+impl<'a> OwnsChildren for Node<'a> {
+    type Child = Node<'a>;
+
+    fn take_children(&mut self) -> Vec<Node<'a>> {
+        std::mem::take(&mut self.children)
+    }
+
+    fn into_node(child: Node<'a>) -> Option<Node<'a>> {
+        Some(child)
+    }
+}
+
+impl<'a> Node<'a> {
+    pub(crate) fn new(payload: Payload<'a>) -> Node<'a> {
+        Node {
+            payload,
+            children: Vec::new(),
+        }
+    }
+
+    pub(crate) fn push_child(&mut self, child: Node<'a>) {
+        self.children.push(child);
+    }
+
+    pub fn get_payload(&self) -> &Payload<'a> {
+        &self.payload
+    }
+
+    pub fn get_children(&self) -> &[Node<'a>] {
+        &self.children
+    }
+
+    /// Returns an iterator over the descendants in document order.
+    /// The node itself is not included.
+    pub fn descendants(&self) -> impl Iterator<Item = &Node<'a>> {
+        let mut stack: Vec<&Node<'a>> = self.children.iter().rev().collect();
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+
+    /// Copies the tree into a [`Node`](super::Node), which can be changed,
+    /// queried and serialized.
+    pub fn to_node(&self) -> super::Node {
+        let copy = |node: &Node<'a>| {
+            super::Node::new(match &node.payload {
+                Payload::Tag(tag) => super::Payload::Tag(tag.to_tag()),
+                Payload::Text(text) => super::Payload::Text(text.to_string()),
+                Payload::RawText(text) => super::Payload::RawText(text.to_string()),
+                Payload::Comment(text) => super::Payload::Comment(text.to_string()),
+                Payload::Doctype(doctype) => super::Payload::Doctype(doctype.clone()),
+            })
+        };
+
+        // copies the descendants with a stack, as copying them recursively
+        // overflows the call stack for deeply nested trees
+        let root = copy(self);
+        let mut stack = vec![(self, root.clone())];
+        while let Some((node, new_node)) = stack.pop() {
+            for child in &node.children {
+                let new_child = copy(child);
+                new_node.add_child_and_update_parent(&new_child);
+                stack.push((child, new_child));
+            }
+        }
+
+        root
+    }
+}
+
+impl<'a> Tag<'a> {
+    pub(crate) fn new(name: &'a str, attributes: Vec<(&'a str, AttrValue<'a>)>, self_closing: bool) -> Tag<'a> {
+        Tag {
+            name,
+            attributes,
+            self_closing,
+        }
+    }
+
+    pub fn get_name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the value of the attribute, or "" if it has no value.
+    pub fn get_attribute_value(&self, attribute_name: &str) -> Option<&str> {
+        self.get_attribute(attribute_name).map(AttrValue::as_str)
+    }
+
+    pub fn get_attribute(&self, attribute_name: &str) -> Option<&AttrValue<'a>> {
+        self.attributes
+            .iter()
+            .find(|(name, _)| *name == attribute_name)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the attributes with their values in the order they were written.
+    pub fn get_attributes(&self) -> &[(&'a str, AttrValue<'a>)] {
+        &self.attributes
+    }
+
+    pub fn is_self_closing(&self) -> bool {
+        self.self_closing
+    }
+
+    fn to_tag(&self) -> super::Tag {
+        let mut tag = super::Tag::new(self.name);
+        tag.set_self_closing(self.self_closing);
+        if !self.attributes.is_empty() {
+            let attributes: Attributes = self
+                .attributes
+                .iter()
+                .map(|(name, value)| {
+                    let value = match value {
+                        AttrValue::Empty => super::AttrValue::Empty,
+                        AttrValue::Unquoted(value) => super::AttrValue::Unquoted(value.to_string()),
+                        AttrValue::Quoted(value) => super::AttrValue::Quoted(value.to_string()),
+                    };
+                    (name.to_string(), value)
+                })
+                .collect();
+            tag.set_attributes(attributes);
+        }
+
+        tag
+    }
+}
+
+impl AttrValue<'_> {
+    pub fn as_str(&self) -> &str {
+        match self {
+            AttrValue::Empty => "",
+            AttrValue::Unquoted(value) | AttrValue::Quoted(value) => value,
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use super::{AttrValue, Attributes, Node, Payload, Tag};
+
+/// Builds an element and its children to generate a document.
+///
+/// # Examples
+/// ```rust
+/// use html::dom::Element;
+///
+/// let node = Element::new("div")
+///     .class("card")
+///     .attr("id", "x")
+///     .child(Element::new("p").text("Hi & bye"))
+///     .build();
+///
+/// assert_eq!(node.to_html(), r#"<div class="card" id="x"><p>Hi &amp; bye</p></div>"#);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Element {
+    tag: Tag,
+    attributes: Attributes,
+    children: Vec<Node>,
+}
+
+impl Element {
+    pub fn new(name: &str) -> Element {
+        Element {
+            tag: Tag::new(name),
+            attributes: Attributes::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Sets the attribute, replacing the value it had.
+    /// An empty value is written as the bare name. `<input disabled>`
+    pub fn attr(mut self, name: &str, value: impl Into<AttrValue>) -> Element {
+        self.attributes.insert(String::from(name), value);
+        self
+    }
+
+    pub fn id(self, id: &str) -> Element {
+        self.attr("id", id)
+    }
+
+    /// Adds `class` to the classes of the element.
+    pub fn class(mut self, class: &str) -> Element {
+        let classes = match self.attributes.get("class") {
+            Some(classes) if !classes.is_empty() => format!("{} {}", classes, class),
+            _ => String::from(class),
+        };
+        self.attributes.insert(String::from("class"), classes);
+        self
+    }
+
+    /// Writes the element as `<tag />`. Default: false
+    pub fn self_closing(mut self, b: bool) -> Element {
+        self.tag.set_self_closing(b);
+        self
+    }
+
+    /// Appends an element or a node to the children.
+    pub fn child(mut self, child: impl Into<Node>) -> Element {
+        self.children.push(child.into());
+        self
+    }
+
+    pub fn children<I, T>(mut self, children: I) -> Element
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Node>,
+    {
+        self.children.extend(children.into_iter().map(Into::into));
+        self
+    }
+
+    /// Appends a text node. It is escaped when serialized.
+    pub fn text(mut self, text: &str) -> Element {
+        self.children.push(Node::new(Payload::Text(String::from(text))));
+        self
+    }
+
+    pub fn comment(mut self, text: &str) -> Element {
+        self.children.push(Node::new(Payload::Comment(String::from(text))));
+        self
+    }
+
+    /// Creates the node of the element with its children.
+    pub fn build(mut self) -> Node {
+        if !self.attributes.is_empty() {
+            self.tag.set_attributes(self.attributes);
+        }
+
+        let node = Node::new(Payload::Tag(self.tag));
+        for child in &self.children {
+            node.add_child_and_update_parent(child);
+        }
+
+        node
+    }
+}
+
+impl From<Element> for Node {
+    fn from(element: Element) -> Node {
+        element.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_test() {
+        let list = Element::new("ul")
+            .id("list")
+            .class("a")
+            .class("b")
+            .children(["x", "y"].iter().map(|item| Element::new("li").text(item)))
+            .child(Node::new(Payload::Text(String::from("z"))))
+            .comment(" end ");
+        let node = Element::new("form")
+            .child(Element::new("input").attr("disabled", "").attr("value", "v"))
+            .child(list)
+            .build();
+
+        assert_eq!(
+            node.to_html(),
+            r#"<form><input disabled value="v"><ul id="list" class="a b"><li>x</li><li>y</li>z<!-- end --></ul></form>"#
+        );
+        assert!(node
+            .get_children()
+            .iter()
+            .all(|child| std::ptr::eq(child.get_parent().unwrap().as_ref(), &*node)));
+    }
+}
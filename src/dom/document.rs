@@ -0,0 +1,516 @@
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::rc::Rc;
+
+use super::{Doctype, Node, NodeData, Payload, Span, Tag};
+use crate::selector::SelectorError;
+use crate::{DiagnosticKind, ParseError, ParseOptions, Position};
+
+/// Elements whose content the tokenizer parses differently, so that an
+/// element in them is not parsed again on its own by [`Document::edit`].
+const CONTEXT_ELEMENTS: [&str; 4] = ["pre", "listing", "svg", "math"];
+
+/// Elements that belong in `<head>`, which an implied `<head>` takes from
+/// the start of the document.
+const METADATA_ELEMENTS: [&str; 8] = ["base", "link", "meta", "noscript", "script", "style", "template", "title"];
+
+/// A whole document, with the `<html>`, `<head>` and `<body>` elements it
+/// has whether or not they were written.
+/// Created by [`parse_document`](crate::parse_document).
+///
+/// The elements that are missing are created the way a browser implies
+/// them: the nodes after the doctype go into `<html>`, the metadata at
+/// their start such as `<title>` and `<meta>` into `<head>`, and the rest
+/// into `<body>`.
+///
+/// # Examples
+/// ```rust
+/// let doc = html::parse_document(r#"<!DOCTYPE html><title>Hi</title><meta charset="utf-8"><p>text</p>"#).unwrap();
+///
+/// assert_eq!(doc.doctype().unwrap().get_name(), "html");
+/// assert_eq!(doc.title().as_deref(), Some("Hi"));
+/// assert_eq!(doc.charset().as_deref(), Some("utf-8"));
+/// assert_eq!(doc.body().to_html(), "<body><p>text</p></body>");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Document {
+    root: Node,
+    html: Node,
+    head: Node,
+    body: Node,
+    // built on first use by `ids`
+    ids: OnceCell<HashMap<String, Node>>,
+    // the source with the edits, if the document was parsed
+    source: Option<String>,
+}
+
+impl Document {
+    /// Returns the synthetic `root` node, whose children are the doctype,
+    /// `<html>` and the comments around them.
+    pub fn root(&self) -> &Node {
+        &self.root
+    }
+
+    pub fn into_root(self) -> Node {
+        self.root
+    }
+
+    pub fn doctype(&self) -> Option<Doctype> {
+        self.root.get_children().iter().find_map(|child| match &*child.get_payload() {
+            Payload::Doctype(doctype) => Some(doctype.clone()),
+            _ => None,
+        })
+    }
+
+    pub fn html(&self) -> &Node {
+        &self.html
+    }
+
+    pub fn head(&self) -> &Node {
+        &self.head
+    }
+
+    pub fn body(&self) -> &Node {
+        &self.body
+    }
+
+    /// Returns the text of the first `<title>` in `<head>` with its
+    /// whitespace collapsed, or `None` if there is none.
+    pub fn title(&self) -> Option<String> {
+        let title = self.head.descendants().find(|node| is_tag(node, "title"))?;
+        let text: String = title
+            .get_children()
+            .iter()
+            .filter_map(|child| match &*child.get_payload() {
+                Payload::Text(text) => Some(text.clone()),
+                _ => None,
+            })
+            .collect();
+        Some(text.split_whitespace().collect::<Vec<_>>().join(" "))
+    }
+
+    /// Returns the elements by their id, built on first use so that each
+    /// lookup after it is O(1). If an id appears more than once, the first
+    /// element has it, as in [`get_node_by_id`](crate::get_node_by_id).
+    ///
+    /// The index is not updated when the tree changes after it is built.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let doc = html::parse_document(r#"<p id="a">x</p><p id="a">y</p><b id="b">z</b>"#).unwrap();
+    ///
+    /// assert_eq!(doc.ids().len(), 2);
+    /// assert_eq!(doc.get_node_by_id("a").unwrap().inner_text(), "x");
+    /// assert!(doc.get_node_by_id("c").is_none());
+    /// ```
+    pub fn ids(&self) -> &HashMap<String, Node> {
+        self.ids.get_or_init(|| {
+            let mut ids = HashMap::new();
+            for node in self.root.descendants() {
+                if let Payload::Tag(tag) = &*node.get_payload() {
+                    if let Some(id) = tag.get_attribute_value("id").filter(|id| !id.is_empty()) {
+                        ids.entry(id).or_insert_with(|| Node::from(Rc::clone(&node)));
+                    }
+                }
+            }
+            ids
+        })
+    }
+
+    /// Returns the element whose id is `id` with the index of [`ids`](Document::ids).
+    pub fn get_node_by_id(&self, id: &str) -> Option<&Node> {
+        self.ids().get(id)
+    }
+
+    /// Removes the elements that match any selector of the comma-separated
+    /// `selectors` with [`Node::remove_all`] and returns how many were removed.
+    /// The index of [`ids`](Document::ids) is built again on next use.
+    ///
+    /// # Errors
+    /// * [`SelectorError`] if a selector cannot be parsed. Nothing is removed.
+    pub fn remove_all(&mut self, selectors: &str) -> Result<usize, SelectorError> {
+        let removed = self.root.remove_all(selectors)?;
+        if removed > 0 {
+            self.ids = OnceCell::new();
+        }
+        Ok(removed)
+    }
+
+    /// Returns a copy of `node` to add to this document, with its
+    /// descendants if `deep` is true. `node` may be in another document,
+    /// which is not changed. The copy has no parent and no spans.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let doc = html::parse_document("<p>x</p>").unwrap();
+    /// let other = html::parse("<ul><li>a</li></ul>").unwrap();
+    /// let ul = html::dom::Node::from(other.first_child().unwrap());
+    ///
+    /// doc.body().adopt(&doc.import_node(&ul, true));
+    /// doc.body().adopt(&doc.import_node(&ul, false));
+    /// assert_eq!(doc.body().inner_html(), "<p>x</p><ul><li>a</li></ul><ul></ul>");
+    /// assert_eq!(other.to_html(), "<ul><li>a</li></ul>");
+    /// ```
+    pub fn import_node(&self, node: &Node, deep: bool) -> Node {
+        let copy = match deep {
+            true => node.deep_clone(),
+            false => Node::new(node.get_payload().clone()),
+        };
+        copy.forget_spans();
+        copy
+    }
+
+    /// Returns the source of the document with the edits made by
+    /// [`edit`](Document::edit), or `None` if it was not parsed from source.
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    pub(crate) fn set_source(&mut self, source: &str) {
+        self.source = Some(source.to_string());
+    }
+
+    /// Replaces `range` of the source with `replacement` and updates the tree.
+    ///
+    /// Only the innermost element around `range` whose markup parses to the
+    /// same element on its own is parsed again, and the spans of the nodes
+    /// after it are moved. The other nodes are kept as they are. If there is
+    /// no such element, such as when an end tag is removed, the whole source
+    /// is parsed again. The tree is the same either way.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let mut doc = html::parse_document("<ul><li>one</li><li>two</li></ul><p>end</p>").unwrap();
+    /// let p = doc.body().get_children()[1].clone();
+    ///
+    /// doc.edit(8..11, "first").unwrap();
+    /// assert_eq!(doc.source(), Some("<ul><li>first</li><li>two</li></ul><p>end</p>"));
+    /// assert_eq!(doc.body().inner_html(), "<ul><li>first</li><li>two</li></ul><p>end</p>");
+    ///
+    /// // the nodes after the edit are kept and moved
+    /// assert!(std::rc::Rc::ptr_eq(&doc.body().get_children()[1], &p));
+    /// assert_eq!(p.get_span().unwrap().start, 35);
+    /// ```
+    ///
+    /// # Errors
+    /// * [`ParseError::InvalidRange`] if `range` is not in the source or not
+    ///   at character boundaries, or the document was not parsed from source.
+    /// * [`ParseError`] if the edited source cannot be parsed. The document is not changed.
+    pub fn edit(&mut self, range: Range<usize>, replacement: &str) -> Result<(), ParseError> {
+        let source = match &self.source {
+            Some(source) if range.start <= range.end && source.get(range.clone()).is_some() => source,
+            source => {
+                let source = source.as_deref().unwrap_or_default();
+                let mut offset = range.start.min(source.len());
+                while !source.is_char_boundary(offset) {
+                    offset -= 1;
+                }
+                let (line, column) = advance(1, 1, &source[..offset]);
+                return Err(ParseError::InvalidRange(Position { offset, line, column }));
+            }
+        };
+
+        let mut edited = String::with_capacity(source.len() - range.len() + replacement.len());
+        edited.push_str(&source[..range.start]);
+        edited.push_str(replacement);
+        edited.push_str(&source[range.end..]);
+
+        // the innermost elements first
+        let elements: Vec<Rc<NodeData>> = self
+            .root
+            .descendants()
+            .filter(|node| {
+                matches!(&*node.get_payload(), Payload::Tag(_))
+                    && node.get_span().is_some_and(|span| span.start < range.start && range.end < span.end)
+            })
+            .collect();
+        for element in elements.iter().rev() {
+            if let Some(node) = self.reparse(element, &range, replacement, &edited) {
+                self.move_spans(element, &range, replacement, &edited);
+                let parent = Node::from(element.get_parent().unwrap());
+                parent.replace_child(element, &node);
+                self.source = Some(edited);
+                self.ids = OnceCell::new();
+                return Ok(());
+            }
+        }
+
+        *self = crate::parse_document(&edited)?;
+        Ok(())
+    }
+
+    /// Parses the markup of `element` in the `edited` source on its own,
+    /// or returns `None` if it may be parsed differently in the document.
+    fn reparse(&self, element: &NodeData, range: &Range<usize>, replacement: &str, edited: &str) -> Option<Node> {
+        let is_document_element = [&self.html, &self.head, &self.body]
+            .iter()
+            .any(|node| std::ptr::eq::<NodeData>(&***node, element));
+        let is_in_context = element
+            .ancestors()
+            .any(|ancestor| CONTEXT_ELEMENTS.iter().any(|name| is_tag(&ancestor, name)));
+        if is_document_element || is_in_context {
+            return None;
+        }
+
+        let span = element.get_span()?;
+        let markup = edited.get(span.start..span.end - range.len() + replacement.len())?;
+        let result = crate::parse_with_diagnostics(markup, &ParseOptions::default()).ok()?;
+        let is_structural = |kind: &DiagnosticKind| {
+            matches!(
+                kind,
+                DiagnosticKind::StrayEndTag(_)
+                    | DiagnosticKind::MismatchedEndTag { .. }
+                    | DiagnosticKind::UnclosedTag(_)
+            )
+        };
+        if result.warnings.iter().any(|warning| is_structural(&warning.kind)) {
+            return None;
+        }
+
+        // one element with its own end tag, as an omitted end tag depends on what follows
+        let node = match result.dom.get_children().as_slice() {
+            [node] => Node::from(Rc::clone(node)),
+            _ => return None,
+        };
+        let is_same_element = match (&*element.get_payload(), &*node.get_payload()) {
+            (Payload::Tag(old), Payload::Tag(tag)) => {
+                let name = old.get_name();
+                let end_tag = markup.len().checked_sub(name.len() + 3).and_then(|i| markup.get(i..));
+                let is_closed = tag.is_void_element()
+                    || tag.is_self_closing()
+                    || end_tag.is_some_and(|end_tag| end_tag.eq_ignore_ascii_case(&format!("</{}>", name)));
+                tag.get_name().eq_ignore_ascii_case(name) && is_closed
+            }
+            _ => false,
+        };
+        if !is_same_element {
+            return None;
+        }
+
+        node.detach();
+        for descendant in std::iter::once(node.get_copy_of_internal_arc()).chain(node.descendants()) {
+            if let Some(inner) = descendant.get_span() {
+                let (line, column) = match inner.line {
+                    1 => (span.line, span.column + inner.column - 1),
+                    line => (span.line + line - 1, inner.column),
+                };
+                descendant.set_span(Span {
+                    start: span.start + inner.start,
+                    end: span.start + inner.end,
+                    line,
+                    column,
+                });
+            }
+        }
+        Some(node)
+    }
+
+    /// Moves the spans of the nodes that end after `range` in the source
+    /// before the edit, where `element` is, to where they are in `edited`.
+    fn move_spans(&self, element: &NodeData, range: &Range<usize>, replacement: &str, edited: &str) {
+        let span = element.get_span().unwrap();
+        let source = self.source.as_deref().unwrap_or_default();
+        // where the source after `range` starts, before and after the edit
+        let (old_line, old_column) = advance(span.line, span.column, &source[span.start..range.end]);
+        let (new_line, new_column) =
+            advance(span.line, span.column, &edited[span.start..range.start + replacement.len()]);
+
+        for node in self.root.descendants() {
+            let mut node_span = match node.get_span() {
+                Some(node_span) if node_span.end >= range.end => node_span,
+                _ => continue,
+            };
+            node_span.end = node_span.end - range.len() + replacement.len();
+            if node_span.start >= range.end {
+                node_span.start = node_span.start - range.len() + replacement.len();
+                if node_span.line == old_line {
+                    node_span.column = node_span.column - old_column + new_column;
+                }
+                node_span.line = node_span.line - old_line + new_line;
+            }
+            node.set_span(node_span);
+        }
+    }
+
+    /// Returns the charset declared by the first `<meta charset>` or
+    /// `<meta http-equiv="Content-Type">` in `<head>`, as it was written.
+    pub fn charset(&self) -> Option<String> {
+        self.head.descendants().filter(|node| is_tag(node, "meta")).find_map(|meta| match &*meta.get_payload() {
+            Payload::Tag(tag) => match tag.get_attribute_value("charset") {
+                Some(charset) => Some(charset.trim().to_string()),
+                None => crate::parser::charset_parameter(&tag.get_attribute_value("content")?),
+            },
+            _ => None,
+        })
+    }
+}
+
+/// Finds or creates `<html>`, `<head>` and `<body>` in the tree of `root`.
+impl From<Node> for Document {
+    fn from(root: Node) -> Document {
+        let html = match find_child(&root, "html") {
+            Some(html) => html,
+            None => {
+                let html = Node::new(Payload::Tag(Tag::new("html")));
+                move_children(&root, &html, |child| !matches!(&*child.get_payload(), Payload::Doctype(_)));
+                root.add_child_and_update_parent(&html);
+                html
+            }
+        };
+
+        let head = match find_child(&html, "head") {
+            Some(head) => head,
+            None => {
+                let head = Node::new(Payload::Tag(Tag::new("head")));
+                // only the leading metadata moves, up to the first other child
+                let mut leading = true;
+                move_children(&html, &head, |child| {
+                    leading = leading
+                        && (METADATA_ELEMENTS.iter().any(|name| is_tag(child, name))
+                            || matches!(&*child.get_payload(), Payload::Comment(_)));
+                    leading
+                });
+                html.insert_child_at(0, &head);
+                head
+            }
+        };
+
+        let body = match find_child(&html, "body") {
+            Some(body) => body,
+            None => {
+                let body = Node::new(Payload::Tag(Tag::new("body")));
+                move_children(&html, &body, |child| !std::ptr::eq(child, &*head));
+                html.add_child_and_update_parent(&body);
+                body
+            }
+        };
+
+        Document {
+            root,
+            html,
+            head,
+            body,
+            ids: OnceCell::new(),
+            source: None,
+        }
+    }
+}
+
+fn is_tag(node: &NodeData, name: &str) -> bool {
+    match &*node.get_payload() {
+        Payload::Tag(tag) => tag.get_name().eq_ignore_ascii_case(name),
+        _ => false,
+    }
+}
+
+/// Returns the line and column after `text`, which starts at `line` and `column`.
+fn advance(line: usize, column: usize, text: &str) -> (usize, usize) {
+    match text.rfind('\n') {
+        Some(i) => (line + text.matches('\n').count(), text[i + 1..].chars().count() + 1),
+        None => (line, column + text.chars().count()),
+    }
+}
+
+fn find_child(parent: &NodeData, name: &str) -> Option<Node> {
+    parent.get_children().iter().find(|child| is_tag(child, name)).cloned().map(Node::from)
+}
+
+/// Moves the children of `from` that `moves` accepts, in order, to the end
+/// of the children of `to`. The children are taken out of `from` once, so
+/// that moving many children does not remove them one by one.
+fn move_children(from: &NodeData, to: &Node, mut moves: impl FnMut(&NodeData) -> bool) {
+    let children = std::mem::take(&mut *from.children.borrow_mut());
+    let (moved, kept): (Vec<Rc<NodeData>>, Vec<Rc<NodeData>>) = children.into_iter().partition(|child| moves(child));
+    *from.children.borrow_mut() = kept;
+
+    let parent = Rc::downgrade(&to.get_copy_of_internal_arc());
+    for child in &moved {
+        *child.parent.borrow_mut() = parent.clone();
+    }
+    to.children.borrow_mut().extend(moved);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_document, ParseError};
+
+    #[test]
+    fn document_test() {
+        let doc = parse_document("<!-- a --><title> A\n title </title><script>x</script><p>one</p><style>s</style>").unwrap();
+        assert!(doc.doctype().is_none());
+        assert_eq!(
+            doc.root().to_html(),
+            "<html><head><!-- a --><title> A\n title </title><script>x</script></head><body><p>one</p><style>s</style></body></html>"
+        );
+        assert_eq!(doc.title().as_deref(), Some("A title"));
+        assert_eq!(doc.charset(), None);
+
+        let html = r#"<!DOCTYPE html><html><head><meta http-equiv="Content-Type" content="text/html; charset=ISO-8859-1"></head><body>x</body></html>"#;
+        let doc = parse_document(html).unwrap();
+        assert_eq!(doc.root().to_html(), html);
+        assert_eq!(doc.charset().as_deref(), Some("ISO-8859-1"));
+
+        // only the missing elements are created
+        let doc = parse_document("<html><body><p>x</p></body></html>").unwrap();
+        assert_eq!(doc.root().to_html(), "<html><head></head><body><p>x</p></body></html>");
+        assert_eq!(doc.title(), None);
+        let mut doc = parse_document(r#"<script>x</script><p id="a">x</p>"#).unwrap();
+        assert!(doc.get_node_by_id("a").is_some());
+        assert_eq!(doc.remove_all("script, p").unwrap(), 2);
+        assert!(doc.get_node_by_id("a").is_none());
+        assert_eq!(doc.root().to_html(), "<html><head></head><body></body></html>");
+        let doc = parse_document("").unwrap();
+        assert_eq!(doc.into_root().to_html(), "<html><head></head><body></body></html>");
+
+        // the children are moved at once, and each gets its new parent
+        let count = 100_000;
+        let doc = parse_document(&format!("<!DOCTYPE html><meta charset=utf-8>{}", "<p>x</p>".repeat(count))).unwrap();
+        assert_eq!(doc.root().get_children().len(), 2);
+        assert_eq!(doc.head().get_children().len(), 1);
+        assert_eq!(doc.body().get_children().len(), count);
+        let body = doc.body().get_copy_of_internal_arc();
+        assert!(doc.body().children().iter().all(|p| std::rc::Rc::ptr_eq(&p.get_parent().unwrap(), &body)));
+        assert!(std::rc::Rc::ptr_eq(&doc.head().get_parent().unwrap(), &doc.html().get_copy_of_internal_arc()));
+    }
+
+    #[test]
+    fn edit_test() {
+        let source = concat!(
+            "<!DOCTYPE html>\n<title>t</title>\n<div id=a>\n  <p>one <b>two</b></p>\n  <ul><li>x<li>y</ul>\n</div>\n",
+            "<pre>\n <i>z</i></pre>\n<p>last</p>",
+        );
+        let spans = |doc: &super::Document| {
+            doc.root().descendants().map(|node| (node.get_span(), node.to_html())).collect::<Vec<_>>()
+        };
+        // each replaces the first occurrence of a part of the source
+        let edits = [
+            ("one", "1\nline"),
+            ("two", "<i>2</i>"),
+            ("<li>x", "<li>x</li>"),
+            ("z", "ü"),
+            ("id=a", "id=b class=c"),
+            ("</div>", ""),
+            ("", "<!-- new -->"),
+        ];
+        let mut doc = parse_document(source).unwrap();
+        let last = doc.body().last_child().unwrap();
+        let mut expected = String::from(source);
+        for (i, (part, replacement)) in edits.into_iter().enumerate() {
+            let start = expected.find(part).unwrap();
+            expected.replace_range(start..start + part.len(), replacement);
+            doc.edit(start..start + part.len(), replacement).unwrap();
+            assert_eq!(doc.source(), Some(expected.as_str()));
+            assert_eq!(spans(&doc), spans(&parse_document(&expected).unwrap()), "edit {}", i);
+            // only the edits inside an element with its end tag are parsed in part
+            assert_eq!(std::rc::Rc::ptr_eq(&doc.body().last_child().unwrap(), &last), i < 5, "edit {}", i);
+        }
+
+        assert!(matches!(doc.edit(0..1000, "x"), Err(ParseError::InvalidRange(_))));
+        assert!(matches!(doc.edit(0..1000, ""), Err(ParseError::InvalidRange(_))));
+        assert!(doc.edit(0..0, "<p a=\"").is_err());
+        assert_eq!(doc.source(), Some(expected.as_str()));
+        let mut doc = super::Document::from(crate::parse("<p>x</p>").unwrap());
+        assert!(matches!(doc.edit(0..0, ""), Err(ParseError::InvalidRange(_))));
+    }
+}
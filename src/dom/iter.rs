@@ -0,0 +1,180 @@
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use super::{NodeData, Payload};
+
+/// Iterator over the descendants of a node in depth-first order.
+/// Created by [`NodeData::descendants`].
+#[derive(Debug, Clone)]
+pub struct Descendants {
+    // the next node is at the end
+    stack: Vec<Rc<NodeData>>,
+}
+
+impl Iterator for Descendants {
+    type Item = Rc<NodeData>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.stack.extend(node.get_children().iter().rev().cloned());
+
+        Some(node)
+    }
+}
+
+/// Iterator over the descendants of a node in breadth-first order.
+/// Created by [`NodeData::descendants_bfs`].
+#[derive(Debug, Clone)]
+pub struct DescendantsBfs {
+    queue: VecDeque<Rc<NodeData>>,
+}
+
+impl Iterator for DescendantsBfs {
+    type Item = Rc<NodeData>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.queue.pop_front()?;
+        self.queue.extend(node.get_children().iter().cloned());
+
+        Some(node)
+    }
+}
+
+/// Iterator over the ancestors of a node, from its parent up to the root.
+/// Created by [`NodeData::ancestors`].
+#[derive(Debug, Clone)]
+pub struct Ancestors {
+    next: Option<Rc<NodeData>>,
+}
+
+impl Iterator for Ancestors {
+    type Item = Rc<NodeData>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.take()?;
+        self.next = node.get_parent();
+
+        Some(node)
+    }
+}
+
+impl NodeData {
+    /// Returns an iterator over the descendants of this node in depth-first
+    /// order, which is the order they appear in the document.
+    /// The node itself is not included.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use html::dom::Payload;
+    ///
+    /// let node = html::parse("<p><a href='/a'>a</a><b><a href='/b'>b</a></b></p>").unwrap();
+    /// let links = node
+    ///     .descendants()
+    ///     .filter_map(|node| match &*node.get_payload() {
+    ///         Payload::Tag(tag) => tag.get_attribute_value("href"),
+    ///         _ => None,
+    ///     })
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(links, ["/a", "/b"]);
+    /// ```
+    pub fn descendants(&self) -> Descendants {
+        Descendants {
+            stack: self.children().into_iter().rev().collect(),
+        }
+    }
+
+    /// Returns an iterator over the ancestors of this node, from its parent
+    /// up to the root. The node itself is not included.
+    pub fn ancestors(&self) -> Ancestors {
+        Ancestors {
+            next: self.get_parent(),
+        }
+    }
+
+    /// Returns the nearest ancestor named `tag_name`.
+    /// The node itself is not included.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let root = html::parse("<article><h2>Title</h2><p>by <b>me</b></p></article>").unwrap();
+    /// let b = html::get_node_by_name(&root.get_copy_of_internal_arc(), "b").unwrap();
+    ///
+    /// let article = b.closest("article").unwrap();
+    /// assert!(article.inner_text().starts_with("Title"));
+    /// ```
+    pub fn closest(&self, tag_name: &str) -> Option<Rc<NodeData>> {
+        self.ancestors().find(|node| match &*node.get_payload() {
+            Payload::Tag(tag) => tag.get_name() == tag_name && !node.is_document_root(),
+            _ => false,
+        })
+    }
+
+    /// Returns the tag names from the root down to this node.
+    /// The synthetic `root` node is not included. `["html", "body", "p"]`
+    pub fn path(&self) -> Vec<String> {
+        let mut path: Vec<String> = self
+            .ancestors()
+            .filter(|node| !node.is_document_root())
+            .filter_map(|node| match &*node.get_payload() {
+                Payload::Tag(tag) => Some(tag.get_name().to_string()),
+                _ => None,
+            })
+            .collect();
+        path.reverse();
+
+        if let Payload::Tag(tag) = &*self.get_payload() {
+            if !self.is_document_root() {
+                path.push(tag.get_name().to_string());
+            }
+        }
+
+        path
+    }
+
+    /// Returns an iterator over the descendants of this node in breadth-first
+    /// order, level by level. The node itself is not included.
+    pub fn descendants_bfs(&self) -> DescendantsBfs {
+        DescendantsBfs {
+            queue: self.children().into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dom::Payload;
+    use crate::parse;
+
+    fn names(iter: impl Iterator<Item = std::rc::Rc<crate::dom::NodeData>>) -> Vec<String> {
+        iter.map(|node| match &*node.get_payload() {
+            Payload::Tag(tag) => tag.get_name().to_string(),
+            Payload::Text(text) | Payload::RawText(text) => text.clone(),
+            Payload::Comment(text) | Payload::ConditionalComment(text) => text.clone(),
+            Payload::Doctype(doctype) => doctype.get_name().to_string(),
+        })
+        .collect()
+    }
+
+    #[test]
+    fn descendants_test() {
+        let root = parse("<div><p>a</p><ul><li>b</li></ul></div><span>c</span>").unwrap();
+
+        assert_eq!(names(root.descendants()), ["div", "p", "a", "ul", "li", "b", "span", "c"]);
+        assert_eq!(names(root.descendants_bfs()), ["div", "span", "p", "ul", "c", "a", "li", "b"]);
+    }
+
+    #[test]
+    fn ancestors_test() {
+        let root = parse("<div><ul><li><b>a</b></li></ul></div>").unwrap();
+        let b = crate::get_node_by_name(&root.get_copy_of_internal_arc(), "b").unwrap();
+        let text = b.first_child().unwrap();
+
+        assert_eq!(names(text.ancestors()), ["b", "li", "ul", "div", "root"]);
+        assert_eq!(text.path(), ["div", "ul", "li", "b"]);
+        assert_eq!(b.path(), ["div", "ul", "li", "b"]);
+        assert!(b.closest("ul").is_some());
+        assert!(b.closest("b").is_none());
+        assert!(b.closest("root").is_none());
+    }
+}
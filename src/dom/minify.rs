@@ -0,0 +1,411 @@
+use std::rc::Rc;
+
+use super::serialize::{
+    escape, is_unquotable, write_comment, write_conditional_comment_start, write_doctype, write_quoted,
+};
+use super::text::BLOCK_ELEMENTS;
+use super::{AttrValue, NodeData, Payload, Tag};
+
+/// Options to control how a node is minified.
+/// See [`NodeData::to_minified_html_with_options`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinifyOptions {
+    /// Keeps conditional comments such as `<!--[if IE]>...<![endif]-->`.
+    /// Default: true
+    pub keep_conditional_comments: bool,
+    /// Omits end tags that the parser of a browser implies, such as `</li>`
+    /// before another `<li>`. Default: true
+    pub omit_optional_end_tags: bool,
+    /// Writes the attribute values that were quoted with quotes, and
+    /// `alt=""` as it is, instead of leaving out what is not needed.
+    /// Default: false
+    pub keep_attribute_quotes: bool,
+}
+
+impl Default for MinifyOptions {
+    fn default() -> MinifyOptions {
+        MinifyOptions {
+            keep_conditional_comments: true,
+            omit_optional_end_tags: true,
+            keep_attribute_quotes: false,
+        }
+    }
+}
+
+impl MinifyOptions {
+    /// The options for HTML email, whose clients parse less than browsers
+    /// do: conditional comments, end tags and attribute quotes are kept.
+    /// See [`email`](crate::email).
+    pub fn email() -> MinifyOptions {
+        MinifyOptions {
+            keep_conditional_comments: true,
+            omit_optional_end_tags: false,
+            keep_attribute_quotes: true,
+        }
+    }
+}
+
+impl NodeData {
+    /// Serializes this node and its descendants to HTML markup that is as
+    /// short as possible while rendering the same.
+    ///
+    /// Whitespace between elements is collapsed, comments are dropped,
+    /// attribute values are unquoted where it is safe and optional end tags
+    /// are omitted. The content of `<pre>`, `<textarea>`, `<script>` and
+    /// `<style>` is kept as it is.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let node = html::parse(r#"
+    ///     <ul class="list">
+    ///       <li>one</li>
+    ///       <!-- comment -->
+    ///       <li>two   three</li>
+    ///     </ul>
+    /// "#).unwrap();
+    ///
+    /// assert_eq!(node.to_minified_html(), "<ul class=list><li>one<li>two three</ul>");
+    /// ```
+    pub fn to_minified_html(&self) -> String {
+        self.to_minified_html_with_options(&MinifyOptions::default())
+    }
+
+    /// Same as [`to_minified_html`](NodeData::to_minified_html), but with options.
+    pub fn to_minified_html_with_options(&self, options: &MinifyOptions) -> String {
+        let mut html = String::new();
+        let minifier = Minifier { options, raw_entities: self.has_raw_entities() };
+        minifier.write(&mut html, self);
+        html
+    }
+}
+
+/// Where a node is among its siblings after minification.
+#[derive(Default)]
+struct Context {
+    parent: Option<Rc<str>>,
+    prev: Option<Rc<NodeData>>,
+    next: Option<Rc<NodeData>>,
+    preformatted: bool,
+}
+
+/// What [`Minifier::write`] writes next.
+enum Step {
+    Start(Rc<NodeData>, Context),
+    /// The end tag, or "" if it is omitted.
+    End(String),
+}
+
+struct Minifier<'a> {
+    options: &'a MinifyOptions,
+    /// The character references are kept as they were written.
+    raw_entities: bool,
+}
+
+impl Minifier<'_> {
+    /// Writes `node` and its descendants, or only the descendants if `node`
+    /// is the document root.
+    fn write(&self, html: &mut String, node: &NodeData) {
+        let mut steps = Vec::new();
+        if node.is_document_root() {
+            self.push_children(&mut steps, node, false);
+        } else if let Some((preformatted, end)) = self.write_start(html, node, &Context::default()) {
+            steps.push(Step::End(end));
+            self.push_children(&mut steps, node, preformatted);
+        }
+
+        while let Some(step) = steps.pop() {
+            match step {
+                Step::Start(node, context) => {
+                    if let Some((preformatted, end)) = self.write_start(html, &node, &context) {
+                        steps.push(Step::End(end));
+                        self.push_children(&mut steps, &node, preformatted);
+                    }
+                }
+                Step::End(end) => html.push_str(&end),
+            }
+        }
+    }
+
+    /// Writes what comes before the children of `node`, or all of `node` if
+    /// it has no children. Returns whether the children are preformatted
+    /// and what comes after them, or `None` if nothing follows.
+    fn write_start(&self, html: &mut String, node: &NodeData, context: &Context) -> Option<(bool, String)> {
+        match &*node.get_payload() {
+            Payload::Tag(tag) => {
+                let name = tag.get_name();
+                write_start_tag(html, tag, self.options.keep_attribute_quotes, self.raw_entities);
+                if tag.is_self_closing() || tag.is_void_element() {
+                    return None;
+                }
+
+                let preformatted = context.preformatted
+                    || name == "pre"
+                    || tag.is_raw_text_element()
+                    || tag.is_escapable_raw_text_element();
+                let end = match self.options.omit_optional_end_tags && is_end_tag_optional(tag, context) {
+                    true => String::new(),
+                    false => format!("</{}>", name),
+                };
+                return Some((preformatted, end));
+            }
+            Payload::Text(text) => {
+                let parent = context.parent.as_deref();
+                let raw = match parent {
+                    Some(parent) => Tag::new(parent).is_raw_text_element(),
+                    None => false,
+                };
+
+                if raw {
+                    html.push_str(text);
+                } else if context.preformatted {
+                    escape(html, text, &['&', '<', '>'], self.raw_entities);
+                } else {
+                    let text = collapse_whitespace(text);
+                    let text = match is_edge(context.prev.as_deref(), parent) {
+                        true => text.trim_start_matches(' '),
+                        false => &text,
+                    };
+                    let text = match is_edge(context.next.as_deref(), parent) {
+                        true => text.trim_end_matches(' '),
+                        false => text,
+                    };
+                    escape(html, text, &['&', '<', '>'], self.raw_entities);
+                }
+            }
+            Payload::RawText(text) => html.push_str(text),
+            Payload::Comment(text) => {
+                if self.options.keep_conditional_comments && is_conditional_comment(text) {
+                    write_comment(html, text);
+                }
+            }
+            Payload::ConditionalComment(condition) => {
+                if self.options.keep_conditional_comments {
+                    write_conditional_comment_start(html, condition);
+                    return Some((false, String::from("<![endif]-->")));
+                }
+            }
+            Payload::Doctype(doctype) => write_doctype(html, doctype),
+        }
+        None
+    }
+
+    /// Pushes the steps that write the children of `node` that are kept,
+    /// in reverse so that they are written in order.
+    fn push_children(&self, steps: &mut Vec<Step>, node: &NodeData, preformatted: bool) {
+        let parent: Option<Rc<str>> = match &*node.get_payload() {
+            Payload::Tag(tag) if !node.is_document_root() => Some(Rc::from(tag.get_name())),
+            _ => None,
+        };
+
+        let children = node.get_children();
+        let kept: Vec<&Rc<NodeData>> = children
+            .iter()
+            .enumerate()
+            .filter(|(i, child)| {
+                preformatted || !self.is_dropped(child, parent.as_deref(), &children, *i)
+            })
+            .map(|(_, child)| child)
+            .collect();
+
+        for (i, child) in kept.iter().enumerate().rev() {
+            let context = Context {
+                parent: parent.clone(),
+                prev: i.checked_sub(1).map(|i| Rc::clone(kept[i])),
+                next: kept.get(i + 1).map(|child| Rc::clone(child)),
+                preformatted,
+            };
+            steps.push(Step::Start(Rc::clone(child), context));
+        }
+    }
+
+    /// Returns true if the child at `index` is not written at all.
+    fn is_dropped(&self, child: &NodeData, parent: Option<&str>, children: &[Rc<NodeData>], index: usize) -> bool {
+        match &*child.get_payload() {
+            Payload::Comment(text) => {
+                !(self.options.keep_conditional_comments && is_conditional_comment(text))
+            }
+            Payload::ConditionalComment(_) => !self.options.keep_conditional_comments,
+            Payload::Text(text) if text.chars().all(|c| c.is_ascii_whitespace()) => {
+                // whitespace between elements only matters between inline content
+                let prev = index.checked_sub(1).map(|i| children[i].as_ref());
+                let next = children.get(index + 1).map(|child| child.as_ref());
+                is_edge(prev, parent) || is_edge(next, parent)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Returns true if whitespace next to `sibling` does not render, because
+/// there is a block element or the edge of a block element.
+fn is_edge(sibling: Option<&NodeData>, parent: Option<&str>) -> bool {
+    match sibling {
+        Some(sibling) => match &*sibling.get_payload() {
+            Payload::Tag(tag) => is_block(tag.get_name()),
+            Payload::Doctype(_) => true,
+            _ => false,
+        },
+        None => parent.is_none_or(is_block),
+    }
+}
+
+fn is_block(name: &str) -> bool {
+    BLOCK_ELEMENTS.contains(&name) || ["head", "meta", "link", "title", "script", "style"].contains(&name)
+}
+
+/// Returns true for `<!--[if IE]>...<![endif]-->` and `<!--<![endif]-->`.
+fn is_conditional_comment(text: &str) -> bool {
+    text.starts_with("[if") || text.starts_with("<![endif]")
+}
+
+/// Returns true if the end tag of `tag` can be omitted, following the rules
+/// of the spec for the siblings that follow it.
+fn is_end_tag_optional(tag: &Tag, context: &Context) -> bool {
+    let next = context.next.as_ref().map(|next| next.get_payload());
+    let next = match next.as_deref() {
+        Some(Payload::Tag(next)) => Some(next.get_name()),
+        // text after the element would end up inside it
+        Some(_) => return false,
+        None => None,
+    };
+
+    let followed_by = |names: &[&str]| match next {
+        Some(next) => names.contains(&next),
+        None => true,
+    };
+
+    match tag.get_name() {
+        "li" => followed_by(&["li"]),
+        "dt" => next.is_some() && followed_by(&["dt", "dd"]),
+        "dd" => followed_by(&["dt", "dd"]),
+        "option" => followed_by(&["option", "optgroup"]),
+        "optgroup" => followed_by(&["optgroup"]),
+        "tr" => followed_by(&["tr"]),
+        "td" | "th" => followed_by(&["td", "th"]),
+        "thead" => next.is_some() && followed_by(&["tbody", "tfoot"]),
+        "tbody" => followed_by(&["tbody", "tfoot"]),
+        "tfoot" => next.is_none(),
+        "p" => match next {
+            Some(next) => BLOCK_ELEMENTS.contains(&next) && !["li", "dd", "dt", "tr"].contains(&next),
+            // the content of `<a>` and such is not closed by the end of its parent
+            None => !matches!(
+                context.parent.as_deref(),
+                Some("a" | "audio" | "del" | "ins" | "map" | "noscript" | "video")
+            ),
+        },
+        _ => false,
+    }
+}
+
+/// Replaces each run of whitespace in `text` with one ' '. A no-break
+/// space is not whitespace in HTML and is kept.
+fn collapse_whitespace(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for c in text.chars() {
+        if !c.is_ascii_whitespace() {
+            res.push(c);
+        } else if !res.ends_with(' ') {
+            res.push(' ');
+        }
+    }
+    res
+}
+
+/// Writes `<tag attribute=value>` with as few quotes as possible, or with
+/// the quotes of the values that were quoted if `keep_quotes` is true.
+fn write_start_tag(html: &mut String, tag: &Tag, keep_quotes: bool, raw_entities: bool) {
+    html.push('<');
+    html.push_str(tag.get_name());
+
+    if let Some(attributes) = tag.get_attributes() {
+        for (name, value) in attributes.iter() {
+            html.push(' ');
+            html.push_str(name);
+
+            let (value, keep_quotes) = match value {
+                AttrValue::Empty => continue,
+                AttrValue::Unquoted(value) => (value, false),
+                AttrValue::Quoted(value) => (value, keep_quotes),
+            };
+
+            // `alt=""` is the same as `alt`
+            if value.is_empty() && !keep_quotes {
+                continue;
+            }
+
+            html.push('=');
+            if is_unquotable(value) && !keep_quotes {
+                html.push_str(value);
+            } else {
+                write_quoted(html, value, raw_entities);
+            }
+        }
+    }
+
+    if tag.is_self_closing() && !tag.is_void_element() {
+        html.push_str("/>");
+    } else {
+        html.push('>');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn minify_test() {
+        let html = r#"
+        <!DOCTYPE html>
+        <div id="main">
+          <p>Hello,   <b>world</b>!</p>
+          <!-- comment -->
+          <!--[if IE]><p>old</p><![endif]-->
+          <table>
+            <tr><td>a</td> <td title="a b">b</td></tr>
+          </table>
+          <pre>keep
+  this </pre>
+          <input disabled value="">
+        </div>
+        "#;
+        let node = parse(html).unwrap();
+
+        assert_eq!(
+            node.to_minified_html(),
+            "<!DOCTYPE html><div id=main><p>Hello, <b>world</b>!</p>\
+             <!--[if IE]><p>old</p><![endif]--><table><tr><td>a<td title=\"a b\">b</table>\
+             <pre>keep\n  this </pre><input disabled value></div>"
+        );
+        // a no-break space is not whitespace
+        assert_eq!(parse("<p>&nbsp;a&nbsp; </p>").unwrap().to_minified_html(), "<p>\u{a0}a\u{a0}");
+        // a node that is not the document root is written with its tag
+        let root = parse("<div> <p> a </p> </div>").unwrap();
+        assert_eq!(root.first_child().unwrap().to_minified_html(), "<div><p>a</div>");
+
+        // deeply nested elements do not overflow the stack
+        let html = "<div>".repeat(100_000) + "a";
+        let minified = parse(&html).unwrap().to_minified_html();
+        assert_eq!(minified, "<div>".repeat(100_000) + "a" + &"</div>".repeat(100_000));
+    }
+
+    #[test]
+    fn minify_options_test() {
+        let node = parse("<ul><li>a</li><!--[if IE]>x<![endif]--><li>b</li></ul>").unwrap();
+        let options = MinifyOptions {
+            keep_conditional_comments: false,
+            omit_optional_end_tags: false,
+            ..Default::default()
+        };
+
+        assert_eq!(node.to_minified_html_with_options(&options), "<ul><li>a</li><li>b</li></ul>");
+
+        let html = r#"<table width=600 class="x"><tr><td align='left' title="a b">a</td></tr></table><img alt="">"#;
+        let node = parse(html).unwrap();
+        assert_eq!(
+            node.to_minified_html_with_options(&MinifyOptions::email()),
+            r#"<table width=600 class="x"><tr><td align="left" title="a b">a</td></tr></table><img alt="">"#
+        );
+    }
+}
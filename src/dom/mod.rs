@@ -1,12 +1,57 @@
-use std::cell::RefCell;
+use std::cell::{Cell, Ref, RefCell, RefMut};
 use std::ops::Deref;
 use std::rc::{Rc, Weak};
 
+pub mod arena;
+mod atom;
+mod attributes;
+pub mod borrowed;
+mod builder;
+mod document;
+mod iter;
+mod minify;
+mod normalize;
 mod payload;
+mod role;
+mod semantic;
+mod serialize;
+mod style;
+pub mod sync;
+mod teardown;
+mod template;
+mod text;
+mod visit;
 
+pub use atom::Atom;
+pub use attributes::AttrValue;
+pub use attributes::Attributes;
+pub use builder::Element;
+pub use document::Document;
+pub use iter::Ancestors;
+pub use iter::Descendants;
+pub use iter::DescendantsBfs;
+pub use minify::MinifyOptions;
+pub use normalize::NormalizeOptions;
+pub use payload::Doctype;
 pub use payload::Payload;
 pub use payload::Tag;
 pub use payload::Text;
+pub use payload::split_qualified_name;
+pub use serialize::EscapeMode;
+pub use serialize::SerializeOptions;
+pub use style::StyleDeclarations;
+pub use template::LiveDescendants;
+pub use template::ShadowRootMode;
+pub use visit::Visit;
+pub use visit::Visitor;
+
+pub(crate) use serialize::write_start_tag;
+pub(crate) use style::strip_comments;
+pub(crate) use teardown::{drop_descendants, OwnsChildren};
+pub(crate) use text::HIDDEN_ELEMENTS;
+
+/// The tag name of the node that wraps a parsed document.
+pub(crate) const ROOT_TAG_NAME: &str = "root";
 
 type NodeDataRef = Rc<NodeData>;
 type WeakNodeDataRef = Weak<NodeData>;
@@ -19,15 +64,47 @@ type Parent = RefCell<WeakNodeDataRef>;
 type Child = NodeDataRef;
 type Children = RefCell<Vec<Child>>;
 
+/// The range of the document a node was parsed from.
+///
+/// For a tag, the range covers its end tag too if it has one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    /// Byte offset of the first character.
+    pub start: usize,
+    /// Byte offset after the last character.
+    pub end: usize,
+    /// Line number of the first character, starting from 1.
+    pub line: usize,
+    /// Column number of the first character in characters, starting from 1.
+    pub column: usize,
+}
+
+/// The markup a node was parsed from by a lossless parse.
+/// See [`ParseOptions::lossless`](crate::ParseOptions::lossless).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Original {
+    /// The source before the node that no node was parsed from, such as the
+    /// whitespace between tags or a discarded end tag.
+    pub leading: String,
+    /// The start tag, text, comment or doctype as it was written, or `None`
+    /// once the payload may have been changed.
+    pub start: Option<String>,
+    /// The source after the last child that no node was parsed from.
+    /// For the document root, the source after the last node.
+    pub trailing: String,
+    /// The end tag as it was written, or `None` if it was omitted.
+    pub end: Option<String>,
+}
+
 /// This struct holds underlying data. It shouldn't be created directly, instead use:
 /// [`Node`](struct@Node).
 ///
 /// ```text
 /// NodeData
 ///  | | |
-///  | | +- payload: T ---------------------------------------+
+///  | | +- payload: RefCell<T> ------------------------------+
 ///  | |                                                    |
-///  | |                                        Simple ownership of payload
+///  | |                          Ownership of payload, which can be changed in place
 ///  | |
 ///  | +-- parent: RefCell<WeakNodeDataRef<T>> --------+
 ///  |                                            |
@@ -41,9 +118,12 @@ type Children = RefCell<Vec<Child>>;
 /// ```
 #[derive(Debug, Clone)]
 pub struct NodeData {
-    payload: Payload,
+    payload: RefCell<Payload>,
     parent: Parent,
     children: Children,
+    span: Cell<Option<Span>>,
+    original: RefCell<Option<Box<Original>>>,
+    raw_entities: Cell<bool>,
 }
 
 impl PartialEq for NodeData {
@@ -53,26 +133,334 @@ impl PartialEq for NodeData {
     }
 }
 
+impl Drop for NodeData {
+    fn drop(&mut self) {
+        drop_descendants(self);
+    }
+}
+
+impl OwnsChildren for NodeData {
+    type Child = NodeDataRef;
+
+    fn take_children(&mut self) -> Vec<NodeDataRef> {
+        std::mem::take(self.children.get_mut())
+    }
+
+    fn into_node(child: NodeDataRef) -> Option<NodeData> {
+        Rc::try_unwrap(child).ok()
+    }
+}
+
 impl NodeData {
-    pub fn get_payload(&self) -> &Payload {
-        &self.payload
+    /// Returns the payload.
+    ///
+    /// # Panics
+    /// * If the payload is borrowed by `payload_mut`.
+    pub fn get_payload(&self) -> Ref<'_, Payload> {
+        self.payload.borrow()
+    }
+
+    /// Returns the tag if this node is a tag.
+    ///
+    /// # Panics
+    /// * If the payload is borrowed by `payload_mut`.
+    pub fn get_tag(&self) -> Option<Ref<'_, Tag>> {
+        Ref::filter_map(self.get_payload(), |payload| match payload {
+            Payload::Tag(tag) => Some(tag),
+            _ => None,
+        })
+        .ok()
+    }
+
+    /// Returns the payload to change it in place, such as renaming a tag or
+    /// removing an attribute, without rebuilding the node.
+    ///
+    /// # Panics
+    /// * If the payload is already borrowed, such as by `get_payload`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use html::dom::Payload;
+    ///
+    /// let root = html::parse(r#"<b class="x">bold</b>"#).unwrap();
+    /// let b = root.first_child().unwrap();
+    /// if let Payload::Tag(tag) = &mut *b.payload_mut() {
+    ///     tag.set_name("strong");
+    ///     tag.remove_attribute("class");
+    /// }
+    ///
+    /// assert_eq!(root.to_html(), "<strong>bold</strong>");
+    /// ```
+    pub fn payload_mut(&self) -> RefMut<'_, Payload> {
+        self.forget_original_markup();
+        self.payload.borrow_mut()
+    }
+
+    /// Replaces the payload and returns the old one.
+    ///
+    /// # Panics
+    /// * If the payload is borrowed, such as by `get_payload`.
+    pub fn set_payload(&self, payload: Payload) -> Payload {
+        self.forget_original_markup();
+        self.payload.replace(payload)
     }
 
-    pub fn get_children(&self) -> &Vec<NodeDataRef> {
-        self.children.borrow().as_ref()
+    /// Returns the name of this node if it is a tag.
+    pub fn tag_name(&self) -> Option<String> {
+        match &*self.get_payload() {
+            Payload::Tag(tag) => Some(tag.get_name().to_string()),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the attribute `name` if this node is a tag that
+    /// has it. A valueless attribute has the value "". `<input disabled>`
+    pub fn attr(&self, name: &str) -> Option<String> {
+        match &*self.get_payload() {
+            Payload::Tag(tag) => tag.get_attribute(name).map(|value| value.as_str().to_string()),
+            _ => None,
+        }
+    }
+
+    /// Returns the space-separated words of the `class` attribute, or an
+    /// empty list if this node is not a tag or has no `class`.
+    pub fn classes(&self) -> Vec<String> {
+        self.attr("class").map_or_else(Vec::new, |class| class.split_ascii_whitespace().map(String::from).collect())
+    }
+
+    /// Returns true if this node is a tag that has `class`.
+    /// See [`Tag::has_class`].
+    pub fn has_class(&self, class: &str) -> bool {
+        match &*self.get_payload() {
+            Payload::Tag(tag) => tag.has_class(class),
+            _ => false,
+        }
+    }
+
+    /// Adds `class` if this node is a tag. See [`Tag::add_class`].
+    ///
+    /// # Panics
+    /// * If the payload is borrowed by `payload_mut`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let root = html::parse(r#"<li class="item  active">a</li>"#).unwrap();
+    /// let li = root.first_child().unwrap();
+    /// li.remove_class("active");
+    /// li.add_class("done");
+    ///
+    /// assert!(li.has_class("done"));
+    /// assert_eq!(root.to_html(), r#"<li class="item done">a</li>"#);
+    /// ```
+    pub fn add_class(&self, class: &str) {
+        if let Payload::Tag(tag) = &mut *self.payload_mut() {
+            tag.add_class(class);
+        }
+    }
+
+    /// Removes `class` if this node is a tag. See [`Tag::remove_class`].
+    ///
+    /// # Panics
+    /// * If the payload is borrowed by `payload_mut`.
+    pub fn remove_class(&self, class: &str) -> bool {
+        match &mut *self.payload_mut() {
+            Payload::Tag(tag) => tag.remove_class(class),
+            _ => false,
+        }
+    }
+
+    /// Toggles `class` if this node is a tag. See [`Tag::toggle_class`].
+    /// Returns false if it is not a tag.
+    ///
+    /// # Panics
+    /// * If the payload is borrowed by `payload_mut`.
+    pub fn toggle_class(&self, class: &str) -> bool {
+        match &mut *self.payload_mut() {
+            Payload::Tag(tag) => tag.toggle_class(class),
+            _ => false,
+        }
+    }
+
+    /// Returns the text of this node if it is text, or else the text of its
+    /// children that are text, without the text of other descendants.
+    /// See [`inner_text`](NodeData::inner_text) for all of it.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let root = html::parse("<p>one <b>two</b> three</p>").unwrap();
+    /// let p = root.first_child().unwrap();
+    ///
+    /// assert_eq!(p.text(), "one  three");
+    /// assert_eq!(p.tag_name().as_deref(), Some("p"));
+    /// ```
+    pub fn text(&self) -> String {
+        match &*self.get_payload() {
+            Payload::Text(text) | Payload::RawText(text) => return text.clone(),
+            Payload::Tag(_) => {}
+            _ => return String::new(),
+        }
+
+        self.get_children()
+            .iter()
+            .filter_map(|child| match &*child.get_payload() {
+                Payload::Text(text) | Payload::RawText(text) => Some(text.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns where this node was in the parsed document.
+    /// Returns `None` if the node was not created by the parser.
+    pub fn get_span(&self) -> Option<Span> {
+        self.span.get()
+    }
+
+    pub(crate) fn set_span(&self, span: Span) {
+        self.span.set(Some(span));
+    }
+
+    /// Forgets the spans of this node and its descendants, whose offsets
+    /// are in the source of another document.
+    fn forget_spans(&self) {
+        self.span.set(None);
+        for node in self.descendants() {
+            node.span.set(None);
+        }
+    }
+
+    pub(crate) fn get_original(&self) -> Ref<'_, Option<Box<Original>>> {
+        self.original.borrow()
+    }
+
+    pub(crate) fn set_original(&self, original: Option<Original>) {
+        self.original.replace(original.map(Box::new));
+    }
+
+    pub(crate) fn take_original(&self) -> Option<Original> {
+        self.original.take().map(|original| *original)
+    }
+
+    /// Returns true if the tree of this node was parsed with its character
+    /// references kept as they were written, so that they are not escaped
+    /// again. See [`ParseOptions::decode_entities`](crate::ParseOptions::decode_entities).
+    pub(crate) fn has_raw_entities(&self) -> bool {
+        match self.ancestors().last() {
+            Some(root) => root.raw_entities.get(),
+            None => self.raw_entities.get(),
+        }
+    }
+
+    pub(crate) fn set_raw_entities(&self, raw_entities: bool) {
+        self.raw_entities.set(raw_entities);
+    }
+
+    // the payload may be changed, so it is written anew instead of as it was parsed,
+    // with the source around it kept
+    fn forget_original_markup(&self) {
+        if let Some(original) = self.original.borrow_mut().as_mut() {
+            original.start = None;
+        }
+    }
+
+    /// Returns the children without copying them.
+    ///
+    /// Use [`children`](NodeData::children) for children that are held
+    /// while the tree is changed.
+    ///
+    /// # Panics
+    /// * If the children are changed while they are borrowed, such as by
+    ///   [`Node::add_child_and_update_parent`] or by [`Node::detach`] of a
+    ///   child.
+    pub fn get_children(&self) -> Ref<'_, Vec<NodeDataRef>> {
+        self.children.borrow()
+    }
+
+    /// Returns a copy of the children, which stays the same when the
+    /// children of this node are changed.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use html::dom::Node;
+    ///
+    /// let root = html::parse("<ul><li>a</li><li>b</li></ul>").unwrap();
+    /// let ul = Node::from(root.first_child().unwrap());
+    ///
+    /// for li in ul.children() {
+    ///     ul.remove_child(&li);
+    /// }
+    /// assert_eq!(root.to_html(), "<ul></ul>");
+    /// ```
+    pub fn children(&self) -> Vec<NodeDataRef> {
+        self.children.borrow().clone()
     }
 
     pub fn get_parent(&self) -> Option<NodeDataRef> {
         let parent_weak = self.parent.borrow();
-        match parent_weak.upgrade() {
-            Some(parent_rc_ref) => Some(parent_rc_ref),
-            None => None,
-        }
+        parent_weak.upgrade()
     }
 
     pub fn has_parent(&self) -> bool {
         self.get_parent().is_some()
     }
+
+    pub fn first_child(&self) -> Option<NodeDataRef> {
+        self.children.borrow().first().cloned()
+    }
+
+    pub fn last_child(&self) -> Option<NodeDataRef> {
+        self.children.borrow().last().cloned()
+    }
+
+    /// Returns the node right after this node in the parent's children.
+    pub fn next_sibling(&self) -> Option<NodeDataRef> {
+        let parent = self.get_parent()?;
+        let index = parent.find_child(self)?;
+        let siblings = parent.children.borrow();
+        siblings.get(index + 1).cloned()
+    }
+
+    /// Returns the node right before this node in the parent's children.
+    pub fn prev_sibling(&self) -> Option<NodeDataRef> {
+        let parent = self.get_parent()?;
+        let index = parent.find_child(self)?;
+        let siblings = parent.children.borrow();
+        siblings.get(index.checked_sub(1)?).cloned()
+    }
+
+    /// Returns the number of ancestors, the document root included.
+    /// A node without a parent is at depth 0.
+    pub fn depth(&self) -> usize {
+        self.ancestors().count()
+    }
+
+    /// Returns the index of this node in the parent's children, text and
+    /// comments included, or `None` if it has no parent.
+    pub fn index_in_parent(&self) -> Option<usize> {
+        self.get_parent()?.find_child(self)
+    }
+
+    /// Returns the number of children that are tags.
+    pub fn child_element_count(&self) -> usize {
+        self.children
+            .borrow()
+            .iter()
+            .filter(|child| matches!(&*child.get_payload(), Payload::Tag(_)))
+            .count()
+    }
+
+    /// Returns true if `other` is an ancestor of this node, compared by
+    /// identity. A node is not a descendant of itself.
+    pub fn is_descendant_of(&self, other: &NodeData) -> bool {
+        self.ancestors().any(|ancestor| std::ptr::eq(Rc::as_ptr(&ancestor), other))
+    }
+
+    /// Returns the index of `child` in the children, compared by identity.
+    fn find_child(&self, child: &NodeData) -> Option<usize> {
+        self.children
+            .borrow()
+            .iter()
+            .position(|c| std::ptr::eq(Rc::as_ptr(c), child))
+    }
 }
 
 /// This struct is used to own a [`NodeData`] inside an [`Rc`]. The [`Rc`]
@@ -117,12 +505,57 @@ impl Deref for Node {
     }
 }
 
+impl From<NodeDataRef> for Node {
+    fn from(rc_ref: NodeDataRef) -> Node {
+        Node { rc_ref }
+    }
+}
+
+/// A handle to a node that does not keep it alive, so that a cache or an
+/// index of nodes does not keep the document from being dropped.
+/// Created by [`Node::downgrade`].
+///
+/// # Examples
+/// ```rust
+/// let root = html::parse("<p>x</p>").unwrap();
+/// let weak = html::dom::Node::from(root.first_child().unwrap()).downgrade();
+///
+/// assert_eq!(weak.upgrade().unwrap().to_html(), "<p>x</p>");
+/// drop(root);
+/// assert!(weak.upgrade().is_none());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WeakNode {
+    weak_ref: WeakNodeDataRef,
+}
+
+impl WeakNode {
+    /// Returns the node, or `None` if it has been dropped.
+    pub fn upgrade(&self) -> Option<Node> {
+        self.weak_ref.upgrade().map(Node::from)
+    }
+
+    /// Returns true if both handles are to the same node.
+    pub fn ptr_eq(&self, other: &WeakNode) -> bool {
+        self.weak_ref.ptr_eq(&other.weak_ref)
+    }
+}
+
+impl From<&NodeDataRef> for WeakNode {
+    fn from(rc_ref: &NodeDataRef) -> WeakNode {
+        WeakNode { weak_ref: Rc::downgrade(rc_ref) }
+    }
+}
+
 impl Node {
     pub fn new(payload: Payload) -> Node {
         let new_node = NodeData {
-            payload,
+            payload: RefCell::new(payload),
             parent: RefCell::new(Weak::new()),
             children: RefCell::new(Vec::new()),
+            span: Cell::new(None),
+            original: RefCell::new(None),
+            raw_entities: Cell::new(false),
         };
 
         let rc_ref = Rc::new(new_node);
@@ -133,7 +566,16 @@ impl Node {
         Rc::clone(&self.rc_ref)
     }
 
+    /// Returns a handle to this node that does not keep it alive.
+    pub fn downgrade(&self) -> WeakNode {
+        WeakNode::from(&self.rc_ref)
+    }
+
+    /// Appends `child` to the children.
+    /// If `child` already has a parent, it is removed from there first.
     pub fn add_child_and_update_parent(&self, child: &Node) {
+        child.detach();
+
         {
             let mut children = self.children.borrow_mut();
             children.push(child.get_copy_of_internal_arc());
@@ -145,6 +587,197 @@ impl Node {
         }
     }
 
+    /// Inserts `child` at `index` in the children.
+    /// If `child` already has a parent, it is removed from there first.
+    ///
+    /// # Panics
+    /// * If `index` is greater than the number of children.
+    pub fn insert_child_at(&self, index: usize, child: &Node) {
+        child.detach();
+
+        {
+            let mut children = self.children.borrow_mut();
+            children.insert(index, child.get_copy_of_internal_arc());
+        }
+
+        {
+            let mut childs_parent = child.parent.borrow_mut();
+            *childs_parent = Rc::downgrade(&self.get_copy_of_internal_arc());
+        }
+    }
+
+    /// Removes `child` from the children and returns it.
+    /// Returns `None` if `child` is not a child of this node.
+    pub fn remove_child(&self, child: &NodeData) -> Option<Node> {
+        let index = self.find_child(child)?;
+        let removed = self.children.borrow_mut().remove(index);
+        *removed.parent.borrow_mut() = Weak::new();
+
+        Some(Node::from(removed))
+    }
+
+    /// Replaces `old_child` with `new_child` and returns `old_child`.
+    /// Returns `None` and changes nothing if `old_child` is not a child of this node.
+    pub fn replace_child(&self, old_child: &NodeData, new_child: &Node) -> Option<Node> {
+        self.find_child(old_child)?;
+        new_child.detach();
+
+        // `new_child` may have been a sibling, so find the index again
+        let index = self.find_child(old_child)?;
+        let removed = std::mem::replace(
+            &mut self.children.borrow_mut()[index],
+            new_child.get_copy_of_internal_arc(),
+        );
+        *removed.parent.borrow_mut() = Weak::new();
+        *new_child.parent.borrow_mut() = Rc::downgrade(&self.rc_ref);
+
+        Some(Node::from(removed))
+    }
+
+    /// Removes this node from its parent. Does nothing if it has no parent.
+    pub fn detach(&self) {
+        if let Some(parent) = self.get_parent() {
+            Node::from(parent).remove_child(self);
+        }
+    }
+
+    /// Removes the descendants that match any selector of the
+    /// comma-separated `selectors` and returns how many were removed.
+    /// The descendants of a removed node are removed with it and not counted.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let root = html::parse("<div><script>x</script><p>text<style>s</style></p><noscript><style>t</style></noscript></div>").unwrap();
+    ///
+    /// assert_eq!(root.remove_all("script, style, noscript").unwrap(), 3);
+    /// assert_eq!(root.to_html(), "<div><p>text</p></div>");
+    /// ```
+    ///
+    /// # Errors
+    /// * [`SelectorError`](crate::selector::SelectorError) if a selector cannot be parsed.
+    ///   Nothing is removed.
+    pub fn remove_all(&self, selectors: &str) -> Result<usize, crate::selector::SelectorError> {
+        let selectors = crate::selector::Selector::parse_list(selectors)?;
+
+        let mut removed = Vec::new();
+        let mut stack: Vec<NodeDataRef> = self.children().into_iter().rev().collect();
+        while let Some(node) = stack.pop() {
+            if selectors.iter().any(|selector| selector.matches(&node)) {
+                removed.push(node);
+            } else {
+                stack.extend(node.get_children().iter().rev().cloned());
+            }
+        }
+
+        let count = removed.len();
+        for node in removed {
+            Node::from(node).detach();
+        }
+        Ok(count)
+    }
+
+    /// Parses `html` as the content of this node and replaces the children
+    /// with it. Same as setting `innerHTML` in the DOM.
+    /// Does nothing if this node is not a tag.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let node = html::parse("<ul><li>old</li></ul>").unwrap();
+    /// let ul = html::dom::Node::from(node.first_child().unwrap());
+    ///
+    /// ul.set_inner_html("<li>one</li><li>two</li>").unwrap();
+    /// assert_eq!(node.to_html(), "<ul><li>one</li><li>two</li></ul>");
+    /// ```
+    ///
+    /// # Errors
+    /// * [`ParseError`](crate::ParseError) if `html` cannot be parsed.
+    ///   The children are not changed.
+    pub fn set_inner_html(&self, html: &str) -> Result<(), crate::ParseError> {
+        let payload = self.get_payload();
+        let context = match &*payload {
+            Payload::Tag(tag) => tag.get_name(),
+            _ => return Ok(()),
+        };
+
+        let nodes = crate::parse_fragment(html, context)?;
+        for child in self.children.take() {
+            *child.parent.borrow_mut() = Weak::new();
+        }
+        for node in &nodes {
+            self.add_child_and_update_parent(node);
+        }
+
+        Ok(())
+    }
+
+    /// Moves `child` with its descendants from the tree it is in, which may
+    /// be another document, to the end of the children. If it comes from
+    /// another tree, its spans are forgotten, as they are in another source.
+    ///
+    /// Does nothing and returns false if `child` is this node or one of its
+    /// ancestors, which cannot be moved into it.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use html::dom::Node;
+    ///
+    /// let page = html::parse("<main></main>").unwrap();
+    /// let template = html::parse("<header><h1>Title</h1></header>").unwrap();
+    /// let main = Node::from(page.first_child().unwrap());
+    ///
+    /// assert!(main.adopt(&Node::from(template.first_child().unwrap())));
+    /// assert_eq!(page.to_html(), "<main><header><h1>Title</h1></header></main>");
+    /// assert_eq!(template.to_html(), "");
+    /// ```
+    pub fn adopt(&self, child: &Node) -> bool {
+        if std::ptr::eq::<NodeData>(&**self, &**child) || self.is_descendant_of(child) {
+            return false;
+        }
+
+        let root_of = |node: &NodeData| node.ancestors().last().map_or(node as *const NodeData, |root| Rc::as_ptr(&root));
+        let same_tree = root_of(self) == root_of(child);
+
+        self.add_child_and_update_parent(child);
+        if !same_tree {
+            child.forget_spans();
+        }
+        true
+    }
+
+    /// Returns a copy of this node and its descendants that shares nothing
+    /// with them, unlike [`clone`](Clone::clone), which shares the node.
+    /// The copy has no parent, and has the spans and markup of the nodes.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let root = html::parse("<p>a</p>").unwrap();
+    /// let copy = root.deep_clone();
+    ///
+    /// copy.first_child().unwrap().add_class("x");
+    /// assert_eq!(copy.to_html(), r#"<p class="x">a</p>"#);
+    /// assert_eq!(root.to_html(), "<p>a</p>");
+    /// ```
+    pub fn deep_clone(&self) -> Node {
+        let copy = |node: &NodeData| {
+            let copy = Node::new(node.get_payload().clone());
+            copy.span.set(node.span.get());
+            *copy.original.borrow_mut() = node.original.borrow().clone();
+            copy
+        };
+
+        let root = copy(self);
+        root.raw_entities.set(self.has_raw_entities());
+        // the children in reverse, so that they are added in order
+        let mut stack: Vec<(NodeDataRef, Node)> =
+            self.get_children().iter().rev().map(|child| (Rc::clone(child), root.clone())).collect();
+        while let Some((node, parent)) = stack.pop() {
+            let node_copy = copy(&node);
+            parent.add_child_and_update_parent(&node_copy);
+            stack.extend(node.get_children().iter().rev().map(|child| (Rc::clone(child), node_copy.clone())));
+        }
+        root
+    }
+
     pub fn create_and_add_child(&self, payload: Payload) -> NodeDataRef {
         let new_child = Node::new(payload);
         self.add_child_and_update_parent(&new_child);
@@ -172,6 +805,226 @@ mod tests {
         assert!(child.has_parent());
     }
 
+    #[test]
+    fn mutation_test() {
+        let parent = Node::new(Payload::Tag(Tag::new("ul")));
+        let one = Node::new(Payload::Text(String::from("one")));
+        let two = Node::new(Payload::Text(String::from("two")));
+        let three = Node::new(Payload::Text(String::from("three")));
+
+        parent.add_child_and_update_parent(&one);
+        parent.add_child_and_update_parent(&three);
+        parent.insert_child_at(1, &two);
+        assert_eq!(parent.get_children().len(), 3);
+        assert_eq!(parent.get_children()[1], two.get_copy_of_internal_arc());
+
+        let removed = parent.remove_child(&one).unwrap();
+        assert!(!removed.has_parent());
+        assert!(parent.remove_child(&one).is_none());
+
+        let replaced = parent.replace_child(&three, &one).unwrap();
+        assert!(!replaced.has_parent());
+        assert!(one.has_parent());
+        assert_eq!(parent.get_children()[1], one.get_copy_of_internal_arc());
+
+        two.detach();
+        assert!(!two.has_parent());
+        assert_eq!(parent.get_children().len(), 1);
+    }
+
+    #[test]
+    fn reparent_test() {
+        let a = Node::new(Payload::Tag(Tag::new("div")));
+        let b = Node::new(Payload::Tag(Tag::new("div")));
+        let child = Node::new(Payload::Tag(Tag::new("p")));
+
+        a.add_child_and_update_parent(&child);
+        b.add_child_and_update_parent(&child);
+
+        assert!(a.get_children().is_empty());
+        assert_eq!(b.get_children().len(), 1);
+        assert_eq!(child.get_parent().unwrap(), b.get_copy_of_internal_arc());
+    }
+
+    #[test]
+    fn sibling_test() {
+        let parent = Node::new(Payload::Tag(Tag::new("tr")));
+        let one = parent.create_and_add_child(Payload::Tag(Tag::new("td")));
+        let two = parent.create_and_add_child(Payload::Tag(Tag::new("td")));
+
+        assert_eq!(parent.first_child().unwrap(), one);
+        assert_eq!(parent.last_child().unwrap(), two);
+        assert!(Rc::ptr_eq(&one.next_sibling().unwrap(), &two));
+        assert!(Rc::ptr_eq(&two.prev_sibling().unwrap(), &one));
+        assert!(one.prev_sibling().is_none());
+        assert!(two.next_sibling().is_none());
+        assert!(parent.next_sibling().is_none());
+    }
+
+    #[test]
+    fn structure_test() {
+        let root = crate::parse("<ul>x<li>a</li><!-- c --><li><b>b</b></li></ul>").unwrap();
+        let ul = root.first_child().unwrap();
+        let b = ul.get_children()[3].first_child().unwrap();
+
+        assert_eq!(root.depth(), 0);
+        assert_eq!(b.depth(), 3);
+        assert_eq!(root.index_in_parent(), None);
+        assert_eq!(ul.get_children()[3].index_in_parent(), Some(3));
+        assert_eq!(ul.child_element_count(), 2);
+        assert_eq!(b.child_element_count(), 0);
+        assert!(b.is_descendant_of(&ul));
+        assert!(b.is_descendant_of(&root));
+        assert!(!b.is_descendant_of(&b));
+        assert!(!ul.is_descendant_of(&b));
+    }
+
+    #[test]
+    fn deep_clone_test() {
+        let options = crate::ParseOptions { lossless: true, ..crate::ParseOptions::default() };
+        let html = "<ul>\n  <li class=a>one</li>\n  <li>two\n</ul>";
+        let root = crate::parse_with_options(html, &options).unwrap();
+        let li = Node::from(root.first_child().unwrap().first_child().unwrap());
+
+        let copy = root.deep_clone();
+        assert_eq!(copy, root);
+        assert_eq!(copy.to_html(), html);
+        let li_copy = Node::from(copy.first_child().unwrap().first_child().unwrap());
+        assert_eq!(li_copy.get_span(), li.get_span());
+        assert!(Rc::ptr_eq(&li_copy.get_parent().unwrap(), &copy.first_child().unwrap()));
+
+        li_copy.detach();
+        assert_eq!(root.to_html(), html);
+        assert!(li.has_parent());
+        // a copy of a node below the root has no parent
+        assert!(!li.deep_clone().has_parent());
+        assert_eq!(li.deep_clone().to_html(), "<li class=a>one</li>");
+    }
+
+    #[test]
+    fn adopt_test() {
+        let a = crate::parse("<div><p>x</p><b>y</b></div>").unwrap();
+        let b = crate::parse("<span>z</span>").unwrap();
+        let div = Node::from(a.first_child().unwrap());
+        let p = Node::from(div.first_child().unwrap());
+        let span = Node::from(b.first_child().unwrap());
+
+        // a node of the same tree keeps its span
+        assert!(div.adopt(&p));
+        assert_eq!(a.to_html(), "<div><b>y</b><p>x</p></div>");
+        assert!(p.get_span().is_some());
+
+        assert!(p.adopt(&span));
+        assert!(b.get_children().is_empty());
+        assert!(Rc::ptr_eq(&span.get_parent().unwrap(), &p.get_copy_of_internal_arc()));
+        assert!(span.get_span().is_none());
+        assert!(span.first_child().unwrap().get_span().is_none());
+
+        assert!(!p.adopt(&p));
+        assert!(!p.adopt(&div));
+        assert!(!span.adopt(&a));
+        assert_eq!(a.to_html(), "<div><b>y</b><p>x<span>z</span></p></div>");
+    }
+
+    #[test]
+    fn weak_node_test() {
+        let root = crate::parse("<ul><li>a</li></ul>").unwrap();
+        let ul = root.first_child().unwrap();
+        let li = WeakNode::from(&ul.first_child().unwrap());
+        assert!(li.ptr_eq(&Node::from(ul.first_child().unwrap()).downgrade()));
+        assert!(!li.ptr_eq(&WeakNode::from(&ul)));
+
+        // the handle does not keep a removed node alive
+        let li_node = li.upgrade().unwrap();
+        Node::from(ul).remove_child(&li_node);
+        assert!(li.upgrade().is_some());
+        drop(li_node);
+        assert!(li.upgrade().is_none());
+        assert!(WeakNode::default().upgrade().is_none());
+    }
+
+    #[test]
+    fn children_test() {
+        let root = crate::parse("<p>a<b>b</b>c</p>").unwrap();
+        let p = Node::from(root.first_child().unwrap());
+        let children = p.children();
+        assert_eq!(children, *p.get_children());
+
+        p.remove_child(&children[0]);
+        p.create_and_add_child(Payload::Text(String::from("d")));
+        assert_eq!(children.len(), 3);
+        assert_eq!(p.get_children().len(), 3);
+        assert!(Rc::ptr_eq(&p.get_children()[0], &children[1]));
+        assert!(!children[0].has_parent());
+
+        // the children cannot be changed while they are borrowed
+        let borrowed = p.get_children();
+        let remove = std::panic::AssertUnwindSafe(|| p.remove_child(&borrowed[0]));
+        assert!(std::panic::catch_unwind(remove).is_err());
+        drop(borrowed);
+        assert_eq!(p.get_children().len(), 3);
+    }
+
+    #[test]
+    fn set_inner_html_test() {
+        let div = Node::new(Payload::Tag(Tag::new("div")));
+        let old = div.create_and_add_child(Payload::Text(String::from("old")));
+
+        div.set_inner_html("<p>new</p>text").unwrap();
+        assert!(!old.has_parent());
+        assert_eq!(div.to_html(), "<div><p>new</p>text</div>");
+        assert!(div.get_children().iter().all(|child| child.has_parent()));
+
+        assert!(div.set_inner_html("<p").is_err());
+        assert_eq!(div.get_children().len(), 2);
+    }
+
+    #[test]
+    fn remove_all_test() {
+        let root = crate::parse(r#"<div><p class="ad">a<b class="ad">b</b></p><a data-x="1,2">x</a><i>i</i></div>"#).unwrap();
+        let div = root.first_child().unwrap();
+
+        assert_eq!(root.remove_all(r#"i, [data-x="1,2"] ,.ad"#).unwrap(), 3);
+        assert_eq!(root.to_html(), "<div></div>");
+        assert!(div.get_children().is_empty());
+        assert_eq!(root.remove_all("p").unwrap(), 0);
+
+        // a root is not removed, and nothing is if a selector is invalid
+        let root = crate::parse("<p>x</p>").unwrap();
+        let p = Node::from(root.first_child().unwrap());
+        assert_eq!(p.remove_all("p").unwrap(), 0);
+        assert_eq!(root.remove_all("p, a[").unwrap_err(), crate::selector::SelectorError::UnexpectedEnd(5));
+        assert_eq!(root.remove_all("p,").unwrap_err(), crate::selector::SelectorError::UnexpectedEnd(2));
+        assert!(p.has_parent());
+    }
+
+    #[test]
+    fn payload_mut_test() {
+        let root = crate::parse(r#"<p><a href="/a" class="x">a</a></p>"#).unwrap();
+        let a = root.first_child().unwrap().first_child().unwrap();
+
+        if let Payload::Tag(tag) = &mut *a.payload_mut() {
+            tag.set_name("span");
+            assert_eq!(tag.remove_attribute("href"), Some(AttrValue::from("/a")));
+            assert!(tag.remove_attribute("href").is_none());
+        }
+        assert_eq!(root.to_html(), r#"<p><span class="x">a</span></p>"#);
+
+        // the payload cannot be changed while it is borrowed
+        let payload = a.get_payload();
+        let set = std::panic::AssertUnwindSafe(|| a.set_payload(Payload::Comment(String::new())));
+        assert!(std::panic::catch_unwind(set).is_err());
+        drop(payload);
+
+        if let Payload::Tag(tag) = &mut *a.payload_mut() {
+            tag.remove_attribute("class");
+            assert!(tag.get_attributes().is_none());
+        }
+        let old = a.first_child().unwrap().set_payload(Payload::Text(String::from("b")));
+        assert_eq!(old, Payload::Text(String::from("a")));
+        assert_eq!(root.to_html(), "<p><span>b</span></p>");
+    }
+
     #[test]
     fn copy_test() {
         let node = Node::new(
@@ -182,4 +1035,112 @@ mod tests {
         let copy_node = node.get_copy_of_internal_arc();
         assert_eq!(node.rc_ref, copy_node)
     }
+
+    #[test]
+    fn accessor_test() {
+        let root = crate::parse(r#"<a href="/x" class=" nav  active ">go <i>now</i>!</a><input disabled><!-- c -->"#).unwrap();
+        let a = root.first_child().unwrap();
+        assert_eq!(a.tag_name().as_deref(), Some("a"));
+        assert_eq!(a.attr("href").as_deref(), Some("/x"));
+        assert_eq!(a.attr("title"), None);
+        assert_eq!(a.classes(), ["nav", "active"]);
+        assert_eq!(a.text(), "go !");
+        assert_eq!(a.first_child().unwrap().text(), "go ");
+
+        let input = a.next_sibling().unwrap();
+        assert_eq!(input.attr("disabled").as_deref(), Some(""));
+        assert!(input.classes().is_empty());
+
+        let comment = root.last_child().unwrap();
+        assert_eq!(comment.tag_name(), None);
+        assert_eq!(comment.text(), "");
+    }
+
+    #[test]
+    fn class_test() {
+        let root = crate::parse(r#"<p class=" a b  a ">x</p>text"#).unwrap();
+        let p = root.first_child().unwrap();
+        assert!(p.has_class("b"));
+        assert!(!p.has_class("c"));
+
+        p.add_class("a");
+        assert_eq!(p.attr("class").as_deref(), Some(" a b  a "));
+        assert!(p.remove_class("a"));
+        assert!(!p.remove_class("a"));
+        assert_eq!(p.attr("class").as_deref(), Some("b"));
+        assert!(p.toggle_class("c"));
+        assert!(!p.toggle_class("b"));
+        assert_eq!(p.attr("class").as_deref(), Some("c"));
+        assert!(!p.toggle_class("c"));
+        assert_eq!(root.to_html(), "<p>x</p>text");
+
+        let text = root.last_child().unwrap();
+        text.add_class("a");
+        assert!(!text.toggle_class("a"));
+        assert!(!text.has_class("a"));
+    }
+
+    #[test]
+    fn prefixed_name_test() {
+        let html = r##"<svg:use xlink:href="#a" xml:lang="en"></svg:use><b v-bind:class="c" @click="go()" :prop=x v-bind:id=i>y</b>"##;
+        let root = crate::parse(html).unwrap();
+        assert_eq!(root.to_html(), html);
+
+        let tag = |node: &NodeData| match &*node.get_payload() {
+            Payload::Tag(tag) => tag.clone(),
+            _ => panic!("expected a tag"),
+        };
+        let svg = tag(&root.first_child().unwrap());
+        assert_eq!((svg.get_prefix(), svg.get_local_name()), (Some("svg"), "use"));
+        assert_eq!(svg.get_prefixed_attribute("xlink", "href"), Some(&AttrValue::from("#a")));
+        assert_eq!(svg.get_prefixed_attribute("xml", "href"), None);
+
+        let b = tag(&root.last_child().unwrap());
+        assert_eq!((b.get_prefix(), b.get_local_name()), (None, "b"));
+        assert_eq!(b.get_attribute_value("@click").as_deref(), Some("go()"));
+        assert_eq!(b.get_attribute_value(":prop").as_deref(), Some("x"));
+        let bound: Vec<(&str, &str)> = b.get_attributes_with_prefix("v-bind").map(|(name, value)| (name, value.as_str())).collect();
+        assert_eq!(bound, [("class", "c"), ("id", "i")]);
+    }
+
+    #[test]
+    fn custom_element_test() {
+        let html = concat!(
+            r#"<p>a<my-widget x="1"><li-item>b</li-item><li-item>c</li-item></my-widget>d</p>"#,
+            r#"<ul><li>e<todo-item>f</todo-item><li>g</ul><x-br>h</x-br>"#,
+            r#"<button is=" fancy-button">i</button><my-img/>"#,
+        );
+        let root = crate::parse(html).unwrap();
+        assert_eq!(
+            root.to_html(),
+            concat!(
+                r#"<p>a<my-widget x="1"><li-item>b</li-item><li-item>c</li-item></my-widget>d</p>"#,
+                r#"<ul><li>e<todo-item>f</todo-item></li><li>g</li></ul><x-br>h</x-br>"#,
+                r#"<button is=" fancy-button">i</button><my-img />"#,
+            )
+        );
+
+        let names: Vec<Option<String>> = root
+            .descendants()
+            .filter_map(|node| match &*node.get_payload() {
+                Payload::Tag(tag) => Some(tag.custom_element_name()),
+                _ => None,
+            })
+            .collect();
+        let names: Vec<&str> = names.iter().map(|name| name.as_deref().unwrap_or("-")).collect();
+        assert_eq!(
+            names,
+            ["-", "my-widget", "li-item", "li-item", "-", "-", "todo-item", "-", "x-br", "fancy-button", "my-img"]
+        );
+
+        for name in ["My-Widget", "a-", "x-ü", "a.b-c_1"] {
+            assert!(Tag::new(name).is_custom_element(), "{}", name);
+        }
+        for name in ["div", "-a", "1-a", "font-face", "a-b!", "svg:a-b"] {
+            assert!(!Tag::new(name).is_custom_element(), "{}", name);
+        }
+        let mut button = Tag::new("button");
+        button.set_attribute("is", "Button");
+        assert_eq!(button.custom_element_name(), None);
+    }
 }
@@ -0,0 +1,140 @@
+use std::rc::{Rc, Weak};
+
+use super::{Node, NodeData, Payload, Span};
+
+/// Elements whose whitespace is content, which trimming leaves as it is.
+const PREFORMATTED_ELEMENTS: [&str; 2] = ["pre", "textarea"];
+
+/// Options to control how a tree is normalized.
+/// See [`Node::normalize_with_options`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    /// Trims the whitespace around each text after merging, except in
+    /// `<pre>` and `<textarea>`. Text that is only whitespace is removed.
+    /// Default: false
+    pub trim: bool,
+}
+
+impl Node {
+    /// Merges the adjacent text nodes of this node and its descendants and
+    /// removes the empty ones. Same as `normalize()` in the DOM.
+    ///
+    /// The span of a merged text node is extended over the parsed nodes
+    /// merged into it.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use html::dom::{Node, Payload};
+    ///
+    /// let root = html::parse("<p>a</p>").unwrap();
+    /// let p = Node::from(root.first_child().unwrap());
+    /// p.create_and_add_child(Payload::Text(String::new()));
+    /// p.create_and_add_child(Payload::Text(String::from("b")));
+    /// assert_eq!(p.get_children().len(), 3);
+    ///
+    /// p.normalize();
+    /// assert_eq!(p.get_children().len(), 1);
+    /// assert_eq!(p.text(), "ab");
+    /// ```
+    pub fn normalize(&self) {
+        self.normalize_with_options(&NormalizeOptions::default())
+    }
+
+    /// Same as [`normalize`](Node::normalize), but with options.
+    pub fn normalize_with_options(&self, options: &NormalizeOptions) {
+        let preformatted = std::iter::once(self.get_copy_of_internal_arc())
+            .chain(self.ancestors())
+            .any(|node| is_preformatted(&node));
+
+        let mut stack = vec![(self.get_copy_of_internal_arc(), preformatted)];
+        while let Some((node, preformatted)) = stack.pop() {
+            normalize_children(&node, options.trim && !preformatted);
+            for child in node.get_children().iter() {
+                if matches!(&*child.get_payload(), Payload::Tag(_)) {
+                    stack.push((Rc::clone(child), preformatted || is_preformatted(child)));
+                }
+            }
+        }
+    }
+}
+
+/// Merges the adjacent text children of `node` and removes the empty ones.
+fn normalize_children(node: &NodeData, trim: bool) {
+    let children = node.children.take();
+    let mut kept: Vec<Rc<NodeData>> = Vec::with_capacity(children.len());
+    for child in children {
+        if let (Payload::Text(text), Some(last)) = (&*child.get_payload(), kept.last()) {
+            if matches!(&*last.get_payload(), Payload::Text(_)) {
+                if let Payload::Text(last_text) = &mut *last.payload_mut() {
+                    last_text.push_str(text);
+                }
+                if let (Some(span), Some(end)) = (last.get_span(), child.get_span()) {
+                    last.set_span(Span { end: end.end, ..span });
+                }
+                *child.parent.borrow_mut() = Weak::new();
+                continue;
+            }
+        }
+        kept.push(child);
+    }
+
+    kept.retain(|child| {
+        let trimmed = match &*child.get_payload() {
+            Payload::Text(text) if trim && text.trim().len() != text.len() => Some(text.trim().to_string()),
+            _ => None,
+        };
+        if let Some(trimmed) = trimmed {
+            child.set_payload(Payload::Text(trimmed));
+        }
+        let is_empty = matches!(&*child.get_payload(), Payload::Text(text) if text.is_empty());
+        if is_empty {
+            *child.parent.borrow_mut() = Weak::new();
+        }
+        !is_empty
+    });
+    *node.children.borrow_mut() = kept;
+}
+
+fn is_preformatted(node: &NodeData) -> bool {
+    match &*node.get_payload() {
+        Payload::Tag(tag) => PREFORMATTED_ELEMENTS.iter().any(|name| tag.get_name().eq_ignore_ascii_case(name)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::Tag;
+
+    #[test]
+    fn normalize_test() {
+        let root = crate::parse("<div><p>a</p><pre> x </pre></div>").unwrap();
+        let div = Node::from(root.first_child().unwrap());
+        let p = Node::from(div.first_child().unwrap());
+        let pre = Node::from(div.last_child().unwrap());
+        for text in ["", " b ", "", "c "] {
+            p.create_and_add_child(Payload::Text(String::from(text)));
+        }
+        p.create_and_add_child(Payload::Tag(Tag::new("br")));
+        p.create_and_add_child(Payload::Text(String::from("  ")));
+        pre.create_and_add_child(Payload::Text(String::from("y ")));
+        let removed = p.get_children()[1].clone();
+
+        div.normalize();
+        assert_eq!(p.get_children().len(), 3);
+        assert_eq!(p.first_child().unwrap().to_html(), "a b c ");
+        assert!(!removed.has_parent());
+        assert_eq!(root.to_html(), "<div><p>a b c <br>  </p><pre> x y </pre></div>");
+        // the nodes that were not parsed have no span to extend it with
+        assert_eq!(p.first_child().unwrap().get_span().unwrap().end, 9);
+
+        div.normalize_with_options(&NormalizeOptions { trim: true });
+        assert_eq!(root.to_html(), "<div><p>a b c<br></p><pre> x y </pre></div>");
+        assert!(p.get_children().iter().all(|child| child.has_parent()));
+
+        // in a `<pre>`, the whitespace is kept even if it is normalized on its own
+        pre.normalize_with_options(&NormalizeOptions { trim: true });
+        assert_eq!(pre.inner_html(), " x y ");
+    }
+}
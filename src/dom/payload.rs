@@ -1,28 +1,119 @@
-use std::collections::HashMap;
+use super::{Atom, AttrValue, Attributes};
 
 pub type Text = String;
 
+/// Elements that never have content or an end tag.
+/// <https://html.spec.whatwg.org/multipage/syntax.html#void-elements>
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img",
+    "input", "link", "meta", "param", "source", "track", "wbr",
+];
+
+/// Elements whose content is text that is not parsed at all.
+/// <https://html.spec.whatwg.org/multipage/syntax.html#raw-text-elements>
+const RAW_TEXT_ELEMENTS: [&str; 2] = ["script", "style"];
+
+/// Elements whose content is text in which only character references are decoded.
+/// <https://html.spec.whatwg.org/multipage/syntax.html#escapable-raw-text-elements>
+const ESCAPABLE_RAW_TEXT_ELEMENTS: [&str; 2] = ["textarea", "title"];
+
+/// Names with a hyphen that are not valid custom element names.
+/// <https://html.spec.whatwg.org/multipage/custom-elements.html#valid-custom-element-name>
+const RESERVED_CUSTOM_ELEMENT_NAMES: [&str; 8] = [
+    "annotation-xml", "color-profile", "font-face", "font-face-src",
+    "font-face-uri", "font-face-format", "font-face-name", "missing-glyph",
+];
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Tag {
-    name: String,
-    attributes: Option<HashMap<String, String>>,
+    name: Atom,
+    attributes: Option<Attributes>,
     // A flag that represents a tag whether is self-closing. <tag />
     self_closing: bool,
     // A flag that represents a tag whether is the closed one. </ tag>
     terminator: bool,
 }
 
+/// The document type declaration.
+/// <!DOCTYPE <name> [PUBLIC "<public_id>"] ["<system_id>"]>
+#[derive(Debug, PartialEq, Clone)]
+pub struct Doctype {
+    name: String,
+    public_id: Option<String>,
+    system_id: Option<String>,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Payload {
     Tag(Tag),
     Text(Text),
+    /// The content of `<script>` or `<style>`. It is not parsed as HTML,
+    /// so it is kept and written back as it is, and its span is where the
+    /// content is in the document.
+    RawText(Text),
     Comment(Text),
+    /// A conditional comment for legacy Internet Explorer, with its
+    /// condition such as `if IE`. Its content is parsed into its children
+    /// if [`ParseOptions::conditional_comments`](crate::ParseOptions::conditional_comments)
+    /// is true. `<!--[if IE]><p>IE only</p><![endif]-->`
+    ConditionalComment(Text),
+    Doctype(Doctype),
+}
+
+/// Splits a name at its first ':' into its prefix and its local name.
+/// A name with nothing before or after the ':' has no prefix, so that the
+/// shorthands of templates are names of their own.
+///
+/// # Examples
+/// ```rust
+/// use html::dom::split_qualified_name;
+///
+/// assert_eq!(split_qualified_name("xlink:href"), (Some("xlink"), "href"));
+/// assert_eq!(split_qualified_name("v-on:click.prevent"), (Some("v-on"), "click.prevent"));
+/// assert_eq!(split_qualified_name(":prop"), (None, ":prop"));
+/// assert_eq!(split_qualified_name("@click"), (None, "@click"));
+/// ```
+pub fn split_qualified_name(name: &str) -> (Option<&str>, &str) {
+    match name.split_once(':') {
+        Some((prefix, local_name)) if !prefix.is_empty() && !local_name.is_empty() => (Some(prefix), local_name),
+        _ => (None, name),
+    }
+}
+
+impl Doctype {
+    pub fn new(name: &str) -> Doctype {
+        Doctype {
+            name: String::from(name),
+            public_id: None,
+            system_id: None,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_public_id(&mut self, public_id: &str) {
+        self.public_id = Some(String::from(public_id));
+    }
+
+    pub fn get_public_id(&self) -> Option<&str> {
+        self.public_id.as_deref()
+    }
+
+    pub fn set_system_id(&mut self, system_id: &str) {
+        self.system_id = Some(String::from(system_id));
+    }
+
+    pub fn get_system_id(&self) -> Option<&str> {
+        self.system_id.as_deref()
+    }
 }
 
 impl Tag {
     pub fn new(name: &str) -> Tag {
         Tag {
-            name: String::from(name),
+            name: Atom::from(name),
             attributes: None,
             self_closing: false,
             terminator: false,
@@ -33,35 +124,142 @@ impl Tag {
         &self.name
     }
 
-    pub fn set_attributes(&mut self, attributes: HashMap<String, String>) {
+    /// Returns the interned name, which compares with another in O(1).
+    pub fn get_name_atom(&self) -> &Atom {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.name = Atom::from(name);
+    }
+
+    /// Returns the prefix of the name. `svg` of `svg:rect`
+    /// See [`split_qualified_name`].
+    pub fn get_prefix(&self) -> Option<&str> {
+        split_qualified_name(&self.name).0
+    }
+
+    /// Returns the name without its prefix. `rect` of `svg:rect`
+    pub fn get_local_name(&self) -> &str {
+        split_qualified_name(&self.name).1
+    }
+
+    pub fn set_attributes(&mut self, attributes: Attributes) {
         self.attributes = Some(attributes);
     }
 
-    pub fn get_attributes(&self) -> Option<&HashMap<String, String>> {
+    pub fn get_attributes(&self) -> Option<&Attributes> {
         self.attributes.as_ref()
     }
 
     pub fn set_attribute(&mut self, attribute: &str, value: &str) {
         match self.attributes.as_mut() {
             Some(attributes) => {
-                attributes.insert(String::from(attribute), String::from(value));
+                attributes.insert(attribute, value);
             }
             None => {
-                let mut attributes = HashMap::new();
-                attributes.insert(String::from(attribute), String::from(value));
+                let mut attributes = Attributes::new();
+                attributes.insert(attribute, value);
                 self.attributes = Some(attributes);
             }
         }
     }
 
+    /// Returns the value of the attribute, or "" if it has no value.
     pub fn get_attribute_value(&self, attribute_name: &str) -> Option<String> {
-        if let Some(attributes) = &self.attributes {
-            if let Some(v) = attributes.get(attribute_name) {
-                return Some(v.to_string());
+        self.get_attribute(attribute_name).map(|v| v.to_string())
+    }
+
+    /// Returns the value of the attribute with how it was written, so that
+    /// `<input disabled>` and `<input disabled="">` can be told apart.
+    pub fn get_attribute(&self, attribute_name: &str) -> Option<&AttrValue> {
+        self.attributes.as_ref()?.get(attribute_name)
+    }
+
+    pub fn has_attribute(&self, attribute_name: &str) -> bool {
+        self.get_attribute(attribute_name).is_some()
+    }
+
+    /// Returns the value of the attribute with `prefix` and `local_name`.
+    /// `("xlink", "href")` for `xlink:href`
+    pub fn get_prefixed_attribute(&self, prefix: &str, local_name: &str) -> Option<&AttrValue> {
+        let attributes = self.attributes.as_ref()?;
+        let (_, value) = attributes.iter().find(|(name, _)| split_qualified_name(name) == (Some(prefix), local_name))?;
+        Some(value)
+    }
+
+    /// Returns the local names and values of the attributes with `prefix`,
+    /// in order. `click` and `class` of `v-bind:click` and `v-bind:class`
+    pub fn get_attributes_with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = (&'a str, &'a AttrValue)> {
+        self.attributes.iter().flat_map(Attributes::iter).filter_map(move |(name, value)| {
+            match split_qualified_name(name) {
+                (Some(p), local_name) if p == prefix => Some((local_name, value)),
+                _ => None,
             }
+        })
+    }
+
+    /// Removes the attribute and returns its value.
+    pub fn remove_attribute(&mut self, attribute_name: &str) -> Option<AttrValue> {
+        let attributes = self.attributes.as_mut()?;
+        let value = attributes.remove(attribute_name);
+        if attributes.is_empty() {
+            // the same as a tag parsed without attributes
+            self.attributes = None;
+        }
+
+        value
+    }
+
+    /// Returns true if `class` is one of the space-separated words of the
+    /// `class` attribute.
+    pub fn has_class(&self, class: &str) -> bool {
+        self.get_attribute("class").is_some_and(|value| value.as_str().split_ascii_whitespace().any(|c| c == class))
+    }
+
+    /// Adds `class` to the end of the `class` attribute if it is not there yet.
+    pub fn add_class(&mut self, class: &str) {
+        if self.has_class(class) {
+            return;
+        }
+
+        let mut classes = self.class_words();
+        classes.push(class.to_string());
+        self.set_attribute("class", &classes.join(" "));
+    }
+
+    /// Removes every `class` from the `class` attribute, and the attribute
+    /// itself if no class is left. Returns true if there was one.
+    pub fn remove_class(&mut self, class: &str) -> bool {
+        if !self.has_class(class) {
+            return false;
+        }
+
+        let classes: Vec<String> = self.class_words().into_iter().filter(|c| c != class).collect();
+        if classes.is_empty() {
+            self.remove_attribute("class");
+        } else {
+            self.set_attribute("class", &classes.join(" "));
         }
+        true
+    }
+
+    /// Removes `class` if the tag has it, or else adds it.
+    /// Returns true if the tag has it afterwards.
+    pub fn toggle_class(&mut self, class: &str) -> bool {
+        if self.remove_class(class) {
+            return false;
+        }
+
+        self.add_class(class);
+        true
+    }
 
-        None
+    fn class_words(&self) -> Vec<String> {
+        match self.get_attribute("class") {
+            Some(value) => value.as_str().split_ascii_whitespace().map(String::from).collect(),
+            None => Vec::new(),
+        }
     }
 
     pub fn set_terminator(&mut self, b: bool) {
@@ -78,4 +276,53 @@ impl Tag {
     pub fn is_self_closing(&self) -> bool {
         self.self_closing
     }
+
+    /// Returns true if the tag is a void element such as `<br>` or `<img>`,
+    /// which is always treated as self-closing.
+    pub fn is_void_element(&self) -> bool {
+        VOID_ELEMENTS.iter().any(|name| name.eq_ignore_ascii_case(&self.name))
+    }
+
+    /// Returns true if the content of the tag is raw text, such as `<script>`.
+    pub fn is_raw_text_element(&self) -> bool {
+        RAW_TEXT_ELEMENTS.iter().any(|name| name.eq_ignore_ascii_case(&self.name))
+    }
+
+    /// Returns true if the content of the tag is text that may contain
+    /// character references, such as `<textarea>`.
+    pub fn is_escapable_raw_text_element(&self) -> bool {
+        ESCAPABLE_RAW_TEXT_ELEMENTS.iter().any(|name| name.eq_ignore_ascii_case(&self.name))
+    }
+
+    /// Returns true if the tag is an autonomous custom element, whose name
+    /// has a hyphen, such as `<my-widget>`. It is parsed as any other
+    /// unknown element: it has content and an end tag, and closes nothing.
+    pub fn is_custom_element(&self) -> bool {
+        is_custom_element_name(&self.name)
+    }
+
+    /// Returns the name of the custom element that the tag is: its own name
+    /// for `<my-widget>`, or the `is` attribute of a built-in element that
+    /// a custom element extends, `fancy-button` for `<button is="fancy-button">`.
+    /// Returns `None` for other tags and for an `is` that is not a custom
+    /// element name.
+    pub fn custom_element_name(&self) -> Option<String> {
+        if self.is_custom_element() {
+            return Some(self.name.to_ascii_lowercase());
+        }
+        self.get_attribute_value("is")
+            .map(|name| name.trim().to_string())
+            .filter(|name| is_custom_element_name(name))
+    }
+}
+
+/// Returns true if `name` is a valid custom element name, without case:
+/// a letter, then letters, digits, `-`, `.`, `_` or non-ASCII characters
+/// with at least one `-`, and not a reserved name such as `font-face`.
+fn is_custom_element_name(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.contains('-')
+        && name.chars().all(|c| !c.is_ascii() || c.is_ascii_lowercase() || c.is_ascii_digit() || "-._".contains(c))
+        && !RESERVED_CUSTOM_ELEMENT_NAMES.contains(&name.as_str())
 }
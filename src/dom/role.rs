@@ -0,0 +1,155 @@
+use super::{NodeData, Payload, Tag};
+
+/// Elements in which `<header>` and `<footer>` are not the banner and
+/// content info of the page.
+const SECTIONING_ELEMENTS: [&str; 5] = ["article", "aside", "main", "nav", "section"];
+
+/// Elements whose implicit role does not depend on their attributes.
+const IMPLICIT_ROLES: [(&str, &str); 30] = [
+    ("article", "article"),
+    ("aside", "complementary"),
+    ("button", "button"),
+    ("details", "group"),
+    ("dialog", "dialog"),
+    ("fieldset", "group"),
+    ("figure", "figure"),
+    ("form", "form"),
+    ("h1", "heading"),
+    ("h2", "heading"),
+    ("h3", "heading"),
+    ("h4", "heading"),
+    ("h5", "heading"),
+    ("h6", "heading"),
+    ("hr", "separator"),
+    ("li", "listitem"),
+    ("main", "main"),
+    ("menu", "list"),
+    ("meter", "meter"),
+    ("nav", "navigation"),
+    ("ol", "list"),
+    ("option", "option"),
+    ("output", "status"),
+    ("progress", "progressbar"),
+    ("table", "table"),
+    ("tbody", "rowgroup"),
+    ("td", "cell"),
+    ("textarea", "textbox"),
+    ("tr", "row"),
+    ("ul", "list"),
+];
+
+impl NodeData {
+    /// Returns the ARIA role of this tag: the first word of its `role`
+    /// attribute, or else the implicit role of the element, such as `link`
+    /// for `<a href>` and `checkbox` for `<input type="checkbox">`.
+    /// <https://www.w3.org/TR/html-aria/>
+    ///
+    /// Returns `None` for other nodes and for the elements that have no
+    /// role, such as `<div>`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let root = html::parse(r#"<a href="/">a</a><div role="button">b</div><div>c</div>"#).unwrap();
+    /// let roles: Vec<Option<String>> = root.get_children().iter().map(|node| node.role()).collect();
+    ///
+    /// assert_eq!(roles, [Some("link".to_string()), Some("button".to_string()), None]);
+    /// ```
+    pub fn role(&self) -> Option<String> {
+        let payload = self.get_payload();
+        let tag = match &*payload {
+            Payload::Tag(tag) if !self.is_document_root() => tag,
+            _ => return None,
+        };
+        let explicit = tag.get_attribute_value("role");
+        match explicit.as_deref().and_then(|role| role.split_ascii_whitespace().next()) {
+            Some(role) => Some(role.to_ascii_lowercase()),
+            None => self.implicit_role(tag).map(String::from),
+        }
+    }
+
+    fn implicit_role(&self, tag: &Tag) -> Option<&'static str> {
+        let name = tag.get_name().to_ascii_lowercase();
+        let role = match name.as_str() {
+            "a" | "area" if tag.has_attribute("href") => "link",
+            "img" => match tag.get_attribute_value("alt") {
+                Some(alt) if alt.is_empty() => "presentation",
+                _ => "img",
+            },
+            "input" => return input_role(tag),
+            "select" => {
+                let size = tag.get_attribute_value("size").and_then(|size| size.trim().parse::<u32>().ok());
+                match tag.has_attribute("multiple") || size.is_some_and(|size| size > 1) {
+                    true => "listbox",
+                    false => "combobox",
+                }
+            }
+            "th" => match tag.get_attribute_value("scope").as_deref() {
+                Some("row" | "rowgroup") => "rowheader",
+                _ => "columnheader",
+            },
+            "header" | "footer" => {
+                let in_section = self.ancestors().any(|ancestor| match &*ancestor.get_payload() {
+                    Payload::Tag(tag) => SECTIONING_ELEMENTS.contains(&tag.get_name().to_ascii_lowercase().as_str()),
+                    _ => false,
+                });
+                match (in_section, name.as_str()) {
+                    (true, _) => return None,
+                    (false, "header") => "banner",
+                    (false, _) => "contentinfo",
+                }
+            }
+            // a section without a name is no landmark
+            "section" if tag.has_attribute("aria-label") || tag.has_attribute("aria-labelledby") => "region",
+            name => return IMPLICIT_ROLES.iter().find(|(element, _)| *element == name).map(|(_, role)| *role),
+        };
+        Some(role)
+    }
+}
+
+fn input_role(tag: &Tag) -> Option<&'static str> {
+    let input_type = tag.get_attribute_value("type").unwrap_or_default().to_ascii_lowercase();
+    let role = match input_type.as_str() {
+        "checkbox" => "checkbox",
+        "radio" => "radio",
+        "range" => "slider",
+        "number" => "spinbutton",
+        "button" | "image" | "reset" | "submit" => "button",
+        "hidden" | "color" | "date" | "datetime-local" | "file" | "month" | "password" | "time" | "week" => {
+            return None
+        }
+        _ if tag.has_attribute("list") => "combobox",
+        "search" => "searchbox",
+        // `text`, `email`, `tel`, `url` and unknown types, which are text
+        _ => "textbox",
+    };
+    Some(role)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    #[test]
+    fn role_test() {
+        let root = parse(concat!(
+            r#"<header><nav><a href="/">home</a><a>no link</a></nav></header>"#,
+            r#"<main><article><header>h</header><h2>t</h2></article>"#,
+            r#"<input type="checkbox"><input type="Submit"><input><input type="search"><input list="l">"#,
+            r#"<input type="hidden"><img src="a.png"><img src="b.png" alt=""><select multiple></select>"#,
+            r#"<div role="Tab  button">x</div><span role=" ">y</span></main><footer>f</footer>"#,
+        ))
+        .unwrap();
+        let roles: Vec<Option<String>> = root.descendants().map(|node| node.role()).collect();
+        let roles: Vec<&str> = roles.iter().map(|role| role.as_deref().unwrap_or("-")).collect();
+
+        assert_eq!(
+            roles,
+            [
+                "banner", "navigation", "link", "-", "-", "-", "main", "article", "-", "-", "heading", "-",
+                "checkbox", "button", "textbox", "searchbox", "combobox", "-", "img", "presentation", "listbox",
+                "tab", "-", "-", "-", "contentinfo", "-",
+            ]
+        );
+        assert_eq!(root.role(), None);
+    }
+}
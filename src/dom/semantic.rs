@@ -0,0 +1,180 @@
+use std::rc::Rc;
+
+use super::{NodeData, Payload, Tag};
+
+/// A child that takes part in [`NodeData::eq_semantic`].
+enum Significant {
+    /// The adjacent text of the children with its whitespace collapsed.
+    Text(String),
+    Node(Rc<NodeData>),
+}
+
+impl NodeData {
+    /// Returns true if this node and `other` are the same document to a
+    /// reader, which [`PartialEq`] is too strict for in golden-file tests.
+    ///
+    /// Comments are ignored, and so are conditional comments with their
+    /// content. Adjacent text is compared as one, with runs of whitespace
+    /// collapsed and leading and trailing whitespace ignored, so text that
+    /// is only whitespace is the same as no text. Attributes are compared
+    /// without their order or how their values are quoted.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let a = html::parse(r#"<ul class="x" id="a">
+    ///   <li>one  two</li>
+    /// </ul>"#).unwrap();
+    /// let b = html::parse(r#"<ul id="a" class='x'><!-- list --><li>one two</li></ul>"#).unwrap();
+    ///
+    /// assert!(a.eq_semantic(&b));
+    /// assert!(a != b);
+    /// html::assert_dom_eq!(a, b);
+    /// ```
+    pub fn eq_semantic(&self, other: &NodeData) -> bool {
+        if !payload_eq_semantic(&self.get_payload(), &other.get_payload()) {
+            return false;
+        }
+
+        // the children to compare, on a stack rather than the call stack
+        let mut stack = vec![(significant_children(self), significant_children(other))];
+        while let Some((children, other_children)) = stack.pop() {
+            if children.len() != other_children.len() {
+                return false;
+            }
+
+            for (child, other_child) in children.into_iter().zip(other_children) {
+                match (child, other_child) {
+                    (Significant::Text(text), Significant::Text(other_text)) if text == other_text => {}
+                    (Significant::Node(node), Significant::Node(other_node))
+                        if payload_eq_semantic(&node.get_payload(), &other_node.get_payload()) =>
+                    {
+                        stack.push((significant_children(&node), significant_children(&other_node)));
+                    }
+                    _ => return false,
+                }
+            }
+        }
+
+        true
+    }
+}
+
+/// Compares the payloads as [`NodeData::eq_semantic`] does, without the children.
+fn payload_eq_semantic(payload: &Payload, other: &Payload) -> bool {
+    match (payload, other) {
+        (Payload::Tag(tag), Payload::Tag(other_tag)) => {
+            tag.get_name() == other_tag.get_name() && sorted_attributes(tag) == sorted_attributes(other_tag)
+        }
+        (Payload::Text(text), Payload::Text(other_text)) => collapse(text) == collapse(other_text),
+        (Payload::RawText(text), Payload::RawText(other_text)) => text.trim() == other_text.trim(),
+        (Payload::Comment(_), Payload::Comment(_)) => true,
+        (Payload::ConditionalComment(_), Payload::ConditionalComment(_)) => true,
+        (Payload::Doctype(doctype), Payload::Doctype(other_doctype)) => doctype == other_doctype,
+        _ => false,
+    }
+}
+
+fn sorted_attributes(tag: &Tag) -> Vec<(&str, &str)> {
+    let mut attributes: Vec<(&str, &str)> = tag
+        .get_attributes()
+        .into_iter()
+        .flat_map(|attributes| attributes.iter())
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+    attributes.sort_unstable();
+    attributes
+}
+
+/// Returns the children of `node` without comments, with adjacent text
+/// joined and text that is only whitespace dropped.
+fn significant_children(node: &NodeData) -> Vec<Significant> {
+    let mut children = Vec::new();
+    let mut text = String::new();
+    for child in node.get_children().iter() {
+        match &*child.get_payload() {
+            Payload::Text(child_text) => text.push_str(child_text),
+            Payload::Comment(_) | Payload::ConditionalComment(_) => {}
+            _ => {
+                push_text(&mut children, &mut text);
+                children.push(Significant::Node(Rc::clone(child)));
+            }
+        }
+    }
+    push_text(&mut children, &mut text);
+
+    children
+}
+
+fn push_text(children: &mut Vec<Significant>, text: &mut String) {
+    let collapsed = collapse(text);
+    if !collapsed.is_empty() {
+        children.push(Significant::Text(collapsed));
+    }
+    text.clear();
+}
+
+/// Collapses each run of whitespace into one space and trims the ends.
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Asserts that two nodes are equal by [`NodeData::eq_semantic`](crate::dom::NodeData::eq_semantic).
+///
+/// On failure, the panic message has both nodes as HTML.
+///
+/// # Examples
+/// ```rust
+/// let expected = html::parse("<p>Hello, <b>world</b></p>").unwrap();
+/// let actual = html::parse("<p>\n  Hello,\n  <b>world</b>\n</p>").unwrap();
+///
+/// html::assert_dom_eq!(actual, expected);
+/// ```
+#[macro_export]
+macro_rules! assert_dom_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if !left.eq_semantic(right) {
+                    panic!(
+                        "assertion `left.eq_semantic(right)` failed\n  left: {}\n right: {}",
+                        left.to_html(),
+                        right.to_html(),
+                    );
+                }
+            }
+        }
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if !left.eq_semantic(right) {
+                    panic!(
+                        "assertion `left.eq_semantic(right)` failed: {}\n  left: {}\n right: {}",
+                        format_args!($($arg)+),
+                        left.to_html(),
+                        right.to_html(),
+                    );
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    #[test]
+    fn eq_semantic_test() {
+        let a = parse("<div a=\"1\" b=\"2\">x<!-- c -->y <br> <p>z</p></div>").unwrap();
+        assert!(a.eq_semantic(&parse("<div b='2' a=1>xy<br><p> z </p></div>").unwrap()));
+        assert!(!a.eq_semantic(&parse("<div a=\"1\">xy<br><p>z</p></div>").unwrap()));
+        assert!(!a.eq_semantic(&parse("<div a=\"1\" b=\"2\">x y<br><p>z</p></div>").unwrap()));
+        assert!(!a.eq_semantic(&parse("<div a=\"1\" b=\"2\">xy<br><p>z</p><p></p></div>").unwrap()));
+        assert!(!a.eq_semantic(&parse("<span a=\"1\" b=\"2\">xy<br><p>z</p></span>").unwrap()));
+
+        let result = std::panic::catch_unwind(|| crate::assert_dom_eq!(parse("<p>a</p>").unwrap(), parse("<p>b</p>").unwrap(), "case {}", 1));
+        let message = result.unwrap_err().downcast::<String>().unwrap();
+        assert_eq!(*message, "assertion `left.eq_semantic(right)` failed: case 1\n  left: <p>a</p>\n right: <p>b</p>");
+    }
+}
@@ -0,0 +1,825 @@
+use std::fmt;
+use std::rc::Rc;
+
+use super::{AttrValue, Doctype, Node, NodeData, Original, Payload, Tag, ROOT_TAG_NAME};
+use crate::parser::{entity, is_foreign_root, is_integration_point};
+
+/// Which characters are replaced with character references when text and
+/// attribute values are serialized.
+///
+/// The text of `<script>` and `<style>` and the content of comments are
+/// never escaped, as character references are not decoded there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EscapeMode {
+    /// `&`, `<` and `>` in text, and `&` and the quote around an attribute
+    /// value. The markup is as close to the source as it can be.
+    #[default]
+    Minimal,
+    /// `&`, `<`, `>`, `"` and `'` everywhere, and the characters that are
+    /// not ASCII as numeric references, so the markup is ASCII only.
+    /// `é` becomes `&#xE9;`
+    Full,
+}
+
+/// Options to control how a node is serialized.
+/// See [`NodeData::to_html_with_options`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SerializeOptions {
+    /// Default: [`EscapeMode::Minimal`]
+    pub escape: EscapeMode,
+}
+
+impl NodeData {
+    /// Serializes this node and its descendants back to HTML markup.
+    ///
+    /// The synthetic `root` node returned by [`parse`](crate::parse) is not
+    /// emitted itself, only its children are, so that parsing and serializing
+    /// a document gives back the same markup.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let node = html::parse(r#"<p class="x">Hello</p>"#).unwrap();
+    /// assert_eq!(node.to_html(), r#"<p class="x">Hello</p>"#);
+    /// ```
+    pub fn to_html(&self) -> String {
+        self.to_html_with_options(&SerializeOptions::default())
+    }
+
+    /// Same as [`to_html`](NodeData::to_html), but with options.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use html::dom::{EscapeMode, SerializeOptions};
+    ///
+    /// let node = html::parse(r#"<p title='say "hé"'>"1 &lt; 2"</p><script>"é" < 1</script>"#).unwrap();
+    /// let options = SerializeOptions { escape: EscapeMode::Full };
+    ///
+    /// assert_eq!(
+    ///     node.to_html_with_options(&options),
+    ///     r#"<p title="say &quot;h&#xE9;&quot;">&quot;1 &lt; 2&quot;</p><script>"é" < 1</script>"#
+    /// );
+    /// ```
+    pub fn to_html_with_options(&self, options: &SerializeOptions) -> String {
+        let mut html = String::new();
+        write_node(&mut html, self, false, options.escape, self.has_raw_entities());
+        html
+    }
+
+    /// Serializes the children of this node. Same as `innerHTML` in the DOM.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let node = html::parse("<p>Hello, <b>world</b></p>").unwrap();
+    /// assert_eq!(node.get_children()[0].inner_html(), "Hello, <b>world</b>");
+    /// ```
+    pub fn inner_html(&self) -> String {
+        let raw = match &*self.get_payload() {
+            Payload::Tag(tag) => has_raw_text(self, tag),
+            _ => false,
+        };
+
+        let mut html = String::new();
+        write_children(&mut html, self, raw, EscapeMode::Minimal, self.has_raw_entities());
+        html
+    }
+
+    /// Serializes this node and its descendants. Same as `outerHTML` in the DOM.
+    ///
+    /// This is the same as [`to_html`](NodeData::to_html).
+    pub fn outer_html(&self) -> String {
+        self.to_html()
+    }
+
+    /// Returns an outline of this node and its descendants, one node on each
+    /// line indented by its depth. A tag is written with its classes and id,
+    /// text is quoted, and comments and doctypes are written as markup.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let node = html::parse(r#"<div class="a b" id="x"><p>Hello, <b>world</b></p><!-- c --></div>"#).unwrap();
+    ///
+    /// assert_eq!(
+    ///     node.debug_tree(),
+    ///     "#document\n  div.a.b#x\n    p\n      \"Hello, \"\n      b\n        \"world\"\n    <!-- c -->\n"
+    /// );
+    /// ```
+    pub fn debug_tree(&self) -> String {
+        let mut tree = label(self, usize::MAX);
+        tree.push('\n');
+        let mut stack: Vec<(Rc<NodeData>, usize)> =
+            self.get_children().iter().rev().map(|child| (Rc::clone(child), 1)).collect();
+        while let Some((node, depth)) = stack.pop() {
+            for _ in 0..depth {
+                tree.push_str("  ");
+            }
+            tree.push_str(&label(&node, usize::MAX));
+            tree.push('\n');
+
+            stack.extend(node.get_children().iter().rev().map(|child| (Rc::clone(child), depth + 1)));
+        }
+        tree
+    }
+
+    /// Returns a [Graphviz](https://graphviz.org) graph of this node and its
+    /// descendants in the DOT language, with the labels of
+    /// [`debug_tree`](NodeData::debug_tree). Text longer than 20 characters
+    /// is cut. Nodes that are not tags are drawn without a border.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let node = html::parse(r#"<p class="x">Hello</p>"#).unwrap();
+    ///
+    /// assert_eq!(
+    ///     node.to_dot(),
+    ///     concat!(
+    ///         "digraph {\n",
+    ///         "  n0 [label=\"#document\"];\n",
+    ///         "  n1 [label=\"p.x\"];\n",
+    ///         "  n0 -> n1;\n",
+    ///         "  n2 [label=\"\\\"Hello\\\"\", shape=plaintext];\n",
+    ///         "  n1 -> n2;\n",
+    ///         "}\n",
+    ///     )
+    /// );
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph {\n");
+        write_dot_node(&mut dot, self, 0, None);
+        let mut count = 1;
+        // each node with the number of its parent
+        let mut stack: Vec<(Rc<NodeData>, usize)> =
+            self.get_children().iter().rev().map(|child| (Rc::clone(child), 0)).collect();
+        while let Some((node, parent)) = stack.pop() {
+            let id = count;
+            count += 1;
+            write_dot_node(&mut dot, &node, id, Some(parent));
+
+            stack.extend(node.get_children().iter().rev().map(|child| (Rc::clone(child), id)));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Returns true if this is the synthetic node wrapping a parsed document.
+    pub(crate) fn is_document_root(&self) -> bool {
+        match &*self.get_payload() {
+            Payload::Tag(tag) => tag.get_name() == ROOT_TAG_NAME && !self.has_parent(),
+            _ => false,
+        }
+    }
+}
+
+/// Returns the line of `node` in [`NodeData::debug_tree`], with the text
+/// cut after `max_text_len` characters.
+fn label(node: &NodeData, max_text_len: usize) -> String {
+    match &*node.get_payload() {
+        Payload::Tag(_) if node.is_document_root() => String::from("#document"),
+        Payload::Tag(tag) => {
+            let mut label = String::from(tag.get_name());
+            for class in node.classes() {
+                label.push('.');
+                label.push_str(&class);
+            }
+            if let Some(id) = node.attr("id") {
+                label.push('#');
+                label.push_str(&id);
+            }
+            label
+        }
+        Payload::Text(text) | Payload::RawText(text) => match text.char_indices().nth(max_text_len) {
+            Some((end, _)) => format!("{:?}…", &text[..end]),
+            None => format!("{:?}", text),
+        },
+        _ => node
+            .to_html()
+            .chars()
+            .map(|c| if c.is_control() { c.escape_debug().to_string() } else { c.to_string() })
+            .collect(),
+    }
+}
+
+/// Writes the markup of [`to_html`](NodeData::to_html).
+impl fmt::Display for NodeData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_html())
+    }
+}
+
+/// Writes the line of `node` in [`NodeData::to_dot`], and the edge from its
+/// parent.
+fn write_dot_node(dot: &mut String, node: &NodeData, id: usize, parent: Option<usize>) {
+    dot.push_str(&format!("  n{} [label=\"", id));
+    for c in label(node, 20).chars() {
+        if c == '"' || c == '\\' {
+            dot.push('\\');
+        }
+        dot.push(c);
+    }
+    dot.push('"');
+    if !matches!(&*node.get_payload(), Payload::Tag(_)) {
+        dot.push_str(", shape=plaintext");
+    }
+    dot.push_str("];\n");
+    if let Some(parent) = parent {
+        dot.push_str(&format!("  n{} -> n{};\n", parent, id));
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+/// Writes `node` and its descendants to `html`.
+/// If `raw` is true, the text is not escaped.
+///
+/// The tree is walked with a stack rather than by recursion, so that a
+/// deeply nested tree cannot overflow the call stack.
+fn write_node(html: &mut String, node: &NodeData, raw: bool, mode: EscapeMode, raw_entities: bool) {
+    let raw = match write_start(html, node, raw, mode, raw_entities) {
+        Some(raw) => raw,
+        None => return,
+    };
+    let mut steps: Vec<Step> =
+        node.get_children().iter().rev().map(|child| Step::Leading(Rc::clone(child), raw)).collect();
+    while let Some(step) = steps.pop() {
+        match step {
+            Step::Start(node, raw) => {
+                if let Some(raw) = write_start(html, &node, raw, mode, raw_entities) {
+                    steps.push(Step::End(Rc::clone(&node)));
+                    steps.extend(node.get_children().iter().rev().map(|child| Step::Leading(Rc::clone(child), raw)));
+                }
+            }
+            Step::Leading(child, raw) => {
+                write_leading(html, &child, mode);
+                steps.push(Step::Start(child, raw));
+            }
+            Step::End(node) => write_end(html, &node, mode),
+        }
+    }
+    write_end(html, node, mode);
+}
+
+/// What [`write_node`] writes next for a descendant.
+enum Step {
+    Start(Rc<NodeData>, bool),
+    /// The source before a child of a lossless parse, then the child.
+    Leading(Rc<NodeData>, bool),
+    End(Rc<NodeData>),
+}
+
+/// Writes what comes before the children of `node`, or all of `node` if it
+/// has no children and end. Returns whether the children are raw text, or
+/// `None` if nothing follows. If `raw_entities` is true, the character
+/// references in the text and attribute values are not escaped again.
+fn write_start(html: &mut String, node: &NodeData, raw: bool, mode: EscapeMode, raw_entities: bool) -> Option<bool> {
+    // the markup of a lossless parse, which is ASCII only if the source is
+    let original = node.get_original();
+    let original = original.as_deref().filter(|_| mode == EscapeMode::Minimal);
+    let original_start = original.and_then(|original| original.start.as_deref());
+    if let (Some(start), Payload::Text(_) | Payload::RawText(_) | Payload::Comment(_) | Payload::Doctype(_)) =
+        (original_start, &*node.get_payload())
+    {
+        html.push_str(start);
+        return None;
+    }
+
+    match &*node.get_payload() {
+        Payload::Tag(_) if node.is_document_root() => return Some(false),
+        Payload::Tag(tag) => {
+            match original_start {
+                Some(start) => html.push_str(start),
+                None => write_start_tag_with_mode(html, tag, mode, raw_entities),
+            }
+            if tag.is_self_closing() || tag.is_void_element() {
+                return None;
+            }
+            return Some(has_raw_text(node, tag));
+        }
+        Payload::Text(text) if raw => html.push_str(text),
+        Payload::RawText(text) => html.push_str(text),
+        Payload::Text(text) => match mode {
+            EscapeMode::Minimal => escape(html, text, &['&', '<', '>'], raw_entities),
+            EscapeMode::Full => escape_full(html, text, raw_entities),
+        },
+        Payload::Comment(text) => write_comment(html, text),
+        Payload::ConditionalComment(condition) => {
+            match original_start {
+                Some(start) => html.push_str(start),
+                None => write_conditional_comment_start(html, condition),
+            }
+            return Some(false);
+        }
+        Payload::Doctype(doctype) => write_doctype(html, doctype),
+    }
+    None
+}
+
+/// Returns true if the text of `node`, whose tag is `tag`, is written as it
+/// is. That of a raw text element such as `<script>` is, unless it is in
+/// `<svg>` or `<math>`, where the text is parsed as HTML.
+fn has_raw_text(node: &NodeData, tag: &Tag) -> bool {
+    if !tag.is_raw_text_element() {
+        return false;
+    }
+
+    // the nearest `<svg>` or `<math>`, unless an integration point is nearer
+    for ancestor in node.ancestors() {
+        if let Payload::Tag(tag) = &*ancestor.get_payload() {
+            if is_foreign_root(tag.get_name()) {
+                return false;
+            }
+            if is_integration_point(tag.get_name()) {
+                return true;
+            }
+        }
+    }
+    true
+}
+
+/// Writes what comes after the children of `node`.
+fn write_end(html: &mut String, node: &NodeData, mode: EscapeMode) {
+    let original = node.get_original();
+    let original = original.as_deref().filter(|_| mode == EscapeMode::Minimal);
+    if write_original_end(html, original) || node.is_document_root() {
+        return;
+    }
+
+    match &*node.get_payload() {
+        Payload::Tag(tag) => {
+            html.push_str("</");
+            html.push_str(tag.get_name());
+            html.push('>');
+        }
+        Payload::ConditionalComment(_) => html.push_str("<![endif]-->"),
+        _ => {}
+    }
+}
+
+/// Writes the source before the end tag and the end tag as they were
+/// parsed. Returns false if the end tag is to be written anew, as the
+/// payload may have been changed.
+fn write_original_end(html: &mut String, original: Option<&Original>) -> bool {
+    let original = match original {
+        Some(original) => original,
+        None => return false,
+    };
+
+    html.push_str(&original.trailing);
+    if original.start.is_none() {
+        return false;
+    }
+    if let Some(end) = &original.end {
+        html.push_str(end);
+    }
+    true
+}
+
+/// Writes `<!--text-->`. See [`escape_comment`].
+pub(super) fn write_comment(html: &mut String, text: &str) {
+    html.push_str("<!--");
+    html.push_str(&escape_comment(text));
+    html.push_str("-->");
+}
+
+/// Returns `text` with the `>` that would end the comment early written as
+/// `&gt;`: that of a `-->` or `--!>`, and a leading `>` or `->`. `<!-->`
+/// The text is changed when it is parsed back, as character references are
+/// not decoded in comments.
+fn escape_comment(text: &str) -> String {
+    let text = text.replace("-->", "--&gt;").replace("--!>", "--!&gt;");
+    if let Some(rest) = text.strip_prefix('>') {
+        format!("&gt;{}", rest)
+    } else if let Some(rest) = text.strip_prefix("->") {
+        format!("-&gt;{}", rest)
+    } else {
+        text
+    }
+}
+
+/// Writes `<!--[condition]>`, which `<![endif]-->` closes.
+pub(super) fn write_conditional_comment_start(html: &mut String, condition: &str) {
+    html.push_str("<!--[");
+    html.push_str(condition);
+    html.push_str("]>");
+}
+
+fn write_children(html: &mut String, node: &NodeData, raw: bool, mode: EscapeMode, raw_entities: bool) {
+    for child in node.get_children().iter() {
+        write_leading(html, child, mode);
+        write_node(html, child, raw, mode, raw_entities);
+    }
+}
+
+/// Writes the source before `child` if it was parsed losslessly.
+fn write_leading(html: &mut String, child: &NodeData, mode: EscapeMode) {
+    if mode == EscapeMode::Minimal {
+        if let Some(original) = child.get_original().as_deref() {
+            html.push_str(&original.leading);
+        }
+    }
+}
+
+/// Writes `"value"`, or `'value'` if the value contains '"'.
+pub(super) fn write_quoted(html: &mut String, value: &str, raw_entities: bool) {
+    // the value was stored without its delimiter,
+    // use '\'' only if the value itself contains '"'
+    let delimiter = if value.contains('"') { '\'' } else { '"' };
+    html.push(delimiter);
+    escape(html, value, &['&', delimiter], raw_entities);
+    html.push(delimiter);
+}
+
+/// Returns true if `value` can be written without quotes. `<td width=10>`
+pub(super) fn is_unquotable(value: &str) -> bool {
+    !value.is_empty()
+        && !value.contains(|c: char| c.is_ascii_whitespace() || "\"'=<>`&".contains(c))
+}
+
+/// Writes `<!DOCTYPE name PUBLIC "public_id" "system_id">`.
+pub(super) fn write_doctype(html: &mut String, doctype: &Doctype) {
+    html.push_str("<!DOCTYPE ");
+    html.push_str(doctype.get_name());
+
+    match (doctype.get_public_id(), doctype.get_system_id()) {
+        (Some(public_id), system_id) => {
+            html.push_str(" PUBLIC ");
+            write_doctype_id(html, public_id);
+            if let Some(system_id) = system_id {
+                html.push(' ');
+                write_doctype_id(html, system_id);
+            }
+        }
+        (None, Some(system_id)) => {
+            html.push_str(" SYSTEM ");
+            write_doctype_id(html, system_id);
+        }
+        (None, None) => {}
+    }
+
+    html.push('>');
+}
+
+/// Writes `"id"`, or `'id'` if `id` contains '"', as character references
+/// are not decoded in the doctype. An id with both quotes cannot be
+/// written, so its '"' are left out.
+fn write_doctype_id(html: &mut String, id: &str) {
+    let delimiter = if id.contains('"') && !id.contains('\'') { '\'' } else { '"' };
+    html.push(delimiter);
+    html.extend(id.chars().filter(|&c| c != delimiter));
+    html.push(delimiter);
+}
+
+/// Writes `text` to `html`, replacing the characters in `chars` with
+/// character references. If `raw_entities` is true, an '&' that starts a
+/// character reference is kept, as the text was not decoded. `1 &amp; 2`
+pub(super) fn escape(html: &mut String, text: &str, chars: &[char], raw_entities: bool) {
+    for (i, c) in text.char_indices() {
+        if !chars.contains(&c) || (c == '&' && raw_entities && entity::starts_with_reference(&text[i..])) {
+            html.push(c);
+            continue;
+        }
+
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            _ => html.push(c),
+        }
+    }
+}
+
+/// Writes `text` to `html` as [`EscapeMode::Full`] does, keeping the
+/// character references if `raw_entities` is true.
+fn escape_full(html: &mut String, text: &str, raw_entities: bool) {
+    for (i, c) in text.char_indices() {
+        match c {
+            '&' if raw_entities && entity::starts_with_reference(&text[i..]) => html.push(c),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c if c.is_ascii() => html.push(c),
+            c => html.push_str(&format!("&#x{:X};", c as u32)),
+        }
+    }
+}
+
+/// Writes `<tag attribute="value">`, or `<tag attribute="value" />` if the
+/// tag is self-closing.
+pub(crate) fn write_start_tag(html: &mut String, tag: &Tag) {
+    write_start_tag_with_mode(html, tag, EscapeMode::Minimal, false)
+}
+
+fn write_start_tag_with_mode(html: &mut String, tag: &Tag, mode: EscapeMode, raw_entities: bool) {
+    html.push('<');
+    html.push_str(tag.get_name());
+
+    if let Some(attributes) = tag.get_attributes() {
+        for (name, value) in attributes.iter() {
+            html.push(' ');
+            html.push_str(name);
+
+            let value = match value {
+                // valueless attribute. <input disabled>
+                AttrValue::Empty => continue,
+                AttrValue::Unquoted(value) if is_unquotable(value) => {
+                    html.push('=');
+                    match mode {
+                        EscapeMode::Minimal => html.push_str(value),
+                        EscapeMode::Full => escape_full(html, value, raw_entities),
+                    }
+                    continue;
+                }
+                AttrValue::Unquoted(value) | AttrValue::Quoted(value) => value,
+            };
+
+            html.push('=');
+            match mode {
+                EscapeMode::Minimal => write_quoted(html, value, raw_entities),
+                EscapeMode::Full => {
+                    html.push('"');
+                    escape_full(html, value, raw_entities);
+                    html.push('"');
+                }
+            }
+        }
+    }
+
+    if tag.is_self_closing() {
+        html.push_str(" />");
+    } else {
+        html.push('>');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EscapeMode, SerializeOptions};
+    use crate::dom::{Doctype, Node, Payload, Tag, ROOT_TAG_NAME};
+    use crate::parse;
+
+    #[test]
+    fn round_trip_test() {
+        let html = r#"<ul class="list"><li>one</li><!-- comment --><li title='say "hi"'>two</li></ul>"#;
+        let node = parse(html).unwrap();
+
+        assert_eq!(node.to_html(), html);
+    }
+
+    #[test]
+    fn doctype_test() {
+        let html = r#"<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Strict//EN" "http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd"><html></html>"#;
+        assert_eq!(parse(html).unwrap().to_html(), html);
+
+        let node = parse("<!doctype html><p>a</p>").unwrap();
+        assert_eq!(node.to_html(), "<!DOCTYPE html><p>a</p>");
+
+        // an id is quoted with the quote it does not contain
+        let mut doctype = Doctype::new("html");
+        doctype.set_public_id("say \"hi\"");
+        doctype.set_system_id("it's \"x\"");
+        let node = Node::new(Payload::Doctype(doctype));
+        assert_eq!(node.to_html(), r#"<!DOCTYPE html PUBLIC 'say "hi"' "it's x">"#);
+    }
+
+    #[test]
+    fn comment_test() {
+        let node = Node::new(Payload::Comment(String::from("a --> <b> --!> c")));
+        assert_eq!(node.to_html(), "<!--a --&gt; <b> --!&gt; c-->");
+        let root = parse(&node.to_html()).unwrap();
+        assert_eq!(root.get_children().len(), 1);
+        assert_eq!(*root.get_children()[0].get_payload(), Payload::Comment(String::from("a --&gt; <b> --!&gt; c")));
+        assert_eq!(Node::new(Payload::Comment(String::from("> a"))).to_html(), "<!--&gt; a-->");
+        assert_eq!(Node::new(Payload::Comment(String::from("->"))).to_html(), "<!---&gt;-->");
+    }
+
+    #[test]
+    fn attribute_order_test() {
+        let html = r#"<img width="10" src="a.png" alt="a" height="20">"#;
+        assert_eq!(parse(html).unwrap().to_html(), html);
+
+        let html = r#"<input disabled value="" size=10 title="a b">"#;
+        assert_eq!(parse(html).unwrap().to_html(), html);
+    }
+
+    #[test]
+    fn escape_test() {
+        let html = r#"<p title="a &amp; b">1 &lt; 2</p><script>if (1 < 2) {}</script>"#;
+        let node = parse(html).unwrap();
+
+        assert_eq!(node.to_html(), html);
+    }
+
+    #[test]
+    fn raw_entities_test() {
+        // the references kept by the parser are not escaped again
+        let options = crate::ParseOptions { decode_entities: false, ..Default::default() };
+        let html = r#"<p title="a &amp; b &c">1 &amp; 2 &#x27; & 3</p>"#;
+        let node = crate::parse_with_options(html, &options).unwrap();
+        let expected = r#"<p title="a &amp; b &amp;c">1 &amp; 2 &#x27; &amp; 3</p>"#;
+        assert_eq!(node.to_html(), expected);
+        assert_eq!(node.first_child().unwrap().to_html(), expected);
+        assert_eq!(node.deep_clone().to_html(), expected);
+        assert_eq!(crate::parse_with_options(expected, &options).unwrap().to_html(), expected);
+        assert_eq!(node.to_minified_html(), r#"<p title="a &amp; b &amp;c">1 &amp; 2 &#x27; &amp; 3"#);
+        assert!(node.to_html_with_options(&SerializeOptions { escape: EscapeMode::Full }).contains("1 &amp; 2 &#x27;"));
+
+        // decoded text is escaped whatever it contains
+        assert_eq!(parse("<p>&amp;amp;</p>").unwrap().to_html(), "<p>&amp;amp;</p>");
+    }
+
+    #[test]
+    fn inner_html_test() {
+        let node = parse(r#"<div id="a"><p>one</p>two</div><script>1 < 2</script>"#).unwrap();
+        let children = node.get_children();
+
+        assert_eq!(children[0].inner_html(), "<p>one</p>two");
+        assert_eq!(children[0].outer_html(), r#"<div id="a"><p>one</p>two</div>"#);
+        assert_eq!(children[1].inner_html(), "1 < 2");
+    }
+
+    #[test]
+    fn debug_tree_test() {
+        let root = parse("<!DOCTYPE html><ul id=\"l\"><li>a \"b\"\n</li></ul><script>if (a < b) {}</script><!--x\ny-->").unwrap();
+        assert_eq!(
+            root.debug_tree(),
+            concat!(
+                "#document\n  <!DOCTYPE html>\n  ul#l\n    li\n      \"a \\\"b\\\"\\n\"\n",
+                "  script\n    \"if (a < b) {}\"\n  <!--x\\ny-->\n",
+            )
+        );
+
+        // a node below the root, and one that is not a tag
+        let ul = Node::from(root.get_children()[1].clone());
+        assert_eq!(ul.debug_tree(), "ul#l\n  li\n    \"a \\\"b\\\"\\n\"\n");
+        assert_eq!(Node::new(Payload::Text(String::from("t"))).debug_tree(), "\"t\"\n");
+
+        assert_eq!(root.to_string(), root.to_html());
+        assert_eq!(format!("{}", *ul), "<ul id=\"l\"><li>a \"b\"\n</li></ul>");
+    }
+
+    #[test]
+    fn to_dot_test() {
+        let root = parse(r#"<div id="a"><!-- "c" -->A text that is longer than twenty characters\</div><br>"#).unwrap();
+        assert_eq!(
+            root.to_dot(),
+            concat!(
+                "digraph {\n  n0 [label=\"#document\"];\n  n1 [label=\"div#a\"];\n  n0 -> n1;\n",
+                "  n2 [label=\"<!-- \\\"c\\\" -->\", shape=plaintext];\n  n1 -> n2;\n",
+                "  n3 [label=\"\\\"A text that is longe\\\"…\", shape=plaintext];\n  n1 -> n3;\n",
+                "  n4 [label=\"br\"];\n  n0 -> n4;\n}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn self_closing_test() {
+        let mut tag = Tag::new("input");
+        tag.set_attribute("disabled", "");
+        tag.set_self_closing(true);
+
+        let node = Node::new(Payload::Tag(tag));
+        assert_eq!(node.to_html(), "<input disabled />");
+
+        let node = parse("<p>a<br>b</p>").unwrap();
+        assert_eq!(node.to_html(), "<p>a<br>b</p>");
+    }
+
+    #[test]
+    fn escape_mode_test() {
+        let html = "<p title='a \"b\" &amp; é'>&lt;&amp;&gt; \"'é\u{a0}</p><style>a > b { content: \"é\" }</style><!-- é & -->";
+        let node = parse(html).unwrap();
+
+        let minimal = node.to_html_with_options(&SerializeOptions { escape: EscapeMode::Minimal });
+        assert_eq!(minimal, node.to_html());
+        assert_eq!(minimal, html);
+
+        let full = node.to_html_with_options(&SerializeOptions { escape: EscapeMode::Full });
+        assert_eq!(
+            full,
+            "<p title=\"a &quot;b&quot; &amp; &#xE9;\">&lt;&amp;&gt; &quot;&#39;&#xE9;&#xA0;</p><style>a > b { content: \"é\" }</style><!-- é & -->"
+        );
+        assert_eq!(parse(&full).unwrap(), node);
+    }
+
+    /// A generator of pseudo-random numbers, so that the trees of the
+    /// round-trip tests are the same in every run.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: usize) -> usize {
+            // xorshift64
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+
+        fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+            items[self.below(items.len())]
+        }
+
+        fn text(&mut self, pieces: &[&str]) -> String {
+            (0..self.below(6) + 1).map(|_| self.pick(pieces)).collect()
+        }
+    }
+
+    const NAMES: [&str; 6] = ["div", "span", "b", "i", "em", "section"];
+    const ATTRIBUTES: [&str; 4] = ["id", "title", "data-x", "lang"];
+    const PIECES: [&str; 14] = ["a", "b c", "&", "&amp;", "<", "</div>", ">", "\"", "'", "é", "\u{a0}", ";", "#x41", "=="];
+    const COMMENT_PIECES: [&str; 6] = ["x", " ", "-", ">", "-->", "--!>"];
+    const ID_PIECES: [&str; 5] = ["-//W3C//DTD", " ", ">", "\"", "'"];
+
+    /// Adds random children to `parent`. No two texts are next to each other
+    /// and no text starts or ends with whitespace, as the parser would merge
+    /// them and trims the end of the document.
+    fn add_random_children(rng: &mut Rng, parent: &Node, depth: usize) {
+        let mut prev_text = false;
+        for _ in 0..rng.below(5) {
+            let kind = rng.below(if depth == 0 { 2 } else { 4 });
+            let child = match kind {
+                0 | 1 if !prev_text => Node::new(Payload::Text(format!("z{}z", rng.text(&PIECES)))),
+                2 => Node::new(Payload::Comment(rng.text(&COMMENT_PIECES))),
+                _ => {
+                    let mut tag = Tag::new(rng.pick(&NAMES));
+                    for name in ATTRIBUTES {
+                        if rng.below(3) == 0 {
+                            tag.set_attribute(name, &rng.text(&PIECES));
+                        }
+                    }
+                    Node::new(Payload::Tag(tag))
+                }
+            };
+            prev_text = matches!(&*child.get_payload(), Payload::Text(_));
+            if matches!(&*child.get_payload(), Payload::Tag(_)) && depth > 0 {
+                add_random_children(rng, &child, depth - 1);
+            }
+            parent.add_child_and_update_parent(&child);
+        }
+    }
+
+    /// Adds a doctype with random ids to `root` in one of three trees.
+    fn add_random_doctype(rng: &mut Rng, root: &Node) {
+        if rng.below(3) != 0 {
+            return;
+        }
+        let mut doctype = Doctype::new("html");
+        if rng.below(2) == 0 {
+            doctype.set_public_id(&rng.text(&ID_PIECES));
+        }
+        if rng.below(2) == 0 {
+            doctype.set_system_id(&rng.text(&ID_PIECES));
+        }
+        root.add_child_and_update_parent(&Node::new(Payload::Doctype(doctype)));
+    }
+
+    /// Returns `tree` as its markup is parsed back: the comments stay
+    /// escaped, and a doctype id with both quotes loses its '"'.
+    fn written(tree: &Node) -> Node {
+        let written = tree.deep_clone();
+        for node in written.descendants() {
+            match &mut *node.payload_mut() {
+                Payload::Comment(text) => *text = super::escape_comment(text),
+                Payload::Doctype(doctype) => {
+                    let unquote = |id: &str| match id.contains('"') && id.contains('\'') {
+                        true => id.replace('"', ""),
+                        false => String::from(id),
+                    };
+                    if let Some(public_id) = doctype.get_public_id().map(unquote) {
+                        doctype.set_public_id(&public_id);
+                    }
+                    if let Some(system_id) = doctype.get_system_id().map(unquote) {
+                        doctype.set_system_id(&system_id);
+                    }
+                }
+                _ => {}
+            }
+        }
+        written
+    }
+
+    /// Serializing a tree and parsing the markup gives back the same tree,
+    /// in both escape modes.
+    #[test]
+    fn round_trip_property_test() {
+        let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+        for _ in 0..500 {
+            let tree = Node::new(Payload::Tag(Tag::new(ROOT_TAG_NAME)));
+            add_random_doctype(&mut rng, &tree);
+            add_random_children(&mut rng, &tree, 4);
+            let expected = written(&tree);
+
+            for escape in [EscapeMode::Minimal, EscapeMode::Full] {
+                let html = tree.to_html_with_options(&SerializeOptions { escape });
+                let parsed = parse(&html).unwrap_or_else(|e| panic!("{} in {}", e, html));
+                assert!(*parsed == *expected, "{:?} does not round-trip: {}", escape, html);
+                assert_eq!(parsed.to_html_with_options(&SerializeOptions { escape }), html);
+            }
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::Tag;
+
+/// The declarations of a `style` attribute, kept in the order they were
+/// written. Created by [`Tag::style`].
+///
+/// Property names are matched without case, except custom properties
+/// such as `--gap`. A property declared twice keeps its first position and
+/// the value that wins in CSS: the last one, unless only an earlier one is
+/// `!important`.
+///
+/// # Examples
+/// ```rust
+/// use html::dom::Payload;
+///
+/// let root = html::parse(r#"<p style="color: red; WIDTH:10px !important">x</p>"#).unwrap();
+/// let p = root.first_child().unwrap();
+/// if let Payload::Tag(tag) = &mut *p.payload_mut() {
+///     let mut style = tag.style().unwrap();
+///     assert_eq!(style.get("width"), Some("10px"));
+///     assert!(style.is_important("width"));
+///
+///     style.set("color", "blue");
+///     style.remove("width");
+///     style.set("margin", "0 auto");
+///     tag.set_style(&style);
+/// }
+///
+/// assert_eq!(root.to_html(), r#"<p style="color: blue; margin: 0 auto">x</p>"#);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StyleDeclarations {
+    entries: Vec<Declaration>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Declaration {
+    name: String,
+    value: String,
+    important: bool,
+}
+
+impl StyleDeclarations {
+    pub fn new() -> StyleDeclarations {
+        StyleDeclarations::default()
+    }
+
+    /// Parses the content of a `style` attribute. Comments are skipped, and
+    /// so are declarations without a name or a `:`, as browsers do.
+    pub fn parse(style: &str) -> StyleDeclarations {
+        let mut declarations = StyleDeclarations::new();
+        for declaration in split_declarations(&strip_comments(style)) {
+            let (name, value) = match declaration.split_once(':') {
+                Some((name, value)) => (name.trim(), value.trim()),
+                None => continue,
+            };
+            if name.is_empty() || value.is_empty() {
+                continue;
+            }
+
+            let (value, important) = split_important(value);
+            declarations.insert(Declaration {
+                name: normalize_name(name),
+                value: value.to_string(),
+                important,
+            });
+        }
+        declarations
+    }
+
+    /// Returns the value of `name` without `!important`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.find(name).map(|declaration| declaration.value.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.find(name).is_some()
+    }
+
+    pub fn is_important(&self, name: &str) -> bool {
+        self.find(name).is_some_and(|declaration| declaration.important)
+    }
+
+    /// Sets the value of `name` and returns the previous value. A new
+    /// property goes last, an existing one keeps its position and loses
+    /// `!important`.
+    pub fn set(&mut self, name: &str, value: &str) -> Option<String> {
+        self.replace(name, value, false)
+    }
+
+    /// Sets the value of `name` as `!important` and returns the previous value.
+    pub fn set_important(&mut self, name: &str, value: &str) -> Option<String> {
+        self.replace(name, value, true)
+    }
+
+    /// Removes `name` and returns its value. The order of the others is kept.
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let name = normalize_name(name);
+        let index = self.entries.iter().position(|declaration| declaration.name == name)?;
+        Some(self.entries.remove(index).value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns an iterator over the names and values in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|declaration| (declaration.name.as_str(), declaration.value.as_str()))
+    }
+
+    fn find(&self, name: &str) -> Option<&Declaration> {
+        let name = normalize_name(name);
+        self.entries.iter().find(|declaration| declaration.name == name)
+    }
+
+    fn replace(&mut self, name: &str, value: &str, important: bool) -> Option<String> {
+        let name = normalize_name(name);
+        match self.entries.iter_mut().find(|declaration| declaration.name == name) {
+            Some(declaration) => {
+                declaration.important = important;
+                Some(std::mem::replace(&mut declaration.value, value.to_string()))
+            }
+            None => {
+                self.entries.push(Declaration {
+                    name,
+                    value: value.to_string(),
+                    important,
+                });
+                None
+            }
+        }
+    }
+
+    /// Adds a parsed declaration by the cascade: a later one wins unless
+    /// only the earlier one is `!important`.
+    fn insert(&mut self, new: Declaration) {
+        match self.entries.iter_mut().find(|declaration| declaration.name == new.name) {
+            Some(declaration) if declaration.important && !new.important => {}
+            Some(declaration) => *declaration = new,
+            None => self.entries.push(new),
+        }
+    }
+}
+
+impl FromStr for StyleDeclarations {
+    type Err = std::convert::Infallible;
+
+    fn from_str(style: &str) -> Result<StyleDeclarations, Self::Err> {
+        Ok(StyleDeclarations::parse(style))
+    }
+}
+
+/// Writes the declarations as a `style` attribute. `color: red; width: 10px`
+impl fmt::Display for StyleDeclarations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, declaration) in self.entries.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{}: {}", declaration.name, declaration.value)?;
+            if declaration.important {
+                f.write_str(" !important")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Tag {
+    /// Parses the `style` attribute, or returns `None` if there is none.
+    pub fn style(&self) -> Option<StyleDeclarations> {
+        self.get_attribute("style").map(|style| StyleDeclarations::parse(style.as_str()))
+    }
+
+    /// Writes `style` to the `style` attribute, or removes the attribute if
+    /// `style` is empty.
+    pub fn set_style(&mut self, style: &StyleDeclarations) {
+        if style.is_empty() {
+            self.remove_attribute("style");
+        } else {
+            self.set_attribute("style", &style.to_string());
+        }
+    }
+}
+
+/// Lowercases a property name unless it is a custom property. `--Gap`
+fn normalize_name(name: &str) -> String {
+    if name.starts_with("--") {
+        name.to_string()
+    } else {
+        name.to_ascii_lowercase()
+    }
+}
+
+/// Removes the `/* */` comments of CSS.
+pub(crate) fn strip_comments(style: &str) -> String {
+    let mut stripped = String::with_capacity(style.len());
+    let mut rest = style;
+    while let Some(bgn) = rest.find("/*") {
+        stripped.push_str(&rest[..bgn]);
+        rest = match rest[bgn + 2..].find("*/") {
+            Some(len) => &rest[bgn + 2 + len + 2..],
+            None => "",
+        };
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// Splits at the `;` that are outside of strings and parentheses, so that
+/// `url("a;b")` stays in one piece.
+fn split_declarations(style: &str) -> Vec<&str> {
+    let mut declarations = Vec::new();
+    let mut quote = None;
+    let mut depth = 0usize;
+    let mut bgn = 0;
+    for (i, c) in style.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth = depth.saturating_sub(1),
+            (None, ';') if depth == 0 => {
+                declarations.push(&style[bgn..i]);
+                bgn = i + 1;
+            }
+            _ => {}
+        }
+    }
+    declarations.push(&style[bgn..]);
+    declarations
+}
+
+/// Splits `red !important` into `red` and true.
+fn split_important(value: &str) -> (&str, bool) {
+    if let Some(bang) = value.rfind('!') {
+        if value[bang + 1..].trim().eq_ignore_ascii_case("important") {
+            return (value[..bang].trim_end(), true);
+        }
+    }
+    (value, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn style_test() {
+        let style = StyleDeclarations::parse(
+            "color:red;; /* a; b */ background: url(\"a;b.png\") ; --Gap: 1px; bad; :x; Color: blue ! IMPORTANT; COLOR: green",
+        );
+        assert_eq!(
+            style.iter().collect::<Vec<_>>(),
+            [("color", "blue"), ("background", "url(\"a;b.png\")"), ("--Gap", "1px")]
+        );
+        assert!(style.is_important("COLOR"));
+        assert!(!style.contains("--gap"));
+        assert_eq!(style.to_string(), "color: blue !important; background: url(\"a;b.png\"); --Gap: 1px");
+        assert_eq!(StyleDeclarations::parse(&style.to_string()), style);
+
+        let mut tag = Tag::new("p");
+        assert_eq!(tag.style(), None);
+        tag.set_attribute("style", " ");
+        let mut style = tag.style().unwrap();
+        assert!(style.is_empty());
+        assert_eq!(style.set_important("width", "1px"), None);
+        assert_eq!(style.set("width", "2px"), Some(String::from("1px")));
+        tag.set_style(&style);
+        assert_eq!(tag.get_attribute_value("style").as_deref(), Some("width: 2px"));
+        style.remove("width");
+        tag.set_style(&style);
+        assert!(!tag.has_attribute("style"));
+    }
+}
@@ -0,0 +1,277 @@
+//! A thread-safe tree, for sharing a parsed document between threads.
+//!
+//! [`Node`](struct@Node) here is the same as [`dom::Node`](crate::dom::Node),
+//! but is built on [`Arc`] and [`RwLock`] instead of `Rc` and `RefCell`, so
+//! that it is `Send` and `Sync`. Convert a parsed document with
+//! [`Node::from`] and back with [`NodeData::to_node`].
+//!
+//! # Examples
+//! ```rust
+//! use html::dom::sync;
+//!
+//! let root = html::parse("<ul><li>one</li><li>two</li></ul>").unwrap();
+//! let root = sync::Node::from(&*root);
+//!
+//! let handle = {
+//!     let root = root.clone();
+//!     std::thread::spawn(move || root.to_html())
+//! };
+//! assert_eq!(handle.join().unwrap(), "<ul><li>one</li><li>two</li></ul>");
+//! ```
+
+use std::rc::Rc;
+use std::ops::Deref;
+use std::sync::{Arc, RwLock, Weak};
+
+use super::{drop_descendants, OwnsChildren, Payload, Span};
+
+type NodeDataRef = Arc<NodeData>;
+type WeakNodeDataRef = Weak<NodeData>;
+
+/// Holds the underlying data of a [`Node`](struct@Node).
+/// Same as [`dom::NodeData`](crate::dom::NodeData).
+#[derive(Debug)]
+pub struct NodeData {
+    payload: Payload,
+    parent: RwLock<WeakNodeDataRef>,
+    children: RwLock<Vec<NodeDataRef>>,
+    span: Option<Span>,
+}
+
+impl PartialEq for NodeData {
+    fn eq(&self, other: &Self) -> bool {
+        self.payload == other.payload && *self.children.read().unwrap() == *other.children.read().unwrap()
+    }
+}
+
+impl Drop for NodeData {
+    fn drop(&mut self) {
+        drop_descendants(self);
+    }
+}
+
+impl OwnsChildren for NodeData {
+    type Child = NodeDataRef;
+
+    // the children of a poisoned lock are left to be dropped with it
+    fn take_children(&mut self) -> Vec<NodeDataRef> {
+        self.children.get_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    fn into_node(child: NodeDataRef) -> Option<NodeData> {
+        Arc::try_unwrap(child).ok()
+    }
+}
+
+impl NodeData {
+    pub fn get_payload(&self) -> &Payload {
+        &self.payload
+    }
+
+    /// Returns where this node was in the parsed document.
+    pub fn get_span(&self) -> Option<Span> {
+        self.span
+    }
+
+    /// Returns a copy of the children.
+    /// The lock is not held, so the children may change after this returns.
+    pub fn get_children(&self) -> Vec<NodeDataRef> {
+        self.children.read().unwrap().clone()
+    }
+
+    pub fn get_parent(&self) -> Option<NodeDataRef> {
+        self.parent.read().unwrap().upgrade()
+    }
+
+    pub fn has_parent(&self) -> bool {
+        self.get_parent().is_some()
+    }
+
+    pub fn first_child(&self) -> Option<NodeDataRef> {
+        self.children.read().unwrap().first().cloned()
+    }
+
+    pub fn last_child(&self) -> Option<NodeDataRef> {
+        self.children.read().unwrap().last().cloned()
+    }
+
+    /// Returns the node right after this node in the parent's children.
+    pub fn next_sibling(&self) -> Option<NodeDataRef> {
+        let parent = self.get_parent()?;
+        let index = parent.find_child(self)?;
+        let siblings = parent.children.read().unwrap();
+        siblings.get(index + 1).cloned()
+    }
+
+    /// Returns the node right before this node in the parent's children.
+    pub fn prev_sibling(&self) -> Option<NodeDataRef> {
+        let parent = self.get_parent()?;
+        let index = parent.find_child(self)?;
+        let siblings = parent.children.read().unwrap();
+        siblings.get(index.checked_sub(1)?).cloned()
+    }
+
+    /// Copies this node and its descendants into a single-threaded tree.
+    pub fn to_node(&self) -> super::Node {
+        let copy = |node: &NodeData| {
+            let copy = super::Node::new(node.payload.clone());
+            if let Some(span) = node.span {
+                copy.set_span(span);
+            }
+            copy
+        };
+
+        // copies the descendants with a stack, as copying them recursively
+        // overflows the call stack for deeply nested trees
+        let root = copy(self);
+        let mut stack = vec![(self.get_children(), root.clone())];
+        while let Some((children, node)) = stack.pop() {
+            for child in children {
+                let child_node = copy(&child);
+                node.add_child_and_update_parent(&child_node);
+                stack.push((child.get_children(), child_node));
+            }
+        }
+
+        root
+    }
+
+    /// Same as [`dom::NodeData::to_html`](crate::dom::NodeData::to_html).
+    pub fn to_html(&self) -> String {
+        self.to_node().to_html()
+    }
+
+    /// Same as [`dom::NodeData::inner_text`](crate::dom::NodeData::inner_text).
+    pub fn inner_text(&self) -> String {
+        self.to_node().inner_text()
+    }
+
+    /// Returns the index of `child` in the children, compared by identity.
+    fn find_child(&self, child: &NodeData) -> Option<usize> {
+        self.children
+            .read()
+            .unwrap()
+            .iter()
+            .position(|c| std::ptr::eq(Arc::as_ptr(c), child))
+    }
+}
+
+/// Owns a [`NodeData`] inside an [`Arc`].
+/// Same as [`dom::Node`](crate::dom::Node), but can be sent to other threads.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Node {
+    arc_ref: NodeDataRef,
+}
+
+impl Deref for Node {
+    type Target = NodeData;
+
+    fn deref(&self) -> &Self::Target {
+        &self.arc_ref
+    }
+}
+
+impl From<NodeDataRef> for Node {
+    fn from(arc_ref: NodeDataRef) -> Node {
+        Node { arc_ref }
+    }
+}
+
+/// Copies a single-threaded tree, such as the one returned by
+/// [`parse`](crate::parse).
+impl From<&super::NodeData> for Node {
+    fn from(node: &super::NodeData) -> Node {
+        let copy = |node: &super::NodeData| Node::new_with_span(node.get_payload().clone(), node.get_span());
+        let root = copy(node);
+        // the children in reverse, so that they are added in order
+        let mut stack: Vec<(Rc<super::NodeData>, Node)> =
+            node.get_children().iter().rev().map(|child| (Rc::clone(child), root.clone())).collect();
+        while let Some((node, new_parent)) = stack.pop() {
+            let new_node = copy(&node);
+            new_parent.add_child_and_update_parent(&new_node);
+            stack.extend(node.get_children().iter().rev().map(|child| (Rc::clone(child), new_node.clone())));
+        }
+
+        root
+    }
+}
+
+impl Node {
+    pub fn new(payload: Payload) -> Node {
+        Node::new_with_span(payload, None)
+    }
+
+    pub(crate) fn new_with_span(payload: Payload, span: Option<Span>) -> Node {
+        let new_node = NodeData {
+            payload,
+            parent: RwLock::new(Weak::new()),
+            children: RwLock::new(Vec::new()),
+            span,
+        };
+
+        Node {
+            arc_ref: Arc::new(new_node),
+        }
+    }
+
+    pub fn get_copy_of_internal_arc(&self) -> NodeDataRef {
+        Arc::clone(&self.arc_ref)
+    }
+
+    /// Appends `child` to the children.
+    /// If `child` already has a parent, it is removed from there first.
+    pub fn add_child_and_update_parent(&self, child: &Node) {
+        child.detach();
+        self.children.write().unwrap().push(child.get_copy_of_internal_arc());
+        *child.parent.write().unwrap() = Arc::downgrade(&self.arc_ref);
+    }
+
+    /// Removes `child` from the children and returns it.
+    /// Returns `None` if `child` is not a child of this node.
+    pub fn remove_child(&self, child: &NodeData) -> Option<Node> {
+        let index = self.find_child(child)?;
+        let removed = self.children.write().unwrap().remove(index);
+        *removed.parent.write().unwrap() = Weak::new();
+
+        Some(Node::from(removed))
+    }
+
+    /// Removes this node from its parent. Does nothing if it has no parent.
+    pub fn detach(&self) {
+        if let Some(parent) = self.get_parent() {
+            Node::from(parent).remove_child(self);
+        }
+    }
+
+    pub fn create_and_add_child(&self, payload: Payload) -> NodeDataRef {
+        let new_child = Node::new(payload);
+        self.add_child_and_update_parent(&new_child);
+        new_child.get_copy_of_internal_arc()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::Tag;
+    use crate::parse;
+
+    #[test]
+    fn sync_test() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Node>();
+
+        let root = parse("<ul><li>one</li><li>two</li></ul>").unwrap();
+        let node = Node::from(&*root);
+        assert_eq!(node.to_node(), root);
+
+        let ul = node.first_child().unwrap();
+        let li = Node::from(ul.first_child().unwrap());
+        assert!(Arc::ptr_eq(&li.next_sibling().unwrap(), &ul.last_child().unwrap()));
+
+        li.detach();
+        assert_eq!(ul.get_children().len(), 1);
+        Node::from(ul).create_and_add_child(Payload::Tag(Tag::new("li")));
+        assert_eq!(node.to_html(), "<ul><li>two</li><li></li></ul>");
+    }
+}
@@ -0,0 +1,25 @@
+/// A node of a tree that owns its children, whose descendants are dropped
+/// by [`drop_descendants`].
+pub(crate) trait OwnsChildren: Sized {
+    /// A child as the node stores it, such as `Rc<NodeData>`.
+    type Child;
+
+    /// Takes the children out of the node.
+    fn take_children(&mut self) -> Vec<Self::Child>;
+
+    /// Returns the node of `child` if nothing else owns it, so that its
+    /// children are dropped next.
+    fn into_node(child: Self::Child) -> Option<Self>;
+}
+
+/// Drops the descendants of `node` with a stack, as dropping them
+/// recursively overflows the call stack for deeply nested trees.
+/// Called by the `Drop` of the node.
+pub(crate) fn drop_descendants<N: OwnsChildren>(node: &mut N) {
+    let mut stack = node.take_children();
+    while let Some(child) = stack.pop() {
+        if let Some(mut child) = N::into_node(child) {
+            stack.append(&mut child.take_children());
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use std::rc::Rc;
+
+use super::{NodeData, Payload, Tag};
+
+/// Elements that may have a shadow root, besides custom elements.
+/// <https://dom.spec.whatwg.org/#dom-element-attachshadow>
+const SHADOW_HOST_ELEMENTS: [&str; 18] = [
+    "article", "aside", "blockquote", "body", "div", "footer", "h1", "h2", "h3",
+    "h4", "h5", "h6", "header", "main", "nav", "p", "section", "span",
+];
+
+/// The `shadowrootmode` of a declarative shadow root, whether its shadow
+/// tree can be reached from outside the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShadowRootMode {
+    Open,
+    Closed,
+}
+
+/// Iterator over the descendants of a node in depth-first order that does
+/// not enter the content of `<template>` elements.
+/// Created by [`NodeData::live_descendants`].
+#[derive(Debug, Clone)]
+pub struct LiveDescendants {
+    // the next node is at the end
+    stack: Vec<Rc<NodeData>>,
+}
+
+impl Iterator for LiveDescendants {
+    type Item = Rc<NodeData>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        if !node.is_template() || node.shadow_root_mode().is_some() {
+            self.stack.extend(node.get_children().iter().rev().cloned());
+        }
+
+        Some(node)
+    }
+}
+
+impl NodeData {
+    /// Returns true if this node is a `<template>` element.
+    pub fn is_template(&self) -> bool {
+        match &*self.get_payload() {
+            Payload::Tag(tag) => tag.get_name().eq_ignore_ascii_case("template"),
+            _ => false,
+        }
+    }
+
+    /// Returns the content of a `<template>` element, or `None` if this node
+    /// is not one.
+    ///
+    /// The content is kept as the children of the element, so it is
+    /// serialized and queried like any other node, but it is inert: a
+    /// browser does not render it or run its scripts until the template is
+    /// instantiated. [`is_template_content`](NodeData::is_template_content)
+    /// tells it apart from the live document.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let root = html::parse(r#"<template id="row"><tr><td></td></tr></template><table></table>"#).unwrap();
+    /// let template = html::get_node_by_id(&root.get_copy_of_internal_arc(), "row").unwrap();
+    ///
+    /// let content = template.template_content().unwrap();
+    /// assert_eq!(content[0].to_html(), "<tr><td></td></tr>");
+    /// assert!(content[0].is_template_content());
+    /// assert!(root.first_child().unwrap().template_content().is_some());
+    /// assert!(root.last_child().unwrap().template_content().is_none());
+    /// ```
+    pub fn template_content(&self) -> Option<Vec<Rc<NodeData>>> {
+        if !self.is_template() {
+            return None;
+        }
+
+        Some(self.children())
+    }
+
+    /// Returns true if this node is in the content of a `<template>`
+    /// element, which is not part of the live document. The content of a
+    /// [`shadow_root`](NodeData::shadow_root) is live.
+    pub fn is_template_content(&self) -> bool {
+        self.ancestors().any(|ancestor| ancestor.is_template() && ancestor.shadow_root_mode().is_none())
+    }
+
+    /// Returns the declarative shadow root of this element, which is its
+    /// first `<template>` child with `shadowrootmode="open"` or `"closed"`,
+    /// or `None` if it has none or is not an element that may have one.
+    /// The children of the template are the shadow tree.
+    ///
+    /// The template is kept as a child of the host, so the document is
+    /// serialized as it was written, as server-side rendered components are.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use html::dom::ShadowRootMode;
+    ///
+    /// let root = html::parse(r#"<my-card><template shadowrootmode="open"><slot></slot></template>Hi</my-card>"#).unwrap();
+    /// let host = root.first_child().unwrap();
+    ///
+    /// let shadow_root = host.shadow_root().unwrap();
+    /// assert_eq!(shadow_root.shadow_root_mode(), Some(ShadowRootMode::Open));
+    /// assert_eq!(shadow_root.inner_html(), "<slot></slot>");
+    /// assert!(!shadow_root.first_child().unwrap().is_template_content());
+    /// ```
+    pub fn shadow_root(&self) -> Option<Rc<NodeData>> {
+        match &*self.get_payload() {
+            Payload::Tag(tag) if !self.is_document_root() && can_be_shadow_host(tag) => {}
+            _ => return None,
+        }
+        self.get_children().iter().find(|child| declared_shadow_root_mode(child).is_some()).cloned()
+    }
+
+    /// Returns the mode of the shadow root that this `<template>` element
+    /// is, or `None` if it is not the [`shadow_root`](NodeData::shadow_root)
+    /// of its parent.
+    pub fn shadow_root_mode(&self) -> Option<ShadowRootMode> {
+        let mode = declared_shadow_root_mode(self)?;
+        let shadow_root = self.get_parent()?.shadow_root()?;
+        std::ptr::eq(Rc::as_ptr(&shadow_root), self).then_some(mode)
+    }
+
+    /// Same as [`descendants`](NodeData::descendants), but the content of
+    /// `<template>` elements is skipped, except that of shadow roots. The
+    /// `<template>` elements themselves are included.
+    pub fn live_descendants(&self) -> LiveDescendants {
+        LiveDescendants {
+            stack: self.children().into_iter().rev().collect(),
+        }
+    }
+}
+
+/// Returns the `shadowrootmode` of a `<template>` element, whether or not
+/// it is attached to its parent.
+fn declared_shadow_root_mode(node: &NodeData) -> Option<ShadowRootMode> {
+    let mode = match &*node.get_payload() {
+        Payload::Tag(tag) if node.is_template() => tag.get_attribute_value("shadowrootmode")?,
+        _ => return None,
+    };
+    match mode.to_ascii_lowercase().as_str() {
+        "open" => Some(ShadowRootMode::Open),
+        "closed" => Some(ShadowRootMode::Closed),
+        _ => None,
+    }
+}
+
+fn can_be_shadow_host(tag: &Tag) -> bool {
+    tag.is_custom_element() || SHADOW_HOST_ELEMENTS.iter().any(|name| tag.get_name().eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dom::{Payload, ShadowRootMode};
+    use crate::parse;
+
+    #[test]
+    fn template_test() {
+        let root = parse("<ul><li>a</li><template><li>b</li><template><li>c</li></template></template><li>d</li></ul>").unwrap();
+        let live: Vec<String> = root
+            .live_descendants()
+            .filter_map(|node| match &*node.get_payload() {
+                Payload::Text(text) => Some(text.clone()),
+                Payload::Tag(tag) => Some(tag.get_name().to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(live, ["ul", "li", "a", "template", "li", "d"]);
+
+        let inert = root.descendants().filter(|node| node.is_template_content()).count();
+        assert_eq!(inert, 5);
+        assert_eq!(root.descendants().count(), 11);
+    }
+
+    #[test]
+    fn shadow_root_test() {
+        let html = concat!(
+            r#"<my-card><b>light</b><template shadowrootmode="Closed"><h2>shadow</h2>"#,
+            r#"<template><i>inert</i></template></template><template shadowrootmode="open">second</template></my-card>"#,
+            r#"<div><template shadowrootmode="none">a</template><template shadowrootmode="open">b</template></div>"#,
+            r#"<ul><template shadowrootmode="open">c</template></ul>"#,
+        );
+        let root = parse(html).unwrap();
+        let children = root.children();
+        let (card, div, ul) = (&children[0], &children[1], &children[2]);
+
+        let shadow_root = card.shadow_root().unwrap();
+        assert_eq!(shadow_root.shadow_root_mode(), Some(ShadowRootMode::Closed));
+        assert_eq!(shadow_root.first_child().unwrap().inner_text(), "shadow");
+        // only the first declarative shadow root is attached
+        assert_eq!(card.last_child().unwrap().shadow_root_mode(), None);
+        assert_eq!(div.shadow_root().unwrap().inner_html(), "b");
+        assert_eq!(div.first_child().unwrap().shadow_root_mode(), None);
+        // a `<ul>` may not be a shadow host
+        assert!(ul.shadow_root().is_none());
+        assert!(ul.first_child().unwrap().shadow_root_mode().is_none());
+        assert!(root.shadow_root().is_none());
+
+        let live: Vec<String> = root
+            .live_descendants()
+            .filter_map(|node| match &*node.get_payload() {
+                Payload::Text(text) => Some(text.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(live, ["light", "shadow", "b"]);
+        assert_eq!(root.to_html(), html);
+    }
+}
@@ -0,0 +1,173 @@
+use std::rc::Rc;
+
+use super::{NodeData, Payload};
+
+/// Elements that start on a new line.
+pub(super) const BLOCK_ELEMENTS: [&str; 37] = [
+    "address", "article", "aside", "blockquote", "body", "caption", "dd", "details",
+    "dialog", "div", "dl", "dt", "fieldset", "figcaption", "figure", "footer",
+    "form", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "html", "li",
+    "main", "nav", "ol", "p", "pre", "section", "summary", "table", "tr", "ul",
+];
+
+/// Elements that are separated from their siblings by a space.
+const CELL_ELEMENTS: [&str; 2] = ["td", "th"];
+
+/// Elements whose content is not text to show.
+pub(crate) const HIDDEN_ELEMENTS: [&str; 6] = ["head", "noscript", "script", "style", "template", "title"];
+
+impl NodeData {
+    /// Returns the text of this node and its descendants.
+    ///
+    /// Runs of whitespace are collapsed into one space except inside `<pre>`,
+    /// block elements such as `<p>` and `<li>` and `<br>` start a new line,
+    /// and the content of `<script>`, `<style>` and comments is skipped.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let node = html::parse("<ul><li>one <b>1</b></li><li>two</li></ul>").unwrap();
+    /// assert_eq!(node.inner_text(), "one 1\ntwo");
+    /// ```
+    pub fn inner_text(&self) -> String {
+        let mut text = String::new();
+        write_text(&mut text, self);
+        text.trim().to_string()
+    }
+}
+
+/// Writes the text of `node` to `text`.
+fn write_text(text: &mut String, node: &NodeData) {
+    let mut steps = Vec::new();
+    if let Some((preformatted, block)) = write_start(text, node, false) {
+        push_children(&mut steps, node, preformatted, block);
+    }
+    while let Some(step) = steps.pop() {
+        match step {
+            Step::Start(node, preformatted) => {
+                if let Some((preformatted, block)) = write_start(text, &node, preformatted) {
+                    push_children(&mut steps, &node, preformatted, block);
+                }
+            }
+            Step::BreakLine => break_line(text),
+        }
+    }
+}
+
+/// What [`write_text`] writes next.
+enum Step {
+    /// A node, and whether whitespace is kept in it.
+    Start(Rc<NodeData>, bool),
+    /// The new line after a block element.
+    BreakLine,
+}
+
+/// Pushes the steps that write the children of `node`, and the new line
+/// after them if `node` is a block element.
+fn push_children(steps: &mut Vec<Step>, node: &NodeData, preformatted: bool, block: bool) {
+    if block {
+        steps.push(Step::BreakLine);
+    }
+    steps.extend(node.get_children().iter().rev().map(|child| Step::Start(Rc::clone(child), preformatted)));
+}
+
+/// Writes the text of `node` that comes before its children.
+/// If `preformatted` is true, whitespace is kept as it is.
+/// Returns whether whitespace is kept in the children and whether `node`
+/// is a block element, or `None` if the children are not written.
+fn write_start(text: &mut String, node: &NodeData, preformatted: bool) -> Option<(bool, bool)> {
+    match &*node.get_payload() {
+        Payload::Tag(tag) => {
+            let name = tag.get_name().to_ascii_lowercase();
+            let name = name.as_str();
+            if name == "br" {
+                trim_spaces(text);
+                text.push('\n');
+                return None;
+            }
+
+            if HIDDEN_ELEMENTS.contains(&name) {
+                return None;
+            }
+
+            let block = BLOCK_ELEMENTS.contains(&name);
+            if block {
+                break_line(text);
+            } else if CELL_ELEMENTS.contains(&name) {
+                push_space(text);
+            }
+
+            return Some((preformatted || name == "pre", block));
+        }
+        Payload::Text(t) if preformatted => text.push_str(t),
+        Payload::Text(t) => {
+            for c in t.chars() {
+                if c.is_whitespace() {
+                    push_space(text);
+                } else {
+                    text.push(c);
+                }
+            }
+        }
+        Payload::RawText(_) | Payload::Comment(_) | Payload::ConditionalComment(_) | Payload::Doctype(_) => {}
+    }
+    None
+}
+
+/// Pushes ' ' unless `text` is at the beginning of a line or ends with a space.
+fn push_space(text: &mut String) {
+    if !text.is_empty() && !text.ends_with([' ', '\n']) {
+        text.push(' ');
+    }
+}
+
+/// Starts a new line unless `text` is already at the beginning of a line.
+fn break_line(text: &mut String) {
+    trim_spaces(text);
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+fn trim_spaces(text: &mut String) {
+    let len = text.trim_end_matches(' ').len();
+    text.truncate(len);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    #[test]
+    fn inner_text_test() {
+        let html = r#"
+        <div>
+          <h1>Title</h1>
+          <p>Hello, <b>world</b>!</p>
+          <!-- comment -->
+          <ul>
+            <li>one</li>
+            <li>two</li>
+          </ul>
+          line<br>break
+          <script>let x = 1;</script>
+          <table><tr><td>a</td><td>b</td></tr></table>
+          <pre>keep
+  this</pre>
+        </div>
+        "#;
+        let root = parse(html).unwrap();
+
+        assert_eq!(
+            root.inner_text(),
+            "Title\nHello, world!\none\ntwo\nline\nbreak\na b\nkeep\n  this"
+        );
+
+        // whatever the case of the names
+        let root = parse("<P>a</P><P>b<BR>c</P><Script>d</Script><PRE>e  f</PRE>").unwrap();
+        assert_eq!(root.inner_text(), "a\nb\nc\ne  f");
+
+        // deeply nested elements do not overflow the stack
+        let html = "<div>".repeat(100_000) + "a";
+        assert_eq!(parse(&html).unwrap().inner_text(), "a");
+    }
+}
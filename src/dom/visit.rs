@@ -0,0 +1,200 @@
+use std::rc::Rc;
+
+use super::{Doctype, NodeData, Payload, Tag};
+
+/// What [`NodeData::accept`] does after a callback of a [`Visitor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visit {
+    /// Visit the children of the node.
+    Continue,
+    /// Do not visit the children of the node. It is still left.
+    SkipChildren,
+    /// Stop the traversal. No more callbacks are called.
+    Stop,
+}
+
+/// Callbacks for the nodes of a tree, called by [`NodeData::accept`] in
+/// document order.
+///
+/// For each node, [`enter`](Visitor::enter) is called first, then the
+/// `visit_*` callback for its payload if `enter` returned
+/// [`Visit::Continue`], then the children are visited if that returned
+/// [`Visit::Continue`] too, and [`leave`](Visitor::leave) is called last.
+/// Every callback does nothing by default, so a visitor implements only
+/// the ones it needs.
+///
+/// # Examples
+/// ```rust
+/// use html::dom::{NodeData, Tag, Visit, Visitor};
+///
+/// /// Collects the links outside of `<nav>`.
+/// struct Links(Vec<String>);
+///
+/// impl Visitor for Links {
+///     fn visit_tag(&mut self, _: &NodeData, tag: &Tag) -> Visit {
+///         match tag.get_name() {
+///             "nav" => return Visit::SkipChildren,
+///             "a" => self.0.extend(tag.get_attribute_value("href")),
+///             _ => {}
+///         }
+///         Visit::Continue
+///     }
+/// }
+///
+/// let root = html::parse(r#"<nav><a href="/">home</a></nav><p><a href="/a">a</a></p>"#).unwrap();
+/// let mut links = Links(Vec::new());
+/// root.accept(&mut links);
+/// assert_eq!(links.0, ["/a"]);
+/// ```
+pub trait Visitor {
+    /// Called for every node before the callback for its payload.
+    fn enter(&mut self, _node: &NodeData) -> Visit {
+        Visit::Continue
+    }
+
+    /// Called for every node entered, after its children.
+    fn leave(&mut self, _node: &NodeData) {}
+
+    fn visit_tag(&mut self, _node: &NodeData, _tag: &Tag) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_text(&mut self, _node: &NodeData, _text: &str) -> Visit {
+        Visit::Continue
+    }
+
+    /// Called for the text of `<script>` and `<style>`.
+    fn visit_raw_text(&mut self, _node: &NodeData, _text: &str) -> Visit {
+        Visit::Continue
+    }
+
+    /// Called for comments, and for conditional comments with their
+    /// condition as `text`, whose content is visited as their children.
+    fn visit_comment(&mut self, _node: &NodeData, _text: &str) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_doctype(&mut self, _node: &NodeData, _doctype: &Doctype) -> Visit {
+        Visit::Continue
+    }
+}
+
+enum Step {
+    Enter(Rc<NodeData>),
+    Leave(Rc<NodeData>),
+}
+
+impl NodeData {
+    /// Visits this node and its descendants with `visitor`.
+    /// See [`Visitor`] for the order of the callbacks.
+    ///
+    /// The nodes are kept on a stack rather than the call stack, so deeply
+    /// nested trees can be visited. Returns [`Visit::Stop`] if a callback
+    /// stopped the traversal, or else [`Visit::Continue`].
+    pub fn accept<V: Visitor + ?Sized>(&self, visitor: &mut V) -> Visit {
+        match visit_node(self, visitor) {
+            Visit::Continue => {}
+            Visit::SkipChildren => {
+                visitor.leave(self);
+                return Visit::Continue;
+            }
+            Visit::Stop => return Visit::Stop,
+        }
+
+        let mut stack: Vec<Step> = self.get_children().iter().rev().cloned().map(Step::Enter).collect();
+        while let Some(step) = stack.pop() {
+            let node = match step {
+                Step::Enter(node) => node,
+                Step::Leave(node) => {
+                    visitor.leave(&node);
+                    continue;
+                }
+            };
+
+            match visit_node(&node, visitor) {
+                Visit::Continue => {
+                    stack.push(Step::Leave(Rc::clone(&node)));
+                    stack.extend(node.get_children().iter().rev().cloned().map(Step::Enter));
+                }
+                Visit::SkipChildren => visitor.leave(&node),
+                Visit::Stop => return Visit::Stop,
+            }
+        }
+        visitor.leave(self);
+
+        Visit::Continue
+    }
+}
+
+/// Calls `enter` and the callback for the payload of `node`.
+fn visit_node<V: Visitor + ?Sized>(node: &NodeData, visitor: &mut V) -> Visit {
+    match visitor.enter(node) {
+        Visit::Continue => {}
+        visit => return visit,
+    }
+
+    match &*node.get_payload() {
+        Payload::Tag(tag) => visitor.visit_tag(node, tag),
+        Payload::Text(text) => visitor.visit_text(node, text),
+        Payload::RawText(text) => visitor.visit_raw_text(node, text),
+        Payload::Comment(text) | Payload::ConditionalComment(text) => visitor.visit_comment(node, text),
+        Payload::Doctype(doctype) => visitor.visit_doctype(node, doctype),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[derive(Default)]
+    struct Outline {
+        events: Vec<String>,
+    }
+
+    impl Visitor for Outline {
+        fn leave(&mut self, node: &NodeData) {
+            if let Payload::Tag(tag) = &*node.get_payload() {
+                self.events.push(format!("/{}", tag.get_name()));
+            }
+        }
+
+        fn visit_tag(&mut self, _: &NodeData, tag: &Tag) -> Visit {
+            self.events.push(tag.get_name().to_string());
+            match tag.get_name() {
+                "script" => Visit::SkipChildren,
+                "footer" => Visit::Stop,
+                _ => Visit::Continue,
+            }
+        }
+
+        fn visit_text(&mut self, _: &NodeData, text: &str) -> Visit {
+            self.events.push(format!("{:?}", text));
+            Visit::Continue
+        }
+
+        fn visit_comment(&mut self, _: &NodeData, text: &str) -> Visit {
+            self.events.push(format!("<!--{}-->", text));
+            Visit::Continue
+        }
+    }
+
+    #[test]
+    fn visitor_test() {
+        let root = parse("<div><p>a<b>b</b></p><!--c--><script>x</script></div><footer>f</footer><p>g</p>").unwrap();
+        let mut outline = Outline::default();
+        assert_eq!(root.accept(&mut outline), Visit::Stop);
+        assert_eq!(
+            outline.events,
+            [
+                "root", "div", "p", "\"a\"", "b", "\"b\"", "/b", "/p", "<!--c-->", "script", "/script", "/div",
+                "footer",
+            ]
+        );
+
+        let mut outline = Outline::default();
+        let div = root.first_child().unwrap();
+        assert_eq!(div.accept(&mut outline), Visit::Continue);
+        assert_eq!(outline.events.last().unwrap(), "/div");
+    }
+}
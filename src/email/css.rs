@@ -0,0 +1,254 @@
+use std::rc::Rc;
+
+use crate::dom::{strip_comments, NodeData, Payload, StyleDeclarations};
+use crate::selector::Selector;
+
+/// Elements that are not rendered, which get no styles and whose
+/// descendants get none either.
+const UNSTYLED_ELEMENTS: [&str; 8] = ["base", "head", "link", "meta", "script", "style", "template", "title"];
+
+/// A style rule of a style sheet. `h1, .title { color: red }`
+struct Rule {
+    selectors: Vec<Selector>,
+    declarations: StyleDeclarations,
+}
+
+/// A declaration that applies to an element, with what it is ordered by.
+struct Applied<'a> {
+    important: bool,
+    specificity: (u32, u32, u32),
+    // the position of the rule in the style sheet
+    order: usize,
+    name: &'a str,
+    value: &'a str,
+}
+
+/// Moves the rules of `style_sheet` into the `style` attributes of the
+/// descendants of `root` that they match, as the cascade of CSS orders
+/// them: by `!important`, then specificity, then the order of the rules.
+/// A declaration in a `style` attribute wins over those of the rules, but
+/// not over an `!important` one.
+///
+/// At-rules such as `@media` and the rules whose selectors are not
+/// supported, such as those with `:hover`, are left out: they are kept in
+/// the `<style>` elements for the clients that read them. The elements in
+/// `<head>` and `<template>` are not styled.
+///
+/// This is a [`CssInliner`](super::CssInliner).
+///
+/// # Examples
+/// ```rust
+/// let root = html::parse(r#"<p class="note" style="margin: 0">Hi <a href="/">you</a></p>"#).unwrap();
+/// html::email::inline_css(&root, "p { color: gray; margin: 1em } .note { color: red } a:hover { color: blue }");
+///
+/// assert_eq!(root.to_html(), r#"<p class="note" style="color: red; margin: 0">Hi <a href="/">you</a></p>"#);
+/// ```
+pub fn inline_css(root: &NodeData, style_sheet: &str) {
+    let rules = parse_style_sheet(style_sheet);
+    if rules.is_empty() {
+        return;
+    }
+
+    let mut stack: Vec<Rc<NodeData>> = root.children().into_iter().rev().collect();
+    while let Some(node) = stack.pop() {
+        let tag = match node.get_tag() {
+            Some(tag) => tag,
+            None => continue,
+        };
+        if UNSTYLED_ELEMENTS.iter().any(|name| tag.get_name().eq_ignore_ascii_case(name)) {
+            continue;
+        }
+        stack.extend(node.get_children().iter().rev().cloned());
+
+        let mut applied = Vec::new();
+        for (order, rule) in rules.iter().enumerate() {
+            // the most specific selector of the rule that matches
+            let matched = rule.selectors.iter().filter(|selector| selector.matches(&node));
+            let specificity = match matched.map(Selector::specificity).max() {
+                Some(specificity) => specificity,
+                None => continue,
+            };
+            applied.extend(rule.declarations.iter().map(|(name, value)| Applied {
+                important: rule.declarations.is_important(name),
+                specificity,
+                order,
+                name,
+                value,
+            }));
+        }
+        if applied.is_empty() {
+            continue;
+        }
+        applied.sort_by_key(|declaration| (declaration.important, declaration.specificity, declaration.order));
+
+        let inline = tag.style().unwrap_or_default();
+        let mut style = StyleDeclarations::new();
+        for declaration in applied.iter().filter(|declaration| !declaration.important) {
+            style.set(declaration.name, declaration.value);
+        }
+        for (name, value) in inline.iter() {
+            match inline.is_important(name) {
+                true => style.set_important(name, value),
+                false => style.set(name, value),
+            };
+        }
+        for declaration in applied.iter().filter(|declaration| declaration.important) {
+            if !inline.is_important(declaration.name) {
+                style.set(declaration.name, declaration.value);
+            }
+        }
+
+        drop(tag);
+        if style != inline {
+            if let Payload::Tag(tag) = &mut *node.payload_mut() {
+                tag.set_style(&style);
+            }
+        }
+    }
+}
+
+/// Returns the style rules of `style_sheet` with a selector that is
+/// supported. At-rules are skipped.
+fn parse_style_sheet(style_sheet: &str) -> Vec<Rule> {
+    let style_sheet = strip_comments(style_sheet);
+    let mut rules = Vec::new();
+    let mut rest = style_sheet.as_str();
+    loop {
+        rest = rest.trim_start();
+        // `@import url(a.css);` or a `{` block
+        let (prelude_end, c) = match find_outside_strings(rest, |c, _| c == ';' || c == '{') {
+            Some(found) => found,
+            None => break,
+        };
+        let prelude = rest[..prelude_end].trim();
+        if c == ';' {
+            rest = &rest[prelude_end + 1..];
+            continue;
+        }
+
+        let block = &rest[prelude_end + 1..];
+        let block_end = find_outside_strings(block, |c, depth| c == '}' && depth == 0);
+        let block_end = block_end.map_or(block.len(), |(i, _)| i);
+        rest = block.get(block_end + 1..).unwrap_or("");
+        if prelude.starts_with('@') {
+            continue;
+        }
+
+        let selectors: Vec<Selector> =
+            split_selectors(prelude).filter_map(|selector| Selector::parse(selector).ok()).collect();
+        if !selectors.is_empty() {
+            rules.push(Rule {
+                selectors,
+                declarations: StyleDeclarations::parse(&block[..block_end]),
+            });
+        }
+    }
+    rules
+}
+
+/// Splits a selector list at the `,` that are outside of strings and
+/// parentheses, so that each selector is parsed on its own and one that is
+/// not supported leaves out only itself. `a:hover, b`
+fn split_selectors(selectors: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(selectors);
+    std::iter::from_fn(move || {
+        let current = rest?;
+        match find_outside_strings(current, |c, depth| c == ',' && depth == 0) {
+            Some((i, _)) => {
+                rest = Some(&current[i + 1..]);
+                Some(&current[..i])
+            }
+            None => {
+                rest = None;
+                Some(current)
+            }
+        }
+    })
+}
+
+/// Returns the offset and the first character outside of strings that
+/// `is_end` is true for, with the depth of the brackets it is in.
+fn find_outside_strings<F>(text: &str, is_end: F) -> Option<(usize, char)>
+where
+    F: Fn(char, usize) -> bool,
+{
+    let mut quote = None;
+    let mut escaped = false;
+    let mut depth = 0usize;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (_, '\\') => escaped = true,
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, _) if is_end(c, depth) => return Some((i, c)),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn inline_css_test() {
+        let root = parse(concat!(
+            r#"<head><title>t</title></head><body><div id="main" class="box">"#,
+            r#"<p class="a" style="color: green; padding: 0 !important">1</p><p class="a b">2</p>"#,
+            r#"<p>3</p><a href="/" title="x{y}">4</a></div></body>"#,
+        ))
+        .unwrap();
+        inline_css(
+            &root,
+            r#"
+            /* p { display: none } */
+            @import url("a;b.css");
+            @media (max-width: 600px) { p { color: black } .a { margin: 0 } }
+            * { margin: 0 }
+            p, h1 { color: gray; padding: 1px }
+            .box .a { color: red }
+            #main p.b { color: blue }
+            div > .a { color: orange }
+            p:hover, p::before, .b { font-weight: bold; padding: 2px !important }
+            a[title="x{y}"] { content: "}" }
+            p:last-child, :not(a) { border: 0 }
+            title { color: red }
+            "#,
+        );
+
+        let styles: Vec<(String, String)> = root
+            .descendants()
+            .filter_map(|node| match &*node.get_payload() {
+                Payload::Tag(tag) => Some((tag.get_name().to_string(), tag.get_attribute_value("style")?)),
+                _ => None,
+            })
+            .collect();
+        let styles: Vec<(&str, &str)> = styles.iter().map(|(name, style)| (name.as_str(), style.as_str())).collect();
+        assert_eq!(
+            styles,
+            [
+                ("body", "margin: 0; border: 0"),
+                ("div", "margin: 0; border: 0"),
+                // the `style` attribute wins over the rules, but not over `!important`
+                ("p", "margin: 0; color: green; padding: 0 !important; border: 0"),
+                ("p", "margin: 0; color: blue; padding: 2px; border: 0; font-weight: bold"),
+                ("p", "margin: 0; color: gray; padding: 1px; border: 0"),
+                ("a", "margin: 0; content: \"}\""),
+            ]
+        );
+
+        let root = parse("<style>p { color: red }</style><p>x</p>").unwrap();
+        crate::email::inline_styles(&root, inline_css);
+        assert_eq!(root.to_html(), r#"<style>p { color: red }</style><p style="color: red">x</p>"#);
+
+        let unchanged = parse(r#"<p style="color:red">x</p>"#).unwrap();
+        inline_css(&unchanged, "@media print { p { color: blue } } p:hover { color: blue } ");
+        assert_eq!(unchanged.to_html(), r#"<p style="color:red">x</p>"#);
+    }
+}
@@ -0,0 +1,136 @@
+//! HTML email, whose clients render much less than browsers do.
+//!
+//! An email is laid out with tables, styled with attributes and inline
+//! styles, and has markup for Outlook in conditional comments and in
+//! prefixed tags such as `<o:p>` and `<v:roundrect>`. [`parse`] parses it
+//! with [`ParseOptions::email`] so that all of this is written back as it
+//! was, and [`MinifyOptions::email`] minifies it without leaving out the
+//! end tags and quotes that email clients need.
+//!
+//! Most clients ignore `<style>` elements, so the rules of the style
+//! sheets are moved into `style` attributes before sending. [`inline_styles`]
+//! hands the style sheets of a tree to a [`CssInliner`] that does that,
+//! such as [`inline_css`].
+//!
+//! # Examples
+//! ```rust
+//! use html::dom::{MinifyOptions, NodeData};
+//!
+//! let email = html::email::parse(concat!(
+//!     "<style>p { color: red }</style>\n",
+//!     "<table width=\"600\"><tr><td align='center'><p>Hi<o:p></o:p></p></td></tr></table>\n",
+//!     "<!--[if mso]><v:rect fillcolor=\"#fff\"></v:rect><![endif]-->",
+//! ))
+//! .unwrap();
+//!
+//! let mut style_sheets = Vec::new();
+//! html::email::inline_styles(&email, |_: &NodeData, style_sheet: &str| style_sheets.push(style_sheet.to_string()));
+//! assert_eq!(style_sheets, ["p { color: red }"]);
+//!
+//! assert_eq!(
+//!     email.to_minified_html_with_options(&MinifyOptions::email()),
+//!     concat!(
+//!         "<style>p { color: red }</style><table width=\"600\"><tr><td align=\"center\"><p>Hi<o:p></o:p></p></td></tr>",
+//!         "</table><!--[if mso]><v:rect fillcolor=\"#fff\"></v:rect><![endif]-->",
+//!     )
+//! );
+//! ```
+
+use crate::dom::{Node, NodeData, Payload};
+#[cfg(doc)]
+use crate::dom::MinifyOptions;
+use crate::{ParseError, ParseOptions};
+
+mod css;
+
+pub use css::inline_css;
+
+/// Moves the rules of a style sheet into the `style` attributes of the
+/// elements of a tree that they match. A closure that takes the root and
+/// the style sheet is one.
+pub trait CssInliner {
+    fn inline_css(&mut self, root: &NodeData, style_sheet: &str);
+}
+
+impl<F> CssInliner for F
+where
+    F: FnMut(&NodeData, &str),
+{
+    fn inline_css(&mut self, root: &NodeData, style_sheet: &str) {
+        self(root, style_sheet)
+    }
+}
+
+/// Parses an email with [`ParseOptions::email`].
+///
+/// # Errors
+/// * [`ParseError`] if the document cannot be parsed.
+pub fn parse(html: &str) -> Result<Node, ParseError> {
+    crate::parse_with_options(html, &ParseOptions::email())
+}
+
+/// Calls `inliner` with `root` and the text of its `<style>` elements in
+/// document order, separated by newlines, if it has any. The `<style>`
+/// elements in `<template>` elements and in conditional comments are left
+/// out, and the `<style>` elements are kept for the clients that read them.
+pub fn inline_styles<I: CssInliner>(root: &NodeData, mut inliner: I) {
+    let style_sheets: Vec<String> = root
+        .live_descendants()
+        .filter(|node| match &*node.get_payload() {
+            Payload::Tag(tag) => tag.get_name().eq_ignore_ascii_case("style"),
+            _ => false,
+        })
+        .filter(|node| {
+            !node.ancestors().any(|ancestor| matches!(&*ancestor.get_payload(), Payload::ConditionalComment(_)))
+        })
+        .map(|style| style.inner_html())
+        .collect();
+
+    if !style_sheets.is_empty() {
+        inliner.inline_css(root, &style_sheets.join("\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::MinifyOptions;
+
+    #[test]
+    fn email_test() {
+        let html = concat!(
+            "<!DOCTYPE html>\n<html xmlns:v=\"urn:schemas-microsoft-com:vml\">\n<head>\n",
+            "  <style>td { padding: 0 }</style>\n",
+            "  <!--[if mso]><style>.x { color: red }</style><![endif]-->\n",
+            "</head>\n<body>\n",
+            "  <table role=presentation cellpadding='0' BORDER=\"0\">\n",
+            "    <tr>\n      <td class=\"x\" align=center><P>Hi&nbsp;<o:p></o:p></P></td>\n    </tr>\n  </table>\n",
+            "  <v:roundrect arcsize=\"10%\"><w:anchorlock/></v:roundrect>\n",
+            "  <template><style>a { b: c }</style></template>\n",
+            "</body>\n</html>\n",
+        );
+        let email = parse(html).unwrap();
+        assert_eq!(email.to_html(), html);
+        assert_eq!(email.query_name("o:p").count(), 1);
+        assert_eq!(email.query_name("v:roundrect").next().unwrap().children().len(), 1);
+
+        let mut calls = Vec::new();
+        inline_styles(&email, |root: &NodeData, style_sheet: &str| {
+            calls.push((root.is_document_root(), style_sheet.to_string()));
+        });
+        assert_eq!(calls, [(true, String::from("td { padding: 0 }"))]);
+
+        // a conditional comment parsed into nodes is left out too
+        let options = ParseOptions { conditional_comments: true, ..ParseOptions::email() };
+        let email = crate::parse_with_options(html, &options).unwrap();
+        let mut style_sheets = Vec::new();
+        inline_styles(&email, |_: &NodeData, style_sheet: &str| style_sheets.push(style_sheet.to_string()));
+        assert_eq!(style_sheets, ["td { padding: 0 }"]);
+        inline_styles(&parse("<p>x</p>").unwrap(), |_: &NodeData, _: &str| panic!("no style sheet"));
+
+        let minified = email.to_minified_html_with_options(&MinifyOptions::email());
+        assert!(minified.contains("<td class=\"x\" align=center><P>Hi\u{a0}<o:p></o:p></P></td></tr></table>"));
+        assert!(minified.contains("<!--[if mso]><style>.x { color: red }</style><![endif]-->"));
+        assert!(minified.contains(r#"<table role=presentation cellpadding="0" BORDER="0">"#));
+    }
+}
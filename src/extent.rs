@@ -1,73 +1,361 @@
 use std::rc::Rc;
-use crate::dom::{NodeData, Payload};
+use crate::dom::{Atom, NodeData, Payload};
 
-pub struct Attribute<'a>(&'a str, &'a str);
+/// An attribute name and the value it must have. `Attribute("class", "h1")`
+pub struct Attribute<'a>(pub &'a str, pub &'a str);
 
-pub fn get_node_by_attribute(result: &mut Option<&Rc<NodeData>>, source: &Rc<NodeData>, attribute: &Attribute) {
-    if let Payload::Tag(tag) = source.get_payload() {
-        if let Some(attribute_value) = tag.get_attribute_value(attribute.0) {
-            if &attribute_value == attribute.1 {
-                result.replace(source);
-                return;
-            }
-        }
+/// Returns `source` followed by its descendants in document order.
+fn self_and_descendants(source: &Rc<NodeData>) -> impl Iterator<Item = Rc<NodeData>> {
+    std::iter::once(Rc::clone(source)).chain(source.descendants())
+}
+
+/// How an attribute value is matched, like the attribute selectors of CSS.
+///
+/// Each variant is the attribute name and the value to compare with.
+/// Except for `Exact`, an empty value matches nothing.
+///
+/// # Examples
+/// ```rust
+/// use html::AttrMatcher;
+///
+/// let root = html::parse(r#"<a href="https://a.com">a</a><a href="/b">b</a>"#).unwrap();
+/// let root = root.get_copy_of_internal_arc();
+///
+/// let external = html::get_nodes_by_attribute(&root, AttrMatcher::Prefix("href", "https://"));
+/// assert_eq!(external.len(), 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttrMatcher<'a> {
+    /// `[name=value]`
+    Exact(&'a str, &'a str),
+    /// `[name^=value]`
+    Prefix(&'a str, &'a str),
+    /// `[name$=value]`
+    Suffix(&'a str, &'a str),
+    /// `[name*=value]`
+    Contains(&'a str, &'a str),
+    /// `[name~=value]`, the value is one of the space-separated words.
+    WordInList(&'a str, &'a str),
+}
+
+impl AttrMatcher<'_> {
+    /// Returns true if `node` is a tag with a matching attribute.
+    pub fn matches(&self, node: &NodeData) -> bool {
+        self.matches_payload(&node.get_payload())
     }
 
-    for child in source.get_children() {
-        get_node_by_attribute(result, child, attribute);
-        if result.is_some() {
-            break;
+    /// Returns true if `payload` is a tag with a matching attribute.
+    pub fn matches_payload(&self, payload: &Payload) -> bool {
+        let (name, value) = match *self {
+            AttrMatcher::Exact(name, value)
+            | AttrMatcher::Prefix(name, value)
+            | AttrMatcher::Suffix(name, value)
+            | AttrMatcher::Contains(name, value)
+            | AttrMatcher::WordInList(name, value) => (name, value),
+        };
+
+        let actual = match payload {
+            Payload::Tag(tag) => match tag.get_attribute(name) {
+                Some(actual) => actual.as_str(),
+                None => return false,
+            },
+            _ => return false,
+        };
+
+        if value.is_empty() && !matches!(self, AttrMatcher::Exact(..)) {
+            return false;
+        }
+
+        match self {
+            AttrMatcher::Exact(..) => actual == value,
+            AttrMatcher::Prefix(..) => actual.starts_with(value),
+            AttrMatcher::Suffix(..) => actual.ends_with(value),
+            AttrMatcher::Contains(..) => actual.contains(value),
+            AttrMatcher::WordInList(..) => actual.split_ascii_whitespace().any(|word| word == value),
         }
     }
 }
 
-pub fn get_node_by_name(result: &mut Option<&Rc<NodeData>>, source: &Rc<NodeData>, tag_name: &str) {
-    if let Payload::Tag(tag) = source.get_payload() {
-        if tag.get_name() == tag_name {
-            result.replace(source);
-            return;
-        }
+impl<'a> From<&Attribute<'a>> for AttrMatcher<'a> {
+    fn from(attribute: &Attribute<'a>) -> AttrMatcher<'a> {
+        AttrMatcher::Exact(attribute.0, attribute.1)
     }
+}
 
-    for child in source.get_children() {
-        get_node_by_name(result, child, tag_name);
-        if result.is_some() {
-            break;
-        }
+impl<'a> From<Attribute<'a>> for AttrMatcher<'a> {
+    fn from(attribute: Attribute<'a>) -> AttrMatcher<'a> {
+        AttrMatcher::Exact(attribute.0, attribute.1)
     }
 }
 
+/// Compares atoms, so that known names are compared by their index.
+fn has_name(node: &NodeData, tag_name: &Atom) -> bool {
+    match &*node.get_payload() {
+        Payload::Tag(tag) => tag.get_name_atom() == tag_name,
+        _ => false,
+    }
+}
 
-pub fn get_nodes_by_attribute(result: &mut Vec<&Rc<NodeData>>, source: &Rc<NodeData>, attribute: &Attribute) {
-    if let Payload::Tag(tag) = source.get_payload() {
-        if let Some(attribute_value) = tag.get_attribute_value(attribute.0) {
-            if &attribute_value == attribute.1 {
-                result.push(source);
-            }
-        }
+/// Returns the first node that has a matching attribute, `source` included.
+/// `attribute` is an [`Attribute`] or an [`AttrMatcher`].
+pub fn get_node_by_attribute<'a>(source: &Rc<NodeData>, attribute: impl Into<AttrMatcher<'a>>) -> Option<Rc<NodeData>> {
+    let matcher = attribute.into();
+    self_and_descendants(source).find(|node| matcher.matches(node))
+}
+
+/// Returns the first node named `tag_name`, `source` included.
+pub fn get_node_by_name(source: &Rc<NodeData>, tag_name: &str) -> Option<Rc<NodeData>> {
+    let tag_name = Atom::from(tag_name);
+    self_and_descendants(source).find(|node| has_name(node, &tag_name))
+}
+
+/// Returns the first node whose `id` attribute is `id`, `source` included.
+pub fn get_node_by_id(source: &Rc<NodeData>, id: &str) -> Option<Rc<NodeData>> {
+    get_node_by_attribute(source, AttrMatcher::Exact("id", id))
+}
+
+/// Returns all the nodes that have a matching attribute, `source` included.
+/// `attribute` is an [`Attribute`] or an [`AttrMatcher`].
+pub fn get_nodes_by_attribute<'a>(source: &Rc<NodeData>, attribute: impl Into<AttrMatcher<'a>>) -> Vec<Rc<NodeData>> {
+    let matcher = attribute.into();
+    self_and_descendants(source)
+        .filter(|node| matcher.matches(node))
+        .collect()
+}
+
+/// Returns all the nodes named `tag_name`, `source` included.
+pub fn get_nodes_by_name(source: &Rc<NodeData>, tag_name: &str) -> Vec<Rc<NodeData>> {
+    let tag_name = Atom::from(tag_name);
+    self_and_descendants(source)
+        .filter(|node| has_name(node, &tag_name))
+        .collect()
+}
+
+/// Returns the nodes whose space-separated `class` attribute contains `class_name`.
+///
+/// `class="btn primary"` matches both `"btn"` and `"primary"`.
+pub fn get_nodes_by_class(source: &Rc<NodeData>, class_name: &str) -> Vec<Rc<NodeData>> {
+    self_and_descendants(source)
+        .filter(|node| node.has_class(class_name))
+        .collect()
+}
+
+/// Returns the nodes whose [`role`](NodeData::role) is `role`, explicit or
+/// implicit, `source` included. Roles are matched without case.
+///
+/// # Examples
+/// ```rust
+/// let root = html::parse(r#"<button>a</button><div role="button">b</div><input type="submit" value="c">"#).unwrap();
+/// let buttons = html::get_nodes_by_role(&root.get_copy_of_internal_arc(), "button");
+///
+/// assert_eq!(buttons.len(), 3);
+/// ```
+pub fn get_nodes_by_role(source: &Rc<NodeData>, role: &str) -> Vec<Rc<NodeData>> {
+    self_and_descendants(source)
+        .filter(|node| node.role().is_some_and(|node_role| node_role.eq_ignore_ascii_case(role)))
+        .collect()
+}
+
+/// Returns the first node for which `predicate` is true, `source` included.
+///
+/// # Examples
+/// ```rust
+/// use html::dom::Payload;
+///
+/// let root = html::parse("<div>Name</div><div>Price: 10</div>").unwrap();
+/// let price = html::find(&root.get_copy_of_internal_arc(), |node| {
+///     matches!(&*node.get_payload(), Payload::Tag(tag) if tag.get_name() == "div")
+///         && node.inner_text().contains("Price")
+/// });
+///
+/// assert_eq!(price.unwrap().inner_text(), "Price: 10");
+/// ```
+pub fn find<F>(source: &Rc<NodeData>, predicate: F) -> Option<Rc<NodeData>>
+where
+    F: Fn(&NodeData) -> bool,
+{
+    self_and_descendants(source).find(|node| predicate(node))
+}
+
+/// Returns all the nodes for which `predicate` is true, `source` included.
+pub fn find_all<F>(source: &Rc<NodeData>, predicate: F) -> Vec<Rc<NodeData>>
+where
+    F: Fn(&NodeData) -> bool,
+{
+    self_and_descendants(source)
+        .filter(|node| predicate(node))
+        .collect()
+}
+
+/// Returns the tags whose [`inner_text`](NodeData::inner_text) is matched
+/// by `predicate`, `source` included, in document order.
+///
+/// The text of a tag is the text of its descendants too, so an ancestor of
+/// a match with no other text matches as well. `predicate` may be any
+/// matcher, such as a regular expression: `|text| re.is_match(text)`.
+///
+/// # Examples
+/// ```rust
+/// let root = html::parse("<table><tr><td>Item</td><td>1</td></tr><tr><td>Total</td><td>9</td></tr></table>").unwrap();
+/// let total = html::find_by_text(&root.get_copy_of_internal_arc(), |text| text == "Total");
+///
+/// assert_eq!(total.len(), 1);
+/// assert_eq!(total[0].next_sibling().unwrap().inner_text(), "9");
+/// ```
+pub fn find_by_text<F>(source: &Rc<NodeData>, predicate: F) -> Vec<Rc<NodeData>>
+where
+    F: Fn(&str) -> bool,
+{
+    self_and_descendants(source)
+        .filter(|node| matches!(&*node.get_payload(), Payload::Tag(_)) && !node.is_document_root())
+        .filter(|node| predicate(&node.inner_text()))
+        .collect()
+}
+
+impl NodeData {
+    /// Returns an iterator over the descendants named `tag_name`.
+    /// The node itself is not included.
+    ///
+    /// The tree is walked only as far as the iterator is consumed, so
+    /// taking the first few matches does not visit the rest of the document.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let root = html::parse("<a href='/1'>1</a><p><a href='/2'>2</a></p><a href='/3'>3</a>").unwrap();
+    /// let links: Vec<String> = root.query_name("a").take(2).map(|a| a.inner_text()).collect();
+    ///
+    /// assert_eq!(links, ["1", "2"]);
+    /// ```
+    pub fn query_name<'a>(&self, tag_name: &'a str) -> impl Iterator<Item = Rc<NodeData>> + 'a {
+        let tag_name = Atom::from(tag_name);
+        self.descendants().filter(move |node| has_name(node, &tag_name))
+    }
+
+    /// Returns an iterator over the descendants that have a matching attribute.
+    /// `attribute` is an [`Attribute`] or an [`AttrMatcher`].
+    /// The node itself is not included.
+    pub fn query_attr<'a>(&self, attribute: impl Into<AttrMatcher<'a>>) -> impl Iterator<Item = Rc<NodeData>> + 'a {
+        let matcher = attribute.into();
+        self.descendants().filter(move |node| matcher.matches(node))
     }
 
-    for child in source.get_children() {
-        get_nodes_by_attribute(result, child, attribute);
+    /// Returns an iterator over the descendants whose `class` attribute
+    /// contains `class_name`. The node itself is not included.
+    pub fn query_class<'a>(&self, class_name: &'a str) -> impl Iterator<Item = Rc<NodeData>> + 'a {
+        self.descendants().filter(move |node| node.has_class(class_name))
     }
 }
 
-pub fn get_nodes_by_name(result: &mut Vec<&Rc<NodeData>>, source: &Rc<NodeData>, tag_name: &str) {
-    if let Payload::Tag(tag) = source.get_payload() {
-        if tag.get_name() == tag_name {
-            result.push(source);
-        }
+pub fn get_first_child(node: &Rc<NodeData>) -> Option<Rc<NodeData>> {
+    node.first_child()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn id_test() {
+        let root = parse(r#"<div id="main"><h1 id="header">a</h1><h1 id="header">b</h1></div>"#).unwrap();
+        let root = root.get_copy_of_internal_arc();
+
+        let header = get_node_by_id(&root, "header").unwrap();
+        assert_eq!(header.inner_text(), "a");
+        assert!(get_node_by_id(&root, "main").is_some());
+        assert!(get_node_by_id(&root, "footer").is_none());
     }
 
-    for child in source.get_children() {
-        get_nodes_by_name(result, child, tag_name);
+    #[test]
+    fn class_test() {
+        let html = r#"
+        <button class="btn primary">a</button>
+        <button class=" btn ">b</button>
+        <button class="btn-large">c</button>
+        "#;
+        let root = parse(html).unwrap().get_copy_of_internal_arc();
+
+        assert_eq!(get_nodes_by_class(&root, "btn").len(), 2);
+        assert_eq!(get_nodes_by_class(&root, "primary").len(), 1);
     }
-}
 
-pub fn get_first_child(node: &Rc<NodeData>) -> Option<&Rc<NodeData>> {
-    let children = node.get_children();
-    match children.len() {
-        0 => None,
-        _ => Some(&children[0]),
+    #[test]
+    fn attr_matcher_test() {
+        let html = r#"
+        <a href="https://a.com/x.pdf" rel="nofollow noopener">a</a>
+        <a href="/b.pdf" rel="noopener">b</a>
+        <a href="/c.html">c</a>
+        "#;
+        let root = parse(html).unwrap().get_copy_of_internal_arc();
+
+        assert_eq!(get_nodes_by_attribute(&root, AttrMatcher::Prefix("href", "https://")).len(), 1);
+        assert_eq!(get_nodes_by_attribute(&root, AttrMatcher::Suffix("href", ".pdf")).len(), 2);
+        assert_eq!(get_nodes_by_attribute(&root, AttrMatcher::Contains("href", "b.")).len(), 1);
+        assert_eq!(get_nodes_by_attribute(&root, AttrMatcher::WordInList("rel", "noopener")).len(), 2);
+        assert_eq!(get_nodes_by_attribute(&root, AttrMatcher::WordInList("rel", "nofoll")).len(), 0);
+        assert_eq!(get_nodes_by_attribute(&root, AttrMatcher::Prefix("href", "")).len(), 0);
+        assert_eq!(get_nodes_by_attribute(&root, AttrMatcher::Exact("href", "/c.html")).len(), 1);
+    }
+
+    #[test]
+    fn find_test() {
+        let root = parse("<p>one</p><p>two <b>2</b></p><!-- two -->").unwrap().get_copy_of_internal_arc();
+
+        let found = find(&root, |node| has_name(node, &Atom::from("p")) && node.inner_text().starts_with("two")).unwrap();
+        assert_eq!(found.inner_text(), "two 2");
+        assert_eq!(find_all(&root, |node| matches!(&*node.get_payload(), Payload::Text(_))).len(), 3);
+        assert!(find(&root, |node| has_name(node, &Atom::from("table"))).is_none());
+    }
+
+    #[test]
+    fn find_by_text_test() {
+        let root = parse("<div><p>Total: <b>9</b></p></div><p>total</p>").unwrap().get_copy_of_internal_arc();
+
+        let names = |nodes: Vec<Rc<NodeData>>| -> Vec<String> {
+            nodes.iter().map(|node| node.tag_name().unwrap().to_string()).collect()
+        };
+        assert_eq!(names(find_by_text(&root, |text| text.starts_with("Total"))), ["div", "p"]);
+        assert_eq!(names(find_by_text(&root, |text| text.eq_ignore_ascii_case("total"))), ["p"]);
+        assert_eq!(names(find_by_text(&root, |text| text == "9")), ["b"]);
+    }
+
+    #[test]
+    fn query_test() {
+        let html = r#"<div class="a"><a href="/1">1</a><a href="https://2">2</a><p class="b a"><a>3</a></p></div>"#;
+        let root = parse(html).unwrap();
+
+        let mut links = root.query_name("a");
+        assert_eq!(links.next().unwrap().inner_text(), "1");
+        assert_eq!(links.next().unwrap().inner_text(), "2");
+        assert_eq!(links.count(), 1);
+
+        let external: Vec<String> = root
+            .query_attr(AttrMatcher::Prefix("href", "https://"))
+            .map(|node| node.inner_text())
+            .collect();
+        assert_eq!(external, ["2"]);
+        assert_eq!(root.query_attr(Attribute("href", "/1")).count(), 1);
+        assert_eq!(root.query_class("a").count(), 2);
+
+        let p = root.query_class("b").next().unwrap();
+        assert_eq!(p.query_class("a").count(), 0);
+    }
+
+    #[test]
+    fn nested_query_test() {
+        let html = r#"
+        <ul class="list"><li>a</li><li>b</li></ul>
+        <ul><li>c</li></ul>
+        "#;
+        let root = parse(html).unwrap().get_copy_of_internal_arc();
+
+        let list = get_node_by_attribute(&root, Attribute("class", "list")).unwrap();
+        let items = get_nodes_by_name(&list, "li");
+        assert_eq!(items.len(), 2);
+        assert_eq!(get_nodes_by_name(&root, "li").len(), 3);
+
+        let first = get_node_by_name(&items[1], "li").unwrap();
+        assert!(Rc::ptr_eq(&first, &items[1]));
+        assert_eq!(get_first_child(&first).unwrap().inner_text(), "b");
     }
 }
@@ -0,0 +1,226 @@
+use crate::dom::{NodeData, Payload, Tag};
+
+/// A form and its fields as the document writes them, before a user
+/// changes anything. Returned by [`form`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormModel {
+    /// The `action` as it is written.
+    pub action: Option<String>,
+    /// The lowercased `method`, `get` if it is missing or invalid.
+    pub method: String,
+    /// The `<input>`, `<select>` and `<textarea>` fields in document order.
+    pub fields: Vec<FormField>,
+}
+
+/// A field of a [`FormModel`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormField {
+    pub name: Option<String>,
+    /// The lowercased `type` of an `<input>`, `text` if it is missing,
+    /// `select`, `select-multiple` or `textarea`.
+    pub field_type: String,
+    /// The default value: the `value` of an `<input>`, `on` for a checkbox
+    /// or radio button without one, the content of a `<textarea>`, or the
+    /// first selected option of a `<select>`.
+    pub value: String,
+    /// The `checked` of a checkbox or radio button.
+    pub checked: bool,
+    pub disabled: bool,
+    /// The options of a `<select>`, those in `<optgroup>` included.
+    pub options: Vec<FormOption>,
+}
+
+/// An `<option>` of a `<select>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormOption {
+    /// The `value`, or the text of the option if it has none.
+    pub value: String,
+    /// The text of the option with its whitespace collapsed.
+    pub text: String,
+    pub selected: bool,
+}
+
+const METHODS: [&str; 3] = ["get", "post", "dialog"];
+
+/// Input types that are never submitted with the form data.
+const UNSUBMITTED_TYPES: [&str; 5] = ["submit", "reset", "button", "image", "file"];
+
+impl FormModel {
+    /// Returns the name and value pairs the form submits if nothing is
+    /// changed: fields with a name that are not disabled, checked
+    /// checkboxes and radio buttons, and every selected option.
+    /// Buttons and file inputs are left out.
+    pub fn data(&self) -> Vec<(String, String)> {
+        let mut data = Vec::new();
+        for field in self.fields.iter().filter(|field| !field.disabled) {
+            let name = match &field.name {
+                Some(name) if !name.is_empty() => name,
+                _ => continue,
+            };
+
+            match field.field_type.as_str() {
+                "checkbox" | "radio" if !field.checked => {}
+                "select-multiple" => data.extend(
+                    field
+                        .options
+                        .iter()
+                        .filter(|option| option.selected)
+                        .map(|option| (name.clone(), option.value.clone())),
+                ),
+                "select" if field.options.is_empty() => {}
+                field_type if UNSUBMITTED_TYPES.contains(&field_type) => {}
+                _ => data.push((name.clone(), field.value.clone())),
+            }
+        }
+        data
+    }
+}
+
+/// Returns the form `node` with its fields, which are its descendants.
+/// If `node` is not a `<form>`, its fields are returned without an action.
+///
+/// # Examples
+/// ```rust
+/// let root = html::parse(r#"
+///     <form action="/login" method="POST">
+///       <input name="user" value="me">
+///       <input type="password" name="password">
+///       <input type="checkbox" name="remember" checked>
+///       <select name="lang"><option>en</option><option value="fr" selected>French</option></select>
+///       <button>Log in</button>
+///     </form>
+/// "#).unwrap();
+///
+/// let form = html::extract::form(&root.first_child().unwrap());
+/// assert_eq!(form.action.as_deref(), Some("/login"));
+/// assert_eq!(form.method, "post");
+/// assert_eq!(form.fields[1].field_type, "password");
+/// assert_eq!(form.fields[3].options[0].value, "en");
+/// assert_eq!(
+///     form.data(),
+///     [("user", "me"), ("password", ""), ("remember", "on"), ("lang", "fr")]
+///         .map(|(name, value)| (name.to_string(), value.to_string()))
+/// );
+/// ```
+pub fn form(node: &NodeData) -> FormModel {
+    let mut model = FormModel {
+        method: String::from("get"),
+        ..FormModel::default()
+    };
+
+    if let Payload::Tag(tag) = &*node.get_payload() {
+        if tag.get_name().eq_ignore_ascii_case("form") {
+            model.action = tag.get_attribute_value("action");
+            if let Some(method) = tag.get_attribute_value("method").map(|method| method.to_ascii_lowercase()) {
+                if METHODS.contains(&method.as_str()) {
+                    model.method = method;
+                }
+            }
+        }
+    }
+
+    for field in node.descendants() {
+        let tag = match field.get_tag() {
+            Some(tag) => tag,
+            None => continue,
+        };
+
+        let mut model_field = FormField {
+            name: tag.get_attribute_value("name"),
+            disabled: tag.has_attribute("disabled"),
+            ..FormField::default()
+        };
+        match tag.get_name().to_ascii_lowercase().as_str() {
+            "input" => {
+                model_field.field_type = tag
+                    .get_attribute_value("type")
+                    .map_or_else(|| String::from("text"), |field_type| field_type.to_ascii_lowercase());
+                let is_checkable = matches!(model_field.field_type.as_str(), "checkbox" | "radio");
+                model_field.value = match tag.get_attribute_value("value") {
+                    Some(value) => value,
+                    None if is_checkable => String::from("on"),
+                    None => String::new(),
+                };
+                model_field.checked = is_checkable && tag.has_attribute("checked");
+            }
+            "textarea" => {
+                model_field.field_type = String::from("textarea");
+                model_field.value = field.text();
+            }
+            "select" => {
+                let multiple = tag.has_attribute("multiple");
+                model_field.field_type = String::from(if multiple { "select-multiple" } else { "select" });
+                model_field.options = field
+                    .descendants()
+                    .filter_map(|option| {
+                        let tag = option.get_tag().filter(|tag| is_option(tag))?;
+                        Some(form_option(&tag, &option))
+                    })
+                    .collect();
+                // a single select without a selected option shows the first one
+                if !multiple && !model_field.options.iter().any(|option| option.selected) {
+                    if let Some(first) = model_field.options.first_mut() {
+                        first.selected = true;
+                    }
+                }
+                if let Some(selected) = model_field.options.iter().find(|option| option.selected) {
+                    model_field.value = selected.value.clone();
+                }
+            }
+            _ => continue,
+        }
+        model.fields.push(model_field);
+    }
+
+    model
+}
+
+fn form_option(tag: &Tag, node: &NodeData) -> FormOption {
+    let text = node.inner_text().split_ascii_whitespace().collect::<Vec<_>>().join(" ");
+    FormOption {
+        value: tag.get_attribute_value("value").unwrap_or_else(|| text.clone()),
+        text,
+        selected: tag.has_attribute("selected"),
+    }
+}
+
+fn is_option(tag: &Tag) -> bool {
+    tag.get_name().eq_ignore_ascii_case("option")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn form_test() {
+        let root = parse(
+            r#"<div><form method="put"><textarea name="t">a &amp; b</textarea><input type="Radio" name="r" value="1"><input type="radio" name="r" value="2" checked><input name="off" disabled value="x"><select name="m" multiple><optgroup><option selected> A  b </option><option>c</option><option selected value="d">D</option></optgroup></select><select name="empty"></select><input type="submit" name="go" value="Go"><input value="no name"></form></div>"#,
+        )
+        .unwrap();
+        let div = root.first_child().unwrap();
+        let form = form(&div.first_child().unwrap());
+
+        assert_eq!(form.action, None);
+        assert_eq!(form.method, "get");
+        assert_eq!(form.fields.len(), 8);
+        assert_eq!(form.fields[0].value, "a & b");
+        assert_eq!(form.fields[1].field_type, "radio");
+        assert!(!form.fields[1].checked);
+        assert_eq!(
+            form.fields[4].options[0],
+            FormOption { value: String::from("A b"), text: String::from("A b"), selected: true }
+        );
+        assert_eq!(form.fields[4].value, "A b");
+        assert_eq!(
+            form.data(),
+            [("t", "a & b"), ("r", "2"), ("m", "A b"), ("m", "d")].map(|(n, v)| (n.to_string(), v.to_string()))
+        );
+
+        // the fields of any other node
+        let form = super::form(&div);
+        assert_eq!(form.fields.len(), 8);
+        assert_eq!(form.method, "get");
+    }
+}
@@ -0,0 +1,257 @@
+use crate::dom::NodeData;
+
+use super::url::{base_url, resolve};
+
+/// A link of the document. `<a href="/about" rel="nofollow">About</a>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub href: String,
+    /// The text of the link, as [`inner_text`](NodeData::inner_text) returns it.
+    pub text: String,
+    pub rel: Option<String>,
+}
+
+/// A resource the document loads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Asset {
+    pub kind: AssetKind,
+    pub url: String,
+}
+
+/// Where an [`Asset`] comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetKind {
+    /// `<img src>`
+    Image,
+    /// `<script src>`
+    Script,
+    /// `<link href>`, with its `rel`. `stylesheet`, `icon`
+    Link { rel: String },
+}
+
+/// A feed the document announces.
+/// `<link rel="alternate" type="application/rss+xml" href="/feed.xml" title="News">`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedLink {
+    pub href: String,
+    pub title: Option<String>,
+    pub kind: FeedKind,
+}
+
+/// The format of a [`FeedLink`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedKind {
+    /// `application/rss+xml`
+    Rss,
+    /// `application/atom+xml`
+    Atom,
+}
+
+/// Returns the links of `<a href>` and `<area href>` in document order.
+/// The URLs are returned as they are written.
+///
+/// # Examples
+/// ```rust
+/// let root = html::parse(r#"<p><a href="/a" rel="next">Next <b>page</b></a><a>no href</a></p>"#).unwrap();
+/// let links = html::extract::links(&root);
+///
+/// assert_eq!(links.len(), 1);
+/// assert_eq!(links[0].href, "/a");
+/// assert_eq!(links[0].text, "Next page");
+/// assert_eq!(links[0].rel.as_deref(), Some("next"));
+/// ```
+pub fn links(root: &NodeData) -> Vec<Link> {
+    collect_links(root, None)
+}
+
+/// Returns the links like [`links`] does, with the URLs resolved against
+/// `base` and the `<base href>` of the document.
+///
+/// # Examples
+/// ```rust
+/// let root = html::parse(r#"<a href="../b">b</a>"#).unwrap();
+/// let links = html::extract::links_with_base(&root, "https://a.com/x/y/z");
+///
+/// assert_eq!(links[0].href, "https://a.com/x/b");
+/// ```
+pub fn links_with_base(root: &NodeData, base: &str) -> Vec<Link> {
+    collect_links(root, Some(base_url(root, base)))
+}
+
+/// Returns the URLs of `<img src>`, `<script src>` and `<link href>` in
+/// document order. The URLs are returned as they are written.
+pub fn assets(root: &NodeData) -> Vec<Asset> {
+    collect_assets(root, None)
+}
+
+/// Returns the assets like [`assets`] does, with the URLs resolved against
+/// `base` and the `<base href>` of the document.
+pub fn assets_with_base(root: &NodeData, base: &str) -> Vec<Asset> {
+    collect_assets(root, Some(base_url(root, base)))
+}
+
+/// Returns the feeds of `<link rel="alternate">` with an RSS or Atom
+/// `type` in document order. The URLs are returned as they are written.
+///
+/// # Examples
+/// ```rust
+/// use html::extract::FeedKind;
+///
+/// let root = html::parse(r#"
+///     <link rel="alternate" type="application/rss+xml" href="/rss" title="News">
+///     <link rel="alternate" type="application/atom+xml" href="/atom">
+///     <link rel="alternate" hreflang="fr" href="/fr">
+/// "#).unwrap();
+/// let feeds = html::extract::feed_links(&root);
+///
+/// assert_eq!(feeds.len(), 2);
+/// assert_eq!(feeds[0].title.as_deref(), Some("News"));
+/// assert_eq!(feeds[1].kind, FeedKind::Atom);
+/// ```
+pub fn feed_links(root: &NodeData) -> Vec<FeedLink> {
+    collect_feed_links(root, None)
+}
+
+/// Returns the feeds like [`feed_links`] does, with the URLs resolved
+/// against `base` and the `<base href>` of the document.
+pub fn feed_links_with_base(root: &NodeData, base: &str) -> Vec<FeedLink> {
+    collect_feed_links(root, Some(base_url(root, base)))
+}
+
+fn collect_links(root: &NodeData, base: Option<String>) -> Vec<Link> {
+    root.descendants()
+        .filter_map(|node| {
+            let tag = node.get_tag()?;
+            if tag.get_name() != "a" && tag.get_name() != "area" {
+                return None;
+            }
+
+            Some(Link {
+                href: resolve_with(&base, &tag.get_attribute_value("href")?),
+                text: node.inner_text(),
+                rel: tag.get_attribute_value("rel"),
+            })
+        })
+        .collect()
+}
+
+fn collect_assets(root: &NodeData, base: Option<String>) -> Vec<Asset> {
+    root.descendants()
+        .filter_map(|node| {
+            let tag = node.get_tag()?;
+            let (kind, url) = match tag.get_name() {
+                "img" => (AssetKind::Image, tag.get_attribute_value("src")?),
+                "script" => (AssetKind::Script, tag.get_attribute_value("src")?),
+                "link" => {
+                    let rel = tag.get_attribute_value("rel").unwrap_or_default();
+                    (AssetKind::Link { rel }, tag.get_attribute_value("href")?)
+                }
+                _ => return None,
+            };
+
+            if url.trim().is_empty() {
+                return None;
+            }
+
+            Some(Asset {
+                kind,
+                url: resolve_with(&base, &url),
+            })
+        })
+        .collect()
+}
+
+fn collect_feed_links(root: &NodeData, base: Option<String>) -> Vec<FeedLink> {
+    root.descendants()
+        .filter_map(|node| {
+            let tag = node.get_tag().filter(|tag| tag.get_name().eq_ignore_ascii_case("link"))?;
+            let rel = tag.get_attribute_value("rel")?;
+            if !rel.split_ascii_whitespace().any(|rel| rel.eq_ignore_ascii_case("alternate")) {
+                return None;
+            }
+
+            // the type may have parameters. `application/rss+xml; charset=utf-8`
+            let feed_type = tag.get_attribute_value("type")?;
+            let feed_type = feed_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+            let kind = match feed_type.as_str() {
+                "application/rss+xml" => FeedKind::Rss,
+                "application/atom+xml" => FeedKind::Atom,
+                _ => return None,
+            };
+
+            let href = tag.get_attribute_value("href").filter(|href| !href.trim().is_empty())?;
+            Some(FeedLink {
+                href: resolve_with(&base, &href),
+                title: tag.get_attribute_value("title"),
+                kind,
+            })
+        })
+        .collect()
+}
+
+fn resolve_with(base: &Option<String>, url: &str) -> String {
+    match base {
+        Some(base) => resolve(base, url),
+        None => url.to_string(),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn links_test() {
+        let html = r#"
+        <head><base href="/docs/"><link rel="stylesheet" href="style.css"><script src="//cdn.com/a.js"></script></head>
+        <body><a href="intro">Intro</a><map><area href="/map" alt="m"></map><img src=""><img src="x.png"></body>
+        "#;
+        let root = parse(html).unwrap();
+
+        let hrefs: Vec<String> = links(&root).into_iter().map(|link| link.href).collect();
+        assert_eq!(hrefs, ["intro", "/map"]);
+
+        let links = links_with_base(&root, "https://a.com/index.html");
+        assert_eq!(links[0], Link {
+            href: String::from("https://a.com/docs/intro"),
+            text: String::from("Intro"),
+            rel: None,
+        });
+        assert_eq!(links[1].href, "https://a.com/map");
+
+        assert_eq!(assets_with_base(&root, "https://a.com/"), [
+            Asset {
+                kind: AssetKind::Link { rel: String::from("stylesheet") },
+                url: String::from("https://a.com/docs/style.css"),
+            },
+            Asset { kind: AssetKind::Script, url: String::from("https://cdn.com/a.js") },
+            Asset { kind: AssetKind::Image, url: String::from("https://a.com/docs/x.png") },
+        ]);
+    }
+
+    #[test]
+    fn feed_links_test() {
+        let html = r#"
+        <head><base href="https://b.com/blog/">
+        <LINK rel="Alternate Home" type="Application/RSS+XML; charset=utf-8" href="rss.xml" title="All">
+        <link rel="alternate" type="application/atom+xml" href="">
+        <link rel="alternate" type="text/html" href="/en">
+        <link rel="stylesheet" type="application/atom+xml" href="a.css">
+        <link rel="alternate" type="application/atom+xml" href="/atom.xml"></head>
+        "#;
+        let root = parse(html).unwrap();
+
+        let feeds = feed_links_with_base(&root, "https://a.com/");
+        assert_eq!(feeds, [
+            FeedLink {
+                href: String::from("https://b.com/blog/rss.xml"),
+                title: Some(String::from("All")),
+                kind: FeedKind::Rss,
+            },
+            FeedLink { href: String::from("https://b.com/atom.xml"), title: None, kind: FeedKind::Atom },
+        ]);
+        assert_eq!(feed_links(&root)[1].href, "/atom.xml");
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use crate::dom::{NodeData, Payload};
+use crate::parser::charset_parameter;
+
+/// The metadata of a page, read from its `<head>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageMetadata {
+    /// The text of `<title>`.
+    pub title: Option<String>,
+    /// `<meta name="description">`
+    pub description: Option<String>,
+    /// `<link rel="canonical">`
+    pub canonical: Option<String>,
+    /// `<meta charset>`, or the charset of `<meta http-equiv="Content-Type">`.
+    pub charset: Option<String>,
+    /// The content of `<meta name>`, by the lowercased name.
+    pub meta: HashMap<String, String>,
+    /// The content of `<meta property="og:*">`, by the property without
+    /// `og:`. `title`, `image`
+    pub open_graph: HashMap<String, String>,
+}
+
+/// Returns the metadata of the document.
+/// If a name appears more than once, the first one is used.
+///
+/// # Examples
+/// ```rust
+/// let root = html::parse(r#"
+///     <head>
+///       <meta charset="utf-8">
+///       <title>Home</title>
+///       <meta name="Description" content="A page">
+///       <meta property="og:image" content="https://a.com/x.png">
+///       <link rel="canonical" href="https://a.com/">
+///     </head>
+/// "#).unwrap();
+///
+/// let metadata = html::extract::metadata(&root);
+/// assert_eq!(metadata.title.as_deref(), Some("Home"));
+/// assert_eq!(metadata.description.as_deref(), Some("A page"));
+/// assert_eq!(metadata.charset.as_deref(), Some("utf-8"));
+/// assert_eq!(metadata.canonical.as_deref(), Some("https://a.com/"));
+/// assert_eq!(metadata.open_graph["image"], "https://a.com/x.png");
+/// ```
+pub fn metadata(root: &NodeData) -> PageMetadata {
+    let mut metadata = PageMetadata::default();
+
+    for node in root.descendants() {
+        let tag = match node.get_tag() {
+            Some(tag) => tag,
+            None => continue,
+        };
+
+        match tag.get_name() {
+            "title" if metadata.title.is_none() => {
+                let title: String = node
+                    .get_children()
+                    .iter()
+                    .filter_map(|child| match &*child.get_payload() {
+                        Payload::Text(text) => Some(text.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                metadata.title = Some(title.trim().to_string());
+            }
+            "link" if metadata.canonical.is_none() => {
+                let canonical = tag
+                    .get_attribute_value("rel")
+                    .is_some_and(|rel| rel.split_ascii_whitespace().any(|rel| rel.eq_ignore_ascii_case("canonical")));
+                if canonical {
+                    metadata.canonical = tag.get_attribute_value("href");
+                }
+            }
+            "meta" => {
+                if let Some(charset) = tag.get_attribute_value("charset") {
+                    metadata.charset.get_or_insert(charset.trim().to_string());
+                }
+
+                let content = match tag.get_attribute_value("content") {
+                    Some(content) => content,
+                    None => continue,
+                };
+
+                if let Some(name) = tag.get_attribute_value("name") {
+                    metadata.meta.entry(name.to_ascii_lowercase()).or_insert(content.clone());
+                }
+                if let Some(property) = tag.get_attribute_value("property") {
+                    if let Some(property) = property.strip_prefix("og:") {
+                        metadata.open_graph.entry(property.to_string()).or_insert(content.clone());
+                    }
+                }
+                let content_type = tag
+                    .get_attribute_value("http-equiv")
+                    .is_some_and(|http_equiv| http_equiv.eq_ignore_ascii_case("content-type"));
+                if content_type {
+                    if let Some(charset) = charset_parameter(&content) {
+                        metadata.charset.get_or_insert(charset);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    metadata.description = metadata.meta.get("description").cloned();
+    metadata
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn metadata_test() {
+        let html = r#"
+        <head>
+          <meta http-equiv="Content-Type" content="text/html; charset=&quot;Shift_JIS&quot;">
+          <title> A &amp; B </title><title>second</title>
+          <meta name="keywords" content="a, b"><meta name="KEYWORDS" content="c">
+          <meta property="og:title" content="OG"><meta property="twitter:card" content="summary">
+          <link rel="alternate canonical" href="/c">
+        </head>
+        "#;
+        let metadata = metadata(&parse(html).unwrap());
+
+        assert_eq!(metadata.title.as_deref(), Some("A & B"));
+        assert_eq!(metadata.charset.as_deref(), Some("Shift_JIS"));
+        assert_eq!(metadata.canonical.as_deref(), Some("/c"));
+        assert_eq!(metadata.description, None);
+        assert_eq!(metadata.meta["keywords"], "a, b");
+        assert_eq!(metadata.open_graph.len(), 1);
+        assert_eq!(metadata.open_graph["title"], "OG");
+
+        assert_eq!(super::metadata(&parse("<p>x</p>").unwrap()), PageMetadata::default());
+    }
+}
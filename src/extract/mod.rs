@@ -0,0 +1,51 @@
+//! Extracting structured data from a tree.
+
+mod form;
+mod links;
+mod metadata;
+mod srcset;
+mod structured;
+mod table;
+mod url;
+
+pub use form::form;
+pub use form::FormField;
+pub use form::FormModel;
+pub use form::FormOption;
+
+pub use links::assets;
+pub use links::assets_with_base;
+pub use links::feed_links;
+pub use links::feed_links_with_base;
+pub use links::links;
+pub use links::links_with_base;
+pub use links::Asset;
+pub use links::AssetKind;
+pub use links::FeedKind;
+pub use links::FeedLink;
+pub use links::Link;
+
+pub use metadata::metadata;
+pub use metadata::PageMetadata;
+
+pub use srcset::parse_sizes;
+pub use srcset::parse_srcset;
+pub use srcset::srcset;
+pub use srcset::Descriptor;
+pub use srcset::ImageCandidate;
+pub use srcset::ImageSources;
+pub use srcset::SourceSize;
+
+pub use structured::json_ld;
+pub use structured::microdata;
+pub use structured::MicrodataItem;
+pub use structured::MicrodataValue;
+
+pub use table::table_to_records;
+pub use table::table_to_rows;
+
+pub use url::base_url;
+#[cfg(feature = "url")]
+pub use url::absolutize_urls;
+#[cfg(feature = "url")]
+pub use url::resolve as resolve_url;
@@ -0,0 +1,258 @@
+use crate::dom::Tag;
+
+/// The images an `<img>` or `<source>` offers. Returned by [`srcset`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageSources {
+    /// The candidates of `srcset`, in the order they are written.
+    pub candidates: Vec<ImageCandidate>,
+    /// The entries of `sizes`, in the order they are written.
+    pub sizes: Vec<SourceSize>,
+}
+
+/// A candidate of `srcset`. `photo-800.jpg 800w`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageCandidate {
+    /// The URL as it is written.
+    pub url: String,
+    pub descriptor: Descriptor,
+}
+
+/// What a candidate of `srcset` is for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Descriptor {
+    /// The width of the image in pixels. `800w`
+    Width(u32),
+    /// The pixel density the image is for. `2x`
+    /// A candidate without a descriptor is `1x`.
+    Density(f64),
+}
+
+/// An entry of `sizes`. `(max-width: 600px) 100vw`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceSize {
+    /// The media condition, or `None` for the default size at the end.
+    pub condition: Option<String>,
+    /// The length the image is shown at. `100vw`, `calc(50vw - 10px)`
+    pub size: String,
+}
+
+/// Parses the `srcset` and `sizes` of an `<img>` or `<source>`.
+/// Returns `None` if `tag` is another element or has no `srcset`.
+///
+/// Candidates with a descriptor that is invalid, or with more than one,
+/// are skipped as browsers do.
+///
+/// # Examples
+/// ```rust
+/// use html::extract::Descriptor;
+///
+/// let root = html::parse(r#"<img srcset="a.jpg 480w, b.jpg 800w" sizes="(max-width: 600px) 480px, 800px">"#).unwrap();
+/// let img = root.first_child().unwrap();
+/// let sources = match &*img.get_payload() {
+///     html::dom::Payload::Tag(tag) => html::extract::srcset(tag).unwrap(),
+///     _ => unreachable!(),
+/// };
+///
+/// assert_eq!(sources.candidates[1].url, "b.jpg");
+/// assert_eq!(sources.candidates[1].descriptor, Descriptor::Width(800));
+/// assert_eq!(sources.sizes[0].condition.as_deref(), Some("(max-width: 600px)"));
+/// assert_eq!(sources.sizes[1].size, "800px");
+/// ```
+pub fn srcset(tag: &Tag) -> Option<ImageSources> {
+    if !tag.get_name().eq_ignore_ascii_case("img") && !tag.get_name().eq_ignore_ascii_case("source") {
+        return None;
+    }
+
+    let srcset = tag.get_attribute("srcset")?;
+    Some(ImageSources {
+        candidates: parse_srcset(srcset.as_str()),
+        sizes: tag.get_attribute("sizes").map_or_else(Vec::new, |sizes| parse_sizes(sizes.as_str())),
+    })
+}
+
+/// Parses the value of a `srcset` attribute.
+/// <https://html.spec.whatwg.org/multipage/images.html#parsing-a-srcset-attribute>
+pub fn parse_srcset(srcset: &str) -> Vec<ImageCandidate> {
+    let mut candidates = Vec::new();
+    let mut rest = srcset;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+
+        let url_len = rest.find(|c: char| c.is_ascii_whitespace()).unwrap_or(rest.len());
+        let url = &rest[..url_len];
+        rest = &rest[url_len..];
+
+        // a URL that ends with a comma ends the candidate
+        let descriptors = if url.ends_with(',') {
+            ""
+        } else {
+            let len = descriptors_len(rest);
+            let descriptors = &rest[..len];
+            rest = &rest[len..];
+            descriptors
+        };
+
+        let url = url.trim_end_matches(',');
+        if let Some(descriptor) = parse_descriptors(descriptors) {
+            candidates.push(ImageCandidate {
+                url: url.to_string(),
+                descriptor,
+            });
+        }
+    }
+    candidates
+}
+
+/// Returns `srcset` with the URL of each candidate replaced by `f` of it.
+/// The descriptors are kept as they are written, invalid ones included.
+#[cfg(feature = "url")]
+pub(crate) fn map_srcset_urls(srcset: &str, f: impl Fn(&str) -> String) -> String {
+    let mut candidates = Vec::new();
+    let mut rest = srcset;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+
+        let url_len = rest.find(|c: char| c.is_ascii_whitespace()).unwrap_or(rest.len());
+        let url = &rest[..url_len];
+        rest = &rest[url_len..];
+
+        let mut candidate = f(url.trim_end_matches(','));
+        if !url.ends_with(',') {
+            let len = descriptors_len(rest);
+            let descriptors = rest[..len].trim_end_matches(',').trim();
+            rest = &rest[len..];
+            if !descriptors.is_empty() {
+                candidate.push(' ');
+                candidate.push_str(descriptors);
+            }
+        }
+        candidates.push(candidate);
+    }
+    candidates.join(", ")
+}
+
+/// Parses the value of a `sizes` attribute. Entries are not validated.
+pub fn parse_sizes(sizes: &str) -> Vec<SourceSize> {
+    split_outside_parentheses(sizes, |c| c == ',')
+        .into_iter()
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            // the size is the last component, which may be `calc(50vw - 10px)`
+            let words = split_outside_parentheses(entry, |c| c.is_ascii_whitespace());
+            let size = words.iter().rev().find(|word| !word.is_empty()).copied().unwrap_or(entry);
+            let condition = entry[..entry.len() - size.len()].trim_end();
+            SourceSize {
+                condition: Some(condition).filter(|c| !c.is_empty()).map(String::from),
+                size: size.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Returns the length of the descriptors up to the comma that ends the
+/// candidate, which is not a comma in parentheses.
+fn descriptors_len(rest: &str) -> usize {
+    let mut depth = 0usize;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => return i + 1,
+            _ => {}
+        }
+    }
+    rest.len()
+}
+
+/// Returns the descriptor of a candidate, or `None` if it is invalid.
+fn parse_descriptors(descriptors: &str) -> Option<Descriptor> {
+    let mut width = None;
+    let mut density = None;
+    let mut height = None;
+    for descriptor in descriptors.trim_end_matches(',').split_ascii_whitespace() {
+        let (last, unit) = descriptor.char_indices().last()?;
+        let number = &descriptor[..last];
+        match unit {
+            'w' if width.is_none() && density.is_none() => {
+                width = Some(number.parse::<u32>().ok().filter(|&w| w > 0)?)
+            }
+            'x' if width.is_none() && density.is_none() && height.is_none() => {
+                density = Some(number.parse::<f64>().ok().filter(|d| d.is_finite() && *d >= 0.0)?)
+            }
+            'h' if height.is_none() && density.is_none() => {
+                height = Some(number.parse::<u32>().ok().filter(|&h| h > 0)?)
+            }
+            _ => return None,
+        }
+    }
+
+    match (width, density) {
+        (Some(width), _) => Some(Descriptor::Width(width)),
+        // a height is only allowed with a width
+        (None, _) if height.is_some() => None,
+        (None, Some(density)) => Some(Descriptor::Density(density)),
+        (None, None) => Some(Descriptor::Density(1.0)),
+    }
+}
+
+fn split_outside_parentheses(s: &str, is_separator: impl Fn(char) -> bool) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut bgn = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ if depth == 0 && is_separator(c) => {
+                parts.push(&s[bgn..i]);
+                bgn = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[bgn..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srcset_test() {
+        let candidates = parse_srcset(concat!(
+            " a.jpg, b.jpg 2x,c.jpg 1.5x , d,e.jpg 100w 50h, data:image/png;base64,iVBO 3x,",
+            "f.jpg 0w, g.jpg 1x 2x,h.jpg 10h,i.jpg 1é",
+        ));
+        let actual: Vec<(&str, Descriptor)> = candidates.iter().map(|c| (c.url.as_str(), c.descriptor)).collect();
+        assert_eq!(
+            actual,
+            [
+                ("a.jpg", Descriptor::Density(1.0)),
+                ("b.jpg", Descriptor::Density(2.0)),
+                ("c.jpg", Descriptor::Density(1.5)),
+                ("d,e.jpg", Descriptor::Width(100)),
+                ("data:image/png;base64,iVBO", Descriptor::Density(3.0)),
+            ]
+        );
+
+        let sizes = parse_sizes("(min-width: 36em) calc(50vw - (2 * 1em)), ,100vw");
+        assert_eq!(sizes[0].condition.as_deref(), Some("(min-width: 36em)"));
+        assert_eq!(sizes[0].size, "calc(50vw - (2 * 1em))");
+        assert_eq!(sizes[1], SourceSize { condition: None, size: String::from("100vw") });
+
+        let mut tag = Tag::new("video");
+        tag.set_attribute("srcset", "a.jpg");
+        assert_eq!(srcset(&tag), None);
+        tag.set_name("SOURCE");
+        assert_eq!(srcset(&tag).unwrap().candidates.len(), 1);
+        assert!(srcset(&tag).unwrap().sizes.is_empty());
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::dom::{NodeData, Payload, Tag};
+
+/// An element with `itemscope` and the properties it has. Returned by [`microdata`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MicrodataItem {
+    /// The words of `itemtype`. `https://schema.org/Person`
+    pub item_type: Vec<String>,
+    /// `itemid`
+    pub id: Option<String>,
+    /// The names and values of the properties, those of `itemref` after
+    /// the others. A name appears once for each value.
+    pub properties: Vec<(String, MicrodataValue)>,
+}
+
+/// The value of a property of a [`MicrodataItem`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MicrodataValue {
+    /// The value of an element without `itemscope`: the `content` of
+    /// `<meta>`, the URL of `<a href>` or `<img src>` as it is written, the
+    /// `datetime` of `<time>`, or the text of any other element.
+    Text(String),
+    /// An element with `itemprop` and `itemscope`.
+    Item(MicrodataItem),
+}
+
+impl MicrodataItem {
+    /// Returns the first value of the property `name`.
+    pub fn get(&self, name: &str) -> Option<&MicrodataValue> {
+        self.properties.iter().find(|(n, _)| n == name).map(|(_, value)| value)
+    }
+
+    /// Returns the values of the property `name` in order.
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a MicrodataValue> {
+        self.properties.iter().filter(move |(n, _)| n == name).map(|(_, value)| value)
+    }
+}
+
+impl MicrodataValue {
+    /// Returns the text, or `None` for an item.
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            MicrodataValue::Text(text) => Some(text),
+            MicrodataValue::Item(_) => None,
+        }
+    }
+
+    pub fn as_item(&self) -> Option<&MicrodataItem> {
+        match self {
+            MicrodataValue::Text(_) => None,
+            MicrodataValue::Item(item) => Some(item),
+        }
+    }
+}
+
+/// Elements whose value is the URL of an attribute, with the attribute.
+const URL_PROPERTIES: [(&str, &str); 11] = [
+    ("a", "href"),
+    ("area", "href"),
+    ("audio", "src"),
+    ("embed", "src"),
+    ("iframe", "src"),
+    ("img", "src"),
+    ("link", "href"),
+    ("object", "data"),
+    ("source", "src"),
+    ("track", "src"),
+    ("video", "src"),
+];
+
+/// Returns the contents of the `<script type="application/ld+json">`
+/// blocks in document order, as they are written. The JSON is not parsed.
+///
+/// # Examples
+/// ```rust
+/// let root = html::parse(r#"
+///     <script type="application/ld+json">{"@type": "Person", "name": "Ada"}</script>
+///     <script>let x = 1;</script>
+/// "#).unwrap();
+///
+/// assert_eq!(html::extract::json_ld(&root), [r#"{"@type": "Person", "name": "Ada"}"#]);
+/// ```
+pub fn json_ld(root: &NodeData) -> Vec<String> {
+    root.descendants()
+        .filter(|node| {
+            node.get_tag().is_some_and(|tag| {
+                tag.get_name().eq_ignore_ascii_case("script")
+                    && tag
+                        .get_attribute_value("type")
+                        .is_some_and(|script_type| script_type.trim().eq_ignore_ascii_case("application/ld+json"))
+            })
+        })
+        .map(|script| script.text().trim().to_string())
+        .collect()
+}
+
+/// Returns the items of microdata that are not properties of another
+/// item, in document order, with the items in their properties.
+/// <https://html.spec.whatwg.org/multipage/microdata.html>
+///
+/// An item that refers to itself through `itemref` is left out of its
+/// own properties.
+///
+/// # Examples
+/// ```rust
+/// let root = html::parse(r#"
+///     <div itemscope itemtype="https://schema.org/Person">
+///       <span itemprop="name">Ada</span>
+///       <div itemprop="address" itemscope><span itemprop="addressLocality">London</span></div>
+///       <a itemprop="url sameAs" href="/ada">home</a>
+///     </div>
+/// "#).unwrap();
+///
+/// let items = html::extract::microdata(&root);
+/// assert_eq!(items[0].item_type, ["https://schema.org/Person"]);
+/// assert_eq!(items[0].get("name").unwrap().as_text(), Some("Ada"));
+/// let address = items[0].get("address").unwrap().as_item().unwrap();
+/// assert_eq!(address.get("addressLocality").unwrap().as_text(), Some("London"));
+/// assert_eq!(items[0].get("sameAs").unwrap().as_text(), Some("/ada"));
+/// ```
+pub fn microdata(root: &NodeData) -> Vec<MicrodataItem> {
+    let mut ids = HashMap::new();
+    for node in root.descendants() {
+        if let Some(id) = node.get_tag().and_then(|tag| tag.get_attribute_value("id")) {
+            ids.entry(id).or_insert(node);
+        }
+    }
+
+    root.descendants()
+        .filter(|node| {
+            node.get_tag().is_some_and(|tag| tag.has_attribute("itemscope") && !tag.has_attribute("itemprop"))
+        })
+        .map(|node| read_item(&node, &ids, &mut Vec::new()))
+        .collect()
+}
+
+/// Returns the item of the element `node`. `visiting` holds the items
+/// being read, whose properties `node` is among.
+fn read_item(node: &Rc<NodeData>, ids: &HashMap<String, Rc<NodeData>>, visiting: &mut Vec<*const NodeData>) -> MicrodataItem {
+    let tag = node.get_tag().expect("an item is an element");
+    let mut item = MicrodataItem {
+        item_type: tag.get_attribute_value("itemtype").map_or_else(Vec::new, |item_type| words(&item_type)),
+        id: tag.get_attribute_value("itemid"),
+        properties: Vec::new(),
+    };
+    visiting.push(Rc::as_ptr(node));
+
+    // the children, then the elements of `itemref`, each with its descendants
+    // up to the elements of another item
+    let mut pending: Vec<Rc<NodeData>> = tag
+        .get_attribute_value("itemref")
+        .map_or_else(Vec::new, |refs| words(&refs))
+        .iter()
+        .rev()
+        .filter_map(|id| ids.get(id).cloned())
+        .collect();
+    pending.extend(node.get_children().iter().rev().cloned());
+    while let Some(element) = pending.pop() {
+        let tag = match element.get_tag() {
+            Some(tag) => tag,
+            None => continue,
+        };
+        if !tag.has_attribute("itemscope") {
+            pending.extend(element.get_children().iter().rev().cloned());
+        }
+
+        let names = match tag.get_attribute_value("itemprop") {
+            Some(names) => words(&names),
+            None => continue,
+        };
+        let value = if tag.has_attribute("itemscope") {
+            if visiting.contains(&Rc::as_ptr(&element)) {
+                continue;
+            }
+            MicrodataValue::Item(read_item(&element, ids, visiting))
+        } else {
+            MicrodataValue::Text(property_value(&element, &tag))
+        };
+        for name in names {
+            item.properties.push((name, value.clone()));
+        }
+    }
+
+    visiting.pop();
+    item
+}
+
+/// Returns the value of the element `node` without `itemscope`.
+fn property_value(node: &NodeData, tag: &Tag) -> String {
+    let name = tag.get_name().to_ascii_lowercase();
+    let attribute = match name.as_str() {
+        "meta" => Some("content"),
+        "data" | "meter" => Some("value"),
+        "time" if tag.has_attribute("datetime") => Some("datetime"),
+        name => URL_PROPERTIES.iter().find(|(element, _)| *element == name).map(|(_, attribute)| *attribute),
+    };
+
+    match attribute {
+        Some(attribute) => tag.get_attribute_value(attribute).unwrap_or_default(),
+        None => text_content(node),
+    }
+}
+
+/// Returns the text of the descendants, which is not rendered as
+/// [`inner_text`](NodeData::inner_text) does.
+fn text_content(node: &NodeData) -> String {
+    node.descendants()
+        .filter_map(|node| match &*node.get_payload() {
+            Payload::Text(text) | Payload::RawText(text) => Some(text.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn words(value: &str) -> Vec<String> {
+    value.split_ascii_whitespace().map(String::from).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn microdata_test() {
+        let root = parse(
+            r#"<div itemscope itemtype="https://schema.org/Movie  https://schema.org/Thing" itemid="urn:isbn:1" itemref="extra loop">
+                <h1 itemprop="name">Avatar <b>3D</b></h1>
+                <div itemprop="director" itemscope><span itemprop="name">James</span> <time itemprop="born" datetime="1954-08-16">Aug</time></div>
+                <meta itemprop="rating" content="4.5"><img itemprop="image" src="a.jpg"><data itemprop="id" value="7">seven</data>
+                <time itemprop="released">2009</time>
+            </div>
+            <p id="extra" itemprop="genre">Science fiction</p>
+            <div id="loop" itemprop="self" itemscope itemref="loop"><span itemprop="x">y</span></div>
+            <script type=" Application/LD+JSON ">
+              {"a": 1}
+            </script>"#,
+        )
+        .unwrap();
+
+        let items = microdata(&root);
+        assert_eq!(items.len(), 1);
+        let movie = &items[0];
+        assert_eq!(movie.item_type, ["https://schema.org/Movie", "https://schema.org/Thing"]);
+        assert_eq!(movie.id.as_deref(), Some("urn:isbn:1"));
+        let names: Vec<&str> = movie.properties.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["name", "director", "rating", "image", "id", "released", "genre", "self"]);
+        assert_eq!(movie.get("name").unwrap().as_text(), Some("Avatar 3D"));
+        assert_eq!(movie.get("rating"), Some(&MicrodataValue::Text(String::from("4.5"))));
+        assert_eq!(movie.get("image").unwrap().as_text(), Some("a.jpg"));
+        assert_eq!(movie.get("id").unwrap().as_text(), Some("7"));
+        assert_eq!(movie.get("released").unwrap().as_text(), Some("2009"));
+        assert_eq!(movie.get("genre").unwrap().as_text(), Some("Science fiction"));
+
+        let director = movie.get("director").unwrap().as_item().unwrap();
+        assert!(director.item_type.is_empty());
+        assert_eq!(director.get("born").unwrap().as_text(), Some("1954-08-16"));
+        assert!(movie.get("name").unwrap().as_item().is_none());
+
+        // the item of `itemref="loop"` refers to itself, which is left out
+        let looped = movie.get("self").unwrap().as_item().unwrap();
+        assert_eq!(looped.get_all("x").count(), 1);
+        assert!(looped.get("self").is_none());
+
+        assert_eq!(json_ld(&root), [r#"{"a": 1}"#]);
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::dom::{NodeData, Payload};
+
+/// The largest `colspan` and `rowspan` that are honored, as in browsers.
+const MAX_COLSPAN: usize = 1000;
+const MAX_ROWSPAN: usize = 65534;
+
+/// Returns the text of the cells of a table, row by row.
+///
+/// A cell spanning several columns or rows with `colspan` or `rowspan`
+/// is repeated in each of them, and rows are padded with "" to the same
+/// length. `<thead>` rows come first and `<tfoot>` rows last. Tables
+/// nested in a cell are not rows of the outer table.
+///
+/// If `node` is not a `<table>`, the first table in it is used.
+///
+/// # Examples
+/// ```rust
+/// let root = html::parse(r#"
+///     <table>
+///       <tr><th>Name</th><th colspan="2">Scores</th></tr>
+///       <tr><td rowspan="2">a</td><td>1</td><td>2</td></tr>
+///       <tr><td>3</td></tr>
+///     </table>
+/// "#).unwrap();
+///
+/// assert_eq!(html::extract::table_to_rows(&root), [
+///     ["Name", "Scores", "Scores"],
+///     ["a", "1", "2"],
+///     ["a", "3", ""],
+/// ]);
+/// ```
+pub fn table_to_rows(node: &NodeData) -> Vec<Vec<String>> {
+    table_grid(node).0
+}
+
+/// Returns the rows of a table as maps from the header of each column to
+/// the text of the cell.
+///
+/// The header is the last row of `<thead>`, or the first row if the table
+/// has no `<thead>`. The other rows are returned. See [`table_to_rows`]
+/// for how the rows are read.
+///
+/// So that no cell is lost, an empty header is named by its column from 1,
+/// `column_3`, and a header seen before is numbered, `Score_2` for the
+/// second column of a `<th colspan="2">Score</th>`.
+///
+/// # Examples
+/// ```rust
+/// let root = html::parse(r#"
+///     <table>
+///       <thead><tr><th>Name</th><th>Age</th></tr></thead>
+///       <tbody><tr><td>a</td><td>1</td></tr></tbody>
+///     </table>
+/// "#).unwrap();
+///
+/// let records = html::extract::table_to_records(&root);
+/// assert_eq!(records[0]["Name"], "a");
+/// assert_eq!(records[0]["Age"], "1");
+/// ```
+pub fn table_to_records(node: &NodeData) -> Vec<HashMap<String, String>> {
+    let (mut grid, head_rows) = table_grid(node);
+    if grid.is_empty() {
+        return Vec::new();
+    }
+
+    let header_index = head_rows.max(1) - 1;
+    let headers = unique_headers(grid.drain(..=header_index).next_back().unwrap_or_default());
+
+    grid.into_iter()
+        .map(|row| headers.iter().cloned().zip(row).collect())
+        .collect()
+}
+
+/// Returns the headers with the empty ones named by their column and the
+/// repeated ones numbered, so that no two are the same.
+fn unique_headers(headers: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut unique = Vec::with_capacity(headers.len());
+    for (i, header) in headers.into_iter().enumerate() {
+        let header = match header.is_empty() {
+            true => format!("column_{}", i + 1),
+            false => header,
+        };
+
+        let mut name = header.clone();
+        let mut count = 1;
+        while seen.contains(&name) {
+            count += 1;
+            name = format!("{}_{}", header, count);
+        }
+        seen.insert(name.clone());
+        unique.push(name);
+    }
+    unique
+}
+
+/// Returns the grid of cells of the table and the number of `<thead>` rows.
+fn table_grid(node: &NodeData) -> (Vec<Vec<String>>, usize) {
+    if is_tag(node, "table") {
+        return grid_of(node);
+    }
+
+    match node.descendants().find(|descendant| is_tag(descendant, "table")) {
+        Some(table) => grid_of(&table),
+        None => (Vec::new(), 0),
+    }
+}
+
+fn grid_of(table: &NodeData) -> (Vec<Vec<String>>, usize) {
+    let (head, body, foot) = table_rows(table);
+    let head_rows = head.len();
+
+    let mut grid: Vec<Vec<String>> = Vec::new();
+    // the cells spanning down to the next rows, by column: (cell, the rows left)
+    let mut spanning: Vec<Option<(String, usize)>> = Vec::new();
+    for tr in head.iter().chain(body.iter()).chain(foot.iter()) {
+        let mut row: Vec<String> = Vec::new();
+
+        let children = tr.get_children();
+        let mut cells = children.iter().filter_map(|child| {
+            let tag = child.get_tag().filter(|tag| tag.get_name() == "td" || tag.get_name() == "th")?;
+            Some((child, tag))
+        });
+
+        loop {
+            // the cells of the rows above come first
+            let column = row.len();
+            if let Some(Some((cell, rows_left))) = spanning.get_mut(column) {
+                row.push(cell.clone());
+                *rows_left -= 1;
+                if *rows_left == 0 {
+                    spanning[column] = None;
+                }
+                continue;
+            }
+
+            let (td, tag) = match cells.next() {
+                Some(cell) => cell,
+                None if spanning.iter().skip(column).any(Option::is_some) => {
+                    // a cell spanning from above is after the gap
+                    row.push(String::new());
+                    continue;
+                }
+                None => break,
+            };
+
+            let cell = td.inner_text();
+            let colspan = get_span(tag.get_attribute_value("colspan"), MAX_COLSPAN);
+            let rowspan = get_span(tag.get_attribute_value("rowspan"), MAX_ROWSPAN);
+
+            for _ in 0..colspan {
+                if rowspan > 1 {
+                    let column = row.len();
+                    if spanning.len() <= column {
+                        spanning.resize(column + 1, None);
+                    }
+                    spanning[column] = Some((cell.clone(), rowspan - 1));
+                }
+                row.push(cell.clone());
+            }
+        }
+
+        grid.push(row);
+    }
+
+    // pad the rows to the same length
+    let width = grid.iter().map(Vec::len).max().unwrap_or(0);
+    for row in grid.iter_mut() {
+        row.resize(width, String::new());
+    }
+
+    (grid, head_rows)
+}
+
+/// Returns the `<tr>` of the `<thead>`, the body and the `<tfoot>` of `table`.
+#[allow(clippy::type_complexity)]
+fn table_rows(table: &NodeData) -> (Vec<Rc<NodeData>>, Vec<Rc<NodeData>>, Vec<Rc<NodeData>>) {
+    let (mut head, mut body, mut foot) = (Vec::new(), Vec::new(), Vec::new());
+    for child in table.get_children().iter() {
+        let payload = child.get_payload();
+        let name = match &*payload {
+            Payload::Tag(tag) => tag.get_name(),
+            _ => continue,
+        };
+
+        match name {
+            "tr" => body.push(Rc::clone(child)),
+            "thead" | "tbody" | "tfoot" => {
+                let rows = match name {
+                    "thead" => &mut head,
+                    "tfoot" => &mut foot,
+                    _ => &mut body,
+                };
+                rows.extend(child.get_children().iter().filter(|tr| is_tag(tr, "tr")).cloned());
+            }
+            _ => {}
+        }
+    }
+
+    (head, body, foot)
+}
+
+fn is_tag(node: &NodeData, name: &str) -> bool {
+    matches!(&*node.get_payload(), Payload::Tag(tag) if tag.get_name() == name)
+}
+
+/// Returns the value of `colspan` or `rowspan`, from 1 to `max`.
+fn get_span(value: Option<String>, max: usize) -> usize {
+    value
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(1)
+        .clamp(1, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn rows_test() {
+        let html = r#"
+        <div><table>
+          <tfoot><tr><td>f</td></tr></tfoot>
+          <tr><td rowspan="3">a</td><td colspan="2">b</td></tr>
+          <tr><td>c <b>1</b></td><td rowspan="2"><table><tr><td>x</td></tr></table></td></tr>
+          <tr><td>d</td></tr>
+          <thead><tr><th>h</th></tr></thead>
+        </table></div>
+        "#;
+        let root = parse(html).unwrap();
+
+        assert_eq!(table_to_rows(&root), [
+            ["h", "", ""],
+            ["a", "b", "b"],
+            ["a", "c 1", "x"],
+            ["a", "d", "x"],
+            ["f", "", ""],
+        ]);
+        let inner = root.query_name("table").nth(1).unwrap();
+        assert_eq!(table_to_rows(&inner), [["x"]]);
+        assert!(table_to_rows(&parse("<p>no table</p>").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn records_test() {
+        let html = "<table><tr><th>k</th><th>v</th></tr><tr><td>a</td><td>1</td></tr><tr><td>b</td></tr></table>";
+        let records = table_to_records(&parse(html).unwrap());
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["k"], "a");
+        assert_eq!(records[0]["v"], "1");
+        assert_eq!(records[1]["k"], "b");
+        assert_eq!(records[1]["v"], "");
+
+        // headers spanning columns, empty or repeated do not lose cells
+        let html = r#"<table><tr><th>A</th><th colspan="2">B</th><th></th><th>A</th></tr>
+            <tr><td>1</td><td>2</td><td>3</td><td>4</td><td>5</td></tr></table>"#;
+        let records = table_to_records(&parse(html).unwrap());
+        let mut record: Vec<(&str, &str)> = records[0].iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        record.sort_unstable();
+        assert_eq!(record, [("A", "1"), ("A_2", "5"), ("B", "2"), ("B_2", "3"), ("column_4", "4")]);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::dom::{NodeData, Payload};
+
+/// Attributes whose value is a URL, on whichever element they are.
+#[cfg(feature = "url")]
+const URL_ATTRIBUTES: [&str; 6] = ["href", "src", "action", "formaction", "poster", "cite"];
+
+/// Resolves `reference` against `base` as a browser resolves a link.
+/// <https://www.rfc-editor.org/rfc/rfc3986#section-5.2>
+///
+/// `reference` is returned as it is if it is absolute or if `base` is not.
+pub fn resolve(base: &str, reference: &str) -> String {
+    let reference = reference.trim();
+    if scheme_of(reference).is_some() {
+        return reference.to_string();
+    }
+
+    let base = base.trim();
+    let scheme = match scheme_of(base) {
+        Some(scheme) => scheme,
+        None => return reference.to_string(),
+    };
+    if let Some(network_path) = reference.strip_prefix("//") {
+        return format!("{}://{}", scheme, network_path);
+    }
+
+    // scheme://authority/path?query#fragment
+    let base = base.split('#').next().unwrap_or_default();
+    let rest = &base[scheme.len() + 1..];
+    let (authority, path_and_query) = match rest.strip_prefix("//") {
+        Some(rest) => {
+            let end = rest.find(['/', '?']).unwrap_or(rest.len());
+            (Some(&rest[..end]), &rest[end..])
+        }
+        None => (None, rest),
+    };
+    let (path, query) = match path_and_query.find('?') {
+        Some(i) => (&path_and_query[..i], &path_and_query[i..]),
+        None => (path_and_query, ""),
+    };
+
+    let mut resolved = String::from(scheme);
+    resolved.push(':');
+    if let Some(authority) = authority {
+        resolved.push_str("//");
+        resolved.push_str(authority);
+    }
+
+    if reference.is_empty() || reference.starts_with('#') {
+        resolved.push_str(path);
+        resolved.push_str(query);
+        resolved.push_str(reference);
+    } else if reference.starts_with('?') {
+        resolved.push_str(path);
+        resolved.push_str(reference);
+    } else if reference.starts_with('/') {
+        resolved.push_str(&remove_dot_segments(reference));
+    } else {
+        // the reference replaces the last segment of the base path
+        let directory = match path.rfind('/') {
+            Some(i) => &path[..=i],
+            None if authority.is_some() => "/",
+            None => "",
+        };
+        resolved.push_str(&remove_dot_segments(&format!("{}{}", directory, reference)));
+    }
+
+    resolved
+}
+
+/// Returns the URL that the relative URLs of the document are resolved
+/// against: `document_url` joined with the first `<base href>`.
+///
+/// # Examples
+/// ```rust
+/// let root = html::parse(r#"<head><base href="/docs/"></head>"#).unwrap();
+///
+/// assert_eq!(html::extract::base_url(&root, "https://a.com/x/y"), "https://a.com/docs/");
+/// ```
+pub fn base_url(root: &NodeData, document_url: &str) -> String {
+    let base = root.query_name("base").find_map(|node| match &*node.get_payload() {
+        Payload::Tag(tag) => tag.get_attribute_value("href"),
+        _ => None,
+    });
+
+    match base {
+        Some(base) => resolve(document_url, &base),
+        None => document_url.to_string(),
+    }
+}
+
+/// Rewrites the URLs of `href`, `src`, `srcset`, `action`, `formaction`,
+/// `poster` and `cite` to absolute URLs, resolved against `document_url`
+/// and the `<base href>` of the document. Empty values are left as they are.
+///
+/// # Examples
+/// ```rust
+/// let root = html::parse(r#"<a href="b">b</a><img srcset="1.png, 2.png 2x">"#).unwrap();
+/// html::extract::absolutize_urls(&root, "https://a.com/x/");
+///
+/// assert_eq!(
+///     root.to_html(),
+///     r#"<a href="https://a.com/x/b">b</a><img srcset="https://a.com/x/1.png, https://a.com/x/2.png 2x">"#
+/// );
+/// ```
+#[cfg(feature = "url")]
+pub fn absolutize_urls(root: &NodeData, document_url: &str) {
+    let base = base_url(root, document_url);
+    for node in root.descendants() {
+        let tag = match node.get_tag() {
+            Some(tag) => tag,
+            None => continue,
+        };
+
+        let mut changes = Vec::new();
+        for name in URL_ATTRIBUTES {
+            if let Some(value) = tag.get_attribute_value(name).filter(|value| !value.trim().is_empty()) {
+                changes.push((name, resolve(&base, &value), value));
+            }
+        }
+        if let Some(srcset) = tag.get_attribute_value("srcset") {
+            changes.push(("srcset", super::srcset::map_srcset_urls(&srcset, |url| resolve(&base, url)), srcset));
+        }
+
+        changes.retain(|(_, resolved, value)| resolved != value);
+        if changes.is_empty() {
+            continue;
+        }
+        drop(tag);
+        if let Payload::Tag(tag) = &mut *node.payload_mut() {
+            for (name, resolved, _) in changes {
+                tag.set_attribute(name, &resolved);
+            }
+        }
+    }
+}
+
+/// Returns the scheme of `url`, or `None` if it is relative. `https`
+fn scheme_of(url: &str) -> Option<&str> {
+    let end = url.find(':')?;
+    let scheme = &url[..end];
+    let mut chars = scheme.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+
+    valid.then_some(scheme)
+}
+
+/// Removes the `.` and `..` segments of the path, keeping the query and fragment.
+fn remove_dot_segments(path: &str) -> String {
+    let end = path.find(['?', '#']).unwrap_or(path.len());
+    let (path, rest) = path.split_at(end);
+
+    let mut segments: Vec<&str> = Vec::new();
+    let mut trailing_slash = false;
+    for segment in path.split('/').skip(1) {
+        trailing_slash = false;
+        match segment {
+            "." => trailing_slash = true,
+            ".." => {
+                segments.pop();
+                trailing_slash = true;
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    let mut resolved = String::new();
+    for segment in segments {
+        resolved.push('/');
+        resolved.push_str(segment);
+    }
+    if trailing_slash || resolved.is_empty() {
+        resolved.push('/');
+    }
+    resolved.push_str(rest);
+
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_test() {
+        let base = "https://a.com/b/c/d?q#f";
+        let cases = [
+            ("g", "https://a.com/b/c/g"),
+            ("./g/", "https://a.com/b/c/g/"),
+            ("../g", "https://a.com/b/g"),
+            ("../..", "https://a.com/"),
+            ("/g/../h?x", "https://a.com/h?x"),
+            ("//cdn.com/x.js", "https://cdn.com/x.js"),
+            ("?y", "https://a.com/b/c/d?y"),
+            ("#s", "https://a.com/b/c/d?q#s"),
+            ("", "https://a.com/b/c/d?q"),
+            (" mailto:x@a.com ", "mailto:x@a.com"),
+        ];
+        for (reference, expected) in cases {
+            assert_eq!(resolve(base, reference), expected, "{}", reference);
+        }
+
+        assert_eq!(resolve("https://a.com", "x"), "https://a.com/x");
+        assert_eq!(resolve("/relative/base", "x"), "x");
+    }
+
+    #[cfg(feature = "url")]
+    #[test]
+    fn absolutize_urls_test() {
+        let root = crate::parse(concat!(
+            r#"<base href="/docs/"><a href="../a#top">a</a><a href="">self</a><a href="mailto:x@a.com">mail</a>"#,
+            r#"<form action="?q"><button formaction=//cdn.com/b>b</button></form>"#,
+            r#"<video poster="p.jpg" src=' v.mp4 '></video><img srcset="x.png 100w,y.png, z.png 2x">"#,
+        ))
+        .unwrap();
+        absolutize_urls(&root, "https://a.com/x/y");
+
+        assert_eq!(
+            root.to_html(),
+            concat!(
+                r#"<base href="https://a.com/docs/"><a href="https://a.com/a#top">a</a><a href="">self</a>"#,
+                r#"<a href="mailto:x@a.com">mail</a><form action="https://a.com/docs/?q">"#,
+                r#"<button formaction="https://cdn.com/b">b</button></form>"#,
+                r#"<video poster="https://a.com/docs/p.jpg" src="https://a.com/docs/v.mp4"></video>"#,
+                r#"<img srcset="https://a.com/docs/x.png 100w, https://a.com/docs/y.png, https://a.com/docs/z.png 2x">"#,
+            )
+        );
+
+        // the base is already absolute, so a second pass changes nothing
+        let html = root.to_html();
+        absolutize_urls(&root, "https://other.com/");
+        assert_eq!(root.to_html(), html);
+    }
+}
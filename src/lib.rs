@@ -1,14 +1,67 @@
 mod parser;
 mod extent;
+mod selection;
 
+pub mod audit;
+pub mod convert;
 pub mod dom;
+pub mod email;
+pub mod extract;
+pub mod rewrite;
+pub mod report;
+pub mod sanitize;
+pub mod stats;
+pub mod selector;
+pub mod syntax;
+pub mod testing;
+pub mod xpath;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 pub use parser::parse;
+pub use parser::parse_with_options;
+pub use parser::parse_with_diagnostics;
+pub use parser::parse_document;
+pub use parser::parse_lenient;
+pub use parser::parse_borrowed;
+pub use parser::parse_parallel;
+pub use parser::parse_fragment;
+pub use parser::parse_fragment_with_options;
+pub use parser::parse_reader;
+pub use parser::parse_bytes;
+pub use parser::parse_bytes_with_options;
+pub use parser::decode_bytes;
+pub use parser::tokenize;
+pub use parser::Encoding;
+pub use parser::Event;
+pub use parser::Tokenizer;
+pub use parser::ParseOptions;
+pub use parser::Strictness;
+pub use parser::Whitespace;
+pub use parser::ParseResult;
+pub use parser::Diagnostic;
+pub use parser::DiagnosticKind;
+pub use parser::Parser;
+pub use parser::ParseError;
+pub use parser::Position;
+
+pub use selection::Selection;
+
+pub use extent::Attribute;
+pub use extent::AttrMatcher;
 
 pub use extent::get_node_by_name;
 pub use extent::get_node_by_attribute;
+pub use extent::get_node_by_id;
 
 pub use extent::get_nodes_by_name;
 pub use extent::get_nodes_by_attribute;
+pub use extent::get_nodes_by_class;
+pub use extent::get_nodes_by_role;
 
 pub use extent::get_first_child;
+
+pub use extent::find;
+pub use extent::find_all;
+pub use extent::find_by_text;
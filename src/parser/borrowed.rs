@@ -0,0 +1,335 @@
+use std::borrow::Cow;
+
+use crate::dom::borrowed::{AttrValue, Node, Payload, Tag};
+use crate::dom::{self, ROOT_TAG_NAME};
+
+use super::{entity, is_end_implied, parse_doctype, Input};
+
+/// Parses the tag document into a tree that borrows from `doc`.
+/// See [`dom::borrowed`](crate::dom::borrowed).
+///
+/// Like [`parse_lenient`](super::parse_lenient), it never fails: tags
+/// that are not closed are closed at the end of their parent, end tags
+/// that match no open tag are discarded, and a `<` that does not start a
+/// tag is text. The whitespace between tags is dropped.
+///
+/// # Examples
+/// ```rust
+/// use html::dom::borrowed::Payload;
+///
+/// let doc = String::from(r#"<ul><li class="a">one<li>two &amp; three</ul>"#);
+/// let root = html::parse_borrowed(&doc);
+///
+/// let texts: Vec<&str> = root
+///     .descendants()
+///     .filter_map(|node| match node.get_payload() {
+///         Payload::Text(text) => Some(text.as_ref()),
+///         _ => None,
+///     })
+///     .collect();
+///
+/// assert_eq!(texts, ["one", "two & three"]);
+/// assert_eq!(root.to_node().to_html(), r#"<ul><li class="a">one</li><li>two &amp; three</li></ul>"#);
+/// ```
+pub fn parse_borrowed(doc: &str) -> Node<'_> {
+    let mut scanner = Scanner { doc, cursor: 0 };
+    // the open elements, the root first
+    let mut open_nodes = vec![Node::new(Payload::Tag(Tag::new(ROOT_TAG_NAME, Vec::new(), false)))];
+
+    while let Some(token) = scanner.next_token() {
+        match token {
+            Token::EndTag(name) => {
+                let open_idx = open_nodes
+                    .iter()
+                    .skip(1)
+                    .rposition(|node| tag_name(node).is_some_and(|open| open.eq_ignore_ascii_case(name)));
+                if let Some(open_idx) = open_idx {
+                    // close the matched tag and the tags opened after it
+                    while open_nodes.len() > open_idx + 1 {
+                        close(&mut open_nodes);
+                    }
+                }
+            }
+            Token::Node(node) => {
+                let name = match node.get_payload() {
+                    Payload::Tag(tag) => tag.get_name(),
+                    Payload::Text(text) if !is_preformatted(&open_nodes) && text.trim().is_empty() => continue,
+                    _ => {
+                        open_nodes.last_mut().unwrap().push_child(node);
+                        continue;
+                    }
+                };
+
+                // close the tags whose end tag is implied. `<li>one<li>two`
+                while open_nodes.len() > 1 {
+                    let implied = tag_name(open_nodes.last().unwrap()).is_some_and(|open| is_end_implied(open, name));
+                    if !implied {
+                        break;
+                    }
+                    close(&mut open_nodes);
+                }
+
+                let is_void = dom::Tag::new(name).is_void_element();
+                let self_closing = matches!(node.get_payload(), Payload::Tag(tag) if tag.is_self_closing());
+                if is_void || self_closing {
+                    open_nodes.last_mut().unwrap().push_child(node);
+                } else {
+                    open_nodes.push(node);
+                }
+            }
+            Token::Content(content) => open_nodes.last_mut().unwrap().push_child(content),
+        }
+    }
+
+    while open_nodes.len() > 1 {
+        close(&mut open_nodes);
+    }
+
+    open_nodes.pop().unwrap()
+}
+
+/// Moves the last open node into its parent.
+fn close(open_nodes: &mut Vec<Node>) {
+    let node = open_nodes.pop().unwrap();
+    open_nodes.last_mut().unwrap().push_child(node);
+}
+
+fn tag_name<'a>(node: &Node<'a>) -> Option<&'a str> {
+    match node.get_payload() {
+        Payload::Tag(tag) => Some(tag.get_name()),
+        _ => None,
+    }
+}
+
+fn is_preformatted(open_nodes: &[Node]) -> bool {
+    open_nodes.iter().any(|node| tag_name(node) == Some("pre"))
+}
+
+enum Token<'a> {
+    /// A start tag, text, comment or doctype.
+    Node(Node<'a>),
+    EndTag(&'a str),
+    /// The content of a raw text element, which is its child.
+    Content(Node<'a>),
+}
+
+/// Reads the tokens of the document as slices of it.
+///
+/// Every delimiter is ASCII, so the byte positions are always on the
+/// boundaries of characters.
+struct Scanner<'a> {
+    doc: &'a str,
+    cursor: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn rest(&self) -> &'a str {
+        &self.doc[self.cursor..]
+    }
+
+    fn next_token(&mut self) -> Option<Token<'a>> {
+        let rest = self.rest();
+        if rest.is_empty() {
+            return None;
+        }
+
+        let bytes = rest.as_bytes();
+        let token = if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment.find("-->").unwrap_or(comment.len());
+            self.cursor += "<!--".len() + (end + "-->".len()).min(comment.len());
+            Token::Node(Node::new(Payload::Comment(&comment[..end])))
+        } else if bytes.len() >= 9 && bytes[..9].eq_ignore_ascii_case(b"<!doctype") {
+            let end = rest.find('>').map_or(rest.len(), |end| end + 1);
+            self.cursor += end;
+            match parse_doctype(&mut Input::new(&rest[..end])) {
+                Ok(doctype) => Token::Node(Node::new(Payload::Doctype(doctype))),
+                Err(_) => Token::Node(Node::new(Payload::Text(Cow::Borrowed(&rest[..end])))),
+            }
+        } else if bytes.len() >= 3 && bytes[0] == b'<' && bytes[1] == b'/' && bytes[2].is_ascii_alphabetic() {
+            let name_end = find_byte(rest, 2, |b| b.is_ascii_whitespace() || b == b'>' || b == b'/');
+            let end = rest.find('>').map_or(rest.len(), |end| end + 1);
+            self.cursor += end;
+            Token::EndTag(&rest[2..name_end])
+        } else if bytes.len() >= 2 && bytes[0] == b'<' && bytes[1].is_ascii_alphabetic() {
+            let tag = self.read_start_tag();
+            let name = tag.get_name();
+            let is_raw = dom::Tag::new(name).is_raw_text_element();
+            let is_escapable_raw = dom::Tag::new(name).is_escapable_raw_text_element();
+            if !(is_raw || is_escapable_raw) || tag.is_self_closing() {
+                return Some(Token::Node(Node::new(Payload::Tag(tag))));
+            }
+
+            // the content and the end tag of the raw text element
+            let content = self.read_raw_text(name);
+            let mut node = Node::new(Payload::Tag(tag));
+            if !content.is_empty() {
+                node.push_child(Node::new(match is_raw {
+                    true => Payload::RawText(content),
+                    false => Payload::Text(decode(content)),
+                }));
+            }
+            Token::Content(node)
+        } else {
+            // text up to the next '<' that starts a tag, a comment or a doctype
+            let mut end = rest.chars().next().map_or(0, char::len_utf8);
+            while let Some(i) = rest[end..].find('<') {
+                end += i;
+                let next = rest.as_bytes().get(end + 1).copied().unwrap_or(b' ');
+                if next.is_ascii_alphabetic() || next == b'/' || next == b'!' {
+                    break;
+                }
+                end += 1;
+            }
+            if !rest[end..].starts_with('<') {
+                end = rest.len();
+            }
+            self.cursor += end;
+            Token::Node(Node::new(Payload::Text(decode(&rest[..end]))))
+        };
+
+        Some(token)
+    }
+
+    /// Reads `<name attribute="value" ... [/]>`.
+    fn read_start_tag(&mut self) -> Tag<'a> {
+        let rest = self.rest();
+        let name_end = find_byte(rest, 1, |b| b.is_ascii_whitespace() || b == b'>' || b == b'/');
+        let name = &rest[1..name_end];
+
+        let mut attributes: Vec<(&'a str, AttrValue<'a>)> = Vec::new();
+        let mut self_closing = false;
+        let mut i = name_end;
+        let bytes = rest.as_bytes();
+        loop {
+            i = skip_whitespace(rest, i);
+            match bytes.get(i) {
+                None => break,
+                Some(b'>') => {
+                    i += 1;
+                    break;
+                }
+                Some(b'/') => {
+                    i += 1;
+                    if bytes.get(i) == Some(&b'>') {
+                        self_closing = true;
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+
+            let attr_end = find_byte(rest, i, |b| b.is_ascii_whitespace() || b == b'=' || b == b'>' || b == b'/');
+            let attr_name = &rest[i..attr_end.max(i + 1)];
+            i = skip_whitespace(rest, attr_end.max(i + 1));
+
+            let mut value = AttrValue::Empty;
+            if bytes.get(i) == Some(&b'=') {
+                i = skip_whitespace(rest, i + 1);
+                match bytes.get(i) {
+                    Some(&quote) if quote == b'"' || quote == b'\'' => {
+                        let value_end = find_byte(rest, i + 1, |b| b == quote);
+                        value = AttrValue::Quoted(decode(&rest[i + 1..value_end]));
+                        i = (value_end + 1).min(rest.len());
+                    }
+                    _ => {
+                        let value_end = find_byte(rest, i, |b| b.is_ascii_whitespace() || b == b'>');
+                        value = AttrValue::Unquoted(decode(&rest[i..value_end]));
+                        i = value_end;
+                    }
+                }
+            }
+
+            // the first one is kept, as browsers do
+            if !attributes.iter().any(|(name, _)| *name == attr_name) {
+                attributes.push((attr_name, value));
+            }
+        }
+
+        self.cursor += i;
+        Tag::new(name, attributes, self_closing)
+    }
+
+    /// Reads the text up to `</name` and skips the end tag.
+    fn read_raw_text(&mut self, name: &str) -> &'a str {
+        let rest = self.rest();
+        let bytes = rest.as_bytes();
+
+        let mut from = 0;
+        let end = loop {
+            let i = match rest[from..].find("</") {
+                Some(i) => from + i,
+                None => break rest.len(),
+            };
+            let name_end = i + 2 + name.len();
+            let is_end_tag = bytes.get(i + 2..name_end).is_some_and(|n| n.eq_ignore_ascii_case(name.as_bytes()))
+                && bytes.get(name_end).is_none_or(|&b| b.is_ascii_whitespace() || b == b'/' || b == b'>');
+            if is_end_tag {
+                break i;
+            }
+            from = i + 2;
+        };
+
+        let tag_end = rest[end..].find('>').map_or(rest.len(), |i| end + i + 1);
+        self.cursor += tag_end;
+        &rest[..end]
+    }
+}
+
+/// Returns the position of the first byte from `from` for which `f` is
+/// true, or the length of `s`.
+fn find_byte(s: &str, from: usize, f: impl Fn(u8) -> bool) -> usize {
+    s.as_bytes()
+        .iter()
+        .skip(from)
+        .position(|&b| f(b))
+        .map_or(s.len(), |i| from + i)
+}
+
+fn skip_whitespace(s: &str, from: usize) -> usize {
+    find_byte(s, from, |b| !b.is_ascii_whitespace())
+}
+
+/// Decodes the character references, copying the text only if it has one.
+fn decode(text: &str) -> Cow<'_, str> {
+    match text.contains('&') {
+        true => Cow::Owned(entity::decode(text)),
+        false => Cow::Borrowed(text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn parse_borrowed_test() {
+        let html = r#"<!DOCTYPE html>
+        <html><head><title>a &lt; b</title><script>if (a</b) {}</script></head>
+        <body><!-- c --><p class=x id="y" disabled>one<br/>two<p>three &amp; 4</p>
+        <pre> keep  </pre><textarea></textarea></body></html>"#;
+
+        let root = parse_borrowed(html);
+        assert_eq!(root.to_node(), parse(html).unwrap());
+
+        let p = root.descendants().find(|node| tag_name(node) == Some("p")).unwrap();
+        match p.get_payload() {
+            Payload::Tag(tag) => {
+                assert_eq!(tag.get_attribute_value("class"), Some("x"));
+                assert_eq!(tag.get_attribute(""), None);
+                assert_eq!(tag.get_attribute("disabled"), Some(&AttrValue::Empty));
+            }
+            _ => unreachable!(),
+        }
+        assert!(matches!(p.get_children()[0].get_payload(), Payload::Text(Cow::Borrowed("one"))));
+    }
+
+    #[test]
+    fn broken_test() {
+        let root = parse_borrowed("<div a='1 <b>x</i></b> 1 < 2<!-- c");
+        assert_eq!(root.to_node().to_html(), "<div a=\"1 <b>x</i></b> 1 < 2<!-- c\"></div>");
+
+        let root = parse_borrowed("é</b>b < c<b>d");
+        assert_eq!(root.to_node().to_html(), "éb &lt; c<b>d</b>");
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+
+use crate::dom::Node;
+use super::Position;
+
+/// A problem in the document that the parser recovered from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    /// Where the problem starts.
+    pub position: Position,
+}
+
+/// The kinds of [`Diagnostic`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// An end tag that matches no open element, which is discarded. `<p></b></p>`
+    StrayEndTag(String),
+    /// An end tag that closes an open element before the element opened
+    /// inside it was closed. `<b><i>text</b>` closes `i` at `</b>`.
+    MismatchedEndTag { open: String, found: String },
+    /// An element that is still open at the end of the document. `<div>text`
+    UnclosedTag(String),
+    /// An attribute that appears again in the same tag. Only the first one
+    /// is kept. `<a href="x" href="y">`
+    DuplicateAttribute(String),
+    /// An id that an element before this one has already. Lookups by id
+    /// find the first one. `<p id="a"></p><p id="a"></p>`
+    DuplicateId(String),
+    /// A control character or a noncharacter, which should not appear in
+    /// the document. `U+0000`, `U+FFFF`
+    InvalidCharacter(char),
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            DiagnosticKind::StrayEndTag(name) => {
+                write!(f, "End tag </{}> matches no open element", name)?
+            }
+            DiagnosticKind::MismatchedEndTag { open, found } => {
+                write!(f, "End tag </{}> closes <{}> that is not closed", found, open)?
+            }
+            DiagnosticKind::UnclosedTag(name) => write!(f, "<{}> is not closed", name)?,
+            DiagnosticKind::DuplicateAttribute(name) => write!(f, "Duplicate attribute {}", name)?,
+            DiagnosticKind::DuplicateId(id) => write!(f, "Duplicate id {}", id)?,
+            DiagnosticKind::InvalidCharacter(c) => write!(f, "Invalid character U+{:04X}", *c as u32)?,
+        }
+
+        write!(f, " at {}.", self.position)
+    }
+}
+
+/// Returns true if `c` should not appear in a document.
+/// The whitespace characters `\t`, `\n`, `\x0C` and `\r` are allowed.
+pub(super) fn is_invalid_char(c: char) -> bool {
+    let noncharacter = matches!(c, '\u{FDD0}'..='\u{FDEF}') || (c as u32) & 0xFFFE == 0xFFFE;
+    (c.is_control() && !matches!(c, '\t' | '\n' | '\x0C' | '\r')) || noncharacter
+}
+
+/// The tree and the problems found while parsing it.
+/// Returned by [`parse_with_diagnostics`](crate::parse_with_diagnostics).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseResult {
+    pub dom: Node,
+    pub warnings: Vec<Diagnostic>,
+}
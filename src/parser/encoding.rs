@@ -0,0 +1,219 @@
+/// The character encodings a document in bytes can be decoded from.
+/// See [`decode_bytes`](crate::decode_bytes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// Also used for `iso-8859-1` and `us-ascii`, as browsers do.
+    Windows1252,
+    /// An encoding with the name, such as `Shift_JIS`, that can't be
+    /// decoded. It is decoded as UTF-8 with invalid sequences replaced.
+    Unsupported(&'static str),
+}
+
+/// The names of the encodings that are known but can't be decoded, with
+/// their labels. <https://encoding.spec.whatwg.org/#names-and-labels>
+const UNSUPPORTED_ENCODINGS: [(&str, &[&str]); 25] = [
+    ("Shift_JIS", &["shift_jis", "sjis", "ms_kanji", "csshiftjis", "windows-31j", "x-sjis", "ms932"]),
+    ("EUC-JP", &["euc-jp", "cseucpkdfmtjapanese", "x-euc-jp"]),
+    ("ISO-2022-JP", &["iso-2022-jp", "csiso2022jp"]),
+    ("GBK", &[
+        "gbk", "gb2312", "gb_2312", "gb_2312-80", "chinese", "csgb2312", "csiso58gb231280", "iso-ir-58", "x-gbk",
+    ]),
+    ("gb18030", &["gb18030"]),
+    ("Big5", &["big5", "big5-hkscs", "cn-big5", "csbig5", "x-x-big5"]),
+    ("EUC-KR", &["euc-kr", "cseuckr", "csksc56011987", "iso-ir-149", "korean", "ks_c_5601-1987", "windows-949"]),
+    ("IBM866", &["ibm866", "866", "cp866", "csibm866"]),
+    ("ISO-8859-2", &["iso-8859-2", "iso8859-2", "iso_8859-2", "latin2", "l2"]),
+    ("ISO-8859-5", &["iso-8859-5", "iso8859-5", "iso_8859-5", "cyrillic"]),
+    ("ISO-8859-7", &["iso-8859-7", "iso8859-7", "iso_8859-7", "greek"]),
+    ("ISO-8859-8", &["iso-8859-8", "iso8859-8", "iso_8859-8", "hebrew", "visual"]),
+    ("ISO-8859-15", &["iso-8859-15", "iso8859-15", "iso_8859-15", "latin9"]),
+    ("KOI8-R", &["koi8-r", "koi8_r", "koi8", "koi", "cskoi8r"]),
+    ("KOI8-U", &["koi8-u", "koi8-ru"]),
+    ("macintosh", &["macintosh", "mac", "csmacintosh", "x-mac-roman"]),
+    ("windows-874", &["windows-874", "dos-874", "tis-620", "iso-8859-11", "iso8859-11"]),
+    ("windows-1250", &["windows-1250", "cp1250", "x-cp1250"]),
+    ("windows-1251", &["windows-1251", "cp1251", "x-cp1251"]),
+    ("windows-1253", &["windows-1253", "cp1253", "x-cp1253"]),
+    ("windows-1254", &["windows-1254", "cp1254", "x-cp1254", "iso-8859-9", "iso8859-9", "latin5", "l5"]),
+    ("windows-1255", &["windows-1255", "cp1255", "x-cp1255"]),
+    ("windows-1256", &["windows-1256", "cp1256", "x-cp1256"]),
+    ("windows-1257", &["windows-1257", "cp1257", "x-cp1257"]),
+    ("windows-1258", &["windows-1258", "cp1258", "x-cp1258"]),
+];
+
+/// How many bytes are searched for `<meta charset>`.
+const PRESCAN_LEN: usize = 1024;
+
+/// The characters of windows-1252 from 0x80 to 0x9F. The rest is the same as ISO-8859-1.
+const WINDOWS_1252: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+];
+
+impl Encoding {
+    /// Returns the encoding of a label such as `"UTF-8"` or `"latin1"`,
+    /// or `None` if it is not the label of an encoding. The label of an
+    /// encoding that can't be decoded, such as `"shift_jis"`, gives
+    /// [`Encoding::Unsupported`].
+    /// <https://encoding.spec.whatwg.org/#names-and-labels>
+    pub fn for_label(label: &str) -> Option<Encoding> {
+        let label = label.trim().to_ascii_lowercase();
+        let encoding = match label.as_str() {
+            "utf-8" | "utf8" | "unicode-1-1-utf-8" | "unicode11utf8" | "unicode20utf8" | "x-unicode20utf8" => {
+                Encoding::Utf8
+            }
+            "utf-16" | "utf-16le" | "ucs-2" | "unicode" | "iso-10646-ucs-2" | "csunicode" => Encoding::Utf16Le,
+            "utf-16be" | "unicodefffe" => Encoding::Utf16Be,
+            "windows-1252" | "cp1252" | "x-cp1252" | "latin1" | "l1" | "iso-8859-1" | "iso8859-1"
+            | "iso_8859-1" | "iso88591" | "iso_8859-1:1987" | "iso-ir-100" | "cp819" | "ibm819"
+            | "csisolatin1" | "ascii" | "us-ascii" | "ansi_x3.4-1968" | "csascii" => Encoding::Windows1252,
+            label => {
+                let (name, _) = UNSUPPORTED_ENCODINGS.iter().find(|(_, labels)| labels.contains(&label))?;
+                Encoding::Unsupported(name)
+            }
+        };
+
+        Some(encoding)
+    }
+
+    /// Decodes `bytes`. An invalid sequence is replaced with U+FFFD.
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        match self {
+            Encoding::Utf8 | Encoding::Unsupported(_) => String::from_utf8_lossy(bytes).into_owned(),
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let units = bytes.chunks(2).map(|pair| match (pair, self) {
+                    ([a, b], Encoding::Utf16Le) => u16::from_le_bytes([*a, *b]),
+                    ([a, b], _) => u16::from_be_bytes([*a, *b]),
+                    // an odd byte at the end
+                    _ => 0xFFFD,
+                });
+                char::decode_utf16(units)
+                    .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                    .collect()
+            }
+            Encoding::Windows1252 => bytes
+                .iter()
+                .map(|&b| match b {
+                    0x80..=0x9F => WINDOWS_1252[(b - 0x80) as usize],
+                    _ => char::from(b),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Returns the encoding of a document, and the length of its byte order mark.
+///
+/// The encoding is taken from the byte order mark, then the charset of
+/// `content_type`, then a `<meta charset>` in the first 1024 bytes. If none
+/// of them is found or supported, the document is UTF-8 if it is valid
+/// UTF-8, and windows-1252 otherwise.
+pub(super) fn sniff(bytes: &[u8], content_type: Option<&str>) -> (Encoding, usize) {
+    match bytes {
+        [0xEF, 0xBB, 0xBF, ..] => return (Encoding::Utf8, 3),
+        [0xFF, 0xFE, ..] => return (Encoding::Utf16Le, 2),
+        [0xFE, 0xFF, ..] => return (Encoding::Utf16Be, 2),
+        _ => {}
+    }
+
+    let hint = content_type
+        .and_then(charset_parameter)
+        .and_then(|charset| Encoding::for_label(&charset));
+    if let Some(encoding) = hint {
+        return (encoding, 0);
+    }
+
+    if let Some(encoding) = prescan(&bytes[..bytes.len().min(PRESCAN_LEN)]) {
+        // a document read as ASCII can't be UTF-16
+        let encoding = match encoding {
+            Encoding::Utf16Le | Encoding::Utf16Be => Encoding::Utf8,
+            encoding => encoding,
+        };
+        return (encoding, 0);
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(_) => (Encoding::Utf8, 0),
+        Err(_) => (Encoding::Windows1252, 0),
+    }
+}
+
+/// Returns the `charset` parameter of a Content-Type. `text/html; charset=utf-8`
+pub(crate) fn charset_parameter(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches(['"', '\'']).to_string())
+    })
+}
+
+/// Returns the encoding of the first `<meta>` with a charset, such as
+/// `<meta charset="utf-8">` or `<meta content="text/html; charset=utf-8">`.
+fn prescan(bytes: &[u8]) -> Option<Encoding> {
+    let head = String::from_utf8_lossy(bytes).to_ascii_lowercase();
+
+    let mut rest = head.as_str();
+    while let Some(bgn) = rest.find("<meta") {
+        rest = &rest[bgn + "<meta".len()..];
+        let end = rest.find('>').unwrap_or(rest.len());
+        let tag = &rest[..end];
+
+        let mut attrs = tag;
+        while let Some(i) = attrs.find("charset") {
+            attrs = attrs[i + "charset".len()..].trim_start();
+            let value = match attrs.strip_prefix('=') {
+                Some(value) => value.trim_start().trim_start_matches(['"', '\'']),
+                None => continue,
+            };
+            let label_end = value
+                .find(|c: char| !(c.is_ascii_alphanumeric() || "-_.:".contains(c)))
+                .unwrap_or(value.len());
+            if let Some(encoding) = Encoding::for_label(&value[..label_end]) {
+                return Some(encoding);
+            }
+        }
+
+        rest = &rest[end..];
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniff_test() {
+        assert_eq!(sniff(b"\xEF\xBB\xBF<p>", Some("text/html; charset=latin1")), (Encoding::Utf8, 3));
+        assert_eq!(sniff(b"\xFF\xFE<\x00", None), (Encoding::Utf16Le, 2));
+        assert_eq!(sniff(b"<meta charset=utf-8>", Some("text/html; charset=\"ISO-8859-1\"")).0, Encoding::Windows1252);
+        assert_eq!(sniff(b"<meta charset=x-unknown>", Some("text/html; charset=x-unknown")).0, Encoding::Utf8);
+        assert_eq!(
+            sniff(br#"<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=windows-1252">"#, None).0,
+            Encoding::Windows1252
+        );
+        assert_eq!(sniff(b"<meta charset='utf-16'>", None).0, Encoding::Utf8);
+        assert_eq!(sniff(b"<p>caf\xE9</p>", None).0, Encoding::Windows1252);
+
+        // a legacy encoding is recognized, not taken for UTF-8
+        let shift_jis = Encoding::Unsupported("Shift_JIS");
+        assert_eq!(sniff(b"<p>\x93\xFA</p>", Some("text/html; charset=Shift_JIS")).0, shift_jis);
+        assert_eq!(sniff(b"<meta charset=\"euc-kr\">", None).0, Encoding::Unsupported("EUC-KR"));
+        assert_eq!(Encoding::for_label(" Windows-1251 "), Some(Encoding::Unsupported("windows-1251")));
+    }
+
+    #[test]
+    fn decode_test() {
+        assert_eq!(Encoding::Windows1252.decode(b"caf\xE9 \x80\x96"), "café €–");
+        assert_eq!(Encoding::Utf16Be.decode(b"\x00a\xD8\x3D\xDE\x00"), "a😀");
+        assert_eq!(Encoding::Utf16Le.decode(b"a\x00b"), "a\u{FFFD}");
+        assert_eq!(Encoding::Utf8.decode(b"a\xFFb"), "a\u{FFFD}b");
+    }
+}
@@ -0,0 +1,110 @@
+/// Named character references and the characters they represent.
+///
+/// This is not the whole table from the spec, only the references that
+/// commonly appear in documents.
+const NAMED_ENTITIES: [(&str, char); 102] = [
+    ("amp", '&'), ("lt", '<'), ("gt", '>'), ("quot", '"'), ("apos", '\''),
+    ("nbsp", '\u{a0}'), ("iexcl", '¡'), ("cent", '¢'), ("pound", '£'), ("curren", '¤'),
+    ("yen", '¥'), ("brvbar", '¦'), ("sect", '§'), ("uml", '¨'), ("copy", '©'),
+    ("ordf", 'ª'), ("laquo", '«'), ("not", '¬'), ("shy", '\u{ad}'), ("reg", '®'),
+    ("macr", '¯'), ("deg", '°'), ("plusmn", '±'), ("sup2", '²'), ("sup3", '³'),
+    ("acute", '´'), ("micro", 'µ'), ("para", '¶'), ("middot", '·'), ("cedil", '¸'),
+    ("sup1", '¹'), ("ordm", 'º'), ("raquo", '»'), ("frac14", '¼'), ("frac12", '½'),
+    ("frac34", '¾'), ("iquest", '¿'), ("times", '×'), ("divide", '÷'), ("Agrave", 'À'),
+    ("Aacute", 'Á'), ("Acirc", 'Â'), ("Atilde", 'Ã'), ("Auml", 'Ä'), ("Aring", 'Å'),
+    ("AElig", 'Æ'), ("Ccedil", 'Ç'), ("Egrave", 'È'), ("Eacute", 'É'), ("Ntilde", 'Ñ'),
+    ("Ouml", 'Ö'), ("Uuml", 'Ü'), ("szlig", 'ß'), ("agrave", 'à'), ("aacute", 'á'),
+    ("acirc", 'â'), ("atilde", 'ã'), ("auml", 'ä'), ("aring", 'å'), ("aelig", 'æ'),
+    ("ccedil", 'ç'), ("egrave", 'è'), ("eacute", 'é'), ("ecirc", 'ê'), ("euml", 'ë'),
+    ("iacute", 'í'), ("ntilde", 'ñ'), ("oacute", 'ó'), ("ouml", 'ö'), ("uacute", 'ú'),
+    ("uuml", 'ü'), ("yuml", 'ÿ'), ("ensp", '\u{2002}'), ("emsp", '\u{2003}'), ("thinsp", '\u{2009}'),
+    ("zwnj", '\u{200c}'), ("zwj", '\u{200d}'), ("ndash", '–'), ("mdash", '—'), ("lsquo", '‘'),
+    ("rsquo", '’'), ("sbquo", '‚'), ("ldquo", '“'), ("rdquo", '”'), ("bdquo", '„'),
+    ("dagger", '†'), ("Dagger", '‡'), ("bull", '•'), ("hellip", '…'), ("permil", '‰'),
+    ("prime", '′'), ("lsaquo", '‹'), ("rsaquo", '›'), ("euro", '€'), ("trade", '™'),
+    ("larr", '←'), ("uarr", '↑'), ("rarr", '→'), ("darr", '↓'), ("harr", '↔'),
+    ("hearts", '♥'), ("check", '✓'),
+];
+
+/// Decodes the character references in `text`. `&amp;`, `&#39;`, `&#x27;`
+///
+/// References that are unknown or not terminated by ';' are left as they are.
+pub fn decode(text: &str) -> String {
+    if !text.contains('&') {
+        return String::from(text);
+    }
+
+    let mut res = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        res.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        // &name;
+        // ^    ^
+        // the reference is between '&' and ';'
+        let decoded = rest.find(';').and_then(|semicolon| {
+            decode_reference(&rest[1..semicolon]).map(|c| (c, semicolon))
+        });
+
+        match decoded {
+            Some((c, semicolon)) => {
+                res.push(c);
+                rest = &rest[semicolon + 1..];
+            }
+            None => {
+                res.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    res.push_str(rest);
+
+    res
+}
+
+/// Returns true if `text` starts with a character reference that
+/// [`decode`] decodes. `&amp;`
+pub(crate) fn starts_with_reference(text: &str) -> bool {
+    text.strip_prefix('&')
+        .and_then(|rest| rest.split_once(';'))
+        .is_some_and(|(reference, _)| decode_reference(reference).is_some())
+}
+
+/// Returns the character represented by the reference without '&' and ';'.
+fn decode_reference(reference: &str) -> Option<char> {
+    if let Some(number) = reference.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse::<u32>().ok()?,
+        };
+
+        // a null or an invalid code point is replaced with U+FFFD
+        return match code {
+            0 => Some(char::REPLACEMENT_CHARACTER),
+            _ => Some(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)),
+        };
+    }
+
+    NAMED_ENTITIES
+        .iter()
+        .find(|(name, _)| *name == reference)
+        .map(|(_, c)| *c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_test() {
+        assert_eq!(decode("a &amp; b &lt;c&gt;"), "a & b <c>");
+        assert_eq!(decode("&#39;&#x27;&#X27;&copy;"), "''\'©");
+        assert_eq!(decode("&unknown; & &amp"), "&unknown; & &amp");
+        assert_eq!(decode("&#0;&#xD800;"), "\u{fffd}\u{fffd}");
+
+        assert!(starts_with_reference("&amp; b") && starts_with_reference("&#x27;"));
+        assert!(!starts_with_reference("& b;") && !starts_with_reference("&unknown;"));
+        assert!(!starts_with_reference("&amp"));
+    }
+}
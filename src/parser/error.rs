@@ -0,0 +1,101 @@
+use std::error::Error;
+use std::fmt;
+
+/// A position in the document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// Byte offset from the beginning of the document.
+    pub offset: usize,
+    /// Line number, starting from 1.
+    pub line: usize,
+    /// Column number in characters, starting from 1.
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// The error returned when a document cannot be parsed.
+/// Each variant carries the position where the problem starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The document ends in the middle of a tag. `<tag`
+    UnterminatedTag(Position),
+    /// The quoted value of an attribute is not closed. `<tag attribute="value>`
+    UnterminatedAttribute(Position),
+    /// The document ends in the middle of a comment. `<!-- comment`
+    UnterminatedComment(Position),
+    /// The document ends before the end tag of a raw text element. `<script>code`
+    UnterminatedRawText(Position),
+    /// The document ends in the middle of a CDATA section in `<svg>` or
+    /// `<math>`. `<![CDATA[text`
+    UnterminatedCdata(Position),
+    /// The doctype is malformed, such as an identifier that is not quoted.
+    /// `<!doctype html PUBLIC -//W3C//DTD>`
+    InvalidDoctype(Position),
+    /// An end tag does not match the open element and `options.strictness`
+    /// is [`Strictness::Strict`](crate::Strictness::Strict). `<b><i></b>`
+    MismatchedEndTag(Position),
+    /// The document is longer than `options.max_input_len` bytes.
+    /// The position is where the limit is reached.
+    InputLimitExceeded(Position),
+    /// An element is nested deeper than `options.max_depth`.
+    DepthLimitExceeded(Position),
+    /// The document has more nodes than `options.max_nodes`.
+    NodeLimitExceeded(Position),
+    /// An empty or reversed range of the document was requested.
+    InvalidRange(Position),
+    /// A range of the document that doesn't exist was requested.
+    OutOfInput(Position),
+    /// The document in bytes is in an encoding that can't be decoded,
+    /// such as Shift_JIS. See [`Encoding::Unsupported`](crate::Encoding::Unsupported).
+    UnsupportedEncoding(Position),
+}
+
+impl ParseError {
+    /// Returns the position where the problem starts.
+    pub fn position(&self) -> Position {
+        match self {
+            ParseError::UnterminatedTag(position)
+            | ParseError::UnterminatedAttribute(position)
+            | ParseError::UnterminatedComment(position)
+            | ParseError::UnterminatedRawText(position)
+            | ParseError::UnterminatedCdata(position)
+            | ParseError::InvalidDoctype(position)
+            | ParseError::MismatchedEndTag(position)
+            | ParseError::InputLimitExceeded(position)
+            | ParseError::DepthLimitExceeded(position)
+            | ParseError::NodeLimitExceeded(position)
+            | ParseError::InvalidRange(position)
+            | ParseError::OutOfInput(position)
+            | ParseError::UnsupportedEncoding(position) => *position,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            ParseError::UnterminatedTag(_) => "Input ends in the middle of the tag",
+            ParseError::UnterminatedAttribute(_) => "There is no delimiter to terminate the attribute",
+            ParseError::UnterminatedComment(_) => "Input ends in the middle of the comment",
+            ParseError::UnterminatedRawText(_) => "Input ends before the end tag of the raw text",
+            ParseError::UnterminatedCdata(_) => "Input ends in the middle of the CDATA section",
+            ParseError::InvalidDoctype(_) => "The doctype is malformed",
+            ParseError::MismatchedEndTag(_) => "The end tag does not match the open element",
+            ParseError::InputLimitExceeded(_) => "Input is longer than the limit",
+            ParseError::DepthLimitExceeded(_) => "The element is nested deeper than the limit",
+            ParseError::NodeLimitExceeded(_) => "There are more nodes than the limit",
+            ParseError::InvalidRange(_) => "Invalid range",
+            ParseError::OutOfInput(_) => "Out of input",
+            ParseError::UnsupportedEncoding(_) => "The encoding of the document is not supported",
+        };
+
+        write!(f, "{} at {}.", message, self.position())
+    }
+}
+
+impl Error for ParseError {}
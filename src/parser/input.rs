@@ -1,110 +1,104 @@
+use super::error::{ParseError, Position};
+use crate::dom::Span;
+
+/// The document as the parser reads it.
+///
+/// Cursors are indices of characters rather than bytes, so that moving a
+/// cursor never splits a character such as `é` or `😀`. Positions and spans
+/// give byte offsets into the document, which are on character boundaries.
 #[derive(Debug)]
 pub struct Input {
     input: Vec<char>,
     cursor: usize,
+    // the byte offset of each character, and the length of input at the end
+    offsets: Vec<usize>,
+    // the cursors of the first character of each line
+    line_starts: Vec<usize>,
 }
 
 impl Input {
     pub fn new(input: &str) -> Input {
+        let input: Vec<char> = input.trim_end().chars().collect();
+
+        let mut offsets = Vec::with_capacity(input.len() + 1);
+        let mut line_starts = vec![0];
+        let mut offset = 0;
+        for (i, c) in input.iter().enumerate() {
+            offsets.push(offset);
+            offset += c.len_utf8();
+            if *c == '\n' {
+                line_starts.push(i + 1);
+            }
+        }
+        offsets.push(offset);
+
         Input {
-            input: input.trim_end().chars().collect(),
+            input,
             cursor: 0,
+            offsets,
+            line_starts,
         }
     }
 
+    /// Moves the `self.cursor` to `cursor`.
+    /// If `cursor` is out of input, it is moved to the end of input.
     pub fn set_cursor(&mut self, cursor: usize) {
-        if self.input.len() <= cursor {
-            self.cursor = self.input.len() - 1;
-            return;
-        }
-
-        self.cursor = cursor;
+        self.cursor = cursor.min(self.input.len());
     }
 
     pub fn get_cursor(&self) -> usize {
         self.cursor
     }
 
+    /// Returns the number of characters in the `self.input`.
+    pub fn len(&self) -> usize {
+        self.input.len()
+    }
+
     /// Move the `self.cursor` to the next character.
     pub fn next(&mut self) {
-        if self.cursor < self.input.len() - 1 {
+        if self.cursor < self.input.len() {
             self.cursor += 1;
         }
     }
 
     /// Move the `self.cursor` to the next character.
-    /// Skip ' ' and '\n'.
+    /// Skip ASCII whitespace.
     pub fn next_char(&mut self) {
-        if self.cursor < self.input.len() - 1 {
-            self.cursor += 1;
-        }
+        self.next();
 
         // move the cursor until it is not a space
-        let bgn = self.cursor;
-        for i in bgn..self.input.len() {
-            if self.input[i] == ' ' || self.input[i] == '\n' {
-                if self.cursor == self.input.len() - 1 {
-                    break;
-                }
-
-                self.cursor += 1;
-            } else {
-                break;
-            }
+        while self.expect_whitespace() {
+            self.cursor += 1;
         }
     }
 
-    /// Returns true if the `self.cursor` has reached the end of the `self.input`.
+    /// Returns true if the `self.cursor` has passed the last character of the `self.input`.
     pub fn is_end(&self) -> bool {
-        if self.cursor == self.input.len() - 1 {
-            return true;
-        }
-
-        false
+        self.input.len() <= self.cursor
     }
 
     /// Returns true if the character pointed to by `self.cursor` is equal to `exp`.
     pub fn expect(&self, exp: char) -> bool {
-        if self.input[self.cursor] == exp {
-            return true;
-        }
+        self.input.get(self.cursor) == Some(&exp)
+    }
 
-        false
+    /// Returns true if the character pointed to by `self.cursor` is ASCII whitespace.
+    pub fn expect_whitespace(&self) -> bool {
+        self.input.get(self.cursor).is_some_and(char::is_ascii_whitespace)
     }
 
     /// Returns true if the string pointed to by the `self.cursor` is equal to `exp`.
     pub fn expect_str(&self, exp: &str) -> bool {
-        if self.input.len() < self.cursor + exp.len() {
-            return false;
-        }
-
-        let cursor = self.cursor;
-        let exp: Vec<char> = exp.chars().collect();
-        for i in 0..exp.len() {
-            if exp[i] != self.input[cursor + i] {
-                return false;
-            }
-        }
-
-        true
+        let mut rest = self.rest().iter();
+        exp.chars().all(|c| rest.next() == Some(&c))
     }
 
     /// Returns true if the string pointed to by the `self.cursor` is equal to `exp`.
-    /// case insensitive.
+    /// ASCII case insensitive.
     pub fn expect_str_insensitive(&self, exp: &str) -> bool {
-        if self.input.len() < self.cursor + exp.len() {
-            return false;
-        }
-
-        let cursor = self.cursor;
-        let exp: Vec<char> = exp.to_lowercase().chars().collect();
-        for i in 0..exp.len() {
-            if exp[i] != self.input[cursor + i].to_ascii_lowercase() {
-                return false;
-            }
-        }
-
-        true
+        let mut rest = self.rest().iter();
+        exp.chars().all(|c| rest.next().is_some_and(|r| r.eq_ignore_ascii_case(&c)))
     }
 
     /// If there is a `needle` after the `self.cursor` position, that position is returned.
@@ -114,94 +108,117 @@ impl Input {
             return None;
         }
 
-        for i in bgn..self.input.len() {
-            if self.input[i] == needle {
-                return Some(i);
-            }
-        }
-
-        None
+        (bgn..self.input.len()).find(|&i| self.input[i] == needle)
     }
 
     /// If there is a `needle` after the `self.cursor` position, that position is returned.
     pub fn find_str(&self, needle: &str) -> Option<usize> {
         let needle: Vec<char> = needle.chars().collect();
-        let mut i = self.cursor;
-        if self.input.len() <= i {
-            return None;
-        }
+        self.find_by(&needle, |n, c| n == c)
+    }
 
-        let mut bgn_idx;
-        while i < self.input.len() {
-            // first character
-            if self.input[i] == needle[0] {
-                if needle.len() == 1 {
-                    return Some(i);
-                }
-                bgn_idx = i;
-                i += 1;
-            } else {
-                i += 1;
-                continue;
-            }
+    /// Returns the position of the character at `cursor` in the document.
+    pub fn get_position(&self, cursor: usize) -> Position {
+        let cursor = cursor.min(self.input.len());
+        let line = self.line_starts.partition_point(|&line_start| line_start <= cursor);
 
-            // second and subsequent character
-            let mut j = 1;
-            while j < needle.len() {
-                if self.input.len() <= i {
-                    // the cursor in the end, but needle len > 1
-                    return None;
-                }
-
-                if self.input[i] == needle[j] {
-                    if j == needle.len() - 1 {
-                        // the needle in the end
-                        return Some(bgn_idx);
-                    }
-                } else {
-                    // do not return directly, as there may be matching str later
-                    // example:
-                    //         needle: LO
-                    //         input: HELLO
-                    //                  ^
-                    break;
-                }
-
-                i += 1;
-                j += 1;
-            }
+        Position {
+            offset: self.offsets[cursor],
+            line,
+            column: cursor - self.line_starts[line - 1] + 1,
+        }
+    }
+
+    /// Returns the span of the document from `bgn` to `end`.
+    pub fn get_span(&self, bgn: usize, end: usize) -> Span {
+        let position = self.get_position(bgn);
 
-            i = bgn_idx + 1;
+        Span {
+            start: position.offset,
+            end: self.offsets[end.min(self.input.len())],
+            line: position.line,
+            column: position.column,
         }
+    }
+
+    /// If there is a `needle` after the `self.cursor` position, that position is returned.
+    /// ASCII case insensitive.
+    pub fn find_str_insensitive(&self, needle: &str) -> Option<usize> {
+        let needle: Vec<char> = needle.chars().collect();
+        self.find_by(&needle, |n, c| n.eq_ignore_ascii_case(c))
+    }
+
+    /// Returns the first position from `self.cursor` where each character
+    /// of `needle` is equal to the character of the input by `eq`.
+    fn find_by(&self, needle: &[char], eq: impl Fn(&char, &char) -> bool) -> Option<usize> {
+        let last = self.input.len().checked_sub(needle.len())?;
+        (self.cursor..=last).find(|&i| needle.iter().zip(&self.input[i..]).all(|(n, c)| eq(n, c)))
+    }
 
-        None
+    /// Returns the characters from `self.cursor` to the end.
+    fn rest(&self) -> &[char] {
+        &self.input[self.cursor.min(self.input.len())..]
     }
 
     /// Returns the character at the `cursor` position.
     #[allow(dead_code)]
-    pub fn get_char(&self, cursor: usize) -> Result<char, String> {
+    pub fn get_char(&self, cursor: usize) -> Result<char, ParseError> {
         if self.input.len() <= cursor {
-            return Err(String::from("Out of input."));
+            return Err(ParseError::OutOfInput(self.get_position(cursor)));
         }
 
         Ok(self.input[cursor])
     }
 
     /// Returns from `bgn` to `end` as a String.
-    pub fn get_string(&self, bgn: usize, end: usize) -> Result<String, String> {
+    pub fn get_string(&self, bgn: usize, end: usize) -> Result<String, ParseError> {
         if end <= bgn {
-            return Err(String::from("Invalid range."));
+            return Err(ParseError::InvalidRange(self.get_position(bgn)));
         }
 
-        if self.input.len() <= end {
-            return Err(format!("Out of input {:?}:{:?}.", bgn, end));
+        if self.input.len() < end {
+            return Err(ParseError::OutOfInput(self.get_position(bgn)));
         }
 
-        let mut s = String::new();
-        for i in bgn..end {
-            s.push(self.input[i]);
-        }
+        Ok(self.input[bgn..end].iter().collect())
+    }
+}
 
-        Ok(s)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_ascii_test() {
+        let mut input = Input::new("<p title=\"日本語\">😀 é</P>");
+        input.set_cursor(10);
+        assert!(input.expect_str("日本語\""));
+        assert!(!input.expect_str("日本語\">😀 é</P>!"));
+        assert_eq!(input.find('>'), Some(14));
+        assert_eq!(input.find_str("é</"), Some(17));
+        assert_eq!(input.find_str_insensitive("</p"), Some(18));
+        assert_eq!(input.find_str(""), Some(10));
+        assert_eq!(input.get_string(15, 18).unwrap(), "😀 é");
+        assert_eq!(input.get_span(15, 18), Span { start: 21, end: 28, line: 1, column: 16 });
+
+        // only ASCII letters are compared without case, as in the names of tags
+        input.set_cursor(18);
+        assert!(input.expect_str_insensitive("</p>"));
+        assert!(!Input::new("ǅ").expect_str_insensitive("ǆ"));
+
+        // a needle of more bytes than the characters left
+        let mut input = Input::new("a日本");
+        input.set_cursor(1);
+        assert!(input.expect_str("日本"));
+        assert!(input.expect_str_insensitive("日本"));
+        assert_eq!(input.find_str_insensitive("本"), Some(2));
+
+        let root = crate::parse("<p title=\"日本語\" data-é=😀>😀 é<b>中文</b></p>").unwrap();
+        let p = root.first_child().unwrap();
+        assert_eq!(p.attr("title").as_deref(), Some("日本語"));
+        assert_eq!(p.attr("data-é").as_deref(), Some("😀"));
+        assert_eq!(p.inner_text(), "😀 é中文");
+        let b = p.last_child().unwrap();
+        assert_eq!(b.get_span().map(|span| (span.start, span.end, span.column)), Some((41, 54, 28)));
     }
 }
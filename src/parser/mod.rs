@@ -1,9 +1,30 @@
-use std::collections::HashMap;
-use crate::dom::{Node, Payload, Tag};
+use std::rc::Rc;
 
-mod input;
+use crate::dom::{
+    AttrValue, Attributes, Doctype, Document, Node, NodeData, Original, Payload, Span, Tag, ROOT_TAG_NAME,
+};
 
+mod borrowed;
+mod diagnostic;
+mod encoding;
+pub(crate) mod entity;
+mod error;
+mod input;
+mod options;
+mod parallel;
+mod stream;
+mod tokenizer;
+
+pub use borrowed::parse_borrowed;
+pub use diagnostic::{Diagnostic, DiagnosticKind, ParseResult};
+pub use encoding::Encoding;
+pub(crate) use encoding::charset_parameter;
+pub use error::{ParseError, Position};
 pub use input::Input;
+pub use options::{ParseOptions, Strictness, Whitespace};
+pub use parallel::parse_parallel;
+pub use stream::{parse_reader, Parser};
+pub use tokenizer::{tokenize, Event, Tokenizer};
 
 /// Parses the tag document and returns a Dom structure tree.
 ///
@@ -11,7 +32,7 @@ pub use input::Input;
 /// * `doc` - tag document
 ///
 /// # Errors
-/// * If the document ends in the middle of a tag or double quote.
+/// * [`ParseError`] if the document ends in the middle of a tag, comment or double quote.
 ///
 /// # Examples
 /// ```rust
@@ -21,95 +42,312 @@ pub use input::Input;
 /// </body>
 /// "#;
 ///
-/// if let Ok(node) = html::parse(&html) {
-///     println!("{:#?}", node);
+/// if let Ok(node) = html::parse(html) {
+///     print!("{}", node.debug_tree());
 /// }
 /// ```
 ///
 /// output:
 ///
 /// ```text
-/// Node {
-///     rc_ref: NodeData {
-///         payload: Tag(
-///             Tag {
-///                 name: "root",
-///                 attributes: None,
-///                 self_closing: false,
-///                 terminator: false,
-///             },
-///         ),
-///         parent: RefCell {
-///             value: (Weak),
-///         },
-///         children: RefCell {
-///             value: [
-///                 NodeData {
-///                     payload: Tag(
-///                         Tag {
-///                             name: "body",
-///                             attributes: None,
-///                             self_closing: false,
-///                             terminator: false,
-///                         },
-///                     ),
-///                     parent: RefCell {
-///                         value: (Weak),
-///                     },
-///                     children: RefCell {
-///                         value: [
-///                             NodeData {
-///                                 payload: Tag(
-///                                     Tag {
-///                                         name: "h1",
-///                                         attributes: Some(
-///                                             {
-///                                                 "class": "h1",
-///                                             },
-///                                         ),
-///                                         self_closing: false,
-///                                         terminator: false,
-///                                     },
-///                                 ),
-///                                 parent: RefCell {
-///                                     value: (Weak),
-///                                 },
-///                                 children: RefCell {
-///                                     value: [
-///                                         NodeData {
-///                                             payload: Text(
-///                                                 "Hello",
-///                                             ),
-///                                             parent: RefCell {
-///                                                 value: (Weak),
-///                                             },
-///                                             children: RefCell {
-///                                                 value: [],
-///                                             },
-///                                         },
-///                                     ],
-///                                 },
-///                             },
-///                         ],
-///                     },
-///                 },
-///             ],
-///         },
-///     },
-/// }
+/// #document
+///   body
+///     h1.h1
+///       "Hello"
 /// ```
-pub fn parse(doc: &str) -> Result<Node, String> {
-    let mut input = Input::new(doc);
-    let mut node_vec = create_node_vec(&mut input)?;
-    // debug_print_node_vec(&node_vec);
+pub fn parse(doc: &str) -> Result<Node, ParseError> {
+    parse_with_options(doc, &ParseOptions::default())
+}
 
-    let tag = Tag::new("root");
+/// Parses the tag document with `options` and returns a Dom structure tree.
+///
+/// # Arguments
+/// * `doc` - tag document
+/// * `options` - how to parse the document
+///
+/// The parser never panics: any document, however malformed or deeply
+/// nested, gives a tree or a [`ParseError`]. The targets in `fuzz/` check
+/// this with `cargo fuzz`.
+///
+/// # Errors
+/// * [`ParseError`] if the document ends in the middle of a tag, comment or double quote,
+///   or is beyond one of the limits of `options`.
+pub fn parse_with_options(doc: &str, options: &ParseOptions) -> Result<Node, ParseError> {
+    parse_with_diagnostics(doc, options).map(|result| result.dom)
+}
+
+/// Parses a whole document and returns it as a [`Document`], with the
+/// `<html>`, `<head>` and `<body>` elements created if they are missing.
+///
+/// # Errors
+/// * [`ParseError`] as [`parse`] does.
+pub fn parse_document(doc: &str) -> Result<Document, ParseError> {
+    let mut document = Document::from(parse(doc)?);
+    document.set_source(doc);
+    Ok(document)
+}
+
+/// Parses the tag document with `options` and returns the Dom structure
+/// tree with the problems the parser recovered from.
+///
+/// # Examples
+/// ```rust
+/// use html::DiagnosticKind;
+///
+/// let result = html::parse_with_diagnostics("<p><b><i>text</b></i></p>", &Default::default()).unwrap();
+///
+/// assert_eq!(result.dom.to_html(), "<p><b><i>text</i></b></p>");
+/// assert_eq!(result.warnings[0].kind, DiagnosticKind::MismatchedEndTag {
+///     open: String::from("i"),
+///     found: String::from("b"),
+/// });
+/// assert_eq!(result.warnings[1].kind, DiagnosticKind::StrayEndTag(String::from("i")));
+/// ```
+///
+/// # Errors
+/// * [`ParseError`] as [`parse_with_options`] does.
+pub fn parse_with_diagnostics(doc: &str, options: &ParseOptions) -> Result<ParseResult, ParseError> {
+    let tag = Tag::new(ROOT_TAG_NAME);
     let payload = Payload::Tag(tag);
 
-    let mut root = Node::new(payload);
-    create_node_tree(&mut node_vec, &mut root);
+    let root = Node::new(payload);
+    root.set_raw_entities(!options.decode_entities);
+    let mut warnings = build_tree(doc, &root, options)?;
+    if options.lossless {
+        attach_unparsed_source(doc, &root);
+    }
+    warnings.append(&mut duplicate_ids(&root));
+    warnings.sort_by_key(|diagnostic| diagnostic.position.offset);
+
+    Ok(ParseResult { dom: root, warnings })
+}
+
+/// Returns a [`DiagnosticKind::DuplicateId`] for every element whose id an
+/// element before it has already.
+fn duplicate_ids(root: &NodeData) -> Vec<Diagnostic> {
+    let mut ids = std::collections::HashSet::new();
+    root.descendants()
+        .filter_map(|node| {
+            let id = match &*node.get_payload() {
+                Payload::Tag(tag) => tag.get_attribute_value("id").filter(|id| !id.is_empty())?,
+                _ => return None,
+            };
+            if ids.insert(id.clone()) {
+                return None;
+            }
 
-    Ok(root)
+            Some(Diagnostic {
+                kind: DiagnosticKind::DuplicateId(id),
+                position: get_position(&node),
+            })
+        })
+        .collect()
+}
+
+/// Adds the source that no node was parsed from, such as the whitespace
+/// between tags and discarded end tags, to the original markup of the node
+/// after it, or of `root` at the end, so that the markup covers all of `doc`.
+fn attach_unparsed_source(doc: &str, root: &NodeData) {
+    // the end of the source covered so far
+    let mut cursor = 0;
+    // the nodes to attach the source to, with a stack rather than by recursion
+    // for deeply nested trees. A node whose children are done comes back with
+    // its original markup, to attach the source before its end tag.
+    let mut stack: Vec<(Rc<NodeData>, Option<Original>)> =
+        root.get_children().iter().rev().map(|child| (Rc::clone(child), None)).collect();
+    while let Some((node, original)) = stack.pop() {
+        if let Some(mut original) = original {
+            if let (Some(end), Some(span)) = (&original.end, node.get_span()) {
+                original.trailing = unparsed_source(doc, span.end.saturating_sub(end.len()), end.len(), &mut cursor);
+            }
+            node.set_original(Some(original));
+            continue;
+        }
+
+        let (mut original, span) = match (node.take_original(), node.get_span()) {
+            (Some(original), Some(span)) => (original, span),
+            (original, _) => {
+                node.set_original(original);
+                continue;
+            }
+        };
+
+        let start_len = original.start.as_ref().map_or(0, String::len);
+        original.leading = unparsed_source(doc, span.start, start_len, &mut cursor);
+        let children = node.get_children().clone();
+        stack.push((node, Some(original)));
+        stack.extend(children.into_iter().rev().map(|child| (child, None)));
+    }
+    root.set_original(Some(Original {
+        start: Some(String::new()),
+        trailing: doc.get(cursor..).unwrap_or_default().to_string(),
+        ..Original::default()
+    }));
+}
+
+/// Returns the source from `cursor` to the markup of `len` bytes at
+/// `offset`, and moves `cursor` after the markup. Returns "" for markup
+/// before `cursor`, of a node the parser moved.
+fn unparsed_source(doc: &str, offset: usize, len: usize, cursor: &mut usize) -> String {
+    if offset < *cursor {
+        return String::new();
+    }
+    let source = doc.get(*cursor..offset).unwrap_or_default().to_string();
+    *cursor = offset + len;
+    source
+}
+
+/// Parses `doc` and adds the nodes to `parent`.
+/// Returns the problems the parser recovered from.
+fn build_tree(doc: &str, parent: &Node, options: &ParseOptions) -> Result<Vec<Diagnostic>, ParseError> {
+    options.check_input_len(doc)?;
+    let mut diagnostics = Vec::new();
+    if doc.trim_end().is_empty() {
+        return Ok(diagnostics);
+    }
+
+    let mut tokenizer = Tokenizer::new(doc, options.clone());
+    let node_vec = create_node_vec(doc, &mut tokenizer, options, &mut diagnostics)?;
+    diagnostics.append(&mut tokenizer.take_diagnostics());
+    // debug_print_node_vec(&node_vec);
+
+    let recover = options.recover || options.strictness == Strictness::Tolerant;
+    create_node_tree(node_vec, parent, options, recover, &mut diagnostics)?;
+
+    // in the order they appear in the document
+    diagnostics.sort_by_key(|diagnostic| diagnostic.position.offset);
+    Ok(diagnostics)
+}
+
+/// Parses a fragment of a document as if it were the content of a
+/// `context` element, and returns its top-level nodes.
+///
+/// The nodes are not wrapped in the synthetic `root` node. If `context`
+/// is a raw text element such as `script` or `textarea`, the whole
+/// fragment is its text.
+///
+/// # Examples
+/// ```rust
+/// let nodes = html::parse_fragment("<li>one</li><li>two</li>", "ul").unwrap();
+/// assert_eq!(nodes.len(), 2);
+/// assert_eq!(nodes[1].to_html(), "<li>two</li>");
+///
+/// let nodes = html::parse_fragment("<b>not a tag</b>", "textarea").unwrap();
+/// assert_eq!(*nodes[0].get_payload(), html::dom::Payload::Text(String::from("<b>not a tag</b>")));
+/// ```
+///
+/// # Errors
+/// * [`ParseError`] if the fragment ends in the middle of a tag, comment or double quote.
+pub fn parse_fragment(doc: &str, context: &str) -> Result<Vec<Node>, ParseError> {
+    parse_fragment_with_options(doc, context, &ParseOptions::default())
+}
+
+/// Same as [`parse_fragment`], but with `options`.
+///
+/// # Errors
+/// * [`ParseError`] if the fragment is broken and `options.recover` is false.
+pub fn parse_fragment_with_options(doc: &str, context: &str, options: &ParseOptions) -> Result<Vec<Node>, ParseError> {
+    let tag = Tag::new(context);
+    if tag.is_raw_text_element() || tag.is_escapable_raw_text_element() {
+        if doc.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let text = if tag.is_escapable_raw_text_element() && options.decode_entities {
+            entity::decode(doc)
+        } else {
+            String::from(doc)
+        };
+        let node = Node::new(Payload::Text(text));
+        node.set_raw_entities(!options.decode_entities);
+        return Ok(vec![node]);
+    }
+
+    let parent = Node::new(Payload::Tag(tag));
+    build_tree(doc, &parent, options)?;
+
+    let nodes = parent.get_children().iter().cloned().map(Node::from).collect::<Vec<_>>();
+    for node in &nodes {
+        node.detach();
+        node.set_raw_entities(!options.decode_entities);
+    }
+
+    Ok(nodes)
+}
+
+/// Parses the tag document like a browser does, and never fails.
+///
+/// Tags that are not closed are closed at the end of their parent,
+/// end tags that do not match an open tag are discarded, and
+/// broken tags are kept as text.
+///
+/// # Examples
+/// ```rust
+/// let node = html::parse_lenient("<ul><li>one</b></li><li>two");
+/// assert_eq!(node.to_html(), "<ul><li>one</li><li>two</li></ul>");
+/// ```
+pub fn parse_lenient(doc: &str) -> Node {
+    let options = ParseOptions {
+        recover: true,
+        ..Default::default()
+    };
+
+    parse_with_options(doc, &options).unwrap_or_else(|_| {
+        Node::new(Payload::Tag(Tag::new(ROOT_TAG_NAME)))
+    })
+}
+
+/// Decodes a document in bytes and returns it with the encoding it was in.
+///
+/// The encoding is taken from the byte order mark, then the charset of
+/// `content_type`, such as the `Content-Type` header of the response, then a
+/// `<meta charset>` near the start of the document. UTF-8, UTF-16 and
+/// windows-1252 are supported. A document in another encoding, such as
+/// Shift_JIS, is decoded as UTF-8 with invalid sequences replaced with
+/// U+FFFD and returned with [`Encoding::Unsupported`], so that it can be
+/// decoded by other means.
+///
+/// # Examples
+/// ```rust
+/// use html::Encoding;
+///
+/// let (doc, encoding) = html::decode_bytes(b"<p>caf\xE9</p>", Some("text/html; charset=ISO-8859-1"));
+/// assert_eq!(doc, "<p>café</p>");
+/// assert_eq!(encoding, Encoding::Windows1252);
+/// ```
+pub fn decode_bytes(bytes: &[u8], content_type: Option<&str>) -> (String, Encoding) {
+    let (encoding, bom_len) = encoding::sniff(bytes, content_type);
+    (encoding.decode(&bytes[bom_len..]), encoding)
+}
+
+/// Parses a document in bytes, decoding it as [`decode_bytes`] does.
+/// Any bytes can be given, as the invalid sequences are replaced.
+///
+/// # Errors
+/// * [`ParseError::UnsupportedEncoding`] if the document is in an encoding
+///   that can't be decoded.
+/// * [`ParseError`] as [`parse`] does.
+pub fn parse_bytes(bytes: &[u8]) -> Result<Node, ParseError> {
+    parse_bytes_with_options(bytes, None, &ParseOptions::default())
+}
+
+/// Parses a document in bytes with `options`, decoding it as
+/// [`decode_bytes`] does with `content_type` as the hint.
+///
+/// # Errors
+/// * [`ParseError::UnsupportedEncoding`] if the document is in an encoding
+///   that can't be decoded.
+/// * [`ParseError`] as [`parse_with_options`] does.
+pub fn parse_bytes_with_options(
+    bytes: &[u8],
+    content_type: Option<&str>,
+    options: &ParseOptions,
+) -> Result<Node, ParseError> {
+    let (doc, encoding) = decode_bytes(bytes, content_type);
+    if let Encoding::Unsupported(_) = encoding {
+        return Err(ParseError::UnsupportedEncoding(Position { offset: 0, line: 1, column: 1 }));
+    }
+    parse_with_options(&doc, options)
 }
 
 /// Returns the value of the tag's attribute.
@@ -121,7 +359,7 @@ pub fn parse(doc: &str) -> Result<Node, String> {
 /// '<value>'
 /// or
 /// <value>
-fn parse_tag_attr_value(input: &mut Input, tag_end: usize, delimiter: char) -> Result<String, String> {
+fn parse_tag_attr_value(input: &mut Input, tag_end: usize, delimiter: char) -> Result<String, ParseError> {
     if delimiter != ' ' {
         // move cursor to after '"' or '\''
         input.next();
@@ -129,30 +367,23 @@ fn parse_tag_attr_value(input: &mut Input, tag_end: usize, delimiter: char) -> R
 
     let value_bgn = input.get_cursor();
 
-    let value_end;
-    match input.find(delimiter) {
-        Some(cursor) => {
-            if cursor < tag_end {
-                // value"
-                //      ^
-                //      this is delimiter
-                value_end = cursor;
-            } else if delimiter == ' ' {
-                // value>
-                //      ^
-                //      the end of tag
-                value_end = tag_end;
-            } else {
-                return Err(format!(
-                    "There is no delimiter({}) to terminate the attribute.",
-                    delimiter
-                ));
-            }
-        }
-        None => {
-            return Err(format!("Input ends in the middle of delimiter({})", delimiter));
+    let value_end = if delimiter == ' ' {
+        // value>
+        //      ^
+        // an unquoted value ends at whitespace or the end of tag, and may
+        // contain '=', '&', '/' and quotes. `href=/path?a=b&c=d`
+        (value_bgn..tag_end)
+            .find(|&cursor| input.get_char(cursor).is_ok_and(|c| c.is_ascii_whitespace()))
+            .unwrap_or(tag_end)
+    } else {
+        match input.find(delimiter) {
+            // value"
+            //      ^
+            //      this is delimiter
+            Some(cursor) if cursor < tag_end => cursor,
+            _ => return Err(ParseError::UnterminatedAttribute(input.get_position(value_bgn))),
         }
-    }
+    };
 
     if value_bgn == value_end {
         // value is empty
@@ -163,34 +394,33 @@ fn parse_tag_attr_value(input: &mut Input, tag_end: usize, delimiter: char) -> R
     input.get_string(value_bgn, value_end)
 }
 
-/// Gets the cursor position at the end of tag.
+/// Gets the cursor position at the end of tag, whose '>' may be in a
+/// quoted attribute value. `<a title='a > b'>`
 ///
 /// <tag attribute="value" >
 ///                   ^
 ///                   Return this position.
-fn get_tag_end(input: &mut Input) -> Result<usize, String> {
-    let save_cursor_pos = input.get_cursor();
-    let mut res = 0;
-
-    let mut in_double_quote = false;
-    while !input.is_end() {
-        input.next_char();
-        if input.expect('\"') {
-            in_double_quote = !in_double_quote;
-        }
-
-        if !in_double_quote && input.expect('>') {
-            // make sure the '>' is not inside double quotes
-            res = input.get_cursor();
-            break;
+fn get_tag_end(input: &Input) -> Result<usize, ParseError> {
+    let bgn = input.get_cursor();
+    let mut quote = None;
+    // a quote starts a value only after '='. `<a title=it's>`
+    let mut after_eq = false;
+    for cursor in bgn..input.len() {
+        let c = input.get_char(cursor)?;
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '>' => return Ok(cursor),
+                '=' => after_eq = true,
+                '"' | '\'' if after_eq => quote = Some(c),
+                c if c.is_ascii_whitespace() => {}
+                _ => after_eq = false,
+            },
         }
     }
 
-    input.set_cursor(save_cursor_pos);
-    match res {
-        0 => Err(String::from("Input ends in the middle of the tag.")),
-        _ => Ok(res),
-    }
+    Err(ParseError::UnterminatedTag(input.get_position(bgn)))
 }
 
 /// Parses tag attributes.
@@ -202,11 +432,16 @@ fn get_tag_end(input: &mut Input) -> Result<usize, String> {
 /// <attribute>[ = '<value>'] [/]>
 /// or
 /// <attribute>[ = <value>] [/]>
-fn parse_tag_attr(input: &mut Input, mut tag: Tag) -> Result<Tag, String> {
+fn parse_tag_attr(
+    input: &mut Input,
+    mut tag: Tag,
+    options: &ParseOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Tag, ParseError> {
     // get the end position of the tag
     let tag_end = get_tag_end(input)?;
 
-    let mut attr_map = HashMap::new();
+    let mut attr_map = Attributes::new();
 
     // get attribute and their value
     // the terminal '/' is also an attribute
@@ -230,57 +465,80 @@ fn parse_tag_attr(input: &mut Input, mut tag: Tag) -> Result<Tag, String> {
             }
         }
 
-        // if the tag contains an ' ' and it precedes '=',
+        // if the tag contains whitespace and it precedes '=',
         // make that position the end position of the attribute name
         //
         // attribute = "value"
         //     ^
-        if let Some(cursor) = input.find(' ') {
-            if cursor < attr_name_end {
-                attr_name_end = cursor;
-            }
+        if let Some(cursor) =
+            (attr_name_bgn..attr_name_end).find(|&cursor| input.get_char(cursor).is_ok_and(|c| c.is_ascii_whitespace()))
+        {
+            attr_name_end = cursor;
         }
 
         input.set_cursor(attr_name_end);
-        let attr_name = input.get_string(attr_name_bgn, attr_name_end)?;
+        let mut attr_name = input.get_string(attr_name_bgn, attr_name_end)?;
+        if options.lowercase_names {
+            attr_name = attr_name.to_lowercase();
+        }
 
         // get attribute value
-        let mut attr_value = String::new();
+        let mut attr_value = AttrValue::Empty;
         if input.get_cursor() != tag_end {
-            // if the tag contains an "="
-            if let Some(cursor) = input.find('=') {
-                if cursor < tag_end {
-                    // move cursor to '='
-                    input.set_cursor(cursor);
-                    // move cursor to after '='
-                    input.next_char();
-                    if input.expect('"') {
-                        // attribute = "value"
-                        //        ^
-                        match parse_tag_attr_value(input, tag_end, '"') {
-                            Ok(v) => attr_value = v,
-                            Err(e) => return Err(e),
-                        }
-                    } else if input.expect('\'') {
-                        // attribute = 'value'
-                        //        ^
-                        match parse_tag_attr_value(input, tag_end, '\'') {
-                            Ok(v) => attr_value = v,
-                            Err(e) => return Err(e),
-                        }
-                    } else {
-                        // attribute = value
-                        //        ^
-                        match parse_tag_attr_value(input, tag_end, ' ') {
-                            Ok(v) => attr_value = v,
-                            Err(e) => return Err(e),
-                        }
+            // if the attribute name is followed by an "="
+            // `disabled value="x"` is two attributes, the "=" belongs to `value`
+            let name_end = input.get_cursor();
+            while input.expect_whitespace() {
+                input.next();
+            }
+
+            if input.expect('=') {
+                // move cursor to after '='
+                input.next_char();
+                if input.expect('"') {
+                    // attribute = "value"
+                    //        ^
+                    match parse_tag_attr_value(input, tag_end, '"') {
+                        Ok(v) => attr_value = AttrValue::Quoted(v),
+                        Err(e) => return Err(e),
+                    }
+                } else if input.expect('\'') {
+                    // attribute = 'value'
+                    //        ^
+                    match parse_tag_attr_value(input, tag_end, '\'') {
+                        Ok(v) => attr_value = AttrValue::Quoted(v),
+                        Err(e) => return Err(e),
+                    }
+                } else {
+                    // attribute = value
+                    //        ^
+                    match parse_tag_attr_value(input, tag_end, ' ') {
+                        Ok(v) => attr_value = AttrValue::Unquoted(v),
+                        Err(e) => return Err(e),
                     }
                 }
+            } else {
+                input.set_cursor(name_end);
             }
         }
 
-        attr_map.insert(attr_name, attr_value);
+        if options.decode_entities {
+            attr_value = match attr_value {
+                AttrValue::Unquoted(v) => AttrValue::Unquoted(entity::decode(&v)),
+                AttrValue::Quoted(v) => AttrValue::Quoted(entity::decode(&v)),
+                AttrValue::Empty => AttrValue::Empty,
+            };
+        }
+
+        if attr_map.contains_key(&attr_name) {
+            // the first one is kept, as browsers do. `<a href="x" href="y">`
+            diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::DuplicateAttribute(attr_name),
+                position: input.get_position(attr_name_bgn),
+            });
+        } else {
+            attr_map.insert(attr_name, attr_value);
+        }
 
         if input.expect('>') {
             // the end of tag
@@ -293,12 +551,12 @@ fn parse_tag_attr(input: &mut Input, mut tag: Tag) -> Result<Tag, String> {
     }
 
     // if the attribute contains '/', remove it
-    if let Some(_) = attr_map.remove("/") {
+    if attr_map.remove("/").is_some() {
         // set the tag is self-closing
         tag.set_self_closing(true);
     }
 
-    tag.set_attrs(attr_map);
+    tag.set_attributes(attr_map);
 
     Ok(tag)
 }
@@ -311,30 +569,65 @@ fn parse_tag_attr(input: &mut Input, mut tag: Tag) -> Result<Tag, String> {
 /// <tag_name> [<attribute>[="<value>"]] [/]>
 /// or
 /// <tag_name> [<attribute>[='<value>']] [/]>
-fn parse_tag_name(input: &mut Input, terminator: bool) -> Result<Tag, String> {
+fn parse_tag_name(
+    input: &mut Input,
+    terminator: bool,
+    options: &ParseOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Tag, ParseError> {
     // get the start position of the tag name
     let name_bgn = input.get_cursor();
 
+    if input.expect('>') {
+        // there is no tag name. <>
+        return Err(ParseError::InvalidRange(input.get_position(name_bgn)));
+    }
+
     // get the end position of the tag
     let tag_end = get_tag_end(input)?;
 
     let mut name_end = tag_end;
 
-    // if the tag contains ' ', make that position the end position of the tag name
-    if let Some(cursor) = input.find(' ') {
+    // if the tag contains whitespace, make that position the end position of the tag name
+    if let Some(cursor) =
+        (input.get_cursor()..tag_end).find(|&cursor| input.get_char(cursor).is_ok_and(|c| c.is_ascii_whitespace()))
+    {
         // li attribute="value"
         //   ^
-        if cursor < tag_end {
-            name_end = cursor;
-        }
+        name_end = cursor;
     }
 
     input.set_cursor(name_end);
     let tag_name = input.get_string(name_bgn, name_end)?;
     let tag_name = tag_name.trim();
 
-    let mut tag = Tag::new(tag_name);
+    // there is no ' ' between the tag name and '/'
+    // <tag/>
+    //     ^
+    let (tag_name, self_closing) = match tag_name.strip_suffix('/') {
+        Some(tag_name) => (tag_name, true),
+        None => (tag_name, false),
+    };
+
+    let mut tag = if options.lowercase_names {
+        Tag::new(&tag_name.to_lowercase())
+    } else {
+        Tag::new(tag_name)
+    };
+
+    // the attributes of `<svg>` and `<math>` are foreign content. `viewBox`
+    let foreign_options;
+    let options = if options.lowercase_names && is_foreign_root(tag.get_name()) {
+        foreign_options = ParseOptions {
+            lowercase_names: false,
+            ..options.clone()
+        };
+        &foreign_options
+    } else {
+        options
+    };
     tag.set_terminator(terminator);
+    tag.set_self_closing(self_closing);
 
     if input.expect('>') {
         // <tag>
@@ -359,17 +652,17 @@ fn parse_tag_name(input: &mut Input, terminator: bool) -> Result<Tag, String> {
         return Ok(tag);
     }
 
-    return parse_tag_attr(input, tag);
+    parse_tag_attr(input, tag, options, diagnostics)
 }
 
-/// Parses the tag and returns a Node structure.
+/// Parses the tag and returns a Tag structure.
 ///
 /// State to receive:
 /// The cursor points to the first '<'.
 /// <[/]<tag_name> [<attribute>[="<value>"]] [/]>
 /// or
 /// <[/]<tag_name> [<attribute>[='<value>']] [/]>
-fn parse_tag(input: &mut Input) -> Result<Node, String> {
+fn parse_tag(input: &mut Input, options: &ParseOptions, diagnostics: &mut Vec<Diagnostic>) -> Result<Tag, ParseError> {
     // move cursor to after '<'
     input.next();
 
@@ -380,262 +673,645 @@ fn parse_tag(input: &mut Input) -> Result<Node, String> {
         terminator = true;
     }
 
-    let tag = parse_tag_name(input, terminator)?;
-    let payload = Payload::Tag(tag);
-    // TODO debug
-    // println!("{:#?}", payload);
-    let node = Node::new(payload);
-
-    Ok(node)
+    parse_tag_name(input, terminator, options, diagnostics)
 }
 
-/// Parses the comment and returns a Node structure.
+/// Parses the comment and returns its text.
 ///
 /// State to receive:
 /// The cursor points to the first '<'.
 /// <!-- <comment> -->
-fn parse_comment(input: &mut Input) -> Result<Node, String> {
+fn parse_comment(input: &mut Input) -> Result<String, ParseError> {
     // get the position after '<!--'
     let bgn = input.get_cursor() + "<!--".len();
-    let end;
 
-    match input.find_str("-->") {
+    let end = match input.find_str("-->") {
         Some(cursor) => {
             // move cursor to after "-->"
             input.set_cursor(cursor + "-->".len());
-            end = cursor;
+            cursor
         }
-        None => return Err(String::from("Input ends in the middle of the comment.")),
-    }
+        None => return Err(ParseError::UnterminatedComment(input.get_position(input.get_cursor()))),
+    };
 
-    let payload = Payload::Comment(input.get_string(bgn, end)?);
-    // TODO debug
-    // println!("{:#?}", payload);
-    let node = Node::new(payload);
+    input.get_string(bgn, end)
+}
+
+/// Parses the CDATA section and returns its text.
+///
+/// State to receive:
+/// The cursor points to the first '<'.
+/// <![CDATA[ <text> ]]>
+fn parse_cdata(input: &mut Input) -> Result<String, ParseError> {
+    // get the position after '<![CDATA['
+    let bgn = input.get_cursor() + "<![CDATA[".len();
+
+    let end = match input.find_str("]]>") {
+        Some(cursor) if cursor >= bgn => {
+            // move cursor to after "]]>"
+            input.set_cursor(cursor + "]]>".len());
+            cursor
+        }
+        _ => return Err(ParseError::UnterminatedCdata(input.get_position(input.get_cursor()))),
+    };
 
-    Ok(node)
+    if bgn == end {
+        return Ok(String::new());
+    }
+    input.get_string(bgn, end)
 }
 
-/// Parses the text and returns a Node structure.
+/// Parses the text and returns it, handling whitespace as `options.whitespace` says.
+/// Returns an empty String if the text is dropped.
+/// If `preformatted`, the text is in `<pre>` and whitespace is kept.
 ///
 /// State to receive:
 /// <text>
-fn parse_text(input: &mut Input) -> Result<Node, String> {
+fn parse_text(input: &mut Input, options: &ParseOptions, preformatted: bool) -> Result<String, ParseError> {
     let bgn = input.get_cursor();
 
-    let end;
     // get the beginning of the next tag as the end of text
-    match input.find('<') {
+    let end = match input.find('<') {
         Some(cursor) => {
             // text <tag ...
             //      ^
             //      the end of text
             input.set_cursor(cursor);
-            end = cursor;
+            cursor
         }
         None => {
-            input.next_char();
-            end = input.get_cursor();
+            // the rest of input is the text
+            input.set_cursor(input.len());
+            input.len()
+        }
+    };
+
+    let mut text = input.get_string(bgn, end)?;
+    if !preformatted {
+        match options.whitespace {
+            Whitespace::Preserve => (),
+            Whitespace::Collapse => text = collapse_whitespace(&text),
+            Whitespace::DropInterElement => {
+                if text.chars().all(|c| c.is_ascii_whitespace()) {
+                    return Ok(String::new());
+                }
+            }
         }
     }
 
-    let payload = Payload::Text(input.get_string(bgn, end)?);
-    // TODO debug
-    // println!("{:#?}", payload);
-    let node = Node::new(payload);
+    if options.decode_entities {
+        text = entity::decode(&text);
+    }
 
-    Ok(node)
+    Ok(text)
 }
 
-/// Gets the code of the script tag as text.
-fn parse_text_script(input: &mut Input) -> Result<Node, String> {
-    let bgn = input.get_cursor();
-    let end;
-
-    match input.find_str("</script") {
-        Some(cursor) => {
-            // </script
-            // ^
-            // the end of script
-            input.set_cursor(cursor);
-            end = cursor;
+/// Replaces each run of whitespace in `text` with a single ' '.
+fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut in_whitespace = false;
+    for c in text.chars() {
+        if c.is_ascii_whitespace() {
+            if !in_whitespace {
+                collapsed.push(' ');
+            }
+            in_whitespace = true;
+        } else {
+            collapsed.push(c);
+            in_whitespace = false;
         }
-        None => return Err(String::from("Input ends in the middle of the tag.")),
     }
 
-    let payload = Payload::Text(input.get_string(bgn, end)?);
-    let node = Node::new(payload);
-
-    Ok(node)
+    collapsed
 }
 
-/// Parses "<!doctype html>".
-/// case insensitive.
+/// Gets the content of the raw text element such as `<script>` as text.
+/// The text ends at `</tag_name`, followed by ' ', '/' or '>'.
 ///
 /// State to receive:
-/// The cursor points to the first '<'.
-/// <!doctype html>
-#[allow(dead_code)]
-fn parse_doctype(input: &mut Input) -> Result<Node, String> {
-    if !input.expect_str_insensitive("<!doctype html>") {
-        return Err(String::from("Input is not html."));
-    }
-
-    // Set the tag name to "doctype"
-    input.next(); // move cursor to '!'
-    input.next(); // move cursor to 'd'
+/// The cursor points to the first character after `<tag_name>`.
+fn parse_raw_text(input: &mut Input, tag_name: &str) -> Result<String, ParseError> {
     let bgn = input.get_cursor();
-    let end = bgn + "doctype".len();
-    input.set_cursor(end); // move cursor to the ' ' before the "html"
-    let mut tag = Tag::new(&input.get_string(bgn, end)?);
+    let terminator = format!("</{}", tag_name);
 
-    input.next(); // move cursor to 'h'
+    loop {
+        let cursor = match input.find_str_insensitive(&terminator) {
+            Some(cursor) => cursor,
+            None => {
+                input.set_cursor(bgn);
+                return Err(ParseError::UnterminatedRawText(input.get_position(bgn)));
+            }
+        };
 
-    // Set the attribute to "html"
-    // The value of attribute is ""
-    let mut attribute: HashMap<String, String> = HashMap::new();
-    let bgn = input.get_cursor();
-    let end = bgn + "html".len();
-    input.set_cursor(end); // move cursor to '>'
-    attribute.insert(input.get_string(bgn, end)?, String::new());
-    tag.set_attrs(attribute);
+        // </script>
+        // ^
+        // the end of raw text
+        input.set_cursor(cursor + terminator.len());
+        if input.is_end() || input.expect(' ') || input.expect('/') || input.expect('>') || input.expect('\n') {
+            input.set_cursor(cursor);
+            if cursor == bgn {
+                return Ok(String::new());
+            }
+            return input.get_string(bgn, cursor);
+        }
+    }
+}
 
-    let payload = Payload::Tag(tag);
-    let node = Node::new(payload);
+/// Parses the doctype.
+/// "doctype", "PUBLIC" and "SYSTEM" are case insensitive.
+///
+/// State to receive:
+/// The cursor points to the first '<'.
+/// <!doctype <name>>
+/// or
+/// <!doctype <name> PUBLIC "<public_id>" ["<system_id>"]>
+/// or
+/// <!doctype <name> SYSTEM "<system_id>">
+fn parse_doctype(input: &mut Input) -> Result<Doctype, ParseError> {
+    let doctype_bgn = input.get_cursor();
+    let invalid = |input: &Input| ParseError::InvalidDoctype(input.get_position(doctype_bgn));
+
+    // move cursor to after "<!doctype"
+    input.set_cursor(doctype_bgn + "<!doctype".len());
+    if !input.expect(' ') && !input.expect('\n') {
+        return Err(invalid(input));
+    }
+    input.next_char();
 
-    input.next(); // move cursor to after '>'
+    // get the name
+    let name_bgn = input.get_cursor();
+    while !input.is_end() && !input.expect(' ') && !input.expect('\n') && !input.expect('>') {
+        input.next();
+    }
+    if input.is_end() {
+        return Err(ParseError::UnterminatedTag(input.get_position(doctype_bgn)));
+    }
+    let mut doctype = Doctype::new(&input.get_string(name_bgn, input.get_cursor()).map_err(|_| invalid(input))?);
 
-    Ok(node)
-}
+    if input.expect(' ') || input.expect('\n') {
+        input.next_char();
+    }
 
-/// Parses the tag document and returns the Vec of the Node structure.
-fn create_node_vec(input: &mut Input) -> Result<Vec<Node>, String> {
-    let mut node_vec = Vec::new();
+    if input.expect_str_insensitive("public") {
+        // PUBLIC "<public_id>" ["<system_id>"]
+        input.set_cursor(input.get_cursor() + "public".len());
+        input.next_char();
+        doctype.set_public_id(&parse_doctype_id(input).ok_or_else(|| invalid(input))?);
 
-    // move cursor to the fist '<'
-    while !input.expect('<') {
+        if input.expect(' ') || input.expect('\n') {
+            input.next_char();
+        }
+        if !input.expect('>') {
+            doctype.set_system_id(&parse_doctype_id(input).ok_or_else(|| invalid(input))?);
+        }
+    } else if input.expect_str_insensitive("system") {
+        // SYSTEM "<system_id>"
+        input.set_cursor(input.get_cursor() + "system".len());
         input.next_char();
+        doctype.set_system_id(&parse_doctype_id(input).ok_or_else(|| invalid(input))?);
     }
 
-    /*
-    match parse_doctype(input) {
-        Ok(node) => node_vec.push(node),
-        Err(e) => return Err(e),
+    if input.expect(' ') || input.expect('\n') {
+        input.next_char();
+    }
+    if !input.expect('>') {
+        return Err(invalid(input));
     }
-     */
 
-    while !input.is_end() {
-        // TODO debug
-        // println!("check: {}", input.get_char(input.get_cursor())?);
+    // move cursor to after '>'
+    input.next();
 
-        if input.expect_str("<!--") {
-            // comment
-            match parse_comment(input) {
-                Ok(node) => node_vec.push(node),
-                Err(e) => return Err(e),
-            }
-        } else if input.expect('<') {
-            // tag
-            match parse_tag(input) {
-                Ok(node) => {
-                    // if the node is script tag
-                    let mut is_bgn_script = false;
-                    if let Payload::Tag(tag) = node.get_payload() {
-                        if tag.get_name() == "script" && !tag.is_terminator() {
-                            is_bgn_script = true;
-                        }
-                    }
+    Ok(doctype)
+}
 
-                    node_vec.push(node);
+/// Parses the quoted identifier of the doctype and returns it without quotes.
+/// Returns `None` if it is not quoted or the quote is not closed.
+///
+/// State to receive:
+/// The cursor points to the first '"' or '\''.
+fn parse_doctype_id(input: &mut Input) -> Option<String> {
+    let delimiter = if input.expect('"') {
+        '"'
+    } else if input.expect('\'') {
+        '\''
+    } else {
+        return None;
+    };
+
+    // move cursor to after '"' or '\''
+    input.next();
+    let bgn = input.get_cursor();
+    let end = input.find(delimiter)?;
 
-                    // if the node is script tag and has text
-                    if is_bgn_script && !input.expect('<') {
-                        match parse_text_script(input) {
-                            Ok(node) => node_vec.push(node),
-                            Err(e) => return Err(e),
-                        }
-                    }
+    // move cursor to after '"' or '\''
+    input.set_cursor(end + 1);
+    Some(input.get_string(bgn, end).unwrap_or_default())
+}
+
+/// Parses the tag document and returns the Vec of the Node structure.
+/// The content of conditional comments is parsed here if `options` says so,
+/// and the problems found in it are added to `diagnostics`.
+/// If `options.lossless`, each node keeps its markup from `doc`.
+fn create_node_vec(
+    doc: &str,
+    tokenizer: &mut Tokenizer,
+    options: &ParseOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Vec<Node>, ParseError> {
+    let mut node_vec = Vec::new();
+    // the nodes of the tree, which end tags are not
+    let mut nodes = 0;
+
+    while let Some((event, span)) = tokenizer.next_event()? {
+        if let Event::Comment(text) = &event {
+            if options.conditional_comments {
+                if let Some(node) = parse_conditional_comment(text, span, options, diagnostics) {
+                    nodes += 1 + node.descendants().count();
+                    options.check_nodes(nodes, Some(span))?;
+                    node_vec.push(node);
+                    continue;
                 }
-                Err(e) => return Err(e),
-            }
-        } else {
-            if input.expect(' ') || input.expect('\n') {
-                // skip ' ' and '\n'
-                input.next_char();
             }
+        }
 
-            if !input.expect('<') {
-                // text
-                match parse_text(input) {
-                    Ok(node) => node_vec.push(node),
-                    Err(e) => return Err(e),
-                }
+        if !matches!(event, Event::EndTag(_)) {
+            nodes += 1;
+            options.check_nodes(nodes, Some(span))?;
+        }
+
+        let payload = match event {
+            Event::StartTag(tag) => Payload::Tag(tag),
+            Event::EndTag(name) => {
+                let mut tag = Tag::new(&name);
+                tag.set_terminator(true);
+                Payload::Tag(tag)
             }
+            Event::Text(text) => Payload::Text(text),
+            Event::RawText(text) => Payload::RawText(text),
+            Event::Comment(text) => Payload::Comment(text),
+            Event::Doctype(doctype) => Payload::Doctype(doctype),
+        };
+
+        let node = Node::new(payload);
+        node.set_span(span);
+        if options.lossless {
+            node.set_original(Some(Original {
+                start: doc.get(span.start..span.end).map(String::from),
+                ..Original::default()
+            }));
         }
+        node_vec.push(node);
     }
 
     Ok(node_vec)
 }
 
+/// Returns the node of a conditional comment with its content parsed into
+/// its children, or `None` if `text` is not a conditional comment or its
+/// content cannot be parsed, in which case it is kept as a comment.
+///
+/// `text` is the text of the comment at `span`. `[if IE]><p>x</p><![endif]`
+fn parse_conditional_comment(
+    text: &str,
+    span: Span,
+    options: &ParseOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<Node> {
+    let (condition, rest) = text.strip_prefix('[')?.split_once("]>")?;
+    if !condition.get(..2)?.eq_ignore_ascii_case("if") {
+        return None;
+    }
+    let content = rest.trim_end();
+    let content_end = content.len().checked_sub("<![endif]".len())?;
+    if !content.get(content_end..)?.eq_ignore_ascii_case("<![endif]") {
+        return None;
+    }
+    let content = &content[..content_end];
+
+    let node = Node::new(Payload::ConditionalComment(String::from(condition)));
+    node.set_span(span);
+    if options.lossless {
+        let start = text.len() - rest.len();
+        node.set_original(Some(Original {
+            start: Some(format!("<!--{}", &text[..start])),
+            end: Some(format!("{}-->", &text[start + content_end..])),
+            ..Original::default()
+        }));
+    }
+    let mut inner_diagnostics = build_tree(content, &node, options).ok()?;
+
+    // the content was parsed on its own, so move the spans to where it is in the document
+    let prefix = &format!("<!--[{}]>", condition);
+    let base = Position {
+        offset: span.start + prefix.len(),
+        line: span.line + prefix.matches('\n').count(),
+        column: match prefix.rfind('\n') {
+            Some(i) => prefix[i + 1..].chars().count() + 1,
+            None => span.column + prefix.chars().count(),
+        },
+    };
+    for descendant in node.descendants() {
+        if let Some(span) = descendant.get_span() {
+            let start = shift_position(&base, span.start, span.line, span.column);
+            let end = span.end + base.offset;
+            Node::from(descendant).set_span(Span { start: start.offset, end, line: start.line, column: start.column });
+        }
+    }
+    for diagnostic in &mut inner_diagnostics {
+        let position = diagnostic.position;
+        diagnostic.position = shift_position(&base, position.offset, position.line, position.column);
+    }
+    diagnostics.append(&mut inner_diagnostics);
+
+    Some(node)
+}
+
+/// Returns the position in the document of a position in a part of it
+/// that starts at `base`.
+fn shift_position(base: &Position, offset: usize, line: usize, column: usize) -> Position {
+    Position {
+        offset: base.offset + offset,
+        line: base.line + line - 1,
+        column: if line == 1 { base.column + column - 1 } else { column },
+    }
+}
+
 /// Debugging function for node_vec.
 #[allow(dead_code)]
 fn debug_print_node_vec(node_vec: &Vec<Node>) {
     for node in node_vec {
-        match node.get_payload() {
+        match &*node.get_payload() {
             Payload::Tag(tag) => println!("{:#?}", tag),
             Payload::Text(text) => println!("{:#?}", text),
+            Payload::RawText(text) => println!("{:#?}", text),
             Payload::Comment(text) => println!("{:#?}", text),
+            Payload::ConditionalComment(text) => println!("{:#?}", text),
+            Payload::Doctype(doctype) => println!("{:#?}", doctype),
         }
     }
 }
 
-/// Finds the end tag paired with `starter` from node_vec and return its index.
-fn find_terminator(node_vec: &mut Vec<Node>, starter: &Tag) -> Option<usize> {
-    for i in 0..node_vec.len() {
-        let node = node_vec.get(i).unwrap();
-        if let Payload::Tag(tag) = node.get_payload() {
-            if tag.is_terminator() && starter.get_name() == tag.get_name() {
-                return Some(i);
+/// Returns true if `name` is `svg` or `math`, whose content is foreign:
+/// names are case-sensitive and any element may be self-closing.
+pub(crate) fn is_foreign_root(name: &str) -> bool {
+    name.eq_ignore_ascii_case("svg") || name.eq_ignore_ascii_case("math")
+}
+
+/// Returns true if `name` is an element inside `<svg>` or `<math>` whose
+/// content is HTML again. These are `<foreignObject>`, `<desc>` and
+/// `<title>` of SVG, and `<mi>`, `<mo>`, `<mn>`, `<ms>`, `<mtext>` and
+/// `<annotation-xml>` of MathML.
+pub(crate) fn is_integration_point(name: &str) -> bool {
+    ["foreignObject", "desc", "title", "mi", "mo", "mn", "ms", "mtext", "annotation-xml"]
+        .iter()
+        .any(|point| point.eq_ignore_ascii_case(name))
+}
+
+/// Elements whose start tag closes an open `<p>`.
+const CLOSE_P_ELEMENTS: [&str; 26] = [
+    "address", "article", "aside", "blockquote", "details", "div", "dl", "fieldset",
+    "figcaption", "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6",
+    "header", "hr", "main", "nav", "ol", "p", "pre", "section",
+];
+
+/// Returns true if the start tag `next` implies the end tag of the open
+/// element `open`, following the spec and ignoring ASCII case. `<li>`
+/// closes an open `<li>`.
+pub(crate) fn is_end_implied(open: &str, next: &str) -> bool {
+    let (open, next) = (open.to_ascii_lowercase(), next.to_ascii_lowercase());
+    let next = next.as_str();
+    match open.as_str() {
+        "p" => CLOSE_P_ELEMENTS.contains(&next) || next == "table" || next == "ul",
+        "li" => next == "li",
+        "dt" | "dd" => next == "dt" || next == "dd",
+        "td" | "th" => matches!(next, "td" | "th" | "tr" | "tbody" | "thead" | "tfoot"),
+        "tr" => matches!(next, "tr" | "tbody" | "thead" | "tfoot"),
+        "thead" | "tbody" | "tfoot" => matches!(next, "tbody" | "thead" | "tfoot"),
+        "option" => next == "option" || next == "optgroup",
+        "optgroup" => next == "optgroup",
+        _ => false,
+    }
+}
+
+/// Returns true if `name` is the name of `node` or one of its ancestors,
+/// ignoring ASCII case. The node wrapping the tree, which has no parent, is
+/// not included.
+fn is_open(node: &NodeData, name: &str) -> bool {
+    let has_name = |node: &NodeData| match &*node.get_payload() {
+        Payload::Tag(tag) => node.has_parent() && tag.get_name().eq_ignore_ascii_case(name),
+        _ => false,
+    };
+
+    has_name(node) || node.ancestors().any(|ancestor| has_name(&ancestor))
+}
+
+/// Extends the span of `node` to `end` so that it covers the end tag.
+fn extend_span(node: &Node, end: usize) {
+    if let Some(span) = node.get_span() {
+        node.set_span(Span { end, ..span });
+    }
+}
+
+/// Returns where `node` starts in the document.
+fn get_position(node: &NodeData) -> Position {
+    let span = node.get_span().unwrap_or(Span { start: 0, end: 0, line: 1, column: 1 });
+    Position {
+        offset: span.start,
+        line: span.line,
+        column: span.column,
+    }
+}
+
+/// Returns true if the end tag of `name` may be omitted, so that closing
+/// it without one is not a problem, ignoring ASCII case. `<li>`, `<p>`
+pub(crate) fn has_optional_end_tag(name: &str) -> bool {
+    matches!(
+        name.to_ascii_lowercase().as_str(),
+        "p" | "li" | "dt" | "dd" | "td" | "th" | "tr" | "thead" | "tbody" | "tfoot" | "option" | "optgroup"
+    )
+}
+
+/// Returns the end of the span of `node`, or 0 if it has no span.
+fn get_span_end(node: &Node) -> usize {
+    node.get_span().map_or(0, |span| span.end)
+}
+
+/// Adds the nodes to `parent` as its descendants.
+///
+/// A tag is closed by its end tag, by the end tag of one of its ancestors,
+/// or by a start tag that implies its end tag, such as `<li>` for an open
+/// `<li>`. End tags that match no open tag are discarded. If `recover` is
+/// false, either of the first two fails instead.
+///
+/// The open tags are kept on a stack rather than the call stack, so
+/// deeply nested input does not overflow it.
+///
+/// # Errors
+/// * [`ParseError::DepthLimitExceeded`] if an element is nested deeper than `options.max_depth`.
+/// * [`ParseError::MismatchedEndTag`] if `recover` is false and an end tag
+///   is stray or closes other open tags than its own.
+fn create_node_tree(
+    node_vec: Vec<Node>,
+    parent: &Node,
+    options: &ParseOptions,
+    recover: bool,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<(), ParseError> {
+    // `parent` and the tags opened in it, the innermost last
+    let mut open_nodes = vec![parent.clone()];
+    let mut node_vec = node_vec.into_iter().peekable();
+
+    while let Some(next) = node_vec.peek() {
+        let open = open_nodes.last().unwrap();
+        // decided before the open tags change
+        let (is_end_tag, stray, end_implied) = match &*next.get_payload() {
+            Payload::Tag(tag) if tag.is_terminator() => {
+                // void elements have no children, so `</br>` closes nothing
+                let stray = tag.is_void_element() || !is_open(open, tag.get_name());
+                (true, stray.then(|| tag.get_name().to_string()), false)
+            }
+            Payload::Tag(tag) => {
+                let end_implied = match &*open.get_payload() {
+                    Payload::Tag(open_tag) => open.has_parent() && is_end_implied(open_tag.get_name(), tag.get_name()),
+                    _ => false,
+                };
+                (false, None, end_implied)
             }
+            _ => (false, None, false),
+        };
+
+        if let Some(name) = stray {
+            let position = get_position(next);
+            if !recover {
+                return Err(ParseError::MismatchedEndTag(position));
+            }
+            diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::StrayEndTag(name),
+                position,
+            });
+            node_vec.next();
+            continue;
+        }
+        if is_end_tag {
+            // get terminator. `</ tag>`
+            let terminator = node_vec.next().unwrap();
+            if !close_by_end_tag(&mut open_nodes, &terminator, recover, diagnostics)? {
+                // it closed `parent` itself
+                return Ok(());
+            }
+            continue;
+        }
+        if end_implied {
+            // `<li>one<li>two`
+            //         ^ closes the first `<li>`
+            let node = open_nodes.pop().unwrap();
+            if open_nodes.is_empty() {
+                return Ok(());
+            }
+            close_by_end_of_content(&node, false, diagnostics);
+            continue;
+        }
+
+        let node = node_vec.next().unwrap();
+        let open = open_nodes.last().unwrap();
+        let has_children = match &*node.get_payload() {
+            Payload::Tag(tag) => {
+                // the depth of `parent` is 0
+                options.check_depth(open_nodes.len(), node.get_span())?;
+                // not `<tag />` or `<br>`
+                !tag.is_self_closing() && !tag.is_void_element()
+            }
+            _ => false,
+        };
+        open.add_child_and_update_parent(&node);
+        if has_children {
+            open_nodes.push(node);
         }
     }
 
-    None
+    // closed by the end of input
+    for node in open_nodes.iter().skip(1).rev() {
+        close_by_end_of_content(node, true, diagnostics);
+    }
+
+    Ok(())
 }
 
-/// If the tag is not terminator, add it to the child.
-fn create_node_tree(node_vec: &mut Vec<Node>, parent: &Node) {
-    while !node_vec.is_empty() {
-        let mut node = node_vec.remove(0);
+/// Closes the open tags up to the one that `terminator` closes, which is
+/// open. Returns false if that is the first of `open_nodes`, which is
+/// then left open.
+///
+/// # Errors
+/// * [`ParseError::MismatchedEndTag`] if `recover` is false and another
+///   open tag has no optional end tag.
+fn close_by_end_tag(
+    open_nodes: &mut Vec<Node>,
+    terminator: &Node,
+    recover: bool,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<bool, ParseError> {
+    let terminator_name = match &*terminator.get_payload() {
+        Payload::Tag(terminator_tag) => terminator_tag.get_name().to_string(),
+        _ => String::new(),
+    };
+
+    loop {
+        let node = open_nodes.pop().unwrap();
+        if open_nodes.is_empty() {
+            open_nodes.push(node);
+            return Ok(false);
+        }
 
-        if let Payload::Tag(tag) = node.get_payload() {
-            if tag.is_terminator() {
-                // get terminator. `</ tag>`
-                return;
+        let name = match &*node.get_payload() {
+            Payload::Tag(tag) => tag.get_name().to_string(),
+            _ => String::new(),
+        };
+        if name.eq_ignore_ascii_case(&terminator_name) {
+            extend_span(&node, get_span_end(terminator));
+            if let (Some(mut original), Some(end_tag)) = (node.take_original(), terminator.take_original()) {
+                original.end = end_tag.start;
+                node.set_original(Some(original));
             }
+            return Ok(true);
+        }
 
-            if !tag.is_self_closing() {
-                // If not self-closing. not `<tag />`
-                if let Some(terminator_idx) = find_terminator(node_vec, tag) {
-                    // If there is terminator tag
-                    if terminator_idx == 0 {
-                        // If there are no children, delete the terminator tag. <tag></ tag>
-                        node_vec.remove(0);
-                    } else {
-                        // If there are children, recurse
-                        create_node_tree(node_vec, &mut node);
-                    }
-                }
+        // the end tag of an ancestor. `<div><span>a</div>`
+        if !has_optional_end_tag(&name) {
+            if !recover {
+                return Err(ParseError::MismatchedEndTag(get_position(terminator)));
             }
+            diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::MismatchedEndTag {
+                    open: name,
+                    found: terminator_name.clone(),
+                },
+                position: get_position(terminator),
+            });
         }
+        extend_span(&node, terminator.get_span().map_or(0, |span| span.start));
+    }
+}
 
-        parent.add_child_and_update_parent(&node);
+/// Closes `node` without an end tag, by the end of input if `at_end` or
+/// else by a start tag that implies its end tag.
+fn close_by_end_of_content(node: &Node, at_end: bool, diagnostics: &mut Vec<Diagnostic>) {
+    if let Payload::Tag(tag) = &*node.get_payload() {
+        if at_end && !has_optional_end_tag(tag.get_name()) {
+            diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::UnclosedTag(tag.get_name().to_string()),
+                position: get_position(node),
+            });
+        }
+    }
+    if let Some(last_child) = node.last_child() {
+        extend_span(node, get_span_end(&Node::from(last_child)));
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dom::arena::Arena;
 
     #[test]
     fn parse_test() {
@@ -645,12 +1321,440 @@ mod tests {
         </body>
         "#;
 
-        match parse(&html) {
+        match parse(html) {
             Ok(_) => {}
             Err(e) => panic!("{}", e),
         }
     }
 
+    #[test]
+    fn void_element_test() {
+        let html = r#"
+        <p>
+          <img src="a.png">
+          <br/>
+          text
+        </p>
+        <img src="b.png"></img>
+        "#;
+        let root = parse(html).unwrap();
+
+        let children = root.get_children();
+        assert_eq!(children.len(), 2);
+
+        let p = children[0].get_children();
+        assert_eq!(p.len(), 3);
+        for node in &p[..2] {
+            assert!(node.get_children().is_empty());
+        }
+
+        match &*p[1].get_payload() {
+            Payload::Tag(tag) => {
+                assert_eq!(tag.get_name(), "br");
+                assert!(tag.is_self_closing());
+            }
+            _ => panic!("expected <br/>"),
+        };
+    }
+
+    #[test]
+    fn entity_test() {
+        let html = r#"<a title="Tom &amp; Jerry">&lt;b&gt; &#x27;quoted&#39;</a>"#;
+
+        let root = parse(html).unwrap();
+        let a = &root.get_children()[0];
+        match &*a.get_payload() {
+            Payload::Tag(tag) => assert_eq!(tag.get_attribute_value("title").unwrap(), "Tom & Jerry"),
+            _ => panic!("expected <a>"),
+        }
+        assert_eq!(*a.get_children()[0].get_payload(), Payload::Text(String::from("<b> 'quoted'")));
+
+        let options = ParseOptions {
+            decode_entities: false,
+            ..Default::default()
+        };
+        let root = parse_with_options(html, &options).unwrap();
+        let a = &root.get_children()[0];
+        assert_eq!(*a.get_children()[0].get_payload(), Payload::Text(String::from("&lt;b&gt; &#x27;quoted&#39;")));
+    }
+
+    #[test]
+    fn error_test() {
+        let err = parse("<p>\n  <a href=\"x>link</a>\n</p>").unwrap_err();
+        assert_eq!(err, ParseError::UnterminatedTag(Position { offset: 7, line: 2, column: 4 }));
+
+        let err = parse("<p>é</p><!-- comment").unwrap_err();
+        assert_eq!(err, ParseError::UnterminatedComment(Position { offset: 9, line: 1, column: 9 }));
+        assert_eq!(err.to_string(), "Input ends in the middle of the comment at line 1, column 9.");
+
+        // a document in an encoding that can't be decoded is not taken for UTF-8
+        let bytes = b"<meta charset=\"Shift_JIS\"><p>\x93\xFA\x96\x7B</p>";
+        assert_eq!(decode_bytes(bytes, None).1, Encoding::Unsupported("Shift_JIS"));
+        let err = parse_bytes(bytes).unwrap_err();
+        assert_eq!(err, ParseError::UnsupportedEncoding(Position { offset: 0, line: 1, column: 1 }));
+        let gbk = Some("text/html; charset=gbk");
+        assert!(parse_bytes_with_options(b"<p>a</p>", gbk, &ParseOptions::default()).is_err());
+    }
+
+    #[test]
+    fn lenient_test() {
+        let root = parse_lenient("<div><p>one<b>two</i></p><>three</span></div><p>four");
+        assert_eq!(root.to_html(), "<div><p>one<b>two</b></p>&lt;&gt;three</div><p>four</p>");
+
+        let root = parse_lenient("<p>text</p><!-- comment");
+        assert_eq!(root.to_html(), "<p>text</p><!-- comment-->");
+
+        assert!(parse_lenient("").get_children().is_empty());
+        assert!(parse_lenient("<a href=\"x>").get_children().len() == 1);
+    }
+
+    #[test]
+    fn span_test() {
+        let html = "<ul>\n  <li class=\"é\">one</li>\n  <!-- c -->\n</ul>";
+        let root = parse(html).unwrap();
+
+        let ul = &root.get_children()[0];
+        assert_eq!(ul.get_span(), Some(Span { start: 0, end: html.len(), line: 1, column: 1 }));
+
+        let li = &ul.get_children()[0];
+        let span = li.get_span().unwrap();
+        assert_eq!(&html[span.start..span.end], "<li class=\"é\">one</li>");
+        assert_eq!((span.line, span.column), (2, 3));
+
+        let text = li.get_children()[0].get_span().unwrap();
+        assert_eq!(&html[text.start..text.end], "one");
+
+        let comment = ul.get_children()[1].get_span().unwrap();
+        assert_eq!(&html[comment.start..comment.end], "<!-- c -->");
+
+        let root = parse_lenient("<div><p>a</div>b");
+        let div = root.get_children()[0].get_span().unwrap();
+        let p = root.get_children()[0].get_children()[0].get_span().unwrap();
+        assert_eq!((div.start, div.end), (0, 15));
+        assert_eq!((p.start, p.end), (5, 9));
+    }
+
+    #[test]
+    fn doctype_test() {
+        let root = parse("<!DOCTYPE html SYSTEM 'about:legacy-compat'>\n<html></html>").unwrap();
+        match &*root.get_children()[0].get_payload() {
+            Payload::Doctype(doctype) => {
+                assert_eq!(doctype.get_name(), "html");
+                assert_eq!(doctype.get_public_id(), None);
+                assert_eq!(doctype.get_system_id(), Some("about:legacy-compat"));
+            }
+            _ => panic!("expected doctype"),
+        }
+
+        let err = parse("<!DOCTYPE html PUBLIC -//W3C//DTD>").unwrap_err();
+        assert!(matches!(err, ParseError::InvalidDoctype(_)));
+    }
+
+    #[test]
+    fn attr_value_test() {
+        let root = parse(r#"<input disabled value="" size=10 name='q'>"#).unwrap();
+        match &*root.get_children()[0].get_payload() {
+            Payload::Tag(tag) => {
+                assert_eq!(tag.get_attribute("disabled"), Some(&AttrValue::Empty));
+                assert_eq!(tag.get_attribute("value"), Some(&AttrValue::Quoted(String::new())));
+                assert_eq!(tag.get_attribute("size"), Some(&AttrValue::Unquoted(String::from("10"))));
+                assert_eq!(tag.get_attribute("name"), Some(&AttrValue::Quoted(String::from("q"))));
+                assert!(tag.has_attribute("disabled"));
+                assert!(!tag.has_attribute("checked"));
+            }
+            _ => panic!("expected <input>"),
+        }
+
+        // '>' and '<' in quoted values do not end the tag
+        let root = parse(r#"<a title='a>b' data-x="<i>" rel=it's>x</a><b alt='"'>y</b>"#).unwrap();
+        let a = root.first_child().unwrap();
+        assert_eq!(a.attr("title").as_deref(), Some("a>b"));
+        assert_eq!(a.attr("data-x").as_deref(), Some("<i>"));
+        assert_eq!(a.attr("rel").as_deref(), Some("it's"));
+        assert_eq!(a.text(), "x");
+        assert_eq!(root.last_child().unwrap().attr("alt").as_deref(), Some("\""));
+        assert_eq!(root.to_html(), r#"<a title="a>b" data-x="<i>" rel="it's">x</a><b alt='"'>y</b>"#);
+        assert!(matches!(parse("<a title='>"), Err(ParseError::UnterminatedTag(_))));
+
+        // unquoted values end at whitespace or '>' only
+        let root = parse("<a href=/path?a=b&amp;c=d\ttitle=100%>x</a><img src=a.png/>").unwrap();
+        let a = root.first_child().unwrap();
+        assert_eq!(a.attr("href").as_deref(), Some("/path?a=b&c=d"));
+        assert_eq!(a.attr("title").as_deref(), Some("100%"));
+        assert_eq!(root.last_child().unwrap().attr("src").as_deref(), Some("a.png/"));
+    }
+
+    #[test]
+    fn attr_whitespace_test() {
+        for recover in [false, true] {
+            let options = ParseOptions { recover, ..Default::default() };
+
+            let root = parse_with_options("<a\nhref=\"x\"\n  class=\"y\">t</a>", &options).unwrap();
+            let a = root.first_child().unwrap();
+            assert_eq!(a.tag_name().as_deref(), Some("a"));
+            assert_eq!(a.attr("href").as_deref(), Some("x"));
+            assert_eq!(a.attr("class").as_deref(), Some("y"));
+            assert_eq!(a.text(), "t");
+
+            let root = parse_with_options("<div\tid=x>y</div>", &options).unwrap();
+            let div = crate::get_node_by_id(&root.get_copy_of_internal_arc(), "x").unwrap();
+            assert_eq!(div.tag_name().as_deref(), Some("div"));
+            assert_eq!(div.text(), "y");
+        }
+    }
+
+    #[test]
+    fn lowercase_names_test() {
+        let html = r#"<DIV Class="x"><P>a</p></DIV>"#;
+        let options = ParseOptions {
+            lowercase_names: true,
+            ..Default::default()
+        };
+        let root = parse_with_options(html, &options).unwrap();
+
+        assert_eq!(root.to_html(), r#"<div class="x"><p>a</p></div>"#);
+        assert!(crate::get_node_by_name(&root.get_copy_of_internal_arc(), "div").is_some());
+        assert_eq!(crate::get_nodes_by_class(&root.get_copy_of_internal_arc(), "x").len(), 1);
+    }
+
+    #[test]
+    fn fragment_test() {
+        let nodes = parse_fragment("<li>one</li> <li>two</li>", "ul").unwrap();
+        assert_eq!(nodes.len(), 2);
+        assert!(nodes.iter().all(|node| !node.has_parent()));
+        assert_eq!(nodes[0].to_html(), "<li>one</li>");
+
+        let nodes = parse_fragment("a &amp; <b>", "title").unwrap();
+        assert_eq!(*nodes[0].get_payload(), Payload::Text(String::from("a & <b>")));
+        assert!(parse_fragment("", "div").unwrap().is_empty());
+    }
+
+    #[test]
+    fn implied_end_tag_test() {
+        let html = "<ul><li>one<li>two</ul><p>a<div>b</div><table><tr><td>1<td>2<tr><td>3</table><dl><dt>t<dd>d</dl>";
+        let expected = "<ul><li>one</li><li>two</li></ul><p>a</p><div>b</div>\
+            <table><tr><td>1</td><td>2</td></tr><tr><td>3</td></tr></table><dl><dt>t</dt><dd>d</dd></dl>";
+
+        assert_eq!(parse(html).unwrap().to_html(), expected);
+        assert_eq!(parse_lenient(html).to_html(), expected);
+
+        let node = parse("<select><option>a<option>b</select><p>x</p></p>").unwrap();
+        assert_eq!(node.to_html(), "<select><option>a</option><option>b</option></select><p>x</p>");
+
+        // whatever their case
+        let html = "<UL><LI>a<LI>b</UL><P>c<Div>d</Div>";
+        let expected = "<UL><LI>a</LI><LI>b</LI></UL><P>c</P><Div>d</Div>";
+        assert_eq!(parse(html).unwrap().to_html(), expected);
+        assert_eq!(parse_parallel(html, &ParseOptions::default()).unwrap().to_html(), expected);
+    }
+
+    #[test]
+    fn mismatched_end_tag_test() {
+        let html = "<div><b><i>text</b></i><span>a</div></span>";
+        let expected = "<div><b><i>text</i></b><span>a</span></div>";
+        assert_eq!(parse(html).unwrap().to_html(), expected);
+        assert_eq!(parse_lenient(html).to_html(), expected);
+
+        let result = parse_with_diagnostics(html, &ParseOptions::default()).unwrap();
+        let kinds: Vec<DiagnosticKind> = result.warnings.into_iter().map(|warning| warning.kind).collect();
+        assert_eq!(kinds, [
+            DiagnosticKind::MismatchedEndTag { open: String::from("i"), found: String::from("b") },
+            DiagnosticKind::StrayEndTag(String::from("i")),
+            DiagnosticKind::MismatchedEndTag { open: String::from("span"), found: String::from("div") },
+            DiagnosticKind::StrayEndTag(String::from("span")),
+        ]);
+
+        let options = ParseOptions {
+            strictness: Strictness::Strict,
+            ..Default::default()
+        };
+        let error = parse_with_options(html, &options).unwrap_err();
+        assert_eq!(error, ParseError::MismatchedEndTag(Position { offset: 15, line: 1, column: 16 }));
+        assert!(parse_with_options("<ul><li>a<li>b</ul>", &options).is_ok());
+
+        // end tags match the open tags whatever their case
+        assert_eq!(parse_with_options("<DIV>a</div>b", &options).unwrap().to_html(), "<DIV>a</DIV>b");
+        assert_eq!(parse("<Script>a</script>b").unwrap().to_html(), "<Script>a</Script>b");
+        assert_eq!(parse_lenient("<b>a<I>b</B>c").to_html(), "<b>a<I>b</I></b>c");
+    }
+
+    #[test]
+    fn diagnostics_test() {
+        let html = "<div id=\"a\" class=\"b\" id=\"c\">\n<p>x\u{0}</span><ul><li>a<li>b</ul><section>";
+        let result = parse_with_diagnostics(html, &ParseOptions::default()).unwrap();
+        assert_eq!(
+            result.dom.to_html(),
+            "<div id=\"a\" class=\"b\"><p>x\u{0}</p><ul><li>a</li><li>b</li></ul><section></section></div>"
+        );
+
+        let warnings: Vec<(DiagnosticKind, usize, usize)> = result
+            .warnings
+            .into_iter()
+            .map(|warning| (warning.kind, warning.position.line, warning.position.column))
+            .collect();
+        assert_eq!(warnings, [
+            (DiagnosticKind::UnclosedTag(String::from("div")), 1, 1),
+            (DiagnosticKind::DuplicateAttribute(String::from("id")), 1, 23),
+            (DiagnosticKind::InvalidCharacter('\u{0}'), 2, 5),
+            (DiagnosticKind::StrayEndTag(String::from("span")), 2, 6),
+            (DiagnosticKind::UnclosedTag(String::from("section")), 2, 32),
+        ]);
+
+        let lenient = parse_with_diagnostics(html, &ParseOptions { recover: true, ..Default::default() }).unwrap();
+        assert_eq!(lenient.warnings.len(), 5);
+
+        let html = "<p id=\"a\">x</p>\n<div><b id=\"a\">y</b></div><i id=\"b\"></i><i id=\"a\"></i>";
+        let warnings = parse_with_diagnostics(html, &ParseOptions::default()).unwrap().warnings;
+        let warnings: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+        assert_eq!(warnings, [
+            "Duplicate id a at line 2, column 6.",
+            "Duplicate id a at line 2, column 41.",
+        ]);
+    }
+
+    #[test]
+    fn whitespace_test() {
+        let html = "a <ul>\n  <li>b  <i>c</i>\t d</li>\n</ul><pre>\n  e  </pre>";
+        let parse_html = |whitespace| {
+            let options = ParseOptions { whitespace, ..Default::default() };
+            parse_with_options(html, &options).unwrap().to_html()
+        };
+
+        assert_eq!(parse_html(Whitespace::Preserve), html);
+        assert_eq!(parse_html(Whitespace::Collapse), "a <ul> <li>b <i>c</i> d</li> </ul><pre>\n  e  </pre>");
+        assert_eq!(
+            parse_html(Whitespace::DropInterElement),
+            "a <ul><li>b  <i>c</i>\t d</li></ul><pre>\n  e  </pre>"
+        );
+    }
+
+    #[test]
+    fn lossless_test() {
+        let html = concat!(
+            "\u{feff}<!doctype HTML>\r\n<HTML Lang=en>\n  <Body class='a  b'   ID=\"x\">\n",
+            "    <p>x &amp; y&nbsp;&#169; <BR/></span>\n    <img src=a.png alt>\n",
+            "    <ul><li>one<li>two  </ul><!-- c -->\n    <script>if (a < b) {}</script>\n",
+            "    <!--[if IE]> <p>ie</P> <![endif]-->\n  </BODY>\n</html>\n\n",
+        );
+        let options = ParseOptions {
+            lossless: true,
+            conditional_comments: true,
+            lowercase_names: true,
+            ..Default::default()
+        };
+        let root = parse_with_options(html, &options).unwrap();
+        assert_eq!(root.to_html(), html);
+        // the recovering parser keeps the end tags too
+        let lenient = parse_with_options(html, &ParseOptions { recover: true, ..options.clone() }).unwrap();
+        assert_eq!(lenient.to_html(), html);
+        assert!(*lenient == *root);
+        let end_tag = |root: &Node| {
+            let body = root.query_name("body").next().unwrap();
+            let original = body.get_original();
+            original.as_ref().and_then(|original| original.end.clone())
+        };
+        assert_eq!(end_tag(&lenient).as_deref(), Some("</BODY>"));
+        assert_eq!(end_tag(&lenient), end_tag(&root));
+        let body = root.query_name("body").next().unwrap();
+        assert!(body.inner_html().starts_with("\n    <p>x &amp; y&nbsp;&#169; <BR/></span>"));
+        assert!(body.outer_html().ends_with("<![endif]-->\n  </BODY>"));
+        assert_eq!(parse_with_options(" \n", &options).unwrap().to_html(), " \n");
+
+        // only the changed nodes are written anew, the source around them is kept
+        let li = root.query_name("li").next().unwrap();
+        li.add_class("first");
+        if let Payload::Tag(tag) = &mut *root.query_name("img").next().unwrap().payload_mut() {
+            tag.set_attribute("alt", "pic");
+        }
+        let expected = html
+            .replace("<li>one", "<li class=\"first\">one</li>")
+            .replace("<img src=a.png alt>", "<img src=a.png alt=\"pic\">");
+        assert_eq!(root.to_html(), expected);
+
+        // the other modes write everything anew
+        let full = crate::dom::SerializeOptions { escape: crate::dom::EscapeMode::Full };
+        assert!(root.to_html_with_options(&full).contains("<body class=\"a  b\" id=\"x\"><p>x &amp; y&#xA0;&#xA9; <br />"));
+        assert_eq!(parse_with_options(html, &ParseOptions::default()).unwrap().to_html(), parse(html).unwrap().to_html());
+    }
+
+    #[test]
+    fn raw_text_test() {
+        let html = "<p>a</p>\n<script type=\"module\">if (a < b) {}</script><style></style>";
+        let root = parse(html).unwrap();
+        let script = root.query_name("script").next().unwrap();
+
+        let content = script.first_child().unwrap();
+        assert_eq!(*content.get_payload(), Payload::RawText(String::from("if (a < b) {}")));
+        assert_eq!(content.get_span(), Some(Span { start: 31, end: 44, line: 2, column: 23 }));
+        assert_eq!(&html[31..44], "if (a < b) {}");
+        assert!(root.query_name("style").next().unwrap().first_child().is_none());
+        assert_eq!(root.to_html(), html.replace('\n', ""));
+    }
+
+    #[test]
+    fn foreign_content_test() {
+        let html = concat!(
+            r#"<DIV><svg viewBox="0 0 10 10"><linearGradient id="g"/><path d="M0"/>"#,
+            r#"<style>a > b {}</style><![CDATA[x < y]]><foreignObject><TEXTAREA><b></TEXTAREA></foreignObject>"#,
+            "</svg><math><mi>x</mi></math></DIV>",
+        );
+        let options = ParseOptions {
+            lowercase_names: true,
+            ..Default::default()
+        };
+
+        let root = parse_with_options(html, &options).unwrap();
+        assert_eq!(root.to_html(), concat!(
+            r#"<div><svg viewBox="0 0 10 10"><linearGradient id="g" /><path d="M0" />"#,
+            r#"<style>a &gt; b {}</style>x &lt; y<foreignObject><textarea>&lt;b&gt;</textarea></foreignObject>"#,
+            "</svg><math><mi>x</mi></math></div>",
+        ));
+
+        let svg = root.query_name("svg").next().unwrap();
+        assert_eq!(svg.get_children().len(), 5);
+        let style_text = svg.get_children()[2].first_child().unwrap();
+        assert_eq!(*style_text.get_payload(), Payload::Text(String::from("a > b {}")));
+
+        assert_eq!(parse("<svg><![CDATA[x"), Err(ParseError::UnterminatedCdata(Position { offset: 5, line: 1, column: 6 })));
+        assert_eq!(parse_lenient("<svg><![CDATA[x").to_html(), "<svg>x</svg>");
+
+        // the end tag of an element around `<svg>` ends its foreign content
+        let root = parse("<p><svg><g></p><style>a < b</style><textarea><b></textarea>").unwrap();
+        assert_eq!(root.to_html(), "<p><svg><g></g></svg></p><style>a < b</style><textarea>&lt;b&gt;</textarea>");
+
+        // the text of `<style>` in `<svg>` is parsed as HTML, so it is escaped
+        let html = "<svg><style>a &lt; b</style></svg><math><mi><style>a < b</style></mi></math>";
+        assert_eq!(parse(html).unwrap().to_html(), html);
+    }
+
+    #[test]
+    fn conditional_comment_test() {
+        let html = "<body>\n<!--[if gte mso 9]><table><tr><td>a</td></tr></table><![endif]-->\n<!--[if !mso]><!--><p>b</p><!--<![endif]--></body>";
+        let options = ParseOptions {
+            conditional_comments: true,
+            ..Default::default()
+        };
+
+        let root = parse_with_options(html, &options).unwrap();
+        let body = root.first_child().unwrap();
+        let comment = &body.get_children()[0];
+        assert_eq!(*comment.get_payload(), Payload::ConditionalComment(String::from("if gte mso 9")));
+        assert_eq!(comment.get_children()[0].to_html(), "<table><tr><td>a</td></tr></table>");
+        assert_eq!(root.to_html(), html.replace('\n', ""));
+
+        // the content is where it is in the document
+        let td = root.query_name("td").next().unwrap().get_span().unwrap();
+        assert_eq!(&html[td.start..td.end], "<td>a</td>");
+        assert_eq!((td.line, td.column), (2, 31));
+
+        // the revealed form is made of ordinary comments
+        assert_eq!(*body.get_children()[1].get_payload(), Payload::Comment(String::from("[if !mso]><!")));
+
+        let root = parse(html).unwrap();
+        assert!(root.query_name("td").next().is_none());
+        assert_eq!(root.to_html(), html.replace('\n', ""));
+    }
+
     #[test]
     fn eq_test() {
         let a = r#"
@@ -665,7 +1769,7 @@ mod tests {
           </ul>
         </body>
         "#;
-        let a_node = parse(&a).unwrap();
+        let a_node = parse(a).unwrap();
 
         let b = r#"
         <head>
@@ -679,10 +1783,10 @@ mod tests {
           </ul>
         </body>
         "#;
-        let b_node = parse(&b).unwrap();
+        let b_node = parse(b).unwrap();
 
-        assert_eq!(a_node == b_node, true);
-        assert_eq!(a_node != b_node, false);
+        assert!(a_node == b_node);
+        assert!(!(a_node != b_node));
     }
 
     #[test]
@@ -699,7 +1803,7 @@ mod tests {
           </ul>
         </body>
         "#;
-        let a_dom = parse(&a).unwrap();
+        let a_dom = parse(a).unwrap();
 
         let b = r#"
         <head>
@@ -713,9 +1817,127 @@ mod tests {
           </ul>
         </body>
         "#;
-        let b_dom = parse(&b).unwrap();
+        let b_dom = parse(b).unwrap();
+
+        assert!(!(a_dom == b_dom));
+        assert!(a_dom != b_dom);
+    }
+
+    #[test]
+    fn limits_test() {
+        let html = "<div>\n<p><br><b>x</b></p></div>";
+        let options = |max_depth, max_nodes, max_input_len| ParseOptions {
+            max_depth,
+            max_nodes,
+            max_input_len,
+            ..Default::default()
+        };
+        let lenient = |options: ParseOptions| ParseOptions { recover: true, ..options };
+
+        // <br> and <b> are three deep
+        let depth = ParseError::DepthLimitExceeded(Position { offset: 9, line: 2, column: 4 });
+        assert_eq!(parse_with_options(html, &options(Some(2), None, None)), Err(depth.clone()));
+        assert_eq!(parse_with_options(html, &lenient(options(Some(2), None, None))), Err(depth.clone()));
+        assert_eq!(Arena::parse_with_options(html, &options(Some(2), None, None)).err(), Some(depth.clone()));
+        assert_eq!(parse_parallel(html, &options(Some(2), None, None)).err(), Some(depth.clone()));
+        assert!(parse_with_options(html, &options(Some(3), None, None)).is_ok());
+
+        // <div>, <p>, <br>, <b> and "x", as the blank text is dropped
+        let nodes = ParseError::NodeLimitExceeded(Position { offset: 16, line: 2, column: 11 });
+        assert_eq!(parse_with_options(html, &options(None, Some(4), None)), Err(nodes.clone()));
+        assert_eq!(Arena::parse_with_options(html, &options(None, Some(4), None)).err(), Some(nodes.clone()));
+        assert_eq!(parse_parallel(html, &options(None, Some(4), None)).err(), Some(nodes.clone()));
+        assert!(parse_with_options(html, &options(None, Some(5), None)).is_ok());
+
+        let input = ParseError::InputLimitExceeded(Position { offset: 6, line: 2, column: 1 });
+        assert_eq!(parse_with_options(html, &options(None, None, Some(6))), Err(input.clone()));
+        assert_eq!(parse_parallel(html, &options(None, None, Some(6))).err(), Some(input.clone()));
+        assert!(parse_with_options(html, &options(None, None, Some(html.len()))).is_ok());
+
+        // fails before the recursion goes deeper than the limit
+        let deep = "<div>".repeat(5_000);
+        assert!(matches!(
+            parse_with_options(&deep, &options(Some(512), None, None)),
+            Err(ParseError::DepthLimitExceeded(Position { offset: 2560, .. }))
+        ));
+    }
+
+    #[test]
+    fn deep_nesting_test() {
+        let depth = 50_000;
+        let html = format!("{}<p id=\"deepest\">x{}", "<div>".repeat(depth), "</div>".repeat(depth));
+
+        let root = parse(&html).unwrap();
+        let root = root.get_copy_of_internal_arc();
+        let deepest = crate::get_node_by_id(&root, "deepest").unwrap();
+        assert_eq!(deepest.ancestors().count(), depth + 1);
+        assert_eq!(crate::get_nodes_by_name(&root, "div").len(), depth);
+
+        let root = parse_lenient(&html);
+        assert_eq!(root.descendants().count(), depth + 2);
+        assert_eq!(root.to_html(), html.replace("x<", "x</p><"));
+        assert_eq!(crate::sanitize::Sanitizer::new().allow_tags(["div", "p"]).clean(&root).descendants().count(), depth + 2);
+
+        let options = ParseOptions {
+            lossless: true,
+            ..Default::default()
+        };
+        assert_eq!(parse_with_options(&html, &options).unwrap().to_html(), html);
+        assert_eq!(parse_borrowed(&html).descendants().count(), depth + 2);
+        assert_eq!(parse_parallel(&html, &ParseOptions::default()).unwrap().to_node().descendants().count(), depth + 2);
+        assert_eq!(crate::syntax::parse(&html).source(), html);
+    }
 
-        assert_eq!(a_dom == b_dom, false);
-        assert_eq!(a_dom != b_dom, true);
+    /// Parses random documents of markup pieces and bytes with every entry
+    /// point, none of which may panic. The same checks as `fuzz/`, run on
+    /// fewer inputs in every test run.
+    #[test]
+    fn arbitrary_input_test() {
+        struct Rng(u64);
+
+        impl Rng {
+            fn below(&mut self, n: usize) -> usize {
+                // xorshift64
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                (self.0 % n as u64) as usize
+            }
+        }
+
+        const PIECES: [&str; 40] = [
+            "<", ">", "/", "=", "\"", "'", "!", "-", "<!--", "-->", "<!DOCTYPE", "<![CDATA[", "]]>", "&", ";",
+            "&#x", "é", "\u{1F600}", " ", "\n", "\r", "\0", "a", "p", "</script", "<script>", "<textarea>",
+            "<svg>", "<pre>", "<li>", "<td>", "<table>", "<a href=", "<b>", "</b>", "<!--[if IE]>", "<![endif]-->",
+            "<br/>", "<?", "\u{feff}",
+        ];
+
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+        let options = ParseOptions {
+            conditional_comments: true,
+            lowercase_names: true,
+            whitespace: Whitespace::Collapse,
+            lossless: true,
+            ..Default::default()
+        };
+        for _ in 0..500 {
+            let doc: String = (0..rng.below(40)).map(|_| PIECES[rng.below(PIECES.len())]).collect();
+            if let Ok(root) = parse(&doc) {
+                let _ = parse(&root.to_html());
+            }
+            if let Ok(root) = parse_with_options(&doc, &options) {
+                assert_eq!(root.to_html(), doc, "{:?}", doc);
+            }
+            let _ = parse_lenient(&doc);
+            let _ = parse_document(&doc);
+            let _ = parse_borrowed(&doc);
+            let _ = parse_parallel(&doc, &ParseOptions::default());
+            let _ = parse_fragment(&doc, "textarea");
+            assert_eq!(crate::syntax::parse(&doc).root().text(), doc);
+
+            let bytes: Vec<u8> = (0..rng.below(40)).map(|_| rng.below(256) as u8).collect();
+            let _ = parse_bytes(&bytes);
+            let _ = parse_reader(bytes.as_slice(), &ParseOptions::default());
+        }
     }
 }
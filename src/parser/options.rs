@@ -0,0 +1,174 @@
+use crate::dom::Span;
+
+use super::{ParseError, Position};
+
+/// Options to control how a document is parsed.
+///
+/// # Examples
+/// ```rust
+/// let options = html::ParseOptions {
+///     decode_entities: false,
+///     ..Default::default()
+/// };
+///
+/// let node = html::parse_with_options("<p>&amp;</p>", &options).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseOptions {
+    /// Decodes character references such as `&amp;` in text and attribute values.
+    /// If false, the raw references are kept, and are not escaped again when
+    /// the tree is serialized. Default: true
+    pub decode_entities: bool,
+    /// Recovers from errors like a browser does instead of failing.
+    /// See [`parse_lenient`](crate::parse_lenient). Default: false
+    pub recover: bool,
+    /// Lowercases tag and attribute names, so `<DIV Class="x">` is found
+    /// by queries for `div` and `class`. Default: false
+    pub lowercase_names: bool,
+    /// How end tags that do not match the open element are handled.
+    /// Ignored if `recover` is true. Default: [`Strictness::Tolerant`]
+    pub strictness: Strictness,
+    /// How whitespace in text is handled. Default: [`Whitespace::DropInterElement`]
+    pub whitespace: Whitespace,
+    /// Parses the content of conditional comments such as
+    /// `<!--[if IE]><p>IE only</p><![endif]-->` into the children of a
+    /// [`Payload::ConditionalComment`](crate::dom::Payload::ConditionalComment)
+    /// instead of keeping them as a comment. Only [`parse_with_options`](crate::parse_with_options)
+    /// and the functions built on it do this. Default: false
+    pub conditional_comments: bool,
+    /// The deepest an element may be nested, counting the elements it is
+    /// in and itself. `<div><p>` has a depth of 2. Parsing fails with
+    /// [`ParseError::DepthLimitExceeded`] beyond it. Default: None
+    pub max_depth: Option<usize>,
+    /// The most nodes the tree may have, not counting the root. Parsing
+    /// fails with [`ParseError::NodeLimitExceeded`] beyond it. Default: None
+    pub max_nodes: Option<usize>,
+    /// The longest document in bytes that is parsed. Parsing fails with
+    /// [`ParseError::InputLimitExceeded`] before anything is parsed if the
+    /// document is longer. Default: None
+    pub max_input_len: Option<usize>,
+    /// Keeps the markup each node was parsed from, with the whitespace,
+    /// quotes, attribute order, character references and case as they were
+    /// written, so that [`to_html`](crate::dom::NodeData::to_html) writes the
+    /// document back byte for byte. A node whose payload is changed through
+    /// [`payload_mut`](crate::dom::NodeData::payload_mut) or
+    /// [`set_payload`](crate::dom::NodeData::set_payload) is written anew, and
+    /// so is every node with [`EscapeMode::Full`](crate::dom::EscapeMode::Full).
+    /// Only [`parse_with_options`](crate::parse_with_options) and the functions
+    /// built on it do this, and if `recover` is true, the markup of the nodes the
+    /// parser moves may be written out of order. Default: false
+    pub lossless: bool,
+}
+
+/// How strictly a document is parsed. See [`ParseOptions::strictness`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strictness {
+    /// An end tag closes the nearest open element with the same name
+    /// together with the elements opened inside it, and an end tag that
+    /// matches no open element is discarded. `<b><i>text</b>` closes both.
+    #[default]
+    Tolerant,
+    /// Fails with [`ParseError::MismatchedEndTag`](crate::ParseError::MismatchedEndTag)
+    /// where `Tolerant` would close or discard a tag. Omitted optional end
+    /// tags such as `</li>` are still allowed.
+    Strict,
+}
+
+/// How whitespace in text is handled. See [`ParseOptions::whitespace`].
+///
+/// Whitespace is ' ', '\t', '\n', '\x0C' and '\r'. The text of `<pre>`
+/// and raw text elements such as `<script>` is always kept as it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Whitespace {
+    /// Keeps all whitespace, so the whitespace between tags becomes text.
+    /// `<ul>\n  <li>` gives `ul` a text child.
+    Preserve,
+    /// Replaces each run of whitespace with a single ' '.
+    /// The whitespace between tags becomes `" "`.
+    Collapse,
+    /// Drops the text that is only whitespace, such as the indentation
+    /// between tags, and keeps the whitespace in other text.
+    #[default]
+    DropInterElement,
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions {
+            decode_entities: true,
+            recover: false,
+            lowercase_names: false,
+            strictness: Strictness::Tolerant,
+            whitespace: Whitespace::DropInterElement,
+            conditional_comments: false,
+            max_depth: None,
+            max_nodes: None,
+            max_input_len: None,
+            lossless: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// The options for HTML email, which is written back as it was parsed
+    /// so that the quirks email clients rely on survive: the whitespace,
+    /// the quotes and case of the markup, the conditional comments for
+    /// Outlook and prefixed tags such as `<o:p>` and `<v:roundrect>`.
+    /// See [`email`](crate::email).
+    pub fn email() -> ParseOptions {
+        ParseOptions {
+            whitespace: Whitespace::Preserve,
+            lossless: true,
+            ..Default::default()
+        }
+    }
+
+    /// Fails if `doc` is longer than `max_input_len`.
+    pub(crate) fn check_input_len(&self, doc: &str) -> Result<(), ParseError> {
+        let max = match self.max_input_len {
+            Some(max) if doc.len() > max => max,
+            _ => return Ok(()),
+        };
+
+        let mut position = Position { offset: 0, line: 1, column: 1 };
+        for c in doc.chars() {
+            if position.offset + c.len_utf8() > max {
+                break;
+            }
+            position.offset += c.len_utf8();
+            if c == '\n' {
+                position.line += 1;
+                position.column = 1;
+            } else {
+                position.column += 1;
+            }
+        }
+
+        Err(ParseError::InputLimitExceeded(position))
+    }
+
+    /// Fails if an element at `span` nested `depth` deep is beyond `max_depth`.
+    pub(crate) fn check_depth(&self, depth: usize, span: Option<Span>) -> Result<(), ParseError> {
+        match self.max_depth {
+            Some(max) if depth > max => Err(ParseError::DepthLimitExceeded(span_position(span))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails if `nodes`, the number of nodes up to the one at `span`, is beyond `max_nodes`.
+    pub(crate) fn check_nodes(&self, nodes: usize, span: Option<Span>) -> Result<(), ParseError> {
+        match self.max_nodes {
+            Some(max) if nodes > max => Err(ParseError::NodeLimitExceeded(span_position(span))),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn span_position(span: Option<Span>) -> Position {
+    let span = span.unwrap_or(Span { start: 0, end: 0, line: 1, column: 1 });
+    Position {
+        offset: span.start,
+        line: span.line,
+        column: span.column,
+    }
+}
@@ -0,0 +1,258 @@
+use std::thread;
+
+use crate::dom::arena::{Arena, NodeId};
+use crate::dom::{sync, Span, ROOT_TAG_NAME};
+
+use super::{is_end_implied, Event, ParseError, ParseOptions, Tokenizer};
+
+/// The fewest events a thread is given, so that a small document is not
+/// spread over threads that cost more than they save.
+const MIN_BATCH_LEN: usize = 1024;
+
+/// Parses a large document on several threads into a [`sync::Node`] tree.
+///
+/// The document is tokenized on the calling thread. The top-level children
+/// of `<body>` are then split into batches of about the same size, whose
+/// subtrees are built on scoped threads and added to `<body>` in order.
+/// A document without `<body>` is built on the calling thread.
+///
+/// The tree is built as [`Arena::parse_with_options`] builds it, like
+/// [`parse_lenient`](crate::parse_lenient) does: end tags close the nearest
+/// open tag with the same name and end tags that match no open tag are
+/// discarded.
+///
+/// # Examples
+/// ```rust
+/// let mut doc = String::from("<html><body>");
+/// for i in 0..2000 {
+///     doc.push_str(&format!("<p>{}</p>", i));
+/// }
+///
+/// let root = html::parse_parallel(&doc, &Default::default()).unwrap();
+/// assert_eq!(root.to_node(), html::parse(&doc).unwrap());
+/// ```
+///
+/// # Errors
+/// * [`ParseError`] as [`parse_with_options`](crate::parse_with_options) does.
+pub fn parse_parallel(doc: &str, options: &ParseOptions) -> Result<sync::Node, ParseError> {
+    options.check_input_len(doc)?;
+    let mut events = Vec::new();
+    if !doc.trim_end().is_empty() {
+        let mut tokenizer = Tokenizer::new(doc, options.clone());
+        while let Some(event) = tokenizer.next_event()? {
+            events.push(event);
+        }
+    }
+    check_limits(&events, options)?;
+    let doc_end = events.iter().map(|(_, span)| span.end).max().unwrap_or(0);
+
+    let (body_start, body_end, starts) = match find_body_children(&events) {
+        Some(found) => found,
+        None => {
+            let arena = build(events, doc_end);
+            return Ok(arena.to_sync_node(arena.root()));
+        }
+    };
+
+    // the events before `<body>` and from the one that closes it
+    let suffix = events.split_off(body_end);
+    let mut children = events.split_off(body_start + 1);
+
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let batch_len = children.len().div_ceil(threads).max(MIN_BATCH_LEN);
+    let mut bounds = Vec::new();
+    for &start in &starts {
+        if bounds.last().is_none_or(|&last| start - last >= batch_len) {
+            bounds.push(start);
+        }
+    }
+
+    // each batch closes its open tags where the next event starts
+    let mut batches = Vec::new();
+    let mut end = suffix.first().map_or(doc_end, |(_, span)| span.start);
+    for &bound in bounds.iter().rev() {
+        let batch = children.split_off(bound);
+        let next_end = batch[0].1.start;
+        batches.push((batch, end));
+        end = next_end;
+    }
+    batches.reverse();
+
+    let mut body_children: Vec<sync::Node> = thread::scope(|scope| {
+        let handles: Vec<_> = batches
+            .into_iter()
+            .map(|(batch, end)| {
+                scope.spawn(move || {
+                    let arena = build(batch, end);
+                    arena.children(arena.root()).map(|id| arena.to_sync_node(id)).collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+    });
+
+    let mut arena = Arena::new();
+    let mut open_nodes = vec![arena.root()];
+    for (event, span) in events {
+        arena.push_event(&mut open_nodes, event, span);
+    }
+    let body = *open_nodes.last().unwrap();
+    for (event, span) in suffix {
+        arena.push_event(&mut open_nodes, event, span);
+    }
+    arena.close_open_nodes(open_nodes, doc_end);
+
+    Ok(to_sync_node(&arena, arena.root(), body, &mut body_children))
+}
+
+/// Builds the tree of `events`, closing the tags left open at `end`.
+fn build(events: Vec<(Event, Span)>, end: usize) -> Arena {
+    let mut arena = Arena::new();
+    let mut open_nodes = vec![arena.root()];
+    for (event, span) in events {
+        arena.push_event(&mut open_nodes, event, span);
+    }
+    arena.close_open_nodes(open_nodes, end);
+
+    arena
+}
+
+/// Returns the index of the `<body>` start tag, the index of the event
+/// that closes it, and the indexes of the events that start its
+/// top-level children, relative to the event after `<body>`.
+///
+/// The open tags are tracked by name with the rules of [`Arena::push_event`].
+fn find_body_children(events: &[(Event, Span)]) -> Option<(usize, usize, Vec<usize>)> {
+    let mut open = vec![ROOT_TAG_NAME];
+    let mut body: Option<(usize, usize)> = None;
+    let mut starts = Vec::new();
+
+    for (i, (event, _)) in events.iter().enumerate() {
+        if let Event::StartTag(tag) = event {
+            // close the tags whose end tag is implied. `<li>one<li>two`
+            while open.len() > 1 && is_end_implied(open.last().unwrap(), tag.get_name()) {
+                open.pop();
+            }
+        }
+        if let Some((body_start, depth)) = body {
+            if open.len() == depth && !matches!(event, Event::EndTag(_)) {
+                starts.push(i - body_start - 1);
+            }
+        }
+
+        match event {
+            Event::StartTag(tag) if !tag.is_self_closing() && !tag.is_void_element() => open.push(tag.get_name()),
+            Event::EndTag(name) => {
+                if let Some(open_idx) = open.iter().skip(1).rposition(|open| open.eq_ignore_ascii_case(name)) {
+                    open.truncate(open_idx + 1);
+                }
+            }
+            _ => {}
+        }
+
+        match body {
+            None if open.len() > 1 && open.last() == Some(&"body") => body = Some((i, open.len())),
+            Some((body_start, depth)) if open.len() < depth => return Some((body_start, i, starts)),
+            _ => {}
+        }
+    }
+
+    body.map(|(body_start, _)| (body_start, events.len(), starts))
+}
+
+/// Fails if the tree of `events` is beyond `max_nodes` or `max_depth`,
+/// so that no thread is started for a document that would be rejected.
+///
+/// The open tags are tracked by name with the rules of [`Arena::push_event`].
+fn check_limits(events: &[(Event, Span)], options: &ParseOptions) -> Result<(), ParseError> {
+    if options.max_nodes.is_none() && options.max_depth.is_none() {
+        return Ok(());
+    }
+
+    let mut open = vec![ROOT_TAG_NAME];
+    let mut nodes = 0;
+    for (event, span) in events {
+        match event {
+            Event::StartTag(tag) => {
+                while open.len() > 1 && is_end_implied(open.last().unwrap(), tag.get_name()) {
+                    open.pop();
+                }
+                // the root is not an element, so the depth is the number of open tags
+                options.check_depth(open.len(), Some(*span))?;
+                if !tag.is_self_closing() && !tag.is_void_element() {
+                    open.push(tag.get_name());
+                }
+            }
+            Event::EndTag(name) => {
+                if let Some(open_idx) = open.iter().skip(1).rposition(|open| open.eq_ignore_ascii_case(name)) {
+                    open.truncate(open_idx + 1);
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        nodes += 1;
+        options.check_nodes(nodes, Some(*span))?;
+    }
+
+    Ok(())
+}
+
+/// Copies the node and its descendants into a [`sync::Node`], adding
+/// `body_children` to the children of `body`.
+fn to_sync_node(arena: &Arena, id: NodeId, body: NodeId, body_children: &mut Vec<sync::Node>) -> sync::Node {
+    let copy = |id| sync::Node::new_with_span(arena.get_payload(id).clone(), arena.get_span(id));
+    let root = copy(id);
+    let mut stack = vec![(id, root.clone())];
+    while let Some((id, node)) = stack.pop() {
+        for child in arena.children(id) {
+            let child_node = copy(child);
+            node.add_child_and_update_parent(&child_node);
+            stack.push((child, child_node));
+        }
+        if id == body {
+            for child in body_children.drain(..) {
+                node.add_child_and_update_parent(&child);
+            }
+        }
+    }
+
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_parallel_test() {
+        let mut html = String::from("<!doctype html><html><head><title>t</title></head><body>\n</i>text");
+        for i in 0..3000 {
+            html.push_str(&format!(r#"<div class="row{}"><p>{}<p><b>{}</div><ul><li>a<li>b</ul>"#, i % 7, i, i * 2));
+            if i % 500 == 0 {
+                html.push_str("<p>open");
+            }
+        }
+        html.push_str("</body><!-- end --></html>");
+
+        let arena = Arena::parse(&html).unwrap();
+        let root = parse_parallel(&html, &ParseOptions::default()).unwrap();
+        assert_eq!(root.to_node(), arena.to_node(arena.root()));
+
+        // the spans are the same too
+        let arena = Arena::from(&*root.to_node());
+        let expected = Arena::parse(&html).unwrap();
+        let spans = |arena: &Arena| arena.descendants(arena.root()).map(|id| arena.get_span(id)).collect::<Vec<_>>();
+        assert_eq!(spans(&arena), spans(&expected));
+
+        // without </body>, and without <body>
+        let html = html.replace("</body>", "");
+        assert_eq!(parse_parallel(&html, &ParseOptions::default()).unwrap().to_html(), Arena::parse(&html).unwrap().to_html(arena.root()));
+        let html = "<div><p>one<p>two</div>";
+        assert_eq!(parse_parallel(html, &ParseOptions::default()).unwrap().to_html(), html.replace("<p>two", "</p><p>two</p>"));
+        // end tags match whatever their case
+        assert_eq!(parse_parallel("<DIV>a</div>b", &ParseOptions::default()).unwrap().to_html(), "<DIV>a</DIV>b");
+    }
+}
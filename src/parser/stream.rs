@@ -0,0 +1,143 @@
+use std::io::{self, Read};
+
+use crate::dom::Node;
+use super::{parse_with_options, ParseError, ParseOptions};
+
+/// Parses a document that arrives in pieces, such as from a network socket.
+///
+/// The bytes are decoded as UTF-8 as they are fed, an invalid sequence is
+/// replaced with U+FFFD, and a character split between two pieces is kept
+/// until the rest of it arrives. The tree is built by [`Parser::finish`].
+///
+/// # Examples
+/// ```rust
+/// let mut parser = html::Parser::new();
+/// parser.feed(b"<p>Hello, ");
+/// parser.feed(b"world</p>");
+///
+/// let node = parser.finish().unwrap();
+/// assert_eq!(node.inner_text(), "Hello, world");
+/// ```
+#[derive(Debug, Default)]
+pub struct Parser {
+    options: ParseOptions,
+    doc: String,
+    // the first bytes of a character whose rest has not arrived
+    pending: Vec<u8>,
+}
+
+impl Parser {
+    pub fn new() -> Parser {
+        Parser::default()
+    }
+
+    pub fn with_options(options: ParseOptions) -> Parser {
+        Parser {
+            options,
+            ..Default::default()
+        }
+    }
+
+    /// Adds the next piece of the document.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+
+        let mut rest = &self.pending[..];
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(s) => {
+                    self.doc.push_str(s);
+                    rest = &[];
+                    break;
+                }
+                Err(e) => {
+                    let (valid, invalid) = rest.split_at(e.valid_up_to());
+                    // the bytes are valid up to here
+                    self.doc.push_str(std::str::from_utf8(valid).unwrap());
+
+                    match e.error_len() {
+                        Some(len) => {
+                            self.doc.push(char::REPLACEMENT_CHARACTER);
+                            rest = &invalid[len..];
+                        }
+                        None => {
+                            // the character continues in the next piece
+                            rest = invalid;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.pending = rest.to_vec();
+    }
+
+    /// Parses everything that has been fed and returns the Dom structure tree.
+    ///
+    /// # Errors
+    /// * [`ParseError`] as [`parse_with_options`] does.
+    pub fn finish(mut self) -> Result<Node, ParseError> {
+        if !self.pending.is_empty() {
+            // the document ends in the middle of a character
+            self.doc.push(char::REPLACEMENT_CHARACTER);
+        }
+
+        parse_with_options(&self.doc, &self.options)
+    }
+}
+
+/// Reads the whole document from `reader` and parses it.
+///
+/// # Errors
+/// * The error of `reader`.
+/// * [`ParseError`] wrapped in an [`io::Error`] of kind `InvalidData`.
+pub fn parse_reader<R: Read>(mut reader: R, options: &ParseOptions) -> io::Result<Node> {
+    let mut parser = Parser::with_options(options.clone());
+    let mut buf = [0; 8 * 1024];
+
+    loop {
+        let len = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(len) => len,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        parser.feed(&buf[..len]);
+    }
+
+    parser
+        .finish()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feed_test() {
+        let doc = "<p>こんにちは</p>".as_bytes();
+
+        // split in the middle of characters
+        let mut parser = Parser::new();
+        for chunk in doc.chunks(2) {
+            parser.feed(chunk);
+        }
+        let node = parser.finish().unwrap();
+        assert_eq!(node.inner_text(), "こんにちは");
+
+        let mut parser = Parser::new();
+        parser.feed(b"<p>a\xffb</p>");
+        assert_eq!(parser.finish().unwrap().inner_text(), "a\u{fffd}b");
+    }
+
+    #[test]
+    fn reader_test() {
+        let node = parse_reader(&b"<ul><li>one</li></ul>"[..], &ParseOptions::default()).unwrap();
+        assert_eq!(node.inner_text(), "one");
+
+        let err = parse_reader(&b"<p>a</p><!--"[..], &ParseOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
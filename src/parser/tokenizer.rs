@@ -0,0 +1,338 @@
+use crate::dom::{Atom, Doctype, Span, Tag};
+
+use super::diagnostic::is_invalid_char;
+use super::{entity, is_end_implied, is_foreign_root, is_integration_point};
+use super::{parse_cdata, parse_comment, parse_doctype, parse_raw_text, parse_tag, parse_text};
+use super::{Diagnostic, DiagnosticKind, Input, ParseError, ParseOptions};
+
+/// A piece of the document emitted by [`Tokenizer`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// `<tag attribute="value">` or `<tag />`
+    StartTag(Tag),
+    /// `</tag>`, with the tag name.
+    EndTag(String),
+    Text(String),
+    /// The content of a raw text element such as `<script>` or `<style>`,
+    /// which is not parsed as HTML.
+    RawText(String),
+    Comment(String),
+    /// `<!doctype html>`
+    Doctype(Doctype),
+}
+
+/// Parses the tag document into a flat sequence of [`Event`]s without
+/// building a tree.
+///
+/// The document is read lazily, one event at a time. Like
+/// [`parse_lenient`](crate::parse_lenient), it never fails and
+/// broken tags are emitted as text.
+///
+/// # Examples
+/// ```rust
+/// use html::Event;
+///
+/// let titles: Vec<String> = html::tokenize("<h1>a</h1><p>b</p><h1>c</h1>")
+///     .scan(false, |in_h1, event| {
+///         Some(match event {
+///             Event::StartTag(tag) => { *in_h1 = tag.get_name() == "h1"; None }
+///             Event::Text(text) if *in_h1 => Some(text),
+///             _ => None,
+///         })
+///     })
+///     .flatten()
+///     .collect();
+///
+/// assert_eq!(titles, ["a", "c"]);
+/// ```
+pub fn tokenize(doc: &str) -> Tokenizer {
+    let options = ParseOptions {
+        recover: true,
+        ..Default::default()
+    };
+
+    Tokenizer::new(doc, options)
+}
+
+/// An element that is open where the tokenizer is.
+#[derive(Debug)]
+struct OpenElement {
+    name: Atom,
+    // whether its content is foreign. `<svg>` is, `<foreignObject>` is not
+    foreign: bool,
+    // whether it switches between HTML and foreign content
+    switches: bool,
+}
+
+/// Iterator over the [`Event`]s of a document. Created by [`tokenize`].
+#[derive(Debug)]
+pub struct Tokenizer {
+    input: Input,
+    options: ParseOptions,
+    // the previous event was the start tag of a raw text element, so its text follows
+    raw_text_tag: Option<Tag>,
+    // the number of open `<pre>`, whose text keeps its whitespace
+    preformatted: usize,
+    // the elements that the tree builder has open, the innermost last, so
+    // that an end tag that closes `<svg>` ends its foreign content. `<p><svg></p>`
+    open: Vec<OpenElement>,
+    // `options` with the names kept as they are, for foreign content
+    foreign_options: ParseOptions,
+    // the problems found so far, and the cursor up to which the characters are checked
+    diagnostics: Vec<Diagnostic>,
+    checked: usize,
+}
+
+impl Tokenizer {
+    pub(crate) fn new(doc: &str, options: ParseOptions) -> Tokenizer {
+        let foreign_options = ParseOptions {
+            lowercase_names: false,
+            ..options.clone()
+        };
+
+        Tokenizer {
+            input: Input::new(doc),
+            options,
+            raw_text_tag: None,
+            preformatted: 0,
+            open: Vec::new(),
+            foreign_options,
+            diagnostics: Vec::new(),
+            checked: 0,
+        }
+    }
+
+    /// Returns the problems found in the events returned so far.
+    pub(crate) fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// Returns the next event and where it is in the document.
+    ///
+    /// # Errors
+    /// * [`ParseError`] if the event is broken and `options.recover` is false.
+    pub(crate) fn next_event(&mut self) -> Result<Option<(Event, Span)>, ParseError> {
+        let event = self.read_event()?;
+
+        // check the characters that were read
+        let input = &self.input;
+        for cursor in self.checked..input.get_cursor() {
+            let c = input.get_char(cursor)?;
+            if is_invalid_char(c) {
+                self.diagnostics.push(Diagnostic {
+                    kind: DiagnosticKind::InvalidCharacter(c),
+                    position: input.get_position(cursor),
+                });
+            }
+        }
+        self.checked = input.get_cursor();
+
+        Ok(event)
+    }
+
+    /// Reads the next event without checking its characters.
+    fn read_event(&mut self) -> Result<Option<(Event, Span)>, ParseError> {
+        let foreign = self.open.last().is_some_and(|open| open.foreign);
+        let input = &mut self.input;
+        // names in `<svg>` and `<math>` are case-sensitive. `viewBox`
+        let options = if foreign { &self.foreign_options } else { &self.options };
+
+        if let Some(tag) = self.raw_text_tag.take() {
+            let bgn = input.get_cursor();
+            let text = match parse_raw_text(input, tag.get_name()) {
+                Ok(text) => text,
+                Err(_) if options.recover => {
+                    // the rest of input is the raw text
+                    recover_string(input, bgn, input.len())
+                }
+                Err(e) => return Err(e),
+            };
+
+            // if the raw text element has text
+            if !text.is_empty() {
+                let event = if tag.is_raw_text_element() {
+                    Event::RawText(text)
+                } else if options.decode_entities {
+                    Event::Text(entity::decode(&text))
+                } else {
+                    Event::Text(text)
+                };
+
+                return Ok(Some((event, input.get_span(bgn, input.get_cursor()))));
+            }
+        }
+
+        while !input.is_end() {
+            // TODO debug
+            // println!("check: {}", input.get_char(input.get_cursor())?);
+
+            let bgn = input.get_cursor();
+            let event = if input.expect_str("<!--") {
+                // comment
+                match parse_comment(input) {
+                    Ok(text) => Event::Comment(text),
+                    Err(_) if options.recover => {
+                        // the rest of input is the comment
+                        Event::Comment(recover_string(input, bgn + "<!--".len(), input.len()))
+                    }
+                    Err(e) => return Err(e),
+                }
+            } else if foreign && input.expect_str("<![CDATA[") {
+                // CDATA section, which is only text in foreign content
+                match parse_cdata(input) {
+                    Ok(text) if text.is_empty() => continue,
+                    Ok(text) => Event::Text(text),
+                    Err(_) if options.recover => Event::Text(recover_string(input, bgn + "<![CDATA[".len(), input.len())),
+                    Err(e) => return Err(e),
+                }
+            } else if input.expect_str_insensitive("<!doctype") {
+                // doctype
+                match parse_doctype(input) {
+                    Ok(doctype) => Event::Doctype(doctype),
+                    Err(_) if options.recover => Event::Text(recover_tag(input, bgn)),
+                    Err(e) => return Err(e),
+                }
+            } else if input.expect('<') {
+                // tag
+                let diagnostics_len = self.diagnostics.len();
+                match parse_tag(input, options, &mut self.diagnostics) {
+                    Ok(tag) if tag.is_terminator() => {
+                        if tag.get_name().eq_ignore_ascii_case("pre") {
+                            self.preformatted = self.preformatted.saturating_sub(1);
+                        }
+                        // an end tag closes the elements opened after its start tag, as in the tree builder
+                        let name = tag.get_name();
+                        let open_idx = self.open.iter().rposition(|open| open.name.eq_ignore_ascii_case(name));
+                        match open_idx.and_then(|open_idx| self.open.drain(open_idx..).next()) {
+                            // the end tag is named as its start tag was, which may be in other case.
+                            // `<foreignObject>` and `</foreignobject>` with `lowercase_names`
+                            Some(open) if open.switches => Event::EndTag(open.name.to_string()),
+                            _ => Event::EndTag(name.to_string()),
+                        }
+                    }
+                    Ok(tag) => {
+                        if tag.get_name().eq_ignore_ascii_case("pre") && !tag.is_self_closing() {
+                            self.preformatted += 1;
+                        }
+
+                        // close the elements whose end tag is implied. `<li>one<li>two`
+                        while self.open.last().is_some_and(|open| is_end_implied(&open.name, tag.get_name())) {
+                            self.open.pop();
+                        }
+                        if !tag.is_self_closing() && !tag.is_void_element() {
+                            let name = tag.get_name();
+                            let switches = is_foreign_root(name) || foreign && is_integration_point(name);
+                            self.open.push(OpenElement {
+                                name: tag.get_name_atom().clone(),
+                                foreign: if switches { is_foreign_root(name) } else { foreign },
+                                switches,
+                            });
+                        }
+
+                        // if the tag is raw text element, which foreign content has none of
+                        if !tag.is_self_closing()
+                            && !foreign
+                            && (tag.is_raw_text_element() || tag.is_escapable_raw_text_element())
+                        {
+                            self.raw_text_tag = Some(tag.clone());
+                        }
+                        Event::StartTag(tag)
+                    }
+                    Err(_) if options.recover => {
+                        // keep the broken tag as text. `<>` or `<tag` at the end of input
+                        self.diagnostics.truncate(diagnostics_len);
+                        Event::Text(recover_tag(input, bgn))
+                    }
+                    Err(e) => return Err(e),
+                }
+            } else {
+                // text
+                let text = parse_text(input, options, self.preformatted > 0)?;
+                if text.is_empty() {
+                    // the whitespace between tags is dropped. `</li>\n  <li>`
+                    continue;
+                }
+                Event::Text(text)
+            };
+
+            return Ok(Some((event, input.get_span(bgn, input.get_cursor()))));
+        }
+
+        Ok(None)
+    }
+}
+
+impl Iterator for Tokenizer {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_event() {
+            Ok(event) => event.map(|(event, _)| event),
+            Err(_) => None,
+        }
+    }
+}
+
+/// Returns from `bgn` to `end` as a String and moves the cursor to `end`.
+/// Used to keep what could not be parsed in the recover mode.
+fn recover_string(input: &mut Input, bgn: usize, end: usize) -> String {
+    input.set_cursor(end);
+    input.get_string(bgn, end).unwrap_or_default()
+}
+
+/// Returns the broken tag from `bgn` to the next '>' or the end of input.
+fn recover_tag(input: &mut Input, bgn: usize) -> String {
+    input.set_cursor(bgn);
+    let end = match input.find('>') {
+        Some(cursor) => cursor + 1,
+        None => input.len(),
+    };
+
+    recover_string(input, bgn, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize_test() {
+        let html = r#"<!DOCTYPE html><p class="x">a<br/>b</p><!-- c --><script>1 < 2</script>"#;
+        let events: Vec<Event> = tokenize(html).collect();
+
+        let mut p = Tag::new("p");
+        p.set_attribute("class", "x");
+        let mut br = Tag::new("br");
+        br.set_self_closing(true);
+
+        assert_eq!(events, [
+            Event::Doctype(Doctype::new("html")),
+            Event::StartTag(p),
+            Event::Text(String::from("a")),
+            Event::StartTag(br),
+            Event::Text(String::from("b")),
+            Event::EndTag(String::from("p")),
+            Event::Comment(String::from(" c ")),
+            Event::StartTag(Tag::new("script")),
+            Event::RawText(String::from("1 < 2")),
+            Event::EndTag(String::from("script")),
+        ]);
+    }
+
+    #[test]
+    fn raw_text_test() {
+        let html = "<style>a > b { color: red }</style><textarea><b>x</b> &amp;</TEXTAREA><title></title>";
+        let events: Vec<Event> = tokenize(html).collect();
+
+        assert_eq!(events, [
+            Event::StartTag(Tag::new("style")),
+            Event::RawText(String::from("a > b { color: red }")),
+            Event::EndTag(String::from("style")),
+            Event::StartTag(Tag::new("textarea")),
+            Event::Text(String::from("<b>x</b> &")),
+            Event::EndTag(String::from("TEXTAREA")),
+            Event::StartTag(Tag::new("title")),
+            Event::EndTag(String::from("title")),
+        ]);
+    }
+}
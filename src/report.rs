@@ -0,0 +1,92 @@
+//! Reports of how the classes and ids of a tree are used, to compare with
+//! the selectors of a style sheet and find the rules that match nothing.
+//!
+//! # Examples
+//! ```rust
+//! let root = html::parse(r#"<p id="a" class="note">1</p><div class="note wide">2</div><b id="a">3</b>"#).unwrap();
+//! let classes = html::report::class_usage(&root);
+//!
+//! assert_eq!(classes["note"].count, 2);
+//! assert_eq!(classes["wide"].elements["div"], 1);
+//!
+//! let ids = html::report::id_usage(&root);
+//! // an id used more than once
+//! assert_eq!(ids["a"].count, 2);
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::dom::NodeData;
+
+/// How often a class or an id is used.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// The elements that have it.
+    pub count: usize,
+    /// The elements with each lowercased name that have it.
+    pub elements: BTreeMap<String, usize>,
+}
+
+impl Usage {
+    fn add(&mut self, name: &str) {
+        self.count += 1;
+        *self.elements.entry(name.to_ascii_lowercase()).or_default() += 1;
+    }
+}
+
+/// Returns the classes of the descendants of `root`, as they are written,
+/// with how often each is used. A class written twice in the same `class`
+/// attribute counts once.
+pub fn class_usage(root: &NodeData) -> BTreeMap<String, Usage> {
+    let mut usage: BTreeMap<String, Usage> = BTreeMap::new();
+    for node in root.descendants() {
+        let tag = match node.get_tag() {
+            Some(tag) => tag,
+            None => continue,
+        };
+        let mut classes = node.classes();
+        classes.sort_unstable();
+        classes.dedup();
+        for class in classes {
+            usage.entry(class).or_default().add(tag.get_name());
+        }
+    }
+    usage
+}
+
+/// Returns the ids of the descendants of `root` with how often each is
+/// used. An id with a `count` above 1 is a duplicate. Empty ids are left
+/// out.
+pub fn id_usage(root: &NodeData) -> BTreeMap<String, Usage> {
+    let mut usage: BTreeMap<String, Usage> = BTreeMap::new();
+    for node in root.descendants() {
+        let (tag, id) = match (node.get_tag(), node.attr("id")) {
+            (Some(tag), Some(id)) if !id.is_empty() => (tag, id),
+            _ => continue,
+        };
+        usage.entry(id).or_default().add(tag.get_name());
+    }
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn usage_test() {
+        let root = parse(concat!(
+            r#"<DIV class="Box  box box"><p class=" box "  id="x">a</p><!-- <p class="c" id="y"> -->"#,
+            r#"<SPAN id="x" class="">b</SPAN><i id="">c</i><template><p class="box" id="y">d</p></template></DIV>"#,
+        ))
+        .unwrap();
+        let summary = |usage: BTreeMap<String, Usage>| -> Vec<String> {
+            usage.iter().map(|(name, usage)| format!("{} {} {:?}", name, usage.count, usage.elements)).collect()
+        };
+
+        assert_eq!(summary(class_usage(&root)), [r#"Box 1 {"div": 1}"#, r#"box 3 {"div": 1, "p": 2}"#]);
+        assert_eq!(summary(id_usage(&root)), [r#"x 2 {"p": 1, "span": 1}"#, r#"y 1 {"p": 1}"#]);
+        assert!(class_usage(&parse("<p>a</p>").unwrap()).is_empty());
+    }
+}
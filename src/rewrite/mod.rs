@@ -0,0 +1,285 @@
+//! Transforming a tree with rules.
+//!
+//! [`Rules`] pairs matchers, by tag name, [selector](crate::selector) or
+//! predicate, with callbacks that may change the matched node and return
+//! what to do with it: keep it, remove it, replace it with its children,
+//! or replace it with other nodes.
+//!
+//! [`Rewriter`] changes start tags as the document streams through it,
+//! without building a tree.
+//!
+//! [`modernize`] rewrites obsolete presentational markup, such as `<font>`
+//! and `bgcolor`, into inline styles.
+//!
+//! # Examples
+//! ```rust
+//! use html::dom::{Node, Payload, Tag};
+//! use html::rewrite::{Action, Rules};
+//!
+//! let root = html::parse(r#"<nav>menu</nav><p>Hi <span>there</span>, <b>see</b> also <a href="/x">x</a></p>"#).unwrap();
+//!
+//! let root = Rules::new()
+//!     .on_name("nav", |_| Action::Remove)
+//!     .on_name("span", |_| Action::Unwrap)
+//!     .on_name("b", |node| {
+//!         let strong = Node::new(Payload::Tag(Tag::new("strong")));
+//!         for child in node.children() {
+//!             strong.add_child_and_update_parent(&Node::from(child));
+//!         }
+//!         Action::Replace(vec![strong])
+//!     })
+//!     .on("a[href]", |node| {
+//!         if let Payload::Tag(tag) = &mut *node.payload_mut() {
+//!             tag.set_attribute("rel", "nofollow");
+//!         }
+//!         Action::Keep
+//!     })
+//!     .unwrap()
+//!     .run(root);
+//!
+//! assert_eq!(root.to_html(), r#"<p>Hi there, <strong>see</strong> also <a href="/x" rel="nofollow">x</a></p>"#);
+//! ```
+
+use std::rc::Rc;
+
+use crate::dom::{Node, NodeData, Payload};
+use crate::selector::{Selector, SelectorError};
+
+mod modernize;
+mod stream;
+
+pub use modernize::{modernize, modernize_with_options, ModernizeOptions};
+pub use stream::{Element, Rewriter};
+
+/// What to do with a node after a rule matched it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Keep the node and go on with the next rule that matches it.
+    Keep,
+    /// Remove the node and its descendants.
+    Remove,
+    /// Replace the node with its children. `<span>a</span>` becomes `a`
+    Unwrap,
+    /// Replace the node with these nodes, which are not matched by the rules.
+    Replace(Vec<Node>),
+}
+
+/// The order in which [`Rules::run`] visits the nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Order {
+    /// Each node before its children, which are the children it has after
+    /// the rules ran on it. The children of an unwrapped node are visited.
+    #[default]
+    TopDown,
+    /// Each node after its children, so that a rule sees the children
+    /// already transformed.
+    BottomUp,
+}
+
+enum Matcher<'a> {
+    Name(String),
+    Selector(Selector),
+    Predicate(Box<dyn Fn(&NodeData) -> bool + 'a>),
+}
+
+impl Matcher<'_> {
+    fn matches(&self, node: &NodeData) -> bool {
+        match self {
+            Matcher::Name(name) => match &*node.get_payload() {
+                Payload::Tag(tag) => !node.is_document_root() && tag.get_name().eq_ignore_ascii_case(name),
+                _ => false,
+            },
+            Matcher::Selector(selector) => selector.matches(node),
+            Matcher::Predicate(predicate) => predicate(node),
+        }
+    }
+}
+
+struct Rule<'a> {
+    matcher: Matcher<'a>,
+    callback: Box<dyn FnMut(&Node) -> Action + 'a>,
+}
+
+/// A set of rules run over a tree. See the [module](self) documentation.
+///
+/// The rules that match a node are called in the order they were added
+/// until one returns an [`Action`] other than [`Action::Keep`].
+#[derive(Default)]
+pub struct Rules<'a> {
+    rules: Vec<Rule<'a>>,
+    order: Order,
+}
+
+impl<'a> Rules<'a> {
+    /// Creates an empty set of rules that runs [`Order::TopDown`].
+    pub fn new() -> Rules<'a> {
+        Rules::default()
+    }
+
+    /// Sets the order in which the nodes are visited.
+    pub fn order(mut self, order: Order) -> Rules<'a> {
+        self.order = order;
+        self
+    }
+
+    /// Adds a rule for the tags named `name`, without case.
+    pub fn on_name<F>(self, name: &str, callback: F) -> Rules<'a>
+    where
+        F: FnMut(&Node) -> Action + 'a,
+    {
+        self.push(Matcher::Name(name.to_string()), callback)
+    }
+
+    /// Adds a rule for the tags that match `selector`.
+    ///
+    /// # Errors
+    /// * [`SelectorError`] if `selector` cannot be parsed.
+    pub fn on<F>(self, selector: &str, callback: F) -> Result<Rules<'a>, SelectorError>
+    where
+        F: FnMut(&Node) -> Action + 'a,
+    {
+        let selector = Selector::parse(selector)?;
+        Ok(self.push(Matcher::Selector(selector), callback))
+    }
+
+    /// Adds a rule for the nodes that `predicate` returns true for, which
+    /// may be any node, not only tags.
+    pub fn on_match<P, F>(self, predicate: P, callback: F) -> Rules<'a>
+    where
+        P: Fn(&NodeData) -> bool + 'a,
+        F: FnMut(&Node) -> Action + 'a,
+    {
+        self.push(Matcher::Predicate(Box::new(predicate)), callback)
+    }
+
+    fn push<F>(mut self, matcher: Matcher<'a>, callback: F) -> Rules<'a>
+    where
+        F: FnMut(&Node) -> Action + 'a,
+    {
+        self.rules.push(Rule {
+            matcher,
+            callback: Box::new(callback),
+        });
+        self
+    }
+
+    /// Runs the rules over the descendants of `root` and returns it.
+    /// `root` itself is not matched.
+    pub fn run(&mut self, root: Node) -> Node {
+        match self.order {
+            Order::TopDown => {
+                let mut stack: Vec<Rc<NodeData>> = root.children().into_iter().rev().collect();
+                while let Some(node) = stack.pop() {
+                    let node = Node::from(node);
+                    let next = match self.apply(&node) {
+                        Action::Keep => node.children(),
+                        action => perform(&node, action),
+                    };
+                    stack.extend(next.into_iter().rev());
+                }
+            }
+            Order::BottomUp => {
+                // each node is pushed again with true once its children are pushed
+                let mut stack: Vec<(Rc<NodeData>, bool)> =
+                    root.get_children().iter().rev().map(|child| (Rc::clone(child), false)).collect();
+                while let Some((node, children_done)) = stack.pop() {
+                    if !children_done {
+                        stack.push((Rc::clone(&node), true));
+                        stack.extend(node.get_children().iter().rev().map(|child| (Rc::clone(child), false)));
+                        continue;
+                    }
+
+                    let node = Node::from(node);
+                    let action = self.apply(&node);
+                    perform(&node, action);
+                }
+            }
+        }
+
+        root
+    }
+
+    /// Calls the rules that match `node` and returns the first action that is not `Keep`.
+    fn apply(&mut self, node: &Node) -> Action {
+        for rule in &mut self.rules {
+            if rule.matcher.matches(node) {
+                match (rule.callback)(node) {
+                    Action::Keep => {}
+                    action => return action,
+                }
+            }
+        }
+
+        Action::Keep
+    }
+}
+
+/// Performs `action` on `node` and returns the nodes that took its place
+/// and are to be visited. Does nothing if `node` has no parent.
+fn perform(node: &Node, action: Action) -> Vec<Rc<NodeData>> {
+    let parent = match node.get_parent() {
+        Some(parent) => Node::from(parent),
+        None => return Vec::new(),
+    };
+
+    let (replacements, visit) = match action {
+        Action::Keep => return node.children(),
+        Action::Remove => (Vec::new(), false),
+        Action::Unwrap => (node.get_children().iter().cloned().map(Node::from).collect(), true),
+        Action::Replace(nodes) => (nodes, false),
+    };
+
+    let index = parent.get_children().iter().position(|child| Rc::ptr_eq(child, &node.get_copy_of_internal_arc()));
+    if let Some(index) = index {
+        for (i, replacement) in replacements.iter().enumerate() {
+            parent.insert_child_at(index + i, replacement);
+        }
+    }
+    parent.remove_child(node);
+
+    match visit {
+        true => replacements.iter().map(Node::get_copy_of_internal_arc).collect(),
+        false => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::Tag;
+
+    #[test]
+    fn rewrite_test() {
+        let html = "<div class=\"ad\">ad</div><ul><li>a <i>b</i></li><li></li></ul>";
+
+        let mut visited = Vec::new();
+        let root = Rules::new()
+            .on(".ad", |_| Action::Remove)
+            .unwrap()
+            .on_name("li", |node| match node.get_children().is_empty() {
+                true => Action::Remove,
+                false => Action::Keep,
+            })
+            .on_name("LI", |_| Action::Unwrap)
+            .on_match(|node| matches!(&*node.get_payload(), Payload::Tag(_)), |node| {
+                visited.push(node.to_html());
+                Action::Keep
+            })
+            .run(crate::parse(html).unwrap());
+        assert_eq!(root.to_html(), "<ul>a <i>b</i></ul>");
+        assert_eq!(visited, ["<ul><li>a <i>b</i></li><li></li></ul>", "<i>b</i>"]);
+
+        // bottom-up, a rule sees the children transformed
+        let mut visited = Vec::new();
+        let root = Rules::new()
+            .order(Order::BottomUp)
+            .on_name("i", |_| Action::Replace(vec![Node::new(Payload::Tag(Tag::new("em")))]))
+            .on_name("li", |node| {
+                visited.push(node.to_html());
+                Action::Keep
+            })
+            .run(crate::parse(html).unwrap());
+        assert_eq!(root.to_html(), "<div class=\"ad\">ad</div><ul><li>a <em></em></li><li></li></ul>");
+        assert_eq!(visited, ["<li>a <em></em></li>", "<li></li>"]);
+    }
+}
@@ -0,0 +1,247 @@
+use crate::dom::{NodeData, Payload, StyleDeclarations, Tag};
+
+/// The `font-size` of `<font size>` from 1 to 7.
+const FONT_SIZES: [&str; 7] = ["x-small", "small", "medium", "large", "x-large", "xx-large", "xxx-large"];
+
+/// Elements that `align="left"` and `align="right"` float rather than
+/// aligning their content.
+const FLOATED_ELEMENTS: [&str; 5] = ["embed", "iframe", "img", "object", "table"];
+
+/// Options to control what [`modernize_with_options`] rewrites.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModernizeOptions {
+    /// Replaces the obsolete elements `<font>`, `<center>`, `<strike>`,
+    /// `<tt>` and `<big>` with `<span>`, `<div>` and `<s>`, with the styles
+    /// they were shown with.
+    /// Default: true
+    pub elements: bool,
+    /// Moves the presentational attributes `bgcolor`, `align`, `valign` and
+    /// the `text` of `<body>` into the `style` attribute.
+    /// Default: true
+    pub attributes: bool,
+    /// Keeps the attributes that were moved into `style`, for the email
+    /// clients that ignore styles.
+    /// Default: false
+    pub keep_attributes: bool,
+    /// Renames `<b>` to `<strong>` and `<i>` to `<em>`.
+    /// Default: false
+    pub semantic_tags: bool,
+}
+
+impl Default for ModernizeOptions {
+    fn default() -> ModernizeOptions {
+        ModernizeOptions {
+            elements: true,
+            attributes: true,
+            keep_attributes: false,
+            semantic_tags: false,
+        }
+    }
+}
+
+/// Rewrites the obsolete presentational elements and attributes of the
+/// descendants of `root` into elements and inline styles that are not.
+/// A declaration already in `style` is not replaced.
+///
+/// The tags that are not rewritten keep their original markup.
+///
+/// # Examples
+/// ```rust
+/// let root = html::parse(r#"<center><font color="red" size="5">Sale</font></center><p align="right">x</p>"#).unwrap();
+/// html::rewrite::modernize(&root);
+///
+/// assert_eq!(
+///     root.to_html(),
+///     concat!(
+///         r#"<div style="text-align: center"><span style="color: red; font-size: x-large">Sale</span></div>"#,
+///         r#"<p style="text-align: right">x</p>"#,
+///     )
+/// );
+/// ```
+pub fn modernize(root: &NodeData) {
+    modernize_with_options(root, &ModernizeOptions::default())
+}
+
+/// Same as [`modernize`], but with options.
+pub fn modernize_with_options(root: &NodeData, options: &ModernizeOptions) {
+    for node in root.descendants() {
+        let mut tag = match &*node.get_payload() {
+            Payload::Tag(tag) if !tag.is_terminator() => tag.clone(),
+            _ => continue,
+        };
+        if modernize_tag(&mut tag, options) {
+            node.set_payload(Payload::Tag(tag));
+        }
+    }
+}
+
+/// Rewrites `tag` and returns true if it changed.
+fn modernize_tag(tag: &mut Tag, options: &ModernizeOptions) -> bool {
+    let name = tag.get_name().to_ascii_lowercase();
+    let mut style = tag.style().unwrap_or_default();
+    let mut changed = false;
+
+    if options.elements {
+        let new_name = match name.as_str() {
+            "font" => {
+                if let Some(color) = tag.remove_attribute("color") {
+                    add_declaration(&mut style, "color", color.as_str().trim());
+                }
+                if let Some(face) = tag.remove_attribute("face") {
+                    add_declaration(&mut style, "font-family", face.as_str().trim());
+                }
+                if let Some(size) = tag.remove_attribute("size").and_then(|size| font_size(size.as_str())) {
+                    add_declaration(&mut style, "font-size", size);
+                }
+                Some("span")
+            }
+            "center" => {
+                add_declaration(&mut style, "text-align", "center");
+                Some("div")
+            }
+            "strike" => Some("s"),
+            "tt" => {
+                add_declaration(&mut style, "font-family", "monospace");
+                Some("span")
+            }
+            "big" => {
+                add_declaration(&mut style, "font-size", "larger");
+                Some("span")
+            }
+            _ => None,
+        };
+        if let Some(new_name) = new_name {
+            tag.set_name(new_name);
+            changed = true;
+        }
+    }
+
+    if options.semantic_tags {
+        let new_name = match name.as_str() {
+            "b" => Some("strong"),
+            "i" => Some("em"),
+            _ => None,
+        };
+        if let Some(new_name) = new_name {
+            tag.set_name(new_name);
+            changed = true;
+        }
+    }
+
+    if options.attributes {
+        let mut moved = Vec::new();
+        if let Some(color) = tag.get_attribute_value("bgcolor") {
+            add_declaration(&mut style, "background-color", color.trim());
+            moved.push("bgcolor");
+        }
+        if let Some(valign) = tag.get_attribute_value("valign") {
+            add_declaration(&mut style, "vertical-align", &valign.trim().to_ascii_lowercase());
+            moved.push("valign");
+        }
+        if let Some(color) = tag.get_attribute_value("text").filter(|_| name == "body") {
+            add_declaration(&mut style, "color", color.trim());
+            moved.push("text");
+        }
+        if let Some(align) = tag.get_attribute_value("align") {
+            let align = align.trim().to_ascii_lowercase();
+            match (FLOATED_ELEMENTS.contains(&name.as_str()), align.as_str()) {
+                (true, "left" | "right") => add_declaration(&mut style, "float", &align),
+                (true, "center") if name == "table" => {
+                    add_declaration(&mut style, "margin-left", "auto");
+                    add_declaration(&mut style, "margin-right", "auto");
+                }
+                (true, _) => add_declaration(&mut style, "vertical-align", &align),
+                (false, _) => add_declaration(&mut style, "text-align", &align),
+            }
+            moved.push("align");
+        }
+
+        if !moved.is_empty() {
+            changed = true;
+            if !options.keep_attributes {
+                for attribute in moved {
+                    tag.remove_attribute(attribute);
+                }
+            }
+        }
+    }
+
+    if changed {
+        tag.set_style(&style);
+    }
+    changed
+}
+
+/// Sets `name` unless `style` has it or `value` is empty.
+fn add_declaration(style: &mut StyleDeclarations, name: &str, value: &str) {
+    if !value.is_empty() && !style.contains(name) {
+        style.set(name, value);
+    }
+}
+
+/// Returns the `font-size` of `<font size>`, which is 1 to 7 or relative
+/// to 3 with a sign. `+1`
+fn font_size(size: &str) -> Option<&'static str> {
+    let size = size.trim();
+    let value: i32 = size.trim_start_matches('+').parse().ok()?;
+    let value = match size.starts_with(['+', '-']) {
+        true => 3 + value,
+        false => value,
+    };
+    Some(FONT_SIZES[value.clamp(1, 7) as usize - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, parse_with_options, ParseOptions};
+
+    #[test]
+    fn modernize_test() {
+        let html = concat!(
+            r##"<body bgcolor="white" text="#333"><center>c</center>"##,
+            r#"<font face="Arial" size="-1" style="color: blue" color="red">f</font><font size="x">g</font>"#,
+            r#"<strike>s</strike><tt>t</tt><big>b</big><b>bold</b><i>it</i>"#,
+            r##"<table align="center" bgcolor="#eee"><tr valign="TOP"><td align="Right">1</td></tr></table>"##,
+            r#"<img align="left" src="a.png"><img align="middle" src="b.png"><p align=justify>p</p></body>"#,
+        );
+
+        let root = parse(html).unwrap();
+        modernize(&root);
+        assert_eq!(
+            root.to_html(),
+            concat!(
+                r##"<body style="background-color: white; color: #333"><div style="text-align: center">c</div>"##,
+                r#"<span style="color: blue; font-family: Arial; font-size: small">f</span><span>g</span>"#,
+                r#"<s>s</s><span style="font-family: monospace">t</span><span style="font-size: larger">b</span>"#,
+                r#"<b>bold</b><i>it</i>"#,
+                r##"<table style="background-color: #eee; margin-left: auto; margin-right: auto">"##,
+                r#"<tr style="vertical-align: top"><td style="text-align: right">1</td></tr></table>"#,
+                r#"<img src="a.png" style="float: left"><img src="b.png" style="vertical-align: middle">"#,
+                r#"<p style="text-align: justify">p</p></body>"#,
+            )
+        );
+
+        let root = parse(html).unwrap();
+        let options = ModernizeOptions {
+            elements: false,
+            keep_attributes: true,
+            semantic_tags: true,
+            ..Default::default()
+        };
+        modernize_with_options(&root, &options);
+        let td = root.query_name("td").next().unwrap();
+        assert_eq!(td.to_html(), r#"<td align="Right" style="text-align: right">1</td>"#);
+        assert_eq!(root.query_name("center").count(), 1);
+        assert_eq!(root.query_name("strong").next().unwrap().to_html(), "<strong>bold</strong>");
+        assert_eq!(root.query_name("em").count(), 1);
+
+        // the tags that are not rewritten are written as they were
+        let options = ParseOptions { lossless: true, ..Default::default() };
+        let root = parse_with_options("<P CLASS=x>a</P><font color=red>b</font>", &options).unwrap();
+        modernize(&root);
+        assert_eq!(root.to_html(), r#"<P CLASS=x>a</P><span style="color: red">b</span>"#);
+        assert_eq!(font_size("+2"), Some("x-large"));
+        assert_eq!(font_size("9"), Some("xxx-large"));
+    }
+}
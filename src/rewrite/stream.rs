@@ -0,0 +1,377 @@
+use std::io::{self, Read, Write};
+
+use crate::dom::{write_start_tag, Tag};
+use crate::parser::{Event, ParseOptions, Tokenizer};
+use crate::selector::{Selector, SelectorError};
+
+/// A start tag matched by a [`Rewriter`] handler.
+///
+/// Changes to the tag are written in place of the original start tag,
+/// which is otherwise written as it was.
+#[derive(Debug)]
+pub struct Element {
+    tag: Tag,
+    before: String,
+    prepend: String,
+}
+
+impl Element {
+    pub fn tag_name(&self) -> &str {
+        self.tag.get_name()
+    }
+
+    pub fn get_attribute(&self, name: &str) -> Option<&str> {
+        self.tag.get_attribute(name).map(|value| value.as_str())
+    }
+
+    pub fn has_attribute(&self, name: &str) -> bool {
+        self.tag.has_attribute(name)
+    }
+
+    pub fn set_attribute(&mut self, name: &str, value: &str) {
+        self.tag.set_attribute(name, value);
+    }
+
+    pub fn remove_attribute(&mut self, name: &str) {
+        self.tag.remove_attribute(name);
+    }
+
+    /// Inserts `html` before the start tag, as it is.
+    pub fn before(&mut self, html: &str) {
+        self.before.push_str(html);
+    }
+
+    /// Inserts `html` after the start tag, as it is, which makes it the
+    /// first content of the element.
+    pub fn prepend(&mut self, html: &str) {
+        self.prepend.push_str(html);
+    }
+}
+
+type Handler<'a> = Box<dyn FnMut(&mut Element) + 'a>;
+
+/// Rewrites start tags while the document streams from a reader to a
+/// writer, without building a tree.
+///
+/// Only the longest tag or comment is kept in memory, so documents of any
+/// size are rewritten in constant memory. Everything but the start tags
+/// that handlers change is written byte for byte as it was read, broken
+/// markup included. The content of `<script>`, `<style>`, `<textarea>` and
+/// `<title>` is text, so tags in it are not matched.
+///
+/// # Examples
+/// ```rust
+/// use html::rewrite::Rewriter;
+///
+/// let html = r#"<p>See <a href="https://a.com">a</a> and <a name="x">x</a></p>"#;
+/// let mut output = Vec::new();
+/// Rewriter::new()
+///     .on("a[href]", |el| el.set_attribute("rel", "nofollow"))
+///     .transform(html.as_bytes(), &mut output)
+///     .unwrap();
+///
+/// assert_eq!(
+///     String::from_utf8(output).unwrap(),
+///     r#"<p>See <a href="https://a.com" rel="nofollow">a</a> and <a name="x">x</a></p>"#
+/// );
+/// ```
+#[derive(Default)]
+pub struct Rewriter<'a> {
+    handlers: Vec<(Result<Selector, SelectorError>, Handler<'a>)>,
+}
+
+/// The part of the document the scanner is in.
+enum State {
+    Data,
+    /// In the text of a raw text element with this lowercase name.
+    RawText(String),
+}
+
+/// A piece of markup that starts with '<'.
+enum Token {
+    StartTag,
+    /// Anything else that is written as it is: end tags, comments,
+    /// doctypes, or '<' that starts no tag.
+    Other,
+}
+
+impl<'a> Rewriter<'a> {
+    pub fn new() -> Rewriter<'a> {
+        Rewriter::default()
+    }
+
+    /// Adds a handler for the start tags that match `selector`. Handlers are
+    /// called in the order they were added.
+    ///
+    /// An invalid selector is reported by [`transform`](Rewriter::transform),
+    /// and so is a selector with pseudo-classes, which need the tree.
+    pub fn on<F>(mut self, selector: &str, handler: F) -> Rewriter<'a>
+    where
+        F: FnMut(&mut Element) + 'a,
+    {
+        self.handlers.push((Selector::parse_start_tag(selector), Box::new(handler)));
+        self
+    }
+
+    /// Reads the document from `reader` and writes it rewritten to `writer`.
+    ///
+    /// # Errors
+    /// * The error of `reader` or `writer`.
+    /// * [`SelectorError`] wrapped in an [`io::Error`] of kind `InvalidInput`
+    ///   if a selector cannot be parsed, before anything is read.
+    pub fn transform<R: Read, W: Write>(&mut self, mut reader: R, mut writer: W) -> io::Result<()> {
+        if let Some(Err(e)) = self.handlers.iter().map(|(selector, _)| selector).find(|selector| selector.is_err()) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, e.clone()));
+        }
+
+        let mut state = State::Data;
+        let mut buf = Vec::new();
+        let mut chunk = [0; 8 * 1024];
+        loop {
+            let len = match reader.read(&mut chunk) {
+                Ok(len) => len,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            buf.extend_from_slice(&chunk[..len]);
+
+            let eof = len == 0;
+            let consumed = self.process(&buf, eof, &mut state, &mut writer)?;
+            buf.drain(..consumed);
+            if eof {
+                break;
+            }
+        }
+
+        writer.flush()
+    }
+
+    /// Writes the complete pieces at the start of `buf` and returns their length.
+    /// If `eof` is true, `buf` is the rest of the document and is written whole.
+    fn process<W: Write>(&mut self, buf: &[u8], eof: bool, state: &mut State, writer: &mut W) -> io::Result<usize> {
+        let mut i = 0;
+        while i < buf.len() {
+            if let State::RawText(name) = state {
+                match find_end_tag(&buf[i..], name, eof) {
+                    Some(Ok(len)) => {
+                        writer.write_all(&buf[i..i + len])?;
+                        i += len;
+                        *state = State::Data;
+                    }
+                    Some(Err(len)) => {
+                        // the rest may be the start of the end tag
+                        writer.write_all(&buf[i..i + len])?;
+                        return Ok(i + len);
+                    }
+                    None => {
+                        writer.write_all(&buf[i..])?;
+                        return Ok(buf.len());
+                    }
+                }
+                continue;
+            }
+
+            // text
+            let lt = buf[i..].iter().position(|&b| b == b'<').map_or(buf.len(), |len| i + len);
+            writer.write_all(&buf[i..lt])?;
+            i = lt;
+            if i == buf.len() {
+                break;
+            }
+
+            let (token, len) = match scan_markup(&buf[i..], eof) {
+                Some(found) => found,
+                None => return Ok(i),
+            };
+            match token {
+                Token::StartTag => self.rewrite_start_tag(&buf[i..i + len], state, writer)?,
+                Token::Other => writer.write_all(&buf[i..i + len])?,
+            }
+            i += len;
+        }
+
+        Ok(i)
+    }
+
+    fn rewrite_start_tag<W: Write>(&mut self, markup: &[u8], state: &mut State, writer: &mut W) -> io::Result<()> {
+        let text = String::from_utf8_lossy(markup);
+        let options = ParseOptions {
+            recover: true,
+            ..Default::default()
+        };
+        let tag = match Tokenizer::new(&text, options).next() {
+            Some(Event::StartTag(tag)) => tag,
+            _ => return writer.write_all(markup),
+        };
+
+        if !tag.is_self_closing() && (tag.is_raw_text_element() || tag.is_escapable_raw_text_element()) {
+            *state = State::RawText(tag.get_name().to_ascii_lowercase());
+        }
+
+        let mut element = Element {
+            tag: tag.clone(),
+            before: String::new(),
+            prepend: String::new(),
+        };
+        for (selector, handler) in &mut self.handlers {
+            if selector.as_ref().is_ok_and(|selector| selector.matches_tag(&element.tag)) {
+                handler(&mut element);
+            }
+        }
+
+        writer.write_all(element.before.as_bytes())?;
+        if element.tag == tag {
+            writer.write_all(markup)?;
+        } else {
+            let mut html = String::new();
+            write_start_tag(&mut html, &element.tag);
+            writer.write_all(html.as_bytes())?;
+        }
+        writer.write_all(element.prepend.as_bytes())
+    }
+}
+
+/// Returns the kind and the length of the markup at the start of `buf`,
+/// which starts with '<', or `None` if more input is needed to tell.
+fn scan_markup(buf: &[u8], eof: bool) -> Option<(Token, usize)> {
+    // the rest of the document is written as it is
+    let incomplete = || match eof {
+        true => Some((Token::Other, buf.len())),
+        false => None,
+    };
+    let find = |from: usize, needle: &[u8]| {
+        buf.get(from..)?.windows(needle.len()).position(|window| window == needle).map(|len| from + len + needle.len())
+    };
+
+    match buf.get(1) {
+        None => incomplete(),
+        Some(b'!') => {
+            if buf.len() < 4 && b"<!--".starts_with(buf) {
+                return incomplete();
+            }
+            let end = match buf.starts_with(b"<!--") {
+                true => find(4, b"-->"),
+                false => find(2, b">"),
+            };
+            end.map(|end| (Token::Other, end)).or_else(incomplete)
+        }
+        Some(b'/' | b'?') => find(2, b">").map(|end| (Token::Other, end)).or_else(incomplete),
+        Some(c) if c.is_ascii_alphabetic() => match find_start_tag_end(buf) {
+            Some(end) => Some((Token::StartTag, end)),
+            None => incomplete(),
+        },
+        // '<' that starts no tag. `a < b`
+        Some(_) => Some((Token::Other, 1)),
+    }
+}
+
+/// Returns the length of the start tag at the start of `buf`, whose '>'
+/// may be in a quoted attribute value. `<a title="a > b">`
+fn find_start_tag_end(buf: &[u8]) -> Option<usize> {
+    let mut quote = None;
+    // a quote starts a value only after '='. `<a title=it's>`
+    let mut after_eq = false;
+    for (i, &b) in buf.iter().enumerate() {
+        match quote {
+            Some(q) if b == q => quote = None,
+            Some(_) => {}
+            None => match b {
+                b'>' => return Some(i + 1),
+                b'=' => after_eq = true,
+                b'"' | b'\'' if after_eq => quote = Some(b),
+                b if b.is_ascii_whitespace() => {}
+                _ => after_eq = false,
+            },
+        }
+    }
+
+    None
+}
+
+/// Finds the end tag of the raw text element `name` in `buf`.
+/// Returns `Ok` with the length of the text before it, `Err` with the
+/// length of the text that certainly does not start it if more input is
+/// needed, or `None` if `buf` has none and is all text.
+fn find_end_tag(buf: &[u8], name: &str, eof: bool) -> Option<Result<usize, usize>> {
+    let mut from = 0;
+    while let Some(len) = buf[from..].iter().position(|&b| b == b'<') {
+        let lt = from + len;
+        // `</name` followed by whitespace, '/' or '>'
+        let end = lt + 2 + name.len();
+        if buf.len() <= end {
+            return match eof {
+                true => None,
+                false => Some(Err(lt)),
+            };
+        }
+        if buf[lt + 1] == b'/'
+            && buf[lt + 2..end].eq_ignore_ascii_case(name.as_bytes())
+            && (buf[end].is_ascii_whitespace() || buf[end] == b'/' || buf[end] == b'>')
+        {
+            return Some(Ok(lt));
+        }
+        from = lt + 1;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads one byte at a time, so that every token is split.
+    struct ByteReader<'a>(&'a [u8]);
+
+    impl Read for ByteReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((&b, rest)) if !buf.is_empty() => {
+                    buf[0] = b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn rewriter_test() {
+        let html = "<!DOCTYPE html><!-- <a href=x> --><p class=\"x\" title=\"a > b\">1 < 2<a href=\"/a\">a</a>\
+                    <script>var s = '<a href=/b>';</SCRIPT ><a HREF=\"/c\">c</a><br/><a href='/d' rel=\"x\">d</a></p>";
+        let mut tags = Vec::new();
+        let mut rewriter = Rewriter::new()
+            .on("a[href]", |el| {
+                tags.push(el.tag_name().to_string());
+                if !el.has_attribute("rel") {
+                    el.set_attribute("rel", "nofollow");
+                }
+            })
+            .on("[title]", |el| {
+                el.remove_attribute("title");
+                el.before("<div>");
+            })
+            .on("br", |el| el.prepend("<!-- br -->"));
+
+        let mut output = Vec::new();
+        rewriter.transform(ByteReader(html.as_bytes()), &mut output).unwrap();
+        drop(rewriter);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "<!DOCTYPE html><!-- <a href=x> --><div><p class=\"x\">1 < 2<a href=\"/a\" rel=\"nofollow\">a</a>\
+             <script>var s = '<a href=/b>';</SCRIPT ><a HREF=\"/c\" rel=\"nofollow\">c</a><br/><!-- br --><a href='/d' rel=\"x\">d</a></p>"
+        );
+        assert_eq!(tags, ["a", "a", "a"]);
+
+        // the incomplete tag at the end is kept
+        let mut output = Vec::new();
+        Rewriter::new().on("p", |el| el.set_attribute("id", "x")).transform("<p>a<b".as_bytes(), &mut output).unwrap();
+        assert_eq!(output, b"<p id=\"x\">a<b");
+
+        let error = Rewriter::new().on("a > b", |_| {}).transform("".as_bytes(), Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        let error = Rewriter::new().on("li:first-child", |_| {}).transform("".as_bytes(), Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
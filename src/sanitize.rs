@@ -0,0 +1,264 @@
+//! Cleaning untrusted HTML with an allow-list.
+//!
+//! # Examples
+//! ```rust
+//! use html::sanitize::Sanitizer;
+//!
+//! let sanitizer = Sanitizer::new()
+//!     .allow_tags(["p", "a", "b"])
+//!     .allow_attrs("a", ["href"]);
+//!
+//! let html = r#"<p onclick="x()">Hi <a href="javascript:x()" title="t">a</a><script>x()</script><i>i</i></p>"#;
+//! assert_eq!(sanitizer.clean_html(html), "<p>Hi <a>a</a>i</p>");
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::dom::{write_start_tag, Attributes, Node, NodeData, Payload, Tag, ROOT_TAG_NAME};
+
+/// Elements whose content is removed with them when they are not allowed,
+/// because it is not text to show.
+const REMOVED_ELEMENTS: [&str; 9] = [
+    "script", "style", "template", "iframe", "object", "embed", "noscript", "svg", "math",
+];
+
+/// Attributes whose value is a URL.
+const URL_ATTRIBUTES: [&str; 9] = [
+    "href", "src", "action", "formaction", "cite", "poster", "background", "longdesc", "xlink:href",
+];
+
+/// Removes the tags and attributes that are not allowed from a tree.
+///
+/// A tag that is not allowed is replaced with its children, except for
+/// tags like `<script>` whose content is removed too. Event handlers such
+/// as `onclick` and URLs with a scheme that is not allowed, such as
+/// `javascript:`, are always removed. Comments and doctypes are removed.
+#[derive(Debug, Clone)]
+pub struct Sanitizer {
+    tags: HashSet<String>,
+    // the allowed attributes of each tag, "*" for all tags
+    attrs: HashMap<String, HashSet<String>>,
+    url_schemes: HashSet<String>,
+    escape: bool,
+}
+
+impl Default for Sanitizer {
+    fn default() -> Sanitizer {
+        Sanitizer::new()
+    }
+}
+
+impl Sanitizer {
+    /// Creates a sanitizer that allows no tags and no attributes.
+    /// The URL schemes `http`, `https`, `mailto` and `tel` are allowed.
+    pub fn new() -> Sanitizer {
+        Sanitizer {
+            tags: HashSet::new(),
+            attrs: HashMap::new(),
+            url_schemes: ["http", "https", "mailto", "tel"].into_iter().map(String::from).collect(),
+            escape: false,
+        }
+    }
+
+    /// Allows `tags`.
+    pub fn allow_tags<I, S>(mut self, tags: I) -> Sanitizer
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.tags.extend(tags.into_iter().map(|tag| tag.as_ref().to_ascii_lowercase()));
+        self
+    }
+
+    /// Allows `attrs` on `tag`. Use `"*"` as the tag to allow them on all tags.
+    pub fn allow_attrs<I, S>(mut self, tag: &str, attrs: I) -> Sanitizer
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.attrs
+            .entry(tag.to_ascii_lowercase())
+            .or_default()
+            .extend(attrs.into_iter().map(|attr| attr.as_ref().to_ascii_lowercase()));
+        self
+    }
+
+    /// Allows URLs with `schemes` in attributes such as `href` and `src`.
+    /// Relative URLs are always allowed.
+    pub fn allow_url_schemes<I, S>(mut self, schemes: I) -> Sanitizer
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.url_schemes
+            .extend(schemes.into_iter().map(|scheme| scheme.as_ref().to_ascii_lowercase()));
+        self
+    }
+
+    /// If true, tags that are not allowed are kept as text instead of
+    /// being removed. `<i>` becomes `&lt;i&gt;`. Default: false
+    pub fn escape_disallowed(mut self, escape: bool) -> Sanitizer {
+        self.escape = escape;
+        self
+    }
+
+    /// Returns a cleaned copy of `node` and its descendants, wrapped in a
+    /// synthetic `root` node like the tree returned by [`parse`](crate::parse).
+    pub fn clean(&self, node: &NodeData) -> Node {
+        let root = Node::new(Payload::Tag(Tag::new(ROOT_TAG_NAME)));
+        // the tree is walked with a stack rather than by recursion, so that
+        // a deeply nested tree cannot overflow the call stack
+        let mut steps = Vec::new();
+        if node.is_document_root() {
+            push_children(&mut steps, &root, node);
+        } else {
+            self.clean_into(&root, node, &mut steps);
+        }
+        while let Some(step) = steps.pop() {
+            match step {
+                Step::Clean(node, parent) => self.clean_into(&parent, &node, &mut steps),
+                Step::Text(parent, text) => {
+                    parent.create_and_add_child(Payload::Text(text));
+                }
+            }
+        }
+
+        root
+    }
+
+    /// Parses `html` like [`parse_lenient`](crate::parse_lenient), cleans it
+    /// and serializes it back.
+    pub fn clean_html(&self, html: &str) -> String {
+        self.clean(&crate::parse_lenient(html)).to_html()
+    }
+
+    /// Appends the cleaned `node` to `parent`, and adds the steps that
+    /// clean its children to `steps`.
+    fn clean_into(&self, parent: &Node, node: &NodeData, steps: &mut Vec<Step>) {
+        let payload = node.get_payload();
+        let tag = match &*payload {
+            Payload::Tag(tag) => tag,
+            Payload::Text(text) | Payload::RawText(text) => {
+                // raw text becomes text, so it is escaped if its element is not kept
+                parent.create_and_add_child(Payload::Text(text.clone()));
+                return;
+            }
+            Payload::Comment(_) | Payload::ConditionalComment(_) | Payload::Doctype(_) => return,
+        };
+
+        let name = tag.get_name().to_ascii_lowercase();
+        if self.tags.contains(&name) {
+            let mut clean_tag = Tag::new(tag.get_name());
+            clean_tag.set_self_closing(tag.is_self_closing());
+            if let Some(attributes) = tag.get_attributes() {
+                let attributes: Attributes = attributes
+                    .iter()
+                    .filter(|(attr, value)| self.is_allowed_attr(&name, attr, value.as_str()))
+                    .map(|(attr, value)| (attr.clone(), value.clone()))
+                    .collect();
+                if !attributes.is_empty() {
+                    clean_tag.set_attributes(attributes);
+                }
+            }
+
+            let child = Node::from(parent.create_and_add_child(Payload::Tag(clean_tag)));
+            push_children(steps, &child, node);
+        } else if self.escape {
+            // keep the tags as text, which is escaped when serialized
+            let mut start_tag = String::new();
+            write_start_tag(&mut start_tag, tag);
+            parent.create_and_add_child(Payload::Text(start_tag));
+            if !tag.is_self_closing() && !tag.is_void_element() {
+                steps.push(Step::Text(parent.clone(), format!("</{}>", tag.get_name())));
+            }
+            push_children(steps, parent, node);
+        } else if !REMOVED_ELEMENTS.contains(&name.as_str()) {
+            push_children(steps, parent, node);
+        }
+    }
+
+    fn is_allowed_attr(&self, tag: &str, attr: &str, value: &str) -> bool {
+        let attr = attr.to_ascii_lowercase();
+        if attr.starts_with("on") {
+            // event handlers are never allowed
+            return false;
+        }
+
+        let allowed = [tag, "*"]
+            .iter()
+            .any(|tag| self.attrs.get(*tag).is_some_and(|attrs| attrs.contains(&attr)));
+        if !allowed {
+            return false;
+        }
+
+        !URL_ATTRIBUTES.contains(&attr.as_str()) || self.is_allowed_url(value)
+    }
+
+    /// Returns true if `url` is relative or its scheme is allowed.
+    fn is_allowed_url(&self, url: &str) -> bool {
+        // browsers ignore whitespace and control characters in the scheme. `java\tscript:`
+        let url: String = url
+            .chars()
+            .filter(|c| !c.is_whitespace() && !c.is_control())
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        let scheme_end = url.find([':', '/', '?', '#']);
+        match scheme_end {
+            Some(i) if url[i..].starts_with(':') => self.url_schemes.contains(&url[..i]),
+            _ => true,
+        }
+    }
+}
+
+/// What [`Sanitizer::clean`] does next.
+enum Step {
+    /// Appends the cleaned node to the parent.
+    Clean(Rc<NodeData>, Node),
+    /// Appends the text to the parent, such as an escaped end tag.
+    Text(Node, String),
+}
+
+/// Adds the steps that append the cleaned children of `node` to `parent`
+/// in order.
+fn push_children(steps: &mut Vec<Step>, parent: &Node, node: &NodeData) {
+    for child in node.get_children().iter().rev() {
+        steps.push(Step::Clean(Rc::clone(child), parent.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_test() {
+        let sanitizer = Sanitizer::new()
+            .allow_tags(["div", "a", "img"])
+            .allow_attrs("a", ["href"])
+            .allow_attrs("*", ["title"]);
+
+        let html = r#"<div title="t" class="c" onmouseover="x()"><!-- c -->
+            <a href="https://a.com">a</a><a href="JaVa&#x09;Script:x()">b</a><a href="/c">c</a>
+            <img src="x.png" title="i"><style>* {}</style><span>s</span>
+            </div>"#;
+
+        assert_eq!(
+            sanitizer.clean_html(html),
+            r#"<div title="t"><a href="https://a.com">a</a><a>b</a><a href="/c">c</a><img title="i">s</div>"#
+        );
+    }
+
+    #[test]
+    fn escape_test() {
+        let sanitizer = Sanitizer::new()
+            .allow_tags(["p"])
+            .escape_disallowed(true);
+
+        assert_eq!(
+            sanitizer.clean_html("<p><i>a</i><br><script>x()</script></p>"),
+            "<p>&lt;i&gt;a&lt;/i&gt;&lt;br&gt;&lt;script&gt;x()&lt;/script&gt;</p>"
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use crate::dom::{NodeData, Payload};
+use crate::selector::Selector;
+
+/// Nodes to query further, each at most once, in the order they were found.
+///
+/// The methods return a new selection, so that each step of a query reads
+/// as one call. The nodes are shared with the tree, not copied.
+///
+/// # Examples
+/// ```rust
+/// use html::Selection;
+///
+/// let root = html::parse(r#"
+///     <ul class="nav"><li><a href="/a">A</a></li><li><a href="/b">B</a></li></ul>
+///     <p><a href="/c">C</a></p>
+/// "#).unwrap();
+///
+/// let nav = Selection::from(root.get_copy_of_internal_arc()).find_class("nav");
+/// assert_eq!(nav.find_name("a").attr("href"), ["/a", "/b"]);
+/// assert_eq!(nav.find_name("a").first().texts(), ["A"]);
+/// assert_eq!(nav.children().len(), 2);
+/// assert_eq!(nav.find_name("a").parents().filter_name("li").len(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    nodes: Vec<Rc<NodeData>>,
+}
+
+impl Selection {
+    /// Creates a selection of `nodes`. A node that appears again is left out.
+    pub fn new(nodes: Vec<Rc<NodeData>>) -> Selection {
+        let mut seen = HashSet::new();
+        Selection {
+            nodes: nodes.into_iter().filter(|node| seen.insert(Rc::as_ptr(node))).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Rc<NodeData>> {
+        self.nodes.get(index)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Rc<NodeData>> {
+        self.nodes.iter()
+    }
+
+    pub fn nodes(&self) -> &[Rc<NodeData>] {
+        &self.nodes
+    }
+
+    pub fn into_vec(self) -> Vec<Rc<NodeData>> {
+        self.nodes
+    }
+
+    /// Returns the first node, or an empty selection.
+    pub fn first(&self) -> Selection {
+        Selection {
+            nodes: self.nodes.first().cloned().into_iter().collect(),
+        }
+    }
+
+    /// Returns the last node, or an empty selection.
+    pub fn last(&self) -> Selection {
+        Selection {
+            nodes: self.nodes.last().cloned().into_iter().collect(),
+        }
+    }
+
+    /// Returns the nodes for which `predicate` is true.
+    pub fn filter<F>(&self, predicate: F) -> Selection
+    where
+        F: Fn(&NodeData) -> bool,
+    {
+        Selection {
+            nodes: self.nodes.iter().filter(|node| predicate(node)).cloned().collect(),
+        }
+    }
+
+    /// Returns the nodes named `tag_name`, compared without case.
+    pub fn filter_name(&self, tag_name: &str) -> Selection {
+        self.filter(|node| node.tag_name().is_some_and(|name| name.eq_ignore_ascii_case(tag_name)))
+    }
+
+    /// Returns the nodes that have `class`.
+    pub fn filter_class(&self, class: &str) -> Selection {
+        self.filter(|node| node.has_class(class))
+    }
+
+    /// Returns the nodes that match `selector`.
+    pub fn filter_selector(&self, selector: &Selector) -> Selection {
+        self.filter(|node| selector.matches(node))
+    }
+
+    /// Returns the descendants named `tag_name` of the nodes.
+    pub fn find_name(&self, tag_name: &str) -> Selection {
+        self.flat_map(|node| node.query_name(tag_name).collect())
+    }
+
+    /// Returns the descendants of the nodes that have `class`.
+    pub fn find_class(&self, class: &str) -> Selection {
+        self.flat_map(|node| node.query_class(class).collect())
+    }
+
+    /// Returns the descendants of the nodes that match `selector`.
+    pub fn select(&self, selector: &Selector) -> Selection {
+        self.flat_map(|node| crate::selector::select(node, selector))
+    }
+
+    /// Returns the parents of the nodes. The document root is left out.
+    pub fn parents(&self) -> Selection {
+        self.flat_map(|node| node.get_parent().filter(|parent| !parent.is_document_root()).into_iter().collect())
+    }
+
+    /// Returns the children of the nodes that are tags.
+    pub fn children(&self) -> Selection {
+        self.flat_map(|node| {
+            node.get_children()
+                .iter()
+                .filter(|child| matches!(&*child.get_payload(), Payload::Tag(_)))
+                .cloned()
+                .collect()
+        })
+    }
+
+    /// Returns the values of the attribute `name` of the nodes that have it.
+    pub fn attr(&self, name: &str) -> Vec<String> {
+        self.nodes.iter().filter_map(|node| node.attr(name)).collect()
+    }
+
+    /// Returns the [`inner_text`](NodeData::inner_text) of each node.
+    pub fn texts(&self) -> Vec<String> {
+        self.nodes.iter().map(|node| node.inner_text()).collect()
+    }
+
+    fn flat_map<F>(&self, f: F) -> Selection
+    where
+        F: Fn(&Rc<NodeData>) -> Vec<Rc<NodeData>>,
+    {
+        Selection::new(self.nodes.iter().flat_map(f).collect())
+    }
+}
+
+impl From<Vec<Rc<NodeData>>> for Selection {
+    fn from(nodes: Vec<Rc<NodeData>>) -> Selection {
+        Selection::new(nodes)
+    }
+}
+
+impl From<Rc<NodeData>> for Selection {
+    fn from(node: Rc<NodeData>) -> Selection {
+        Selection { nodes: vec![node] }
+    }
+}
+
+impl IntoIterator for Selection {
+    type Item = Rc<NodeData>;
+    type IntoIter = std::vec::IntoIter<Rc<NodeData>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.nodes.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Selection {
+    type Item = &'a Rc<NodeData>;
+    type IntoIter = std::slice::Iter<'a, Rc<NodeData>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.nodes.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn selection_test() {
+        let root = parse(r#"<div class="a"><div class="a"><p>x</p><p>y</p></div><img src="i.png"></div>text"#).unwrap();
+        let root = Selection::from(root.get_copy_of_internal_arc());
+
+        let divs = root.find_class("a");
+        assert_eq!(divs.len(), 2);
+        // the paragraphs are found from both divs, but kept once
+        assert_eq!(divs.find_name("p").texts(), ["x", "y"]);
+        assert_eq!(divs.children().filter_name("P").texts(), ["x", "y"]);
+        assert_eq!(divs.children().len(), 4);
+        assert_eq!(divs.children().attr("src"), ["i.png"]);
+        assert_eq!(divs.children().parents().len(), 2);
+        assert!(divs.first().parents().is_empty());
+        assert_eq!(divs.last().get(0), divs.get(1));
+        assert_eq!(root.select(&Selector::parse("div.a").unwrap()).len(), 2);
+        assert_eq!(root.find_name("div").filter_selector(&Selector::parse("[class]").unwrap()).len(), 2);
+        assert!(Selection::default().first().is_empty());
+        assert_eq!(divs.filter_class("a").into_iter().count(), 2);
+    }
+}
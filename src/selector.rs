@@ -0,0 +1,1108 @@
+//! CSS selectors.
+//!
+//! A [`Selector`] is compound selectors separated by the combinators ` `
+//! (descendant), `>` (child), `+` (next sibling) and `~` (subsequent
+//! sibling), as in `ul.nav > li a`. A compound selector is an optional
+//! type selector or `*` followed by any number of `#id`, `.class`,
+//! attribute selectors and pseudo-classes, all of which a tag must match.
+//! Attribute selectors are `[name]`, `[name=value]`, `[name~=value]`,
+//! `[name|=value]`, `[name^=value]`, `[name$=value]` and `[name*=value]`,
+//! where the value is an identifier or a quoted string. Tag and attribute names are matched without case.
+//! A `\` in an identifier escapes the next character, or the character of
+//! up to 6 hex digits, so that `[xlink\:href]` and `[\@click]` select those
+//! attributes.
+//!
+//! The structural pseudo-classes `:first-child`, `:last-child`,
+//! `:only-child`, `:empty` and `:nth-child(An+B)` are supported too, where
+//! the argument is also `odd` or `even`. Only elements count as siblings.
+//! `:contains(text)` matches the elements whose
+//! [`inner_text`](NodeData::inner_text) contains the identifier or quoted
+//! string `text`, with case. `:not(a, b)` matches the elements that match
+//! none of the selectors, and `:has(> a, b)` those from which an element
+//! that matches one of the selectors is reached by the combinator before
+//! it, the descendant combinator if there is none.
+//!
+//! [`Selector::parse_list`] parses a comma-separated list of selectors,
+//! and [`select_groups`] returns which of them each element matches.
+//!
+//! # Examples
+//! ```rust
+//! use html::selector::Selector;
+//!
+//! let root = html::parse(r#"<a href="/a" class="nav x">a</a><a class="nav">b</a>"#).unwrap();
+//! let selector = Selector::parse("a.nav[href^='/']").unwrap();
+//!
+//! let links = html::selector::select(&root.get_copy_of_internal_arc(), &selector);
+//! assert_eq!(links.len(), 1);
+//! assert_eq!(links[0].inner_text(), "a");
+//!
+//! let rows = html::parse("<tr><td>1</td><td>2</td><td>3</td></tr>").unwrap();
+//! let odd = html::selector::select(&rows.get_copy_of_internal_arc(), &"td:nth-child(odd)".parse().unwrap());
+//! assert_eq!(odd.iter().map(|td| td.inner_text()).collect::<Vec<_>>(), ["1", "3"]);
+//!
+//! let list = html::parse(r#"<ul><li><a href="/a">a</a></li><li class="on">b</li></ul>"#).unwrap();
+//! let selector = Selector::parse("ul > li:not(.on):has(a[href])").unwrap();
+//! assert_eq!(html::selector::select(&list.get_copy_of_internal_arc(), &selector)[0].inner_text(), "a");
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use crate::dom::{NodeData, Payload, Tag};
+
+/// A parsed selector. See the [module](self) documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    // from the left, with `combinators[i]` between `compounds[i]` and `compounds[i + 1]`
+    compounds: Vec<Compound>,
+    combinators: Vec<Combinator>,
+}
+
+/// The conditions on one element. `a.nav[href]:first-child`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Compound {
+    // `None` for `*` and for no type selector
+    name: Option<String>,
+    ids: Vec<String>,
+    classes: Vec<String>,
+    attributes: Vec<AttrSelector>,
+    pseudo_classes: Vec<PseudoClass>,
+}
+
+/// How the elements of two compound selectors are related.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+    /// ` `, the right element is a descendant of the left one.
+    Descendant,
+    /// `>`
+    Child,
+    /// `+`, the right element is the next sibling element of the left one.
+    NextSibling,
+    /// `~`, the right element is any sibling element after the left one.
+    SubsequentSibling,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AttrSelector {
+    name: String,
+    // `None` for `[name]`
+    value: Option<(AttrOp, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttrOp {
+    /// `=`
+    Exact,
+    /// `~=`
+    WordInList,
+    /// `|=`, the value or the value followed by `-`
+    DashMatch,
+    /// `^=`
+    Prefix,
+    /// `$=`
+    Suffix,
+    /// `*=`
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PseudoClass {
+    FirstChild,
+    LastChild,
+    OnlyChild,
+    /// No children but comments.
+    Empty,
+    /// `:nth-child(An+B)`, the elements at the positions `A * n + B` for
+    /// any `n` from 0, counted from 1.
+    NthChild(i64, i64),
+    /// `:contains(text)`
+    Contains(String),
+    /// `:not(a, b)`, the elements that match none of the selectors.
+    Not(Vec<Selector>),
+    /// `:has(> a, b)`, the elements from which an element that matches one
+    /// of the selectors is reached by its combinator.
+    Has(Vec<(Combinator, Selector)>),
+}
+
+/// The error returned when a selector cannot be parsed.
+/// Each variant carries the byte offset in the selector where the problem starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectorError {
+    /// A character that is not allowed there. `a!b`
+    UnexpectedCharacter(usize),
+    /// The selector ends before it is complete. `a[href`
+    UnexpectedEnd(usize),
+}
+
+impl fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectorError::UnexpectedCharacter(offset) => write!(f, "Unexpected character at offset {}.", offset),
+            SelectorError::UnexpectedEnd(offset) => write!(f, "Selector ends at offset {}.", offset),
+        }
+    }
+}
+
+impl Error for SelectorError {}
+
+impl Selector {
+    /// Parses `selector`.
+    ///
+    /// # Errors
+    /// * [`SelectorError`] if `selector` is empty or is not a selector.
+    pub fn parse(selector: &str) -> Result<Selector, SelectorError> {
+        let mut parser = SelectorParser { selector, cursor: 0, allow_tree: true };
+        let parsed = parser.complex()?;
+        parser.end()?;
+        Ok(parsed)
+    }
+
+    /// Parses a selector that [`matches_tag`](Selector::matches_tag) can
+    /// check, which has no pseudo-classes or combinators.
+    pub(crate) fn parse_start_tag(selector: &str) -> Result<Selector, SelectorError> {
+        let mut parser = SelectorParser { selector, cursor: 0, allow_tree: false };
+        let parsed = parser.complex()?;
+        parser.end()?;
+        Ok(parsed)
+    }
+
+    /// Parses a comma-separated list of selectors. `h1, h2, h3`
+    ///
+    /// # Errors
+    /// * [`SelectorError`] if a selector is empty or is not a selector.
+    pub fn parse_list(selectors: &str) -> Result<Vec<Selector>, SelectorError> {
+        let mut parser = SelectorParser { selector: selectors, cursor: 0, allow_tree: true };
+        let parsed = parser.selector_list()?;
+        parser.end()?;
+        Ok(parsed)
+    }
+
+    /// Returns the specificity of the selector, by which CSS orders the
+    /// rules that match an element: the numbers of ids, of classes,
+    /// attribute selectors and pseudo-classes, and of type selectors.
+    /// `:not()` and `:has()` count as their most specific argument.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use html::selector::Selector;
+    ///
+    /// assert_eq!(Selector::parse("ul#nav > li.on a[href]").unwrap().specificity(), (1, 2, 3));
+    /// assert_eq!(Selector::parse("*:not(#a, .b)").unwrap().specificity(), (1, 0, 0));
+    /// ```
+    pub fn specificity(&self) -> (u32, u32, u32) {
+        let mut specificity = (0, 0, 0);
+        for compound in &self.compounds {
+            specificity.0 += compound.ids.len() as u32;
+            specificity.1 += (compound.classes.len() + compound.attributes.len()) as u32;
+            specificity.2 += compound.name.is_some() as u32;
+            for pseudo_class in &compound.pseudo_classes {
+                let most_specific = match pseudo_class {
+                    PseudoClass::Not(selectors) => selectors.iter().map(Selector::specificity).max(),
+                    PseudoClass::Has(selectors) => selectors.iter().map(|(_, selector)| selector.specificity()).max(),
+                    _ => Some((0, 1, 0)),
+                };
+                let (ids, classes, types) = most_specific.unwrap_or_default();
+                specificity = (specificity.0 + ids, specificity.1 + classes, specificity.2 + types);
+            }
+        }
+        specificity
+    }
+
+    /// Returns true if `node` is a tag that matches the selector.
+    pub fn matches(&self, node: &NodeData) -> bool {
+        self.matches_at(node, self.compounds.len() - 1, None, None)
+    }
+
+    /// Returns true if `node` matches the selector with the related
+    /// elements among `within` and its descendants.
+    fn matches_within(&self, node: &NodeData, within: &NodeData) -> bool {
+        self.matches_at(node, self.compounds.len() - 1, None, Some(within))
+    }
+
+    /// Returns true if `tag` matches the selector, which needs nothing but
+    /// the start tag. A selector with pseudo-classes or combinators matches
+    /// no tag, as they need the tree.
+    pub fn matches_tag(&self, tag: &Tag) -> bool {
+        match self.compounds.as_slice() {
+            [compound] => compound.pseudo_classes.is_empty() && compound.matches_start_tag(tag),
+            _ => false,
+        }
+    }
+
+    /// Returns true if `node` matches the compound selector at `index` and
+    /// the elements it is related to match those before it. With `scope`,
+    /// the element of the first compound selector must be reached from the
+    /// element of `scope` by its combinator, as in `:has()`. With `within`,
+    /// the related elements must be `within` or its descendants.
+    fn matches_at(
+        &self,
+        node: &NodeData,
+        index: usize,
+        scope: Option<(&NodeData, Combinator)>,
+        within: Option<&NodeData>,
+    ) -> bool {
+        if !self.compounds[index].matches(node) {
+            return false;
+        }
+        if index == 0 {
+            return scope.is_none_or(|(scope, combinator)| is_related(scope, node, combinator));
+        }
+        // the parent and siblings of `within` are outside
+        let is_within = |other: &NodeData| within.is_some_and(|within| std::ptr::eq(within, other));
+        if is_within(node) {
+            return false;
+        }
+
+        let matches = |other: &NodeData| self.matches_at(other, index - 1, scope, within);
+        match self.combinators[index - 1] {
+            Combinator::Descendant => {
+                for ancestor in node.ancestors() {
+                    if matches(&ancestor) {
+                        return true;
+                    }
+                    if is_within(&ancestor) {
+                        return false;
+                    }
+                }
+                false
+            }
+            Combinator::Child => node.get_parent().is_some_and(|parent| matches(&parent)),
+            Combinator::NextSibling => preceding_elements(node).first().is_some_and(|sibling| matches(sibling)),
+            Combinator::SubsequentSibling => preceding_elements(node).iter().any(|sibling| matches(sibling)),
+        }
+    }
+}
+
+impl Compound {
+    fn matches(&self, node: &NodeData) -> bool {
+        match &*node.get_payload() {
+            Payload::Tag(tag) => {
+                !node.is_document_root()
+                    && self.matches_start_tag(tag)
+                    && self.pseudo_classes.iter().all(|pseudo_class| pseudo_class.matches(node))
+            }
+            _ => false,
+        }
+    }
+
+    fn matches_start_tag(&self, tag: &Tag) -> bool {
+        if self.name.as_ref().is_some_and(|name| !tag.get_name().eq_ignore_ascii_case(name)) {
+            return false;
+        }
+
+        let attribute = |name: &str| {
+            tag.get_attributes()?
+                .iter()
+                .find(|(attr_name, _)| attr_name.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.as_str())
+        };
+        let has_word = |name: &str, word: &str| {
+            attribute(name).is_some_and(|value| value.split_ascii_whitespace().any(|w| w == word))
+        };
+
+        self.ids.iter().all(|id| attribute("id") == Some(id.as_str()))
+            && self.classes.iter().all(|class| has_word("class", class))
+            && self.attributes.iter().all(|selector| {
+                let actual = match attribute(&selector.name) {
+                    Some(actual) => actual,
+                    None => return false,
+                };
+                let (op, value) = match &selector.value {
+                    Some((op, value)) => (*op, value.as_str()),
+                    None => return true,
+                };
+                // an empty value matches nothing, except for `=` and `|=`
+                if value.is_empty() && !matches!(op, AttrOp::Exact | AttrOp::DashMatch) {
+                    return false;
+                }
+
+                match op {
+                    AttrOp::Exact => actual == value,
+                    AttrOp::WordInList => actual.split_ascii_whitespace().any(|word| word == value),
+                    AttrOp::DashMatch => {
+                        actual == value || (actual.starts_with(value) && actual[value.len()..].starts_with('-'))
+                    }
+                    AttrOp::Prefix => actual.starts_with(value),
+                    AttrOp::Suffix => actual.ends_with(value),
+                    AttrOp::Contains => actual.contains(value),
+                }
+            })
+    }
+}
+
+impl PseudoClass {
+    fn matches(&self, node: &NodeData) -> bool {
+        match self {
+            PseudoClass::Empty => {
+                return node.get_children().iter().all(|child| match &*child.get_payload() {
+                    Payload::Comment(_) | Payload::ConditionalComment(_) => true,
+                    Payload::Text(text) | Payload::RawText(text) => text.is_empty(),
+                    Payload::Tag(_) | Payload::Doctype(_) => false,
+                })
+            }
+            PseudoClass::Contains(text) => return node.inner_text().contains(text.as_str()),
+            PseudoClass::Not(selectors) => return !selectors.iter().any(|selector| selector.matches(node)),
+            PseudoClass::Has(selectors) => {
+                return selectors.iter().any(|(combinator, selector)| {
+                    let (last, scope) = (selector.compounds.len() - 1, Some((node, *combinator)));
+                    reachable(node, *combinator).any(|other| selector.matches_at(&other, last, scope, None))
+                })
+            }
+            _ => {}
+        }
+
+        // the position among the sibling elements, counted from 1.
+        // An element without a parent is the only child.
+        let (position, count) = match node.get_parent() {
+            Some(parent) => {
+                let siblings: Vec<_> = parent
+                    .get_children()
+                    .iter()
+                    .filter(|child| matches!(&*child.get_payload(), Payload::Tag(_)))
+                    .map(Rc::as_ptr)
+                    .collect();
+                match siblings.iter().position(|&sibling| std::ptr::eq(sibling, node)) {
+                    Some(index) => (index as i64 + 1, siblings.len() as i64),
+                    None => return false,
+                }
+            }
+            None => (1, 1),
+        };
+
+        match *self {
+            PseudoClass::FirstChild => position == 1,
+            PseudoClass::LastChild => position == count,
+            PseudoClass::OnlyChild => count == 1,
+            PseudoClass::NthChild(a, b) => match a {
+                0 => position == b,
+                _ => (position - b) % a == 0 && (position - b) / a >= 0,
+            },
+            PseudoClass::Empty | PseudoClass::Contains(_) | PseudoClass::Not(_) | PseudoClass::Has(_) => unreachable!(),
+        }
+    }
+}
+
+/// Returns the sibling elements before `node`, the nearest first.
+fn preceding_elements(node: &NodeData) -> Vec<Rc<NodeData>> {
+    let parent = match node.get_parent() {
+        Some(parent) => parent,
+        None => return Vec::new(),
+    };
+    let mut siblings: Vec<Rc<NodeData>> = parent
+        .get_children()
+        .iter()
+        .take_while(|child| !std::ptr::eq(Rc::as_ptr(child), node))
+        .filter(|child| matches!(&*child.get_payload(), Payload::Tag(_)))
+        .cloned()
+        .collect();
+    siblings.reverse();
+    siblings
+}
+
+/// Returns true if `other` is reached from `node` by `combinator`.
+fn is_related(node: &NodeData, other: &NodeData, combinator: Combinator) -> bool {
+    let is_node = |candidate: &Rc<NodeData>| std::ptr::eq(Rc::as_ptr(candidate), node);
+    match combinator {
+        Combinator::Descendant => other.is_descendant_of(node),
+        Combinator::Child => other.get_parent().is_some_and(|parent| is_node(&parent)),
+        Combinator::NextSibling => preceding_elements(other).first().is_some_and(is_node),
+        Combinator::SubsequentSibling => preceding_elements(other).iter().any(is_node),
+    }
+}
+
+/// Returns the elements among which those reached from `node` by
+/// `combinator` and their descendants are.
+fn reachable(node: &NodeData, combinator: Combinator) -> Box<dyn Iterator<Item = Rc<NodeData>> + '_> {
+    match combinator {
+        Combinator::Descendant | Combinator::Child => Box::new(node.descendants()),
+        Combinator::NextSibling | Combinator::SubsequentSibling => {
+            let following: Vec<Rc<NodeData>> = match node.get_parent() {
+                Some(parent) => parent
+                    .get_children()
+                    .iter()
+                    .skip_while(|child| !std::ptr::eq(Rc::as_ptr(child), node))
+                    .skip(1)
+                    .cloned()
+                    .collect(),
+                None => Vec::new(),
+            };
+            Box::new(following.into_iter().flat_map(|sibling| std::iter::once(Rc::clone(&sibling)).chain(sibling.descendants())))
+        }
+    }
+}
+
+impl FromStr for Selector {
+    type Err = SelectorError;
+
+    fn from_str(selector: &str) -> Result<Selector, SelectorError> {
+        Selector::parse(selector)
+    }
+}
+
+/// Returns the descendants of `source` that match `selector` in document order.
+pub fn select(source: &Rc<NodeData>, selector: &Selector) -> Vec<Rc<NodeData>> {
+    source.descendants().filter(|node| selector.matches(node)).collect()
+}
+
+/// Returns the descendants of `source` that match any of `selectors` in
+/// document order, each with the index of the first selector it matches.
+pub fn select_groups(source: &NodeData, selectors: &[Selector]) -> Vec<(Rc<NodeData>, usize)> {
+    source
+        .descendants()
+        .filter_map(|node| {
+            let group = selectors.iter().position(|selector| selector.matches(&node))?;
+            Some((node, group))
+        })
+        .collect()
+}
+
+/// Returns the descendants of `source` that match `selector` in document
+/// order, where the elements the combinators relate are `source` or its
+/// descendants. The descendants that match `boundary`, in the same way,
+/// are left out with their own descendants.
+pub fn select_within(source: &NodeData, selector: &Selector, boundary: Option<&Selector>) -> Vec<Rc<NodeData>> {
+    let mut selected = Vec::new();
+    let mut stack: Vec<Rc<NodeData>> = source.get_children().iter().rev().cloned().collect();
+    while let Some(node) = stack.pop() {
+        if boundary.is_some_and(|boundary| boundary.matches_within(&node, source)) {
+            continue;
+        }
+        if selector.matches_within(&node, source) {
+            selected.push(Rc::clone(&node));
+        }
+        stack.extend(node.get_children().iter().rev().cloned());
+    }
+    selected
+}
+
+impl NodeData {
+    /// Returns the descendants that match any selector of the
+    /// comma-separated `selectors` in document order, each with the index
+    /// of the first selector it matches.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let root = html::parse("<h1>a</h1><h3>b</h3><h2>c</h2>").unwrap();
+    /// let outline: Vec<(String, usize)> = root
+    ///     .select("h1, h2, h3")
+    ///     .unwrap()
+    ///     .iter()
+    ///     .map(|(heading, group)| (heading.inner_text(), *group))
+    ///     .collect();
+    ///
+    /// assert_eq!(outline, [("a".to_string(), 0), ("b".to_string(), 2), ("c".to_string(), 1)]);
+    /// ```
+    ///
+    /// # Errors
+    /// * [`SelectorError`] if a selector cannot be parsed.
+    pub fn select(&self, selectors: &str) -> Result<Vec<(Rc<NodeData>, usize)>, SelectorError> {
+        Ok(select_groups(self, &Selector::parse_list(selectors)?))
+    }
+
+    /// Returns the descendants that match `selector`, which is matched as
+    /// if this node were the root: `div p` selects no `<p>` whose only
+    /// `<div>` ancestor is above this node.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let root = html::parse("<div><article><p>a</p></article><article><p>b</p></article></div>").unwrap();
+    /// let articles = root.query_name("article").collect::<Vec<_>>();
+    ///
+    /// assert_eq!(articles[1].select_within("p").unwrap()[0].inner_text(), "b");
+    /// assert!(articles[1].select_within("div p").unwrap().is_empty());
+    /// ```
+    ///
+    /// # Errors
+    /// * [`SelectorError`] if `selector` cannot be parsed.
+    pub fn select_within(&self, selector: &str) -> Result<Vec<Rc<NodeData>>, SelectorError> {
+        Ok(select_within(self, &Selector::parse(selector)?, None))
+    }
+
+    /// Same as [`select_within`](NodeData::select_within), but the
+    /// descendants that match `boundary` are not searched or selected, so
+    /// that the cards nested in a card are left to their own queries.
+    ///
+    /// # Examples
+    /// ```rust
+    /// let root = html::parse("<article><h2>a</h2><article><h2>b</h2></article></article>").unwrap();
+    /// let outer = root.first_child().unwrap();
+    ///
+    /// let titles = outer.select_within_boundary("h2", "article").unwrap();
+    /// assert_eq!(titles.len(), 1);
+    /// assert_eq!(titles[0].inner_text(), "a");
+    /// ```
+    ///
+    /// # Errors
+    /// * [`SelectorError`] if `selector` or `boundary` cannot be parsed.
+    pub fn select_within_boundary(&self, selector: &str, boundary: &str) -> Result<Vec<Rc<NodeData>>, SelectorError> {
+        Ok(select_within(self, &Selector::parse(selector)?, Some(&Selector::parse(boundary)?)))
+    }
+}
+
+struct SelectorParser<'a> {
+    selector: &'a str,
+    // the byte offset of the next character
+    cursor: usize,
+    // whether pseudo-classes and combinators, which need the tree, are allowed
+    allow_tree: bool,
+}
+
+impl<'a> SelectorParser<'a> {
+    /// Fails unless only whitespace is left.
+    fn end(&mut self) -> Result<(), SelectorError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(_) => Err(SelectorError::UnexpectedCharacter(self.cursor)),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails with the next character, or with the end if there is none.
+    fn unexpected<T>(&self) -> Result<T, SelectorError> {
+        match self.peek() {
+            Some(_) => Err(SelectorError::UnexpectedCharacter(self.cursor)),
+            None => Err(SelectorError::UnexpectedEnd(self.cursor)),
+        }
+    }
+
+    /// Reads selectors separated by commas. `a, b > c`
+    fn selector_list(&mut self) -> Result<Vec<Selector>, SelectorError> {
+        let mut selectors = vec![self.complex()?];
+        loop {
+            self.skip_whitespace();
+            if !self.eat(',') {
+                return Ok(selectors);
+            }
+            selectors.push(self.complex()?);
+        }
+    }
+
+    /// Reads selectors separated by commas, each of which may start with a
+    /// combinator. `> a, + b, c`
+    fn relative_selector_list(&mut self) -> Result<Vec<(Combinator, Selector)>, SelectorError> {
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let combinator = self.combinator().unwrap_or(Combinator::Descendant);
+            selectors.push((combinator, self.complex()?));
+            self.skip_whitespace();
+            if !self.eat(',') {
+                return Ok(selectors);
+            }
+        }
+    }
+
+    /// Reads compound selectors separated by combinators. `ul.nav > li a`
+    fn complex(&mut self) -> Result<Selector, SelectorError> {
+        self.skip_whitespace();
+        let mut selector = Selector {
+            compounds: vec![self.compound()?],
+            combinators: Vec::new(),
+        };
+
+        while self.allow_tree {
+            let bgn = self.cursor;
+            self.skip_whitespace();
+            let combinator = match self.combinator() {
+                Some(combinator) => combinator,
+                // whitespace before another compound selector
+                None if self.cursor > bgn && self.peek().is_some_and(starts_compound) => Combinator::Descendant,
+                None => {
+                    self.cursor = bgn;
+                    break;
+                }
+            };
+            self.skip_whitespace();
+            selector.combinators.push(combinator);
+            selector.compounds.push(self.compound()?);
+        }
+        Ok(selector)
+    }
+
+    /// Reads `>`, `+` or `~` and the whitespace after it.
+    fn combinator(&mut self) -> Option<Combinator> {
+        let combinator = match self.peek()? {
+            '>' => Combinator::Child,
+            '+' => Combinator::NextSibling,
+            '~' => Combinator::SubsequentSibling,
+            _ => return None,
+        };
+        self.cursor += 1;
+        self.skip_whitespace();
+        Some(combinator)
+    }
+
+    /// Reads a compound selector. `a.nav[href]:first-child`
+    fn compound(&mut self) -> Result<Compound, SelectorError> {
+        let mut compound = Compound {
+            name: None,
+            ids: Vec::new(),
+            classes: Vec::new(),
+            attributes: Vec::new(),
+            pseudo_classes: Vec::new(),
+        };
+        let mut is_empty = true;
+        if self.eat('*') {
+            is_empty = false;
+        } else if self.peek().is_some_and(|c| is_name_char(c) || c == '\\') {
+            compound.name = Some(self.name()?.to_ascii_lowercase());
+            is_empty = false;
+        }
+
+        loop {
+            match self.peek() {
+                Some('#') => {
+                    self.cursor += 1;
+                    compound.ids.push(self.name()?);
+                }
+                Some('.') => {
+                    self.cursor += 1;
+                    compound.classes.push(self.name()?);
+                }
+                Some('[') => {
+                    self.cursor += 1;
+                    compound.attributes.push(self.attribute()?);
+                }
+                Some(':') if self.allow_tree => {
+                    self.cursor += 1;
+                    compound.pseudo_classes.push(self.pseudo_class()?);
+                }
+                _ => break,
+            }
+            is_empty = false;
+        }
+
+        match is_empty {
+            true => self.unexpected(),
+            false => Ok(compound),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.selector[self.cursor..].chars().next()
+    }
+
+    /// Moves past `c` if it is the next character.
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.cursor += c.len_utf8();
+            return true;
+        }
+        false
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.cursor += 1;
+        }
+    }
+
+    /// Reads an identifier. `nav-item`, `xlink\:href`
+    fn name(&mut self) -> Result<String, SelectorError> {
+        let bgn = self.cursor;
+        let mut name = String::new();
+        loop {
+            match self.peek() {
+                Some('\\') => {
+                    self.cursor += 1;
+                    name.push(self.escape()?);
+                }
+                Some(c) if is_name_char(c) => {
+                    self.cursor += c.len_utf8();
+                    name.push(c);
+                }
+                _ => break,
+            }
+        }
+
+        match self.peek() {
+            _ if self.cursor > bgn => Ok(name),
+            Some(_) => Err(SelectorError::UnexpectedCharacter(self.cursor)),
+            None => Err(SelectorError::UnexpectedEnd(self.cursor)),
+        }
+    }
+
+    /// Reads an escaped character after its `\`. `:`, `3A `
+    fn escape(&mut self) -> Result<char, SelectorError> {
+        let rest = &self.selector[self.cursor..];
+        let hex_len = rest.chars().take(6).take_while(char::is_ascii_hexdigit).count();
+        if hex_len > 0 {
+            let code = u32::from_str_radix(&rest[..hex_len], 16).unwrap_or(0);
+            self.cursor += hex_len;
+            // a whitespace after the hex digits ends the escape
+            if self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+                self.cursor += 1;
+            }
+            return Ok(char::from_u32(code).filter(|&c| c != '\0').unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+
+        match self.peek() {
+            Some('\n') => Err(SelectorError::UnexpectedCharacter(self.cursor)),
+            Some(c) => {
+                self.cursor += c.len_utf8();
+                Ok(c)
+            }
+            None => Err(SelectorError::UnexpectedEnd(self.cursor)),
+        }
+    }
+
+    /// Reads a pseudo-class after its `:`. `first-child`, `nth-child(2n+1)`
+    fn pseudo_class(&mut self) -> Result<PseudoClass, SelectorError> {
+        let bgn = self.cursor;
+        let name = self.name()?.to_ascii_lowercase();
+        let pseudo_class = match name.as_str() {
+            "first-child" => PseudoClass::FirstChild,
+            "last-child" => PseudoClass::LastChild,
+            "only-child" => PseudoClass::OnlyChild,
+            "empty" => PseudoClass::Empty,
+            "nth-child" => {
+                if !self.eat('(') {
+                    return self.unexpected();
+                }
+                let argument_bgn = self.cursor;
+                let len = self.selector[self.cursor..]
+                    .find(')')
+                    .ok_or(SelectorError::UnexpectedEnd(self.selector.len()))?;
+                let (a, b) = parse_nth(&self.selector[argument_bgn..argument_bgn + len])
+                    .ok_or(SelectorError::UnexpectedCharacter(argument_bgn))?;
+                self.cursor += len + 1;
+                PseudoClass::NthChild(a, b)
+            }
+            "not" | "has" => {
+                if !self.eat('(') {
+                    return self.unexpected();
+                }
+                let pseudo_class = match name.as_str() {
+                    "not" => PseudoClass::Not(self.selector_list()?),
+                    _ => PseudoClass::Has(self.relative_selector_list()?),
+                };
+                self.skip_whitespace();
+                if !self.eat(')') {
+                    return self.unexpected();
+                }
+                pseudo_class
+            }
+            "contains" => {
+                if !self.eat('(') {
+                    return self.unexpected();
+                }
+                self.skip_whitespace();
+                let text = self.value()?;
+                self.skip_whitespace();
+                if !self.eat(')') {
+                    return self.unexpected();
+                }
+                PseudoClass::Contains(text)
+            }
+            _ => return Err(SelectorError::UnexpectedCharacter(bgn)),
+        };
+        Ok(pseudo_class)
+    }
+
+    /// Reads an identifier or a quoted string without its quotes. `'a b'`
+    fn value(&mut self) -> Result<String, SelectorError> {
+        match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                let bgn = self.cursor;
+                self.cursor += 1;
+                match self.selector[self.cursor..].find(quote) {
+                    Some(len) => {
+                        let value = self.selector[self.cursor..self.cursor + len].to_string();
+                        self.cursor += len + 1;
+                        Ok(value)
+                    }
+                    None => Err(SelectorError::UnexpectedEnd(bgn)),
+                }
+            }
+            _ => self.name(),
+        }
+    }
+
+    /// Reads an attribute selector after its `[`. `href^="https:"]`
+    fn attribute(&mut self) -> Result<AttrSelector, SelectorError> {
+        self.skip_whitespace();
+        let name = self.name()?.to_ascii_lowercase();
+        self.skip_whitespace();
+        if self.eat(']') {
+            return Ok(AttrSelector { name, value: None });
+        }
+
+        let op = match self.peek() {
+            Some('=') => AttrOp::Exact,
+            Some('~') => AttrOp::WordInList,
+            Some('|') => AttrOp::DashMatch,
+            Some('^') => AttrOp::Prefix,
+            Some('$') => AttrOp::Suffix,
+            Some('*') => AttrOp::Contains,
+            Some(_) => return Err(SelectorError::UnexpectedCharacter(self.cursor)),
+            None => return Err(SelectorError::UnexpectedEnd(self.cursor)),
+        };
+        self.cursor += 1;
+        if op != AttrOp::Exact && !self.eat('=') {
+            return match self.peek() {
+                Some(_) => Err(SelectorError::UnexpectedCharacter(self.cursor)),
+                None => Err(SelectorError::UnexpectedEnd(self.cursor)),
+            };
+        }
+
+        self.skip_whitespace();
+        let value = self.value()?;
+
+        self.skip_whitespace();
+        match self.peek() {
+            Some(']') => {
+                self.cursor += 1;
+                Ok(AttrSelector {
+                    name,
+                    value: Some((op, value)),
+                })
+            }
+            Some(_) => Err(SelectorError::UnexpectedCharacter(self.cursor)),
+            None => Err(SelectorError::UnexpectedEnd(self.cursor)),
+        }
+    }
+}
+
+/// Parses the argument of `:nth-child`. `2n+1`, `-n + 3`, `odd`
+fn parse_nth(argument: &str) -> Option<(i64, i64)> {
+    let argument = argument.trim().to_ascii_lowercase();
+    match argument.as_str() {
+        "odd" => return Some((2, 1)),
+        "even" => return Some((2, 0)),
+        _ => {}
+    }
+
+    let (a, b) = match argument.find('n') {
+        Some(n) => {
+            let a = match &argument[..n] {
+                "" | "+" => 1,
+                "-" => -1,
+                a => a.parse().ok()?,
+            };
+            let b = argument[n + 1..].trim_start();
+            let b = match b.chars().next() {
+                None => 0,
+                Some(sign @ ('+' | '-')) => {
+                    let digits = b[1..].trim_start();
+                    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+                        return None;
+                    }
+                    let b: i64 = digits.parse().ok()?;
+                    if sign == '-' {
+                        -b
+                    } else {
+                        b
+                    }
+                }
+                Some(_) => return None,
+            };
+            (a, b)
+        }
+        None => (0, argument.parse().ok()?),
+    };
+    Some((a, b))
+}
+
+/// Returns true if `c` starts a compound selector.
+fn starts_compound(c: char) -> bool {
+    is_name_char(c) || "\\*#.[:".contains(c)
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selector_test() {
+        let root = crate::parse(
+            r#"<div id="main" class="a b"><p lang="en-US" data-x="1">x</p><P class="b">y</P><svg viewBox="0 0 1 1"></svg></div>"#,
+        )
+        .unwrap();
+        let root = root.get_copy_of_internal_arc();
+        let count = |selector: &str| select(&root, &Selector::parse(selector).unwrap()).len();
+
+        assert_eq!(count("*"), 4);
+        assert_eq!(count("root"), 0);
+        assert_eq!(count("P"), 2);
+        assert_eq!(count("#main.b"), 1);
+        assert_eq!(count(".b"), 2);
+        assert_eq!(count(".a.b.c"), 0);
+        assert_eq!(count("[lang|=en]"), 1);
+        assert_eq!(count("[lang|=e]"), 0);
+        assert_eq!(count("p[ data-x = '1' ]"), 1);
+        assert_eq!(count("[class~=b]"), 2);
+        assert_eq!(count("[class^='']"), 0);
+        assert_eq!(count("svg[viewbox$=\"1 1\"]"), 1);
+        assert_eq!(count("[id*=ai]"), 1);
+
+        assert_eq!(Selector::parse(""), Err(SelectorError::UnexpectedEnd(0)));
+        assert_eq!(Selector::parse("a ! b"), Err(SelectorError::UnexpectedCharacter(2)));
+        assert_eq!(Selector::parse_start_tag("a > b"), Err(SelectorError::UnexpectedCharacter(2)));
+        assert_eq!(Selector::parse("a[href"), Err(SelectorError::UnexpectedEnd(6)));
+        assert_eq!(Selector::parse("a[href='x]"), Err(SelectorError::UnexpectedEnd(7)));
+        assert_eq!(Selector::parse("a[href!=x]"), Err(SelectorError::UnexpectedCharacter(6)));
+        assert_eq!("a.x".parse::<Selector>().unwrap(), Selector::parse(" a.x ").unwrap());
+
+        // escaped characters in identifiers
+        let root = crate::parse(r##"<svg xlink:href="#a"></svg><b @click="go" :prop="x" class="w-1/2">y</b>"##).unwrap();
+        let root = root.get_copy_of_internal_arc();
+        let count = |selector: &str| select(&root, &Selector::parse(selector).unwrap()).len();
+        assert_eq!(count("[xlink\\:href]"), 1);
+        assert_eq!(count("[\\@click=go][\\:prop]"), 1);
+        assert_eq!(count(".w-1\\/2"), 1);
+        assert_eq!(count("\\62 "), 1);
+        assert_eq!(Selector::parse("a\\"), Err(SelectorError::UnexpectedEnd(2)));
+    }
+
+    #[test]
+    fn pseudo_class_test() {
+        let root = crate::parse(
+            "<ul><li>1</li><!-- c --><li>2</li>text<li>3</li><li>4</li><li>5</li></ul><p><b></b></p><i><!-- c --></i>",
+        )
+        .unwrap();
+        let root = root.get_copy_of_internal_arc();
+        let texts = |selector: &str| -> Vec<String> {
+            select(&root, &Selector::parse(selector).unwrap()).iter().map(|node| node.inner_text()).collect()
+        };
+
+        assert_eq!(texts("li:first-child"), ["1"]);
+        assert_eq!(texts("li:LAST-CHILD"), ["5"]);
+        assert_eq!(texts("li:nth-child(2)"), ["2"]);
+        assert_eq!(texts("li:nth-child(even)"), ["2", "4"]);
+        assert_eq!(texts("li:nth-child(2n+1)"), ["1", "3", "5"]);
+        assert_eq!(texts("li:nth-child(-n + 3)"), ["1", "2", "3"]);
+        assert_eq!(texts("li:nth-child(n+4)"), ["4", "5"]);
+        assert_eq!(texts("li:nth-child(3n-1)"), ["2", "5"]);
+        assert_eq!(texts("li:nth-child(0n)"), Vec::<String>::new());
+        assert_eq!(texts("li:first-child:last-child"), Vec::<String>::new());
+        assert_eq!(select(&root, &Selector::parse(":only-child").unwrap()).len(), 1);
+        assert_eq!(select(&root, &Selector::parse(":empty").unwrap()).len(), 2);
+        assert_eq!(select(&root, &Selector::parse("ul:first-child").unwrap()).len(), 1);
+
+        let mut tag = Tag::new("li");
+        assert!(Selector::parse("li").unwrap().matches_tag(&tag));
+        assert!(!Selector::parse("li:first-child").unwrap().matches_tag(&tag));
+        tag.set_name("b");
+        assert!(Selector::parse("b:first-child").unwrap().matches(&crate::dom::Node::new(Payload::Tag(tag))));
+
+        assert_eq!(Selector::parse("li:hover"), Err(SelectorError::UnexpectedCharacter(3)));
+        assert_eq!(Selector::parse("li:nth-child(2n+)"), Err(SelectorError::UnexpectedCharacter(13)));
+        assert_eq!(Selector::parse("li:nth-child(2"), Err(SelectorError::UnexpectedEnd(14)));
+        assert_eq!(Selector::parse("li:nth-child"), Err(SelectorError::UnexpectedEnd(12)));
+        assert_eq!(Selector::parse_start_tag("li:empty"), Err(SelectorError::UnexpectedCharacter(2)));
+    }
+
+    #[test]
+    fn combinator_test() {
+        let root = crate::parse(concat!(
+            r#"<div class="post"><h2>A</h2><p>1</p><!-- c --><p>2</p><section><p>3</p></section></div>"#,
+            r#"<div><h2>B</h2><ul><li><a href="/x">x</a></li><li class="on">y</li></ul></div>"#,
+        ))
+        .unwrap();
+        let root = root.get_copy_of_internal_arc();
+        let texts = |selector: &str| -> Vec<String> {
+            select(&root, &Selector::parse(selector).unwrap()).iter().map(|node| node.inner_text()).collect()
+        };
+
+        assert_eq!(texts("div p"), ["1", "2", "3"]);
+        assert_eq!(texts(".post>p"), ["1", "2"]);
+        assert_eq!(texts("h2 + p"), ["1"]);
+        assert_eq!(texts("h2 ~ p"), ["1", "2"]);
+        assert_eq!(texts("p + p"), ["2"]);
+        assert_eq!(texts("div > section p:first-child"), ["3"]);
+        assert_eq!(texts("div ul > li ~ li"), ["y"]);
+        assert_eq!(texts("div li a"), ["x"]);
+        assert_eq!(texts("root p"), Vec::<String>::new());
+
+        assert_eq!(texts("li:not(.on)"), ["x"]);
+        assert_eq!(texts("p:not(section > p, h2 + p)"), ["2"]);
+        assert_eq!(texts("div:has(> ul)"), ["B\nx\ny"]);
+        assert_eq!(texts("div:has(section p)"), ["A\n1\n2\n3"]);
+        assert_eq!(texts("h2:has(+ p)"), ["A"]);
+        assert_eq!(texts("h2:has(~ section, ~ ul li a[href])"), ["A", "B"]);
+        assert_eq!(texts("li:has(a) + li"), ["y"]);
+        assert_eq!(texts("div:has(> li)"), Vec::<String>::new());
+        assert_eq!(texts("div:not(:has(h2))"), Vec::<String>::new());
+
+        assert_eq!(Selector::parse("a >"), Err(SelectorError::UnexpectedEnd(3)));
+        assert_eq!(Selector::parse("> a"), Err(SelectorError::UnexpectedCharacter(0)));
+        assert_eq!(Selector::parse("a + > b"), Err(SelectorError::UnexpectedCharacter(4)));
+        assert_eq!(Selector::parse("a:not()"), Err(SelectorError::UnexpectedCharacter(6)));
+        assert_eq!(Selector::parse("a:not(b"), Err(SelectorError::UnexpectedEnd(7)));
+        assert_eq!(Selector::parse("a:has(> b,)"), Err(SelectorError::UnexpectedCharacter(10)));
+        assert_eq!(Selector::parse_start_tag("a b"), Err(SelectorError::UnexpectedCharacter(2)));
+        assert!(!Selector::parse("a b").unwrap().matches_tag(&Tag::new("b")));
+    }
+
+    #[test]
+    fn select_groups_test() {
+        let root = crate::parse(r#"<h1>T</h1><div><h2 class="x">A</h2><p>p</p><h3>B</h3></div><h2>C</h2>"#).unwrap();
+        let groups = |selectors: &str| -> Vec<(String, usize)> {
+            let selected = root.select(selectors).unwrap();
+            selected.iter().map(|(node, group)| (node.inner_text(), *group)).collect()
+        };
+
+        assert_eq!(groups("h1, h2, h3"), [("T".into(), 0), ("A".into(), 1), ("B".into(), 2), ("C".into(), 1)]);
+        // the first selector that matches is the group
+        assert_eq!(groups("div > *, h2"), [("A".into(), 0), ("p".into(), 0), ("B".into(), 0), ("C".into(), 1)]);
+        assert_eq!(groups(".x, h2.x"), [("A".to_string(), 0)]);
+        assert!(groups("li").is_empty());
+        assert_eq!(root.select("h1,"), Err(SelectorError::UnexpectedEnd(3)));
+        assert_eq!(Selector::parse_list("h1, h2").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn select_within_test() {
+        let root = crate::parse(concat!(
+            r#"<div class="list"><article class="card"><h2>A</h2><p>1</p>"#,
+            r#"<article class="card"><h2>B</h2><p>2</p></article></article><p>3</p></div>"#,
+        ))
+        .unwrap();
+        let card = root.query_class("card").next().unwrap();
+        let texts = |nodes: Vec<Rc<NodeData>>| -> Vec<String> { nodes.iter().map(|node| node.inner_text()).collect() };
+
+        assert_eq!(texts(card.select_within("h2").unwrap()), ["A", "B"]);
+        assert_eq!(texts(card.select_within(".card p").unwrap()), ["1", "2"]);
+        assert_eq!(texts(card.select_within("article > p").unwrap()), ["1", "2"]);
+        assert_eq!(texts(card.select_within(".card .card p").unwrap()), ["2"]);
+        // the ancestors and siblings of `card` are outside
+        assert!(card.select_within(".list p").unwrap().is_empty());
+        assert!(card.select_within(".list > article").unwrap().is_empty());
+        assert_eq!(select(&card, &Selector::parse(".list p").unwrap()).len(), 2);
+
+        assert_eq!(texts(card.select_within_boundary("p", "article").unwrap()), ["1"]);
+        assert_eq!(texts(card.select_within_boundary("h2", "h2 ~ *").unwrap()), ["A"]);
+        // the boundary is matched within `card` too
+        assert_eq!(texts(card.select_within_boundary("h2", ".list article").unwrap()), ["A", "B"]);
+        assert_eq!(card.select_within_boundary("p", "a["), Err(SelectorError::UnexpectedEnd(2)));
+        assert_eq!(root.select_within("p:not(article p)").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn contains_test() {
+        let root = crate::parse(
+            "<table><tr><td>Item</td><td>1</td></tr><tr><td><b>Total</b></td><td>9</td></tr></table><p>total cost</p>",
+        )
+        .unwrap();
+        let root = root.get_copy_of_internal_arc();
+        let names = |selector: &str| -> Vec<String> {
+            select(&root, &Selector::parse(selector).unwrap()).iter().map(|node| node.tag_name().unwrap().to_string()).collect()
+        };
+
+        assert_eq!(names("td:contains(Total)"), ["td"]);
+        assert_eq!(names(":contains( 'Total' )"), ["table", "tr", "td", "b"]);
+        assert_eq!(names("p:contains(\"total cost\")"), ["p"]);
+        assert_eq!(names(":contains(tal):last-child"), ["tr", "b", "p"]);
+        assert!(names("td:contains(total)").is_empty());
+
+        assert_eq!(Selector::parse("td:contains(a b)"), Err(SelectorError::UnexpectedCharacter(14)));
+        assert_eq!(Selector::parse("td:contains('a"), Err(SelectorError::UnexpectedEnd(12)));
+        assert_eq!(Selector::parse("td:contains"), Err(SelectorError::UnexpectedEnd(11)));
+    }
+}
@@ -0,0 +1,113 @@
+//! Counting the words and characters of a tree.
+//!
+//! # Examples
+//! ```rust
+//! let root = html::parse("<h1>Title</h1><p>Hello, <b>big</b> world!</p><script>x = 1</script>").unwrap();
+//! let stats = html::stats::text_stats(&root);
+//!
+//! assert_eq!(stats.words, 4);
+//! assert_eq!(stats.characters, 23);
+//! assert_eq!(stats.paragraphs, 2);
+//! assert_eq!(stats.text_lengths["p"], 12);
+//! assert_eq!(stats.text_lengths["b"], 3);
+//! ```
+
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use crate::dom::{NodeData, Payload, HIDDEN_ELEMENTS};
+
+/// What [`text_stats`] counts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextStats {
+    /// The words of the text, separated by whitespace.
+    pub words: usize,
+    /// The characters of the text, whitespace included.
+    pub characters: usize,
+    /// The lines of the text that are not blank, one for each `<p>`,
+    /// `<li>`, heading or other block with text.
+    pub paragraphs: usize,
+    /// The characters of the text directly in the elements with each
+    /// lowercased name, whitespace left out. `p` of `<p>a a <b>b</b></p>` is 2.
+    pub text_lengths: BTreeMap<String, usize>,
+}
+
+/// Counts the text of `node` and its descendants, which is the text
+/// [`inner_text`](NodeData::inner_text) returns.
+pub fn text_stats(node: &NodeData) -> TextStats {
+    let text = node.inner_text();
+    let mut stats = TextStats {
+        words: text.split_whitespace().count(),
+        characters: text.chars().count(),
+        paragraphs: text.lines().filter(|line| !line.trim().is_empty()).count(),
+        text_lengths: BTreeMap::new(),
+    };
+
+    let mut stack = Vec::new();
+    add_text_lengths(node, &mut stats.text_lengths, &mut stack);
+    while let Some(element) = stack.pop() {
+        add_text_lengths(&element, &mut stats.text_lengths, &mut stack);
+    }
+
+    stats
+}
+
+/// Adds the text directly in the element `node` to `text_lengths` and
+/// pushes its child elements to `stack`. Hidden elements are skipped.
+fn add_text_lengths(node: &NodeData, text_lengths: &mut BTreeMap<String, usize>, stack: &mut Vec<Rc<NodeData>>) {
+    let name = match &*node.get_payload() {
+        Payload::Tag(tag) => tag.get_name().to_ascii_lowercase(),
+        _ => return,
+    };
+    if HIDDEN_ELEMENTS.contains(&name.as_str()) {
+        return;
+    }
+
+    let mut length = 0;
+    for child in node.get_children().iter() {
+        match &*child.get_payload() {
+            Payload::Text(text) => length += text.chars().filter(|c| !c.is_whitespace()).count(),
+            Payload::Tag(_) => stack.push(Rc::clone(child)),
+            _ => {}
+        }
+    }
+    if length > 0 && !node.is_document_root() {
+        *text_lengths.entry(name).or_default() += length;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn text_stats_test() {
+        let root = parse(concat!(
+            "<html><head><title>Not counted</title></head><body>\n",
+            "  <h1>A  title</h1>\n  <ul><li>one</li><li>two\n three</li></ul>\n",
+            "  <p>Ünïcode <i>text</i><!-- skipped --><noscript>hidden</noscript></p>\n</body></html>",
+        ))
+        .unwrap();
+        let stats = text_stats(&root);
+
+        assert_eq!(root.inner_text(), "A title\none\ntwo three\nÜnïcode text");
+        assert_eq!(stats.words, 7);
+        assert_eq!(stats.characters, 34);
+        assert_eq!(stats.paragraphs, 4);
+        let lengths: Vec<(&str, usize)> = stats.text_lengths.iter().map(|(name, len)| (name.as_str(), *len)).collect();
+        assert_eq!(lengths, [("h1", 6), ("i", 4), ("li", 11), ("p", 7)]);
+
+        assert_eq!(text_stats(&parse("").unwrap()), TextStats::default());
+
+        // whatever the case of the names
+        let stats = text_stats(&parse("<P>a <SCRIPT>x</SCRIPT></P><P>b<BR>c</P>").unwrap());
+        assert_eq!((stats.words, stats.paragraphs), (3, 3));
+        let lengths: Vec<(&str, usize)> = stats.text_lengths.iter().map(|(name, len)| (name.as_str(), *len)).collect();
+        assert_eq!(lengths, [("p", 3)]);
+
+        // deeply nested elements do not overflow the stack
+        let html = "<div>".repeat(100_000) + "a";
+        assert_eq!(text_stats(&parse(&html).unwrap()).words, 1);
+    }
+}
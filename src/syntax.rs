@@ -0,0 +1,803 @@
+//! A concrete syntax tree that keeps every character of the document.
+//!
+//! The [`dom`](crate::dom) holds what a document means, and the syntax tree
+//! holds how it is written: each tag name, attribute, quote and run of
+//! whitespace is a [`SyntaxToken`] with its range in the source, and the
+//! tokens of the tree in order are the source again. Parsing never fails.
+//! Markup that cannot be parsed, such as a stray end tag or a tag that the
+//! end of the document cuts off, is put in a node of [`NodeKind::Error`],
+//! so that an editor can highlight, fold and check a document while it is
+//! being written.
+//!
+//! # Examples
+//! ```rust
+//! use html::syntax::{self, NodeKind, SyntaxErrorKind, TokenKind};
+//!
+//! let source = "<ul>\n  <li class='a'>one &amp; two</li>\n</ul></p>";
+//! let tree = syntax::parse(source);
+//! assert_eq!(tree.root().text(), source);
+//!
+//! let ul = tree.root().child_nodes().next().unwrap();
+//! assert_eq!(ul.kind(), NodeKind::Element);
+//! assert_eq!(ul.name(), Some("ul"));
+//! assert_eq!(tree.folding_ranges(), [(1, 3)]);
+//!
+//! let token = tree.token_at(source.find("&amp;").unwrap()).unwrap();
+//! assert_eq!(token.kind, TokenKind::CharacterReference);
+//!
+//! let errors = tree.errors();
+//! assert_eq!(errors[0].kind, SyntaxErrorKind::StrayEndTag);
+//! assert_eq!(tree.position(errors[0].range.start).line, 3);
+//! ```
+
+use std::ops::Range;
+
+use crate::dom::{drop_descendants, OwnsChildren, Tag};
+use crate::parser::{has_optional_end_tag, is_end_implied};
+use crate::Position;
+
+/// The kinds of [`SyntaxToken`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// `<` of a start tag.
+    TagOpen,
+    /// `</` of an end tag.
+    EndTagOpen,
+    /// `>` of a tag.
+    TagClose,
+    /// `/>` of a self-closing tag.
+    SelfClosingTagClose,
+    TagName,
+    AttributeName,
+    /// `=` between an attribute name and its value.
+    Equals,
+    /// An attribute value with its quotes. `"a"`, `'a'`, `a`
+    AttributeValue,
+    /// Whitespace in a tag, or text that is only whitespace.
+    Whitespace,
+    Text,
+    /// A character reference in text. `&amp;`, `&#169;`
+    CharacterReference,
+    /// The content of `<script>`, `<style>`, `<textarea>` or `<title>`.
+    RawText,
+    /// A comment with its delimiters. `<!-- a -->`
+    /// CDATA sections and `<?xml ?>` are comments too.
+    Comment,
+    /// `<!DOCTYPE html>`
+    Doctype,
+    /// Characters in a tag that are not allowed there. `/` in `<p / a>`
+    Invalid,
+}
+
+/// The kinds of [`SyntaxNode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    /// The whole document, the root of the tree.
+    Document,
+    /// An element with its start tag, its content and its end tag if it has one.
+    Element,
+    StartTag,
+    EndTag,
+    /// An attribute with its name, and its `=` and value if it has one.
+    Attribute,
+    /// Markup that cannot be parsed. Never [`SyntaxErrorKind::UnclosedElement`].
+    Error(SyntaxErrorKind),
+}
+
+/// The kinds of [`SyntaxError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxErrorKind {
+    /// An end tag that matches no open element.
+    StrayEndTag,
+    /// A tag or doctype that the end of the document cuts off. `<p class=`
+    UnterminatedTag,
+    /// A comment without `-->`.
+    UnterminatedComment,
+    /// Characters in a tag that are not allowed there.
+    UnexpectedCharacter,
+    /// An element without an end tag that may not be omitted. The range is its start tag.
+    UnclosedElement,
+}
+
+/// A problem in the document. Returned by [`SyntaxTree::errors`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub kind: SyntaxErrorKind,
+    /// The byte range of the source.
+    pub range: Range<usize>,
+}
+
+/// A token of the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxToken<'a> {
+    pub kind: TokenKind,
+    /// The byte range of the source.
+    pub range: Range<usize>,
+    /// The source in `range`.
+    pub text: &'a str,
+}
+
+/// A child of a [`SyntaxNode`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxElement<'a> {
+    Node(SyntaxNode<'a>),
+    Token(SyntaxToken<'a>),
+}
+
+/// A node of the syntax tree, which covers the tokens of its descendants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxNode<'a> {
+    kind: NodeKind,
+    range: Range<usize>,
+    children: Vec<SyntaxElement<'a>>,
+}
+
+impl Drop for SyntaxNode<'_> {
+    fn drop(&mut self) {
+        drop_descendants(self);
+    }
+}
+
+impl<'a> OwnsChildren for SyntaxNode<'a> {
+    type Child = SyntaxElement<'a>;
+
+    fn take_children(&mut self) -> Vec<SyntaxElement<'a>> {
+        std::mem::take(&mut self.children)
+    }
+
+    fn into_node(child: SyntaxElement<'a>) -> Option<SyntaxNode<'a>> {
+        match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        }
+    }
+}
+
+impl<'a> SyntaxNode<'a> {
+    /// Creates a node of `children`, which starts at `start` if it has none.
+    fn new(kind: NodeKind, children: Vec<SyntaxElement<'a>>, start: usize) -> SyntaxNode<'a> {
+        let range = match (children.first(), children.last()) {
+            (Some(first), Some(last)) => first.range().start..last.range().end,
+            _ => start..start,
+        };
+        SyntaxNode { kind, range, children }
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        self.kind
+    }
+
+    /// Returns the byte range of the source.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    pub fn children(&self) -> &[SyntaxElement<'a>] {
+        &self.children
+    }
+
+    /// Returns the children that are nodes.
+    pub fn child_nodes(&self) -> impl Iterator<Item = &SyntaxNode<'a>> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// Returns the tokens of the descendants in order.
+    pub fn tokens(&self) -> Tokens<'_, 'a> {
+        Tokens {
+            stack: vec![self.children.iter()],
+        }
+    }
+
+    /// Returns the source of the node.
+    pub fn text(&self) -> String {
+        self.tokens().map(|token| token.text).collect()
+    }
+
+    /// Returns the name of an element, a tag or an attribute as it is written.
+    pub fn name(&self) -> Option<&'a str> {
+        let kind = match self.kind {
+            NodeKind::Element => return self.start_tag()?.name(),
+            NodeKind::StartTag | NodeKind::EndTag => TokenKind::TagName,
+            NodeKind::Attribute => TokenKind::AttributeName,
+            _ => return None,
+        };
+        self.children.iter().find_map(|child| match child {
+            SyntaxElement::Token(token) if token.kind == kind => Some(token.text),
+            _ => None,
+        })
+    }
+
+    /// Returns the start tag of an element.
+    pub fn start_tag(&self) -> Option<&SyntaxNode<'a>> {
+        self.find_child(NodeKind::StartTag)
+    }
+
+    /// Returns the end tag of an element, or `None` if it was omitted.
+    pub fn end_tag(&self) -> Option<&SyntaxNode<'a>> {
+        self.find_child(NodeKind::EndTag)
+    }
+
+    fn find_child(&self, kind: NodeKind) -> Option<&SyntaxNode<'a>> {
+        match self.kind {
+            NodeKind::Element => self.child_nodes().find(|node| node.kind == kind),
+            _ => None,
+        }
+    }
+}
+
+impl SyntaxElement<'_> {
+    /// Returns the byte range of the source.
+    pub fn range(&self) -> Range<usize> {
+        match self {
+            SyntaxElement::Node(node) => node.range(),
+            SyntaxElement::Token(token) => token.range.clone(),
+        }
+    }
+
+    // the text of a token
+    fn text(&self) -> &str {
+        match self {
+            SyntaxElement::Token(token) => token.text,
+            SyntaxElement::Node(_) => "",
+        }
+    }
+}
+
+/// An iterator over the tokens of a [`SyntaxNode`] in order.
+/// Created by [`SyntaxNode::tokens`].
+#[derive(Debug, Clone)]
+pub struct Tokens<'t, 'a> {
+    // the children still to visit of each node on the way down
+    stack: Vec<std::slice::Iter<'t, SyntaxElement<'a>>>,
+}
+
+impl<'t, 'a> Iterator for Tokens<'t, 'a> {
+    type Item = &'t SyntaxToken<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some(SyntaxElement::Token(token)) => return Some(token),
+                Some(SyntaxElement::Node(node)) => self.stack.push(node.children.iter()),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// A document as it is written. Created by [`parse`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxTree<'a> {
+    source: &'a str,
+    root: SyntaxNode<'a>,
+    // the offset of the first character of each line
+    line_starts: Vec<usize>,
+}
+
+impl<'a> SyntaxTree<'a> {
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    /// Returns the node of [`NodeKind::Document`].
+    pub fn root(&self) -> &SyntaxNode<'a> {
+        &self.root
+    }
+
+    /// Returns the tokens in order, which cover the whole source.
+    pub fn tokens(&self) -> Tokens<'_, 'a> {
+        self.root.tokens()
+    }
+
+    /// Returns the token that the character at `offset` is in.
+    pub fn token_at(&self, offset: usize) -> Option<&SyntaxToken<'a>> {
+        self.tokens().find(|token| token.range.contains(&offset))
+    }
+
+    /// Returns the line and column of `offset`, as a [`Diagnostic`](crate::Diagnostic) has them.
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|&line_start| line_start <= offset);
+        let line_start = self.line_starts[line - 1];
+        Position {
+            offset,
+            line,
+            column: self.source.get(line_start..offset).map_or(0, |text| text.chars().count()) + 1,
+        }
+    }
+
+    /// Returns the problems in the document in the order they appear: the
+    /// error nodes, and the elements that are not closed.
+    pub fn errors(&self) -> Vec<SyntaxError> {
+        let mut errors = Vec::new();
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            match node.kind {
+                NodeKind::Error(kind) => errors.push(SyntaxError { kind, range: node.range() }),
+                NodeKind::Element if node.end_tag().is_none() => {
+                    let start_tag = node.start_tag().unwrap();
+                    let name = node.name().unwrap_or_default();
+                    let tag = Tag::new(name);
+                    let is_self_closing = start_tag.children.last().is_some_and(|child| {
+                        matches!(child, SyntaxElement::Token(token) if token.kind == TokenKind::SelfClosingTagClose)
+                    });
+                    if !is_self_closing && !tag.is_void_element() && !has_optional_end_tag(name) {
+                        errors.push(SyntaxError {
+                            kind: SyntaxErrorKind::UnclosedElement,
+                            range: start_tag.range(),
+                        });
+                    }
+                }
+                _ => {}
+            }
+            stack.extend(node.child_nodes().collect::<Vec<_>>().into_iter().rev());
+        }
+        errors.sort_by_key(|error| error.range.start);
+        errors
+    }
+
+    /// Returns the first and last lines of the elements and comments that
+    /// span more than one line, to fold them in an editor.
+    pub fn folding_ranges(&self) -> Vec<(usize, usize)> {
+        let mut ranges = Vec::new();
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            for child in node.children.iter().rev() {
+                let range = match child {
+                    SyntaxElement::Node(child) if child.kind == NodeKind::Element => {
+                        stack.push(child);
+                        child.range()
+                    }
+                    SyntaxElement::Node(child) => {
+                        stack.push(child);
+                        continue;
+                    }
+                    SyntaxElement::Token(token) if token.kind == TokenKind::Comment => token.range.clone(),
+                    SyntaxElement::Token(_) => continue,
+                };
+                let first = self.position(range.start).line;
+                let last = self.position(range.end.saturating_sub(1)).line;
+                if first < last {
+                    ranges.push((first, last));
+                }
+            }
+        }
+        ranges.sort();
+        ranges
+    }
+}
+
+/// Parses `source` into a syntax tree, which has all of its characters.
+pub fn parse(source: &str) -> SyntaxTree<'_> {
+    let mut builder = Builder {
+        source,
+        pos: 0,
+        open: vec![OpenNode {
+            kind: NodeKind::Document,
+            name: String::new(),
+            children: Vec::new(),
+            start: 0,
+        }],
+    };
+    while builder.pos < source.len() {
+        builder.step();
+    }
+    while builder.open.len() > 1 {
+        builder.close();
+    }
+
+    let document = builder.open.pop().unwrap();
+    let mut line_starts = vec![0];
+    line_starts.extend(source.match_indices('\n').map(|(i, _)| i + 1));
+    SyntaxTree {
+        source,
+        root: SyntaxNode::new(NodeKind::Document, document.children, 0),
+        line_starts,
+    }
+}
+
+/// The document or an element whose end is not parsed yet.
+struct OpenNode<'a> {
+    kind: NodeKind,
+    // the lowercased name of an element
+    name: String,
+    children: Vec<SyntaxElement<'a>>,
+    start: usize,
+}
+
+struct Builder<'a> {
+    source: &'a str,
+    pos: usize,
+    // the document and the open elements, the innermost last
+    open: Vec<OpenNode<'a>>,
+}
+
+impl<'a> Builder<'a> {
+    fn rest(&self) -> &'a str {
+        &self.source[self.pos..]
+    }
+
+    /// Parses the markup at `pos` and adds it to the innermost open node.
+    fn step(&mut self) {
+        let rest = self.rest();
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            match comment.find("-->") {
+                Some(len) => self.add_token(TokenKind::Comment, 4 + len + 3),
+                None => {
+                    let token = self.token(TokenKind::Comment, rest.len());
+                    self.add_error(SyntaxErrorKind::UnterminatedComment, vec![token]);
+                }
+            }
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            let kind = match rest.get(..9) {
+                Some(start) if start.eq_ignore_ascii_case("<!doctype") => TokenKind::Doctype,
+                _ => TokenKind::Comment,
+            };
+            match rest.find('>') {
+                Some(len) => self.add_token(kind, len + 1),
+                None => {
+                    let token = self.token(kind, rest.len());
+                    self.add_error(SyntaxErrorKind::UnterminatedTag, vec![token]);
+                }
+            }
+        } else if rest.starts_with("</") && starts_with_letter(&rest[2..]) {
+            self.end_tag();
+        } else if rest.starts_with('<') && starts_with_letter(&rest[1..]) {
+            self.start_tag();
+        } else {
+            // up to the next markup, a '<' that starts nothing is text
+            let len = rest
+                .char_indices()
+                .skip(1)
+                .find(|&(i, c)| c == '<' && starts_markup(&rest[i..]))
+                .map_or(rest.len(), |(i, _)| i);
+            self.text(len);
+        }
+    }
+
+    fn start_tag(&mut self) {
+        let start = self.pos;
+        let mut children = vec![self.token(TokenKind::TagOpen, 1)];
+        children.push(self.token(TokenKind::TagName, name_len(self.rest())));
+        let name = children[1].text().to_ascii_lowercase();
+
+        let close = loop {
+            let rest = self.rest();
+            let len = whitespace_len(rest);
+            if len > 0 {
+                children.push(self.token(TokenKind::Whitespace, len));
+            } else if rest.is_empty() {
+                break None;
+            } else if rest.starts_with('>') {
+                children.push(self.token(TokenKind::TagClose, 1));
+                break Some(false);
+            } else if rest.starts_with("/>") {
+                children.push(self.token(TokenKind::SelfClosingTagClose, 2));
+                break Some(true);
+            } else if rest.starts_with('/') {
+                let token = self.token(TokenKind::Invalid, 1);
+                children.push(error(SyntaxErrorKind::UnexpectedCharacter, vec![token]));
+            } else {
+                children.push(self.attribute());
+            }
+        };
+
+        let self_closing = match close {
+            Some(self_closing) => self_closing,
+            None => {
+                let tag = SyntaxNode::new(NodeKind::StartTag, children, start);
+                self.add_error(SyntaxErrorKind::UnterminatedTag, vec![SyntaxElement::Node(tag)]);
+                return;
+            }
+        };
+
+        // `<li>one<li>two`, the second `<li>` closes the first
+        while self.open.len() > 1 && is_end_implied(&self.open.last().unwrap().name, &name) {
+            self.close();
+        }
+
+        let start_tag = SyntaxElement::Node(SyntaxNode::new(NodeKind::StartTag, children, start));
+        let tag = Tag::new(&name);
+        if self_closing || tag.is_void_element() {
+            let element = SyntaxNode::new(NodeKind::Element, vec![start_tag], start);
+            self.open.last_mut().unwrap().children.push(SyntaxElement::Node(element));
+            return;
+        }
+
+        self.open.push(OpenNode {
+            kind: NodeKind::Element,
+            name,
+            children: vec![start_tag],
+            start,
+        });
+        if tag.is_raw_text_element() || tag.is_escapable_raw_text_element() {
+            let len = raw_text_len(self.rest(), &self.open.last().unwrap().name);
+            if len > 0 {
+                self.add_token(TokenKind::RawText, len);
+            }
+        }
+    }
+
+    /// Parses an attribute, whose name is at `pos`.
+    fn attribute(&mut self) -> SyntaxElement<'a> {
+        let start = self.pos;
+        // the first character may be '=', which no other may be
+        let rest = self.rest();
+        let first_len = rest.chars().next().map_or(0, char::len_utf8);
+        let len = first_len + attribute_name_len(&rest[first_len..]);
+        let mut children = vec![self.token(TokenKind::AttributeName, len)];
+
+        // the whitespace around '=' belongs to the attribute, other whitespace to the tag
+        let rest = self.rest();
+        let before = whitespace_len(rest);
+        if rest[before..].starts_with('=') {
+            if before > 0 {
+                children.push(self.token(TokenKind::Whitespace, before));
+            }
+            children.push(self.token(TokenKind::Equals, 1));
+            let after = whitespace_len(self.rest());
+            if after > 0 {
+                children.push(self.token(TokenKind::Whitespace, after));
+            }
+
+            let rest = self.rest();
+            let len = match rest.chars().next() {
+                Some(quote @ ('"' | '\'')) => rest[1..].find(quote).map_or(rest.len(), |len| len + 2),
+                _ => rest.find(|c: char| is_whitespace(c) || c == '>').unwrap_or(rest.len()),
+            };
+            if len > 0 {
+                children.push(self.token(TokenKind::AttributeValue, len));
+            }
+        }
+
+        SyntaxElement::Node(SyntaxNode::new(NodeKind::Attribute, children, start))
+    }
+
+    fn end_tag(&mut self) {
+        let start = self.pos;
+        let mut children = vec![self.token(TokenKind::EndTagOpen, 2)];
+        children.push(self.token(TokenKind::TagName, name_len(self.rest())));
+        let name = children[1].text().to_ascii_lowercase();
+
+        let closed = loop {
+            let rest = self.rest();
+            let len = whitespace_len(rest);
+            if len > 0 {
+                children.push(self.token(TokenKind::Whitespace, len));
+            } else if rest.is_empty() {
+                break false;
+            } else if rest.starts_with('>') {
+                children.push(self.token(TokenKind::TagClose, 1));
+                break true;
+            } else {
+                // an end tag has no attributes
+                let len = rest.find(|c: char| is_whitespace(c) || c == '>').unwrap_or(rest.len());
+                let token = self.token(TokenKind::Invalid, len);
+                children.push(error(SyntaxErrorKind::UnexpectedCharacter, vec![token]));
+            }
+        };
+
+        let end_tag = SyntaxElement::Node(SyntaxNode::new(NodeKind::EndTag, children, start));
+        if !closed {
+            self.add_error(SyntaxErrorKind::UnterminatedTag, vec![end_tag]);
+            return;
+        }
+
+        // the element it closes, together with the elements opened in it
+        match self.open.iter().skip(1).rposition(|open| open.name == name) {
+            Some(index) => {
+                while self.open.len() > index + 2 {
+                    self.close();
+                }
+                self.open.last_mut().unwrap().children.push(end_tag);
+                self.close();
+            }
+            None => self.add_error(SyntaxErrorKind::StrayEndTag, vec![end_tag]),
+        }
+    }
+
+    /// Adds text of `len` bytes as text, whitespace and character references.
+    fn text(&mut self, len: usize) {
+        let end = self.pos + len;
+        let mut text_start = self.pos;
+        while self.pos < end {
+            let rest = &self.source[self.pos..end];
+            let reference_len = if rest.starts_with('&') { character_reference_len(rest) } else { 0 };
+            if reference_len > 0 {
+                self.text_run(text_start);
+                self.add_token(TokenKind::CharacterReference, reference_len);
+                text_start = self.pos;
+            } else {
+                self.pos += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        self.text_run(text_start);
+    }
+
+    /// Adds the text from `start` to `pos`, which has no character references.
+    fn text_run(&mut self, start: usize) {
+        let text = &self.source[start..self.pos];
+        if text.is_empty() {
+            return;
+        }
+        let kind = if text.chars().all(is_whitespace) { TokenKind::Whitespace } else { TokenKind::Text };
+        self.open.last_mut().unwrap().children.push(SyntaxElement::Token(SyntaxToken {
+            kind,
+            range: start..self.pos,
+            text,
+        }));
+    }
+
+    /// Returns the token of `len` bytes at `pos`, and moves `pos` after it.
+    fn token(&mut self, kind: TokenKind, len: usize) -> SyntaxElement<'a> {
+        let range = self.pos..self.pos + len;
+        self.pos += len;
+        SyntaxElement::Token(SyntaxToken {
+            kind,
+            text: &self.source[range.clone()],
+            range,
+        })
+    }
+
+    fn add_token(&mut self, kind: TokenKind, len: usize) {
+        let token = self.token(kind, len);
+        self.open.last_mut().unwrap().children.push(token);
+    }
+
+    fn add_error(&mut self, kind: SyntaxErrorKind, children: Vec<SyntaxElement<'a>>) {
+        let node = error(kind, children);
+        self.open.last_mut().unwrap().children.push(node);
+    }
+
+    /// Closes the innermost open element and adds it to its parent.
+    fn close(&mut self) {
+        let open = self.open.pop().unwrap();
+        let node = SyntaxNode::new(open.kind, open.children, open.start);
+        self.open.last_mut().unwrap().children.push(SyntaxElement::Node(node));
+    }
+}
+
+fn error(kind: SyntaxErrorKind, children: Vec<SyntaxElement<'_>>) -> SyntaxElement<'_> {
+    let start = children.first().map_or(0, |child| child.range().start);
+    SyntaxElement::Node(SyntaxNode::new(NodeKind::Error(kind), children, start))
+}
+
+fn is_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\x0C' | '\r')
+}
+
+fn whitespace_len(s: &str) -> usize {
+    s.find(|c: char| !is_whitespace(c)).unwrap_or(s.len())
+}
+
+fn starts_with_letter(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic())
+}
+
+/// Returns true if `s` starts with a tag, a comment or a doctype.
+fn starts_markup(s: &str) -> bool {
+    let rest = &s[1..];
+    starts_with_letter(rest)
+        || rest.starts_with('!')
+        || rest.starts_with('?')
+        || (rest.starts_with('/') && starts_with_letter(&rest[1..]))
+}
+
+/// Returns the length of the tag name at the start of `s`.
+fn name_len(s: &str) -> usize {
+    s.find(|c: char| is_whitespace(c) || c == '/' || c == '>').unwrap_or(s.len())
+}
+
+fn attribute_name_len(s: &str) -> usize {
+    s.find(|c: char| is_whitespace(c) || c == '/' || c == '>' || c == '=').unwrap_or(s.len())
+}
+
+/// Returns the length of the raw text up to the end tag named `name`.
+fn raw_text_len(s: &str, name: &str) -> usize {
+    let mut from = 0;
+    while let Some(i) = s[from..].find("</").map(|i| from + i) {
+        let after = &s[i + 2..];
+        let is_end_tag = after.get(..name.len()).is_some_and(|found| found.eq_ignore_ascii_case(name))
+            && after[name.len()..].chars().next().is_none_or(|c| is_whitespace(c) || c == '/' || c == '>');
+        if is_end_tag {
+            return i;
+        }
+        from = i + 2;
+    }
+    s.len()
+}
+
+/// Returns the length of the character reference at the start of `s`,
+/// or 0 if there is none. `&amp;`, `&#169;`, `&#xA9;`
+fn character_reference_len(s: &str) -> usize {
+    let body = &s[1..];
+    let (digits, prefix_len) = match body.strip_prefix('#') {
+        Some(number) => match number.strip_prefix(['x', 'X']) {
+            Some(hex) => (hex.find(|c: char| !c.is_ascii_hexdigit()), 2),
+            None => (number.find(|c: char| !c.is_ascii_digit()), 1),
+        },
+        None => (body.find(|c: char| !c.is_ascii_alphanumeric()), 0),
+    };
+    match digits {
+        Some(len) if len > 0 && body[prefix_len + len..].starts_with(';') => 1 + prefix_len + len + 1,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elements<'a>(node: &SyntaxNode<'a>) -> Vec<SyntaxNode<'a>> {
+        node.child_nodes().filter(|child| child.kind() == NodeKind::Element).cloned().collect()
+    }
+
+    #[test]
+    fn syntax_test() {
+        let source = concat!(
+            "<!DOCTYPE html>\r\n<HTML lang=en>\n<body class = \"a b\" hidden data-x='1'>\n",
+            "<p>x &amp y &#169; &#xZ; < 3\n<li>one<li>two</span> <br/><img / src=a>\n",
+            "<script>if (a </b) {}</script ><!-- c\n-->é</body\n></html>\n<div a=\"",
+        );
+        let tree = parse(source);
+        assert_eq!(tree.root().text(), source);
+        assert_eq!(tree.root().range(), 0..source.len());
+        let mut end = 0;
+        for token in tree.tokens() {
+            assert_eq!(token.range.start, end);
+            assert_eq!(&source[token.range.clone()], token.text);
+            end = token.range.end;
+        }
+
+        let kinds_of = |text: &str| -> Vec<TokenKind> {
+            let start = source.find(text).unwrap();
+            tree.tokens()
+                .filter(|token| token.range.start >= start && token.range.end <= start + text.len())
+                .map(|token| token.kind)
+                .collect()
+        };
+        use TokenKind::*;
+        assert_eq!(
+            kinds_of("<body class = \"a b\" hidden data-x='1'>"),
+            [
+                TagOpen, TagName, Whitespace, AttributeName, Whitespace, Equals, Whitespace, AttributeValue,
+                Whitespace, AttributeName, Whitespace, AttributeName, Equals, AttributeValue, TagClose,
+            ]
+        );
+        assert_eq!(kinds_of("x &amp y &#169; &#xZ; < 3\n"), [Text, CharacterReference, Text]);
+        assert_eq!(kinds_of("if (a </b) {}"), [RawText]);
+        assert_eq!(
+            kinds_of("<img / src=a>"),
+            [TagOpen, TagName, Whitespace, Invalid, Whitespace, AttributeName, Equals, AttributeValue, TagClose]
+        );
+
+        // the elements the parser builds
+        let html = &elements(tree.root())[0];
+        assert_eq!(html.name(), Some("HTML"));
+        assert_eq!(html.end_tag().unwrap().text(), "</html>");
+        let body = &elements(html)[0];
+        assert_eq!(body.end_tag().unwrap().text(), "</body\n>");
+        let p = &elements(body)[0];
+        let items: Vec<String> = elements(p).iter().map(SyntaxNode::text).collect();
+        assert_eq!(items[0], "<li>one");
+        assert!(items[1].ends_with("</script ><!-- c\n-->é"));
+        let attribute = body.start_tag().unwrap().child_nodes().next().unwrap();
+        assert_eq!((attribute.kind(), attribute.name()), (NodeKind::Attribute, Some("class")));
+
+        let errors: Vec<(SyntaxErrorKind, &str)> =
+            tree.errors().into_iter().map(|error| (error.kind, &source[error.range])).collect();
+        assert_eq!(
+            errors,
+            [
+                (SyntaxErrorKind::StrayEndTag, "</span>"),
+                (SyntaxErrorKind::UnexpectedCharacter, "/"),
+                (SyntaxErrorKind::UnterminatedTag, "<div a=\""),
+            ]
+        );
+    }
+}
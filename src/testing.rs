@@ -0,0 +1,224 @@
+//! Assertions for tests of code that produces HTML, such as web handlers.
+//!
+//! The assertions panic with a message that shows what was found instead,
+//! and the snapshots compare the [`debug_tree`](NodeData::debug_tree)
+//! outline of a tree, with the lines that differ in the message.
+//!
+//! # Examples
+//! ```rust
+//! use html::testing::{assert_selector_exists, assert_snapshot, assert_text};
+//!
+//! let page = html::parse(r#"<h1> Welcome </h1><form id="login"><input name="user"></form>"#).unwrap();
+//!
+//! assert_selector_exists(&page, "form#login input[name=user]");
+//! assert_text(&page, "h1", "Welcome");
+//! assert_snapshot(&page, r#"
+//!     #document
+//!       h1
+//!         " Welcome "
+//!       form#login
+//!         input
+//! "#);
+//! ```
+
+use std::rc::Rc;
+
+use crate::dom::NodeData;
+
+/// Panics unless a descendant of `root` matches any selector of the
+/// comma-separated `selectors`.
+#[track_caller]
+pub fn assert_selector_exists(root: &NodeData, selectors: &str) {
+    if select(root, selectors).is_empty() {
+        panic!("no element matches `{}`", selectors);
+    }
+}
+
+/// Panics if a descendant of `root` matches any selector of the
+/// comma-separated `selectors`.
+#[track_caller]
+pub fn assert_selector_absent(root: &NodeData, selectors: &str) {
+    let selected = select(root, selectors);
+    if let Some(node) = selected.first() {
+        panic!("{} elements match `{}`, the first is {}", selected.len(), selectors, node.to_html());
+    }
+}
+
+/// Panics unless the [`inner_text`](NodeData::inner_text) of the first
+/// descendant of `root` that matches `selectors` is `expected`. Runs of
+/// whitespace are compared as one space, and leading and trailing
+/// whitespace is ignored.
+#[track_caller]
+pub fn assert_text(root: &NodeData, selectors: &str, expected: &str) {
+    let node = match select(root, selectors).into_iter().next() {
+        Some(node) => node,
+        None => panic!("no element matches `{}`", selectors),
+    };
+    let text = node.inner_text();
+    if collapse_whitespace(&text) != collapse_whitespace(expected) {
+        panic!("the text of `{}` is not the expected text\n    text: {:?}\nexpected: {:?}", selectors, text, expected);
+    }
+}
+
+/// Panics unless the [`debug_tree`](NodeData::debug_tree) of `actual` is
+/// `expected`, with the lines that differ in the message.
+///
+/// The indentation that all the lines of `expected` have, the blank lines
+/// at its start and end and the whitespace at the end of each line are
+/// ignored, so that the snapshot can be indented with the test code.
+#[track_caller]
+pub fn assert_snapshot(actual: &NodeData, expected: &str) {
+    let expected = dedent(expected);
+    if let Some(diff) = diff_lines(&expected, &actual.debug_tree()) {
+        panic!("the tree does not match the snapshot (- expected, + actual)\n{}", diff);
+    }
+}
+
+/// Panics unless `actual` and `expected` have the same
+/// [`debug_tree`](NodeData::debug_tree), with the lines that differ in the
+/// message.
+#[track_caller]
+pub fn assert_tree_eq(actual: &NodeData, expected: &NodeData) {
+    if let Some(diff) = diff_lines(&expected.debug_tree(), &actual.debug_tree()) {
+        panic!("the trees are not the same (- expected, + actual)\n{}", diff);
+    }
+}
+
+/// Returns the lines of `expected` and `actual`, those only in `expected`
+/// prefixed with `- ` and those only in `actual` with `+ `, or `None` if
+/// they are the same. The whitespace at the end of the lines is ignored.
+///
+/// # Examples
+/// ```rust
+/// let diff = html::testing::diff_lines("a\nb\nc", "a\nx\nc\n");
+///
+/// assert_eq!(diff.unwrap(), "  a\n- b\n+ x\n  c\n");
+/// assert_eq!(html::testing::diff_lines("a\n", "a"), None);
+/// ```
+pub fn diff_lines(expected: &str, actual: &str) -> Option<String> {
+    let expected: Vec<&str> = expected.lines().map(str::trim_end).collect();
+    let actual: Vec<&str> = actual.lines().map(str::trim_end).collect();
+    if expected == actual {
+        return None;
+    }
+
+    // the length of the longest common subsequence of the lines from `i` and `j`
+    let mut common = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = match expected[i] == actual[j] {
+                true => common[i + 1][j + 1] + 1,
+                false => common[i + 1][j].max(common[i][j + 1]),
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        let (prefix, line) = if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            i += 1;
+            j += 1;
+            ("  ", actual[j - 1])
+        } else if i < expected.len() && (j == actual.len() || common[i + 1][j] >= common[i][j + 1]) {
+            i += 1;
+            ("- ", expected[i - 1])
+        } else {
+            j += 1;
+            ("+ ", actual[j - 1])
+        };
+        diff.push_str(prefix);
+        diff.push_str(line);
+        diff.push('\n');
+    }
+    Some(diff)
+}
+
+#[track_caller]
+fn select(root: &NodeData, selectors: &str) -> Vec<Rc<NodeData>> {
+    match root.select(selectors) {
+        Ok(selected) => selected.into_iter().map(|(node, _)| node).collect(),
+        Err(err) => panic!("`{}` is not a selector: {}", selectors, err),
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Removes the blank lines at the start and end of `text` and the
+/// indentation that all its other lines have.
+fn dedent(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    let start = lines.iter().position(|line| !line.is_empty()).unwrap_or(lines.len());
+    let end = lines.iter().rposition(|line| !line.is_empty()).map_or(start, |end| end + 1);
+    let lines = &lines[start..end];
+    let indent = lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    lines.iter().map(|line| format!("{}\n", line.get(indent..).unwrap_or(""))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn testing_test() {
+        let page = parse(r#"<main><h1>Hello,  <b>you</b></h1><p class="x">a</p><p>b</p></main>"#).unwrap();
+        assert_selector_exists(&page, "main > h1 b");
+        assert_selector_exists(&page, "table, p.x");
+        assert_selector_absent(&page, "h2, p.y");
+        assert_text(&page, "h1", " Hello, you");
+        assert_text(&page, "p:not(.x), h1", "Hello, you");
+        assert_snapshot(
+            &page,
+            r#"
+            #document
+              main
+                h1
+                  "Hello,  "
+                  b
+                    "you"
+                p.x
+                  "a"
+                p
+                  "b"
+            "#,
+        );
+        assert_tree_eq(&page, &parse("<main><h1>Hello,  <b>you</b></h1><p class='x'>a<p>b</main>").unwrap());
+
+        let message = |f: fn()| -> String {
+            let err = std::panic::catch_unwind(f).unwrap_err();
+            err.downcast_ref::<String>().cloned().unwrap_or_default()
+        };
+        assert_eq!(message(|| assert_selector_exists(&parse("<p></p>").unwrap(), "div")), "no element matches `div`");
+        assert_eq!(
+            message(|| assert_selector_absent(&parse("<p>1</p><p>2</p>").unwrap(), "p")),
+            "2 elements match `p`, the first is <p>1</p>"
+        );
+        assert!(message(|| assert_text(&parse("<p>a</p>").unwrap(), "p", "b")).contains("text: \"a\""));
+        assert!(message(|| assert_text(&parse("<p>a</p>").unwrap(), "p[", "a")).starts_with("`p[` is not a selector"));
+        assert_eq!(
+            message(|| assert_snapshot(&parse("<ul><li>a</li></ul>").unwrap(), "\n  #document\n    ol\n      li\n")),
+            concat!(
+                "the tree does not match the snapshot (- expected, + actual)\n",
+                "  #document\n-   ol\n+   ul\n      li\n+       \"a\"\n",
+            )
+        );
+    }
+
+    #[test]
+    fn diff_lines_test() {
+        assert_eq!(diff_lines("", ""), None);
+        assert_eq!(diff_lines("a  \nb", "a\nb"), None);
+        assert_eq!(diff_lines("", "a").unwrap(), "+ a\n");
+        assert_eq!(diff_lines("a\nb\nc\nd", "b\nd\ne").unwrap(), "- a\n  b\n- c\n  d\n+ e\n");
+        assert_eq!(dedent("\n   a\n     b\n\n   c  \n  "), "a\n  b\n\nc\n");
+    }
+}
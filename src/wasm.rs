@@ -0,0 +1,247 @@
+//! Bindings for JavaScript, enabled by the `wasm` feature.
+//!
+//! [`WasmDocument`] owns a parsed document and refers to its nodes by
+//! handles, which are plain `u32`s. Its methods take and return only
+//! strings, numbers, `Option`s and `Vec<u32>`s, the types `wasm_bindgen`
+//! passes across the boundary as they are, so an `#[wasm_bindgen]` wrapper
+//! is a thin delegation. A node is described to JavaScript with
+//! [`WasmDocument::node_json`].
+//!
+//! # Examples
+//! ```rust
+//! use html::wasm::WasmDocument;
+//!
+//! let mut doc = WasmDocument::parse(r#"<ul id="list"><li class="a">one</li><li>two</li></ul>"#).unwrap();
+//! let items = doc.select(doc.root(), "//li").unwrap();
+//!
+//! assert_eq!(doc.inner_text(items[1]), "two");
+//! assert_eq!(doc.node_json(items[0]).unwrap(), r#"{"id":2,"type":"tag","name":"li","attributes":{"class":"a"},"parent":1,"children":[3]}"#);
+//! ```
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::dom::{Node, NodeData, Payload};
+
+/// A parsed document whose nodes are referred to by handles.
+/// See the [module](self) documentation.
+#[derive(Debug)]
+pub struct WasmDocument {
+    root: Node,
+    nodes: Vec<Rc<NodeData>>,
+    handles: HashMap<*const NodeData, u32>,
+}
+
+impl WasmDocument {
+    /// Parses the tag document.
+    ///
+    /// # Errors
+    /// * The message of the [`ParseError`](crate::ParseError) as [`parse`](crate::parse) fails with it.
+    pub fn parse(doc: &str) -> Result<WasmDocument, String> {
+        let root = crate::parse(doc).map_err(|e| e.to_string())?;
+        Ok(WasmDocument::from(root))
+    }
+
+    /// Parses the tag document like [`parse_lenient`](crate::parse_lenient), which never fails.
+    pub fn parse_lenient(doc: &str) -> WasmDocument {
+        WasmDocument::from(crate::parse_lenient(doc))
+    }
+
+    /// Returns the handle of the synthetic `root` node, which is always 0.
+    pub fn root(&self) -> u32 {
+        0
+    }
+
+    /// Returns the handles of the nodes selected by the XPath expression
+    /// `expr`, evaluated with `node` as the context node.
+    ///
+    /// # Errors
+    /// * The message of the [`XPathError`](crate::xpath::XPathError) if
+    ///   `expr` is not a valid expression that selects nodes.
+    pub fn select(&mut self, node: u32, expr: &str) -> Result<Vec<u32>, String> {
+        let node = self.node(node).ok_or_else(|| format!("unknown node {}", node))?;
+        let nodes = crate::xpath::select(&node, expr).map_err(|e| e.to_string())?;
+        Ok(self.handles_of(nodes))
+    }
+
+    /// Returns the handles of the nodes named `tag_name`, `node` included.
+    pub fn get_nodes_by_name(&mut self, node: u32, tag_name: &str) -> Vec<u32> {
+        let nodes = self.node(node).map(|node| crate::get_nodes_by_name(&node, tag_name));
+        self.handles_of(nodes.unwrap_or_default())
+    }
+
+    /// Returns the handles of the nodes whose `class` attribute contains
+    /// `class_name`, `node` included.
+    pub fn get_nodes_by_class(&mut self, node: u32, class_name: &str) -> Vec<u32> {
+        let nodes = self.node(node).map(|node| crate::get_nodes_by_class(&node, class_name));
+        self.handles_of(nodes.unwrap_or_default())
+    }
+
+    /// Returns the handle of the first node whose `id` attribute is `id`, `node` included.
+    pub fn get_node_by_id(&mut self, node: u32, id: &str) -> Option<u32> {
+        let found = crate::get_node_by_id(&self.node(node)?, id)?;
+        Some(self.handle_of(found))
+    }
+
+    pub fn get_parent(&mut self, node: u32) -> Option<u32> {
+        let parent = self.node(node)?.get_parent()?;
+        Some(self.handle_of(parent))
+    }
+
+    pub fn get_children(&mut self, node: u32) -> Vec<u32> {
+        let children = self.node(node).map(|node| node.children());
+        self.handles_of(children.unwrap_or_default())
+    }
+
+    /// Returns the text of the node and its descendants, or "" for an
+    /// unknown handle. See [`NodeData::inner_text`].
+    pub fn inner_text(&self, node: u32) -> String {
+        self.node(node).map(|node| node.inner_text()).unwrap_or_default()
+    }
+
+    /// Serializes the node and its descendants, or returns "" for an
+    /// unknown handle. See [`NodeData::to_html`].
+    pub fn to_html(&self, node: u32) -> String {
+        self.node(node).map(|node| node.to_html()).unwrap_or_default()
+    }
+
+    /// Describes the node as a JSON object, with its parent and children
+    /// as handles:
+    ///
+    /// ```text
+    /// {"id":2,"type":"tag","name":"li","attributes":{"class":"a"},"parent":1,"children":[3]}
+    /// {"id":3,"type":"text","text":"one","parent":2,"children":[]}
+    /// ```
+    ///
+    /// `type` is `"tag"`, `"text"`, `"rawtext"`, `"comment"`, `"conditionalcomment"`
+    /// or `"doctype"`.
+    pub fn node_json(&mut self, node: u32) -> Option<String> {
+        let data = self.node(node)?;
+        let mut json = format!(r#"{{"id":{},"type":"#, node);
+
+        match &*data.get_payload() {
+            Payload::Tag(tag) => {
+                json.push_str(r#""tag","name":"#);
+                write_json_string(&mut json, tag.get_name());
+                json.push_str(r#","attributes":{"#);
+                for (i, (name, value)) in tag.get_attributes().into_iter().flat_map(|a| a.iter()).enumerate() {
+                    if i > 0 {
+                        json.push(',');
+                    }
+                    write_json_string(&mut json, name);
+                    json.push(':');
+                    write_json_string(&mut json, value.as_str());
+                }
+                json.push('}');
+            }
+            Payload::Text(text) => {
+                json.push_str(r#""text","text":"#);
+                write_json_string(&mut json, text);
+            }
+            Payload::RawText(text) => {
+                json.push_str(r#""rawtext","text":"#);
+                write_json_string(&mut json, text);
+            }
+            Payload::Comment(text) => {
+                json.push_str(r#""comment","text":"#);
+                write_json_string(&mut json, text);
+            }
+            Payload::ConditionalComment(condition) => {
+                json.push_str(r#""conditionalcomment","condition":"#);
+                write_json_string(&mut json, condition);
+            }
+            Payload::Doctype(doctype) => {
+                json.push_str(r#""doctype","name":"#);
+                write_json_string(&mut json, doctype.get_name());
+            }
+        }
+
+        json.push_str(r#","parent":"#);
+        match self.get_parent(node) {
+            Some(parent) => json.push_str(&parent.to_string()),
+            None => json.push_str("null"),
+        }
+
+        let children = self.get_children(node);
+        let children: Vec<String> = children.iter().map(u32::to_string).collect();
+        json.push_str(&format!(r#","children":[{}]}}"#, children.join(",")));
+
+        Some(json)
+    }
+
+    fn node(&self, handle: u32) -> Option<Rc<NodeData>> {
+        self.nodes.get(handle as usize).cloned()
+    }
+
+    /// Returns the handle of `node`, giving it one if it has none yet.
+    fn handle_of(&mut self, node: Rc<NodeData>) -> u32 {
+        let nodes = &mut self.nodes;
+        *self.handles.entry(Rc::as_ptr(&node)).or_insert_with(|| {
+            nodes.push(node);
+            (nodes.len() - 1) as u32
+        })
+    }
+
+    fn handles_of(&mut self, nodes: Vec<Rc<NodeData>>) -> Vec<u32> {
+        nodes.into_iter().map(|node| self.handle_of(node)).collect()
+    }
+}
+
+impl From<Node> for WasmDocument {
+    fn from(root: Node) -> WasmDocument {
+        let mut doc = WasmDocument {
+            nodes: Vec::new(),
+            handles: HashMap::new(),
+            root,
+        };
+        doc.handle_of(doc.root.get_copy_of_internal_arc());
+        // the handles follow the document order
+        for node in doc.root.descendants() {
+            doc.handle_of(node);
+        }
+
+        doc
+    }
+}
+
+/// Writes `s` as a JSON string literal.
+fn write_json_string(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wasm_test() {
+        let mut doc = WasmDocument::parse(r#"<div id="a"><p class="x">one</p><p class="x y">"two"</p></div>"#).unwrap();
+        let root = doc.root();
+
+        let div = doc.get_node_by_id(root, "a").unwrap();
+        assert_eq!(doc.get_nodes_by_class(root, "x"), doc.get_children(div));
+        assert_eq!(doc.get_nodes_by_name(div, "p"), doc.select(root, "//p").unwrap());
+        assert_eq!(doc.get_parent(div), Some(root));
+        assert_eq!(doc.to_html(div), r#"<div id="a"><p class="x">one</p><p class="x y">"two"</p></div>"#);
+
+        let text = doc.select(root, "//p[2]/text()").unwrap()[0];
+        assert_eq!(doc.node_json(text).unwrap(), r#"{"id":5,"type":"text","text":"\"two\"","parent":4,"children":[]}"#);
+        assert_eq!(doc.node_json(99), None);
+        assert_eq!(doc.inner_text(99), "");
+
+        assert!(doc.select(root, "count(//p)").is_err());
+        assert!(WasmDocument::parse("<p").is_err());
+    }
+}
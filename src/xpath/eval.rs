@@ -0,0 +1,579 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::parser::{Axis, BinaryOp, Expr, NodeTest, Step};
+use super::{Item, Value, XPathError};
+use crate::dom::{NodeData, Payload};
+
+/// Evaluates `expr` with `context` as the context node.
+pub fn evaluate(context: &Rc<NodeData>, expr: &Expr) -> Result<Value, XPathError> {
+    let evaluator = Evaluator::new(context);
+    let context = Context {
+        item: Item::Node(Rc::clone(context)),
+        position: 1,
+        size: 1,
+    };
+
+    evaluator.eval(expr, &context)
+}
+
+/// Returns the text of `node` and its descendants.
+pub fn string_value(node: &NodeData) -> String {
+    match &*node.get_payload() {
+        Payload::Text(text) | Payload::RawText(text) | Payload::Comment(text) => text.clone(),
+        Payload::Tag(_) | Payload::ConditionalComment(_) => node
+            .descendants()
+            .filter_map(|node| match &*node.get_payload() {
+                Payload::Text(text) | Payload::RawText(text) => Some(text.clone()),
+                _ => None,
+            })
+            .collect(),
+        Payload::Doctype(_) => String::new(),
+    }
+}
+
+pub fn to_string(value: &Value) -> String {
+    match value {
+        Value::Nodes(items) => items.first().map(Item::string_value).unwrap_or_default(),
+        Value::Boolean(boolean) => boolean.to_string(),
+        Value::Number(number) => number_to_string(*number),
+        Value::String(string) => string.clone(),
+    }
+}
+
+fn to_number(value: &Value) -> f64 {
+    match value {
+        Value::Boolean(boolean) => f64::from(u8::from(*boolean)),
+        Value::Number(number) => *number,
+        value => string_to_number(&to_string(value)),
+    }
+}
+
+fn to_boolean(value: &Value) -> bool {
+    match value {
+        Value::Nodes(items) => !items.is_empty(),
+        Value::Boolean(boolean) => *boolean,
+        Value::Number(number) => *number != 0.0 && !number.is_nan(),
+        Value::String(string) => !string.is_empty(),
+    }
+}
+
+/// Converts `string` to a number. Only `-digits.digits` is a number,
+/// anything else is NaN.
+fn string_to_number(string: &str) -> f64 {
+    let string = string.trim();
+    let digits = string.strip_prefix('-').unwrap_or(string);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return f64::NAN;
+    }
+
+    string.parse().unwrap_or(f64::NAN)
+}
+
+fn number_to_string(number: f64) -> String {
+    if number.is_nan() {
+        String::from("NaN")
+    } else if number.is_infinite() {
+        String::from(if number > 0.0 { "Infinity" } else { "-Infinity" })
+    } else if number == number.trunc() && number.abs() < 1e15 {
+        format!("{}", number as i64)
+    } else {
+        format!("{}", number)
+    }
+}
+
+/// Rounds half up like XPath's `round()`.
+fn round(number: f64) -> f64 {
+    (number + 0.5).floor()
+}
+
+struct Context {
+    item: Item,
+    position: usize,
+    size: usize,
+}
+
+struct Evaluator {
+    // every node of the tree in document order
+    nodes: Vec<Rc<NodeData>>,
+    // the index of each node in `nodes`
+    order: HashMap<*const NodeData, usize>,
+}
+
+impl Evaluator {
+    fn new(context: &Rc<NodeData>) -> Evaluator {
+        let mut root = Rc::clone(context);
+        while let Some(parent) = root.get_parent() {
+            root = parent;
+        }
+
+        let nodes: Vec<Rc<NodeData>> = std::iter::once(Rc::clone(&root)).chain(root.descendants()).collect();
+        let order = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (Rc::as_ptr(node), i))
+            .collect();
+
+        Evaluator { nodes, order }
+    }
+
+    fn index(&self, node: &Rc<NodeData>) -> usize {
+        self.order.get(&Rc::as_ptr(node)).copied().unwrap_or(usize::MAX)
+    }
+
+    /// Returns the position of `item` in the document.
+    /// The attributes of an element come right after it.
+    fn key(&self, item: &Item) -> (usize, usize) {
+        match item {
+            Item::Node(node) => (self.index(node), 0),
+            Item::Attribute { element, name, .. } => {
+                let position = match &*element.get_payload() {
+                    Payload::Tag(tag) => tag
+                        .get_attributes()
+                        .and_then(|attributes| attributes.keys().position(|n| n == name)),
+                    _ => None,
+                };
+                (self.index(element), position.map_or(usize::MAX, |i| i + 1))
+            }
+        }
+    }
+
+    /// Sorts `items` in document order and removes duplicates.
+    fn sort(&self, items: &mut Vec<Item>) {
+        items.sort_by_key(|item| self.key(item));
+        items.dedup_by_key(|item| self.key(item));
+    }
+
+    fn eval(&self, expr: &Expr, context: &Context) -> Result<Value, XPathError> {
+        let value = match expr {
+            Expr::Binary(BinaryOp::Or, lhs, rhs) => Value::Boolean(
+                to_boolean(&self.eval(lhs, context)?) || to_boolean(&self.eval(rhs, context)?),
+            ),
+            Expr::Binary(BinaryOp::And, lhs, rhs) => Value::Boolean(
+                to_boolean(&self.eval(lhs, context)?) && to_boolean(&self.eval(rhs, context)?),
+            ),
+            Expr::Binary(BinaryOp::Union, lhs, rhs) => {
+                match (self.eval(lhs, context)?, self.eval(rhs, context)?) {
+                    (Value::Nodes(mut lhs), Value::Nodes(rhs)) => {
+                        lhs.extend(rhs);
+                        self.sort(&mut lhs);
+                        Value::Nodes(lhs)
+                    }
+                    _ => return Err(XPathError::NotNodes),
+                }
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = self.eval(lhs, context)?;
+                let rhs = self.eval(rhs, context)?;
+                match op {
+                    BinaryOp::Add => Value::Number(to_number(&lhs) + to_number(&rhs)),
+                    BinaryOp::Sub => Value::Number(to_number(&lhs) - to_number(&rhs)),
+                    BinaryOp::Mul => Value::Number(to_number(&lhs) * to_number(&rhs)),
+                    BinaryOp::Div => Value::Number(to_number(&lhs) / to_number(&rhs)),
+                    BinaryOp::Mod => Value::Number(to_number(&lhs) % to_number(&rhs)),
+                    op => Value::Boolean(compare(*op, &lhs, &rhs)),
+                }
+            }
+            Expr::Negate(expr) => Value::Number(-to_number(&self.eval(expr, context)?)),
+            Expr::Path { absolute, steps } => {
+                let start = match absolute {
+                    true => Item::Node(Rc::clone(&self.nodes[0])),
+                    false => context.item.clone(),
+                };
+                Value::Nodes(self.apply_steps(vec![start], steps)?)
+            }
+            Expr::Filter { primary, predicates, steps } => {
+                let mut items = match self.eval(primary, context)? {
+                    Value::Nodes(items) => items,
+                    _ => return Err(XPathError::NotNodes),
+                };
+                for predicate in predicates {
+                    items = self.filter(items, predicate)?;
+                }
+                Value::Nodes(self.apply_steps(items, steps)?)
+            }
+            Expr::Literal(literal) => Value::String(literal.clone()),
+            Expr::Number(number) => Value::Number(*number),
+            Expr::Function(name, args) => self.call(name, args, context)?,
+        };
+
+        Ok(value)
+    }
+
+    fn apply_steps(&self, mut items: Vec<Item>, steps: &[Step]) -> Result<Vec<Item>, XPathError> {
+        for step in steps {
+            let mut next = Vec::new();
+            for item in &items {
+                let mut selected: Vec<Item> = self
+                    .axis(item, step.axis)
+                    .into_iter()
+                    .filter(|item| matches_test(item, step.axis, &step.test))
+                    .collect();
+
+                // positions in predicates count along the axis
+                for predicate in &step.predicates {
+                    selected = self.filter(selected, predicate)?;
+                }
+                next.extend(selected);
+            }
+
+            self.sort(&mut next);
+            items = next;
+        }
+
+        Ok(items)
+    }
+
+    /// Keeps the items for which `predicate` is true.
+    /// A number is true if it is the position of the item. `[2]`
+    fn filter(&self, items: Vec<Item>, predicate: &Expr) -> Result<Vec<Item>, XPathError> {
+        let size = items.len();
+        let mut res = Vec::new();
+        for (i, item) in items.into_iter().enumerate() {
+            let context = Context {
+                item,
+                position: i + 1,
+                size,
+            };
+
+            let keep = match self.eval(predicate, &context)? {
+                Value::Number(number) => number == context.position as f64,
+                value => to_boolean(&value),
+            };
+            if keep {
+                res.push(context.item);
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Returns the items on `axis` from `item`, nearest first.
+    fn axis(&self, item: &Item, axis: Axis) -> Vec<Item> {
+        let node = match item {
+            Item::Node(node) => node,
+            Item::Attribute { element, .. } => {
+                // an attribute has no children, but its element is its parent
+                return match axis {
+                    Axis::SelfNode => vec![item.clone()],
+                    Axis::Parent => vec![Item::Node(Rc::clone(element))],
+                    Axis::Ancestor | Axis::AncestorOrSelf => {
+                        let mut res = match axis {
+                            Axis::AncestorOrSelf => vec![item.clone()],
+                            _ => Vec::new(),
+                        };
+                        res.extend(self.axis(&Item::Node(Rc::clone(element)), Axis::AncestorOrSelf));
+                        res
+                    }
+                    Axis::Following => {
+                        let mut res = self.axis(&Item::Node(Rc::clone(element)), Axis::Descendant);
+                        res.extend(self.axis(&Item::Node(Rc::clone(element)), Axis::Following));
+                        res
+                    }
+                    Axis::Preceding => self.axis(&Item::Node(Rc::clone(element)), Axis::Preceding),
+                    _ => Vec::new(),
+                };
+            }
+        };
+
+        let nodes: Vec<Rc<NodeData>> = match axis {
+            Axis::Child => node.children(),
+            Axis::Descendant => node.descendants().collect(),
+            Axis::DescendantOrSelf => std::iter::once(Rc::clone(node)).chain(node.descendants()).collect(),
+            Axis::SelfNode => vec![Rc::clone(node)],
+            Axis::Parent => node.get_parent().into_iter().collect(),
+            Axis::Ancestor | Axis::AncestorOrSelf => {
+                let mut res = match axis {
+                    Axis::AncestorOrSelf => vec![Rc::clone(node)],
+                    _ => Vec::new(),
+                };
+                let mut current = node.get_parent();
+                while let Some(parent) = current {
+                    current = parent.get_parent();
+                    res.push(parent);
+                }
+                res
+            }
+            Axis::FollowingSibling | Axis::PrecedingSibling => {
+                let parent = match node.get_parent() {
+                    Some(parent) => parent,
+                    None => return Vec::new(),
+                };
+                let siblings = parent.get_children();
+                let index = siblings.iter().position(|c| Rc::ptr_eq(c, node)).unwrap_or(0);
+                match axis {
+                    Axis::FollowingSibling => siblings[index + 1..].to_vec(),
+                    _ => siblings[..index].iter().rev().cloned().collect(),
+                }
+            }
+            Axis::Following => {
+                // the nodes after the descendants of this node
+                let end = self.index(node).saturating_add(1 + node.descendants().count());
+                self.nodes.get(end..).map(<[_]>::to_vec).unwrap_or_default()
+            }
+            Axis::Preceding => {
+                let ancestors = self.axis(item, Axis::Ancestor);
+                let index = self.index(node).min(self.nodes.len());
+                self.nodes[..index]
+                    .iter()
+                    .rev()
+                    .filter(|n| !ancestors.iter().any(|a| a.as_node().is_some_and(|a| Rc::ptr_eq(a, n))))
+                    .cloned()
+                    .collect()
+            }
+            Axis::Attribute => {
+                return match &*node.get_payload() {
+                    Payload::Tag(tag) => tag
+                        .get_attributes()
+                        .into_iter()
+                        .flat_map(|attributes| attributes.iter())
+                        .map(|(name, value)| Item::Attribute {
+                            element: Rc::clone(node),
+                            name: name.to_string(),
+                            value: value.to_string(),
+                        })
+                        .collect(),
+                    _ => Vec::new(),
+                };
+            }
+        };
+
+        nodes.into_iter().map(Item::Node).collect()
+    }
+
+    fn call(&self, name: &str, args: &[Expr], context: &Context) -> Result<Value, XPathError> {
+        let arity = |min: usize, max: usize| {
+            if args.len() < min || max < args.len() {
+                return Err(XPathError::InvalidArguments(String::from(name)));
+            }
+            Ok(())
+        };
+        let eval = |i: usize| self.eval(&args[i], context);
+        let string = |i: usize| -> Result<String, XPathError> {
+            match args.get(i) {
+                Some(arg) => Ok(to_string(&self.eval(arg, context)?)),
+                // the string-value of the context node
+                None => Ok(context.item.string_value()),
+            }
+        };
+        let nodes = |i: usize| -> Result<Vec<Item>, XPathError> {
+            match args.get(i) {
+                Some(arg) => match self.eval(arg, context)? {
+                    Value::Nodes(items) => Ok(items),
+                    _ => Err(XPathError::InvalidArguments(String::from(name))),
+                },
+                None => Ok(vec![context.item.clone()]),
+            }
+        };
+
+        let value = match name {
+            "last" => {
+                arity(0, 0)?;
+                Value::Number(context.size as f64)
+            }
+            "position" => {
+                arity(0, 0)?;
+                Value::Number(context.position as f64)
+            }
+            "count" => {
+                arity(1, 1)?;
+                Value::Number(nodes(0)?.len() as f64)
+            }
+            "name" | "local-name" => {
+                arity(0, 1)?;
+                let name = match nodes(0)?.first() {
+                    Some(Item::Node(node)) => match &*node.get_payload() {
+                        Payload::Tag(tag) if !node.is_document_root() => tag.get_name().to_string(),
+                        _ => String::new(),
+                    },
+                    Some(Item::Attribute { name, .. }) => name.clone(),
+                    None => String::new(),
+                };
+                match name.split_once(':') {
+                    Some((_, local_name)) if name == "local-name" => Value::String(local_name.to_string()),
+                    _ => Value::String(name),
+                }
+            }
+            "string" => {
+                arity(0, 1)?;
+                Value::String(string(0)?)
+            }
+            "concat" => {
+                if args.len() < 2 {
+                    return Err(XPathError::InvalidArguments(String::from(name)));
+                }
+                let mut res = String::new();
+                for i in 0..args.len() {
+                    res.push_str(&string(i)?);
+                }
+                Value::String(res)
+            }
+            "contains" => {
+                arity(2, 2)?;
+                Value::Boolean(string(0)?.contains(&string(1)?))
+            }
+            "starts-with" => {
+                arity(2, 2)?;
+                Value::Boolean(string(0)?.starts_with(&string(1)?))
+            }
+            "substring-before" => {
+                arity(2, 2)?;
+                let (s, needle) = (string(0)?, string(1)?);
+                Value::String(s.split_once(&needle).map(|(before, _)| before.to_string()).unwrap_or_default())
+            }
+            "substring-after" => {
+                arity(2, 2)?;
+                let (s, needle) = (string(0)?, string(1)?);
+                Value::String(s.split_once(&needle).map(|(_, after)| after.to_string()).unwrap_or_default())
+            }
+            "substring" => {
+                arity(2, 3)?;
+                let s = string(0)?;
+                let start = round(to_number(&eval(1)?));
+                let end = match args.len() {
+                    3 => start + round(to_number(&eval(2)?)),
+                    _ => f64::INFINITY,
+                };
+                let res = s
+                    .chars()
+                    .enumerate()
+                    .filter(|(i, _)| {
+                        let position = (i + 1) as f64;
+                        start <= position && position < end
+                    })
+                    .map(|(_, c)| c)
+                    .collect();
+                Value::String(res)
+            }
+            "string-length" => {
+                arity(0, 1)?;
+                Value::Number(string(0)?.chars().count() as f64)
+            }
+            "normalize-space" => {
+                arity(0, 1)?;
+                Value::String(string(0)?.split_whitespace().collect::<Vec<_>>().join(" "))
+            }
+            "translate" => {
+                arity(3, 3)?;
+                let from: Vec<char> = string(1)?.chars().collect();
+                let to: Vec<char> = string(2)?.chars().collect();
+                let res = string(0)?
+                    .chars()
+                    .filter_map(|c| match from.iter().position(|f| *f == c) {
+                        // characters without a replacement are removed
+                        Some(i) => to.get(i).copied(),
+                        None => Some(c),
+                    })
+                    .collect();
+                Value::String(res)
+            }
+            "boolean" => {
+                arity(1, 1)?;
+                Value::Boolean(to_boolean(&eval(0)?))
+            }
+            "not" => {
+                arity(1, 1)?;
+                Value::Boolean(!to_boolean(&eval(0)?))
+            }
+            "true" | "false" => {
+                arity(0, 0)?;
+                Value::Boolean(name == "true")
+            }
+            "number" => {
+                arity(0, 1)?;
+                match args.is_empty() {
+                    true => Value::Number(string_to_number(&context.item.string_value())),
+                    false => Value::Number(to_number(&eval(0)?)),
+                }
+            }
+            "sum" => {
+                arity(1, 1)?;
+                Value::Number(nodes(0)?.iter().map(|item| string_to_number(&item.string_value())).sum())
+            }
+            "floor" | "ceiling" | "round" => {
+                arity(1, 1)?;
+                let number = to_number(&eval(0)?);
+                Value::Number(match name {
+                    "floor" => number.floor(),
+                    "ceiling" => number.ceil(),
+                    _ => round(number),
+                })
+            }
+            _ => return Err(XPathError::UnknownFunction(String::from(name))),
+        };
+
+        Ok(value)
+    }
+}
+
+/// Returns true if `item` on `axis` passes `test`.
+/// Names and `*` match attributes on the attribute axis, and elements on the others.
+fn matches_test(item: &Item, axis: Axis, test: &NodeTest) -> bool {
+    match item {
+        Item::Attribute { name, .. } => match test {
+            NodeTest::Name(n) => axis == Axis::Attribute && n.eq_ignore_ascii_case(name),
+            NodeTest::Any => axis == Axis::Attribute,
+            NodeTest::Node => true,
+            _ => false,
+        },
+        Item::Node(node) => match (&*node.get_payload(), test) {
+            (_, NodeTest::Node) => true,
+            (Payload::Tag(_), _) if node.is_document_root() => false,
+            (Payload::Tag(tag), NodeTest::Name(n)) => axis != Axis::Attribute && n.eq_ignore_ascii_case(tag.get_name()),
+            (Payload::Tag(_), NodeTest::Any) => axis != Axis::Attribute,
+            (Payload::Text(_) | Payload::RawText(_), NodeTest::Text) => true,
+            (Payload::Comment(_) | Payload::ConditionalComment(_), NodeTest::Comment) => true,
+            _ => false,
+        },
+    }
+}
+
+/// Compares two values like XPath does. Nodes are compared by their
+/// string-values, and the comparison is true if it is true for any of them.
+fn compare(op: BinaryOp, lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        (Value::Nodes(lhs), Value::Nodes(rhs)) => lhs.iter().any(|l| {
+            let l = Value::String(l.string_value());
+            rhs.iter().any(|r| compare_atomic(op, &l, &Value::String(r.string_value())))
+        }),
+        (Value::Nodes(items), Value::Boolean(_)) => compare_atomic(op, &Value::Boolean(!items.is_empty()), rhs),
+        (Value::Boolean(_), Value::Nodes(items)) => compare_atomic(op, lhs, &Value::Boolean(!items.is_empty())),
+        (Value::Nodes(items), _) => items
+            .iter()
+            .any(|item| compare_atomic(op, &atomize(item, rhs), rhs)),
+        (_, Value::Nodes(items)) => items
+            .iter()
+            .any(|item| compare_atomic(op, lhs, &atomize(item, lhs))),
+        _ => compare_atomic(op, lhs, rhs),
+    }
+}
+
+/// Converts `item` to the type of `other` for comparison.
+fn atomize(item: &Item, other: &Value) -> Value {
+    match other {
+        Value::Number(_) => Value::Number(string_to_number(&item.string_value())),
+        _ => Value::String(item.string_value()),
+    }
+}
+
+fn compare_atomic(op: BinaryOp, lhs: &Value, rhs: &Value) -> bool {
+    match op {
+        BinaryOp::Eq | BinaryOp::Ne => {
+            let equal = match (lhs, rhs) {
+                (Value::Boolean(_), _) | (_, Value::Boolean(_)) => to_boolean(lhs) == to_boolean(rhs),
+                (Value::Number(_), _) | (_, Value::Number(_)) => to_number(lhs) == to_number(rhs),
+                _ => to_string(lhs) == to_string(rhs),
+            };
+            equal == (op == BinaryOp::Eq)
+        }
+        _ => {
+            let (lhs, rhs) = (to_number(lhs), to_number(rhs));
+            match op {
+                BinaryOp::Lt => lhs < rhs,
+                BinaryOp::Le => lhs <= rhs,
+                BinaryOp::Gt => lhs > rhs,
+                _ => lhs >= rhs,
+            }
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! XPath 1.0 queries.
+//!
+//! All the axes of XPath 1.0 are supported, as are predicates, the
+//! operators and the core functions such as `text()`, `contains()` and
+//! `position()`. Variables and the `id()` and `lang()` functions are not.
+//!
+//! # Examples
+//! ```rust
+//! let root = html::parse(r#"
+//!     <div class="x"><a href="/a">a</a></div>
+//!     <div class="y"><a href="/b">b</a></div>
+//! "#).unwrap();
+//!
+//! let value = html::xpath::evaluate(&root.get_copy_of_internal_arc(), "//div[@class='x']/a/@href").unwrap();
+//! assert_eq!(value.to_strings(), ["/a"]);
+//!
+//! let links = html::xpath::select(&root.get_copy_of_internal_arc(), "//a[contains(text(), 'b')]").unwrap();
+//! assert_eq!(links[0].to_html(), r#"<a href="/b">b</a>"#);
+//! ```
+
+mod eval;
+mod parser;
+
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::dom::NodeData;
+
+/// A node selected by an XPath expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    /// An element, text, comment or doctype node, or the document root.
+    Node(Rc<NodeData>),
+    /// An attribute of `element`. `//a/@href`
+    Attribute {
+        element: Rc<NodeData>,
+        name: String,
+        value: String,
+    },
+}
+
+impl Item {
+    /// Returns the node, or `None` if this is an attribute.
+    pub fn as_node(&self) -> Option<&Rc<NodeData>> {
+        match self {
+            Item::Node(node) => Some(node),
+            Item::Attribute { .. } => None,
+        }
+    }
+
+    /// Returns the string-value of the item: the text of a node and its
+    /// descendants, or the value of an attribute.
+    pub fn string_value(&self) -> String {
+        match self {
+            Item::Node(node) => eval::string_value(node),
+            Item::Attribute { value, .. } => value.clone(),
+        }
+    }
+}
+
+/// The result of an XPath expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// The selected items in document order.
+    Nodes(Vec<Item>),
+    Boolean(bool),
+    Number(f64),
+    String(String),
+}
+
+impl Value {
+    /// Returns the string-value of each selected item, or the value itself
+    /// converted to a string.
+    pub fn to_strings(&self) -> Vec<String> {
+        match self {
+            Value::Nodes(items) => items.iter().map(Item::string_value).collect(),
+            value => vec![eval::to_string(value)],
+        }
+    }
+
+    /// Returns the selected nodes, leaving attributes out.
+    pub fn into_nodes(self) -> Vec<Rc<NodeData>> {
+        match self {
+            Value::Nodes(items) => items
+                .into_iter()
+                .filter_map(|item| match item {
+                    Item::Node(node) => Some(node),
+                    Item::Attribute { .. } => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// The error returned when an XPath expression cannot be evaluated.
+/// Each variant carries the byte offset in the expression where the problem starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XPathError {
+    /// A character that cannot start a token. `//a#b`
+    UnexpectedCharacter(usize),
+    /// A string literal is not closed. `//a[@id='x]`
+    UnterminatedLiteral(usize),
+    /// A token where it is not allowed. `//a]`
+    UnexpectedToken(usize),
+    /// The expression ends before it is complete. `//a[`
+    UnexpectedEnd(usize),
+    /// An axis that doesn't exist. `sibling::a`
+    UnknownAxis(usize),
+    /// A function that doesn't exist or is not supported.
+    UnknownFunction(String),
+    /// A function is called with the wrong number or type of arguments.
+    InvalidArguments(String),
+    /// A path is applied to something other than nodes. `'a'/b`
+    NotNodes,
+}
+
+impl fmt::Display for XPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XPathError::UnexpectedCharacter(offset) => write!(f, "Unexpected character at offset {}.", offset),
+            XPathError::UnterminatedLiteral(offset) => write!(f, "Unterminated literal at offset {}.", offset),
+            XPathError::UnexpectedToken(offset) => write!(f, "Unexpected token at offset {}.", offset),
+            XPathError::UnexpectedEnd(offset) => write!(f, "Expression ends at offset {}.", offset),
+            XPathError::UnknownAxis(offset) => write!(f, "Unknown axis at offset {}.", offset),
+            XPathError::UnknownFunction(name) => write!(f, "Unknown function {}().", name),
+            XPathError::InvalidArguments(name) => write!(f, "Invalid arguments to {}().", name),
+            XPathError::NotNodes => write!(f, "The expression does not select nodes."),
+        }
+    }
+}
+
+impl Error for XPathError {}
+
+/// Evaluates the XPath expression `expr` with `context` as the context node.
+///
+/// Absolute paths such as `/html/body` start from the root of the tree
+/// `context` is in.
+///
+/// # Errors
+/// * [`XPathError`] if `expr` is not a valid expression.
+pub fn evaluate(context: &Rc<NodeData>, expr: &str) -> Result<Value, XPathError> {
+    let expr = parser::parse(expr)?;
+    eval::evaluate(context, &expr)
+}
+
+/// Evaluates `expr` and returns the selected nodes.
+///
+/// # Errors
+/// * [`XPathError::NotNodes`] if `expr` does not select nodes. `count(//a)`
+pub fn select(context: &Rc<NodeData>, expr: &str) -> Result<Vec<Rc<NodeData>>, XPathError> {
+    match evaluate(context, expr)? {
+        value @ Value::Nodes(_) => Ok(value.into_nodes()),
+        _ => Err(XPathError::NotNodes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const HTML: &str = r#"
+    <html>
+      <body>
+        <div id="main" class="x">
+          <a href="/one">one</a>
+          <!-- note -->
+          <a href="/two" class="ext">two</a>
+          <p>text <b>bold</b></p>
+        </div>
+        <ul><li>1</li><li>2</li><li>3</li></ul>
+      </body>
+    </html>
+    "#;
+
+    fn strings(expr: &str) -> Vec<String> {
+        let root = parse(HTML).unwrap();
+        evaluate(&root.get_copy_of_internal_arc(), expr).unwrap().to_strings()
+    }
+
+    #[test]
+    fn path_test() {
+        assert_eq!(strings("//div[@class='x']/a/@href"), ["/one", "/two"]);
+        assert_eq!(strings("/html/body/ul/li[2]"), ["2"]);
+        assert_eq!(strings("//li[last()]"), ["3"]);
+        assert_eq!(strings("//li[position() > 1]"), ["2", "3"]);
+        assert_eq!(strings("//a[@class]/text()"), ["two"]);
+        assert_eq!(strings("//b/ancestor::div/@id"), ["main"]);
+        assert_eq!(strings("//a[1]/following-sibling::*[1]"), ["two"]);
+        assert_eq!(strings("//li[3]/preceding-sibling::li[1]"), ["2"]);
+        assert_eq!(strings("//b/.."), ["text bold"]);
+        assert_eq!(strings("//div/comment()"), [" note "]);
+        assert_eq!(strings("(//a | //li)[last()]"), ["3"]);
+        assert_eq!(strings("//ul/li[1]/following::li"), ["2", "3"]);
+    }
+
+    #[test]
+    fn function_test() {
+        assert_eq!(strings("count(//li)"), ["3"]);
+        assert_eq!(strings("//a[contains(@href, 'tw')]"), ["two"]);
+        assert_eq!(strings("//a[starts-with(., 'o')]/@href"), ["/one"]);
+        assert_eq!(strings("normalize-space(//p)"), ["text bold"]);
+        assert_eq!(strings("concat('a', 1 + 2, 'b')"), ["a3b"]);
+        assert_eq!(strings("sum(//li) div 2"), ["3"]);
+        assert_eq!(strings("//li[not(. = '2')]"), ["1", "3"]);
+        assert_eq!(strings("name(//*[@id='main'])"), ["div"]);
+        assert_eq!(strings("substring('12345', 2, 3)"), ["234"]);
+        assert_eq!(strings("string-length(translate('abc', 'b', ''))"), ["2"]);
+    }
+
+    #[test]
+    fn error_test() {
+        let root = parse(HTML).unwrap().get_copy_of_internal_arc();
+
+        assert_eq!(evaluate(&root, "//a[@id='x]"), Err(XPathError::UnterminatedLiteral(8)));
+        assert_eq!(evaluate(&root, "//a]"), Err(XPathError::UnexpectedToken(3)));
+        assert_eq!(evaluate(&root, "//a["), Err(XPathError::UnexpectedEnd(4)));
+        assert_eq!(evaluate(&root, "sibling::a"), Err(XPathError::UnknownAxis(0)));
+        assert_eq!(evaluate(&root, "foo()"), Err(XPathError::UnknownFunction(String::from("foo"))));
+        assert_eq!(select(&root, "count(//a)"), Err(XPathError::NotNodes));
+    }
+}
@@ -0,0 +1,576 @@
+use super::XPathError;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Slash,
+    DoubleSlash,
+    Dot,
+    DoubleDot,
+    At,
+    Comma,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Star,
+    DoubleColon,
+    Pipe,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Plus,
+    Minus,
+    Name(String),
+    Literal(String),
+    Number(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Axis {
+    Ancestor,
+    AncestorOrSelf,
+    Attribute,
+    Child,
+    Descendant,
+    DescendantOrSelf,
+    Following,
+    FollowingSibling,
+    Parent,
+    Preceding,
+    PrecedingSibling,
+    SelfNode,
+}
+
+impl Axis {
+    fn from_name(name: &str) -> Option<Axis> {
+        let axis = match name {
+            "ancestor" => Axis::Ancestor,
+            "ancestor-or-self" => Axis::AncestorOrSelf,
+            "attribute" => Axis::Attribute,
+            "child" => Axis::Child,
+            "descendant" => Axis::Descendant,
+            "descendant-or-self" => Axis::DescendantOrSelf,
+            "following" => Axis::Following,
+            "following-sibling" => Axis::FollowingSibling,
+            "parent" => Axis::Parent,
+            "preceding" => Axis::Preceding,
+            "preceding-sibling" => Axis::PrecedingSibling,
+            "self" => Axis::SelfNode,
+            _ => return None,
+        };
+
+        Some(axis)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeTest {
+    /// `name` or `*`
+    Name(String),
+    Any,
+    Text,
+    Comment,
+    Node,
+    ProcessingInstruction,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub axis: Axis,
+    pub test: NodeTest,
+    pub predicates: Vec<Expr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Union,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Negate(Box<Expr>),
+    /// A location path. `/a/b`, `.//c`
+    Path { absolute: bool, steps: Vec<Step> },
+    /// A primary expression with predicates, followed by a path. `(//a)[1]/b`
+    Filter { primary: Box<Expr>, predicates: Vec<Expr>, steps: Vec<Step> },
+    Literal(String),
+    Number(f64),
+    Function(String, Vec<Expr>),
+}
+
+/// Parses an XPath expression.
+pub fn parse(expr: &str) -> Result<Expr, XPathError> {
+    let tokens = lex(expr)?;
+    let mut parser = Parser {
+        tokens,
+        cursor: 0,
+        len: expr.len(),
+    };
+
+    let res = parser.parse_or()?;
+    match parser.tokens.get(parser.cursor) {
+        Some((_, offset)) => Err(XPathError::UnexpectedToken(*offset)),
+        None => Ok(res),
+    }
+}
+
+/// Splits `expr` into tokens with their byte offsets.
+fn lex(expr: &str) -> Result<Vec<(Token, usize)>, XPathError> {
+    let chars: Vec<(usize, char)> = expr.char_indices().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    let peek = |i: usize| chars.get(i).map(|(_, c)| *c);
+    while i < chars.len() {
+        let (offset, c) = chars[i];
+        let next = peek(i + 1);
+
+        let (token, len) = match (c, next) {
+            (c, _) if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('/', Some('/')) => (Token::DoubleSlash, 2),
+            ('/', _) => (Token::Slash, 1),
+            ('.', Some('.')) => (Token::DoubleDot, 2),
+            ('.', Some(n)) if n.is_ascii_digit() => lex_number(&chars, i),
+            ('.', _) => (Token::Dot, 1),
+            ('@', _) => (Token::At, 1),
+            (',', _) => (Token::Comma, 1),
+            ('(', _) => (Token::LParen, 1),
+            (')', _) => (Token::RParen, 1),
+            ('[', _) => (Token::LBracket, 1),
+            (']', _) => (Token::RBracket, 1),
+            ('*', _) => (Token::Star, 1),
+            (':', Some(':')) => (Token::DoubleColon, 2),
+            ('|', _) => (Token::Pipe, 1),
+            ('=', _) => (Token::Eq, 1),
+            ('!', Some('=')) => (Token::Ne, 2),
+            ('<', Some('=')) => (Token::Le, 2),
+            ('<', _) => (Token::Lt, 1),
+            ('>', Some('=')) => (Token::Ge, 2),
+            ('>', _) => (Token::Gt, 1),
+            ('+', _) => (Token::Plus, 1),
+            ('-', _) => (Token::Minus, 1),
+            ('"' | '\'', _) => {
+                // 'literal' or "literal", without escapes
+                let end = (i + 1..chars.len())
+                    .find(|&j| chars[j].1 == c)
+                    .ok_or(XPathError::UnterminatedLiteral(offset))?;
+                let literal = chars[i + 1..end].iter().map(|(_, c)| c).collect();
+                (Token::Literal(literal), end + 1 - i)
+            }
+            (c, _) if c.is_ascii_digit() => lex_number(&chars, i),
+            (c, _) if is_name_start(c) => {
+                let mut j = i + 1;
+                while let Some(c) = peek(j) {
+                    if is_name_char(c) {
+                        j += 1;
+                    } else if c == ':' && peek(j + 1).is_some_and(is_name_start) {
+                        // a prefixed name. `xlink:href`, but not `child::`
+                        j += 1;
+                    } else {
+                        break;
+                    }
+                }
+                let name = chars[i..j].iter().map(|(_, c)| c).collect();
+                (Token::Name(name), j - i)
+            }
+            _ => return Err(XPathError::UnexpectedCharacter(offset)),
+        };
+
+        tokens.push((token, offset));
+        i += len;
+    }
+
+    Ok(tokens)
+}
+
+fn lex_number(chars: &[(usize, char)], bgn: usize) -> (Token, usize) {
+    let mut end = bgn;
+    let mut dot = false;
+    while let Some((_, c)) = chars.get(end) {
+        if c.is_ascii_digit() || (*c == '.' && !dot) {
+            dot |= *c == '.';
+            end += 1;
+        } else {
+            break;
+        }
+    }
+
+    let number: String = chars[bgn..end].iter().map(|(_, c)| c).collect();
+    (Token::Number(number.parse().unwrap_or(f64::NAN)), end - bgn)
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == '.'
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    cursor: usize,
+    // the length of the expression, the offset of the end
+    len: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.cursor).map(|(token, _)| token)
+    }
+
+    fn peek_at(&self, n: usize) -> Option<&Token> {
+        self.tokens.get(self.cursor + n).map(|(token, _)| token)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens.get(self.cursor).map_or(self.len, |(_, offset)| *offset)
+    }
+
+    /// Moves to the next token if the current one is `token`.
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.cursor += 1;
+            return true;
+        }
+
+        false
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<(), XPathError> {
+        match self.eat(token) {
+            true => Ok(()),
+            false => Err(self.error()),
+        }
+    }
+
+    fn error(&self) -> XPathError {
+        match self.peek() {
+            Some(_) => XPathError::UnexpectedToken(self.offset()),
+            None => XPathError::UnexpectedEnd(self.len),
+        }
+    }
+
+    /// Moves to the next token if the current one is the operator `name`.
+    /// `and`, `or`, `div` and `mod` are names where an operator is expected.
+    fn eat_operator_name(&mut self, name: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Name(n)) if n == name) {
+            self.cursor += 1;
+            return true;
+        }
+
+        false
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, XPathError> {
+        let mut lhs = self.parse_and()?;
+        while self.eat_operator_name("or") {
+            let rhs = self.parse_and()?;
+            lhs = Expr::Binary(BinaryOp::Or, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, XPathError> {
+        let mut lhs = self.parse_equality()?;
+        while self.eat_operator_name("and") {
+            let rhs = self.parse_equality()?;
+            lhs = Expr::Binary(BinaryOp::And, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_equality(&mut self) -> Result<Expr, XPathError> {
+        let mut lhs = self.parse_relational()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Eq) => BinaryOp::Eq,
+                Some(Token::Ne) => BinaryOp::Ne,
+                _ => return Ok(lhs),
+            };
+            self.cursor += 1;
+            let rhs = self.parse_relational()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_relational(&mut self) -> Result<Expr, XPathError> {
+        let mut lhs = self.parse_additive()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Lt) => BinaryOp::Lt,
+                Some(Token::Le) => BinaryOp::Le,
+                Some(Token::Gt) => BinaryOp::Gt,
+                Some(Token::Ge) => BinaryOp::Ge,
+                _ => return Ok(lhs),
+            };
+            self.cursor += 1;
+            let rhs = self.parse_additive()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, XPathError> {
+        let mut lhs = self.parse_multiplicative()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Plus) => BinaryOp::Add,
+                Some(Token::Minus) => BinaryOp::Sub,
+                _ => return Ok(lhs),
+            };
+            self.cursor += 1;
+            let rhs = self.parse_multiplicative()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, XPathError> {
+        let mut lhs = self.parse_unary()?;
+        loop {
+            let op = if self.eat(&Token::Star) {
+                BinaryOp::Mul
+            } else if self.eat_operator_name("div") {
+                BinaryOp::Div
+            } else if self.eat_operator_name("mod") {
+                BinaryOp::Mod
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = self.parse_unary()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, XPathError> {
+        if self.eat(&Token::Minus) {
+            return Ok(Expr::Negate(Box::new(self.parse_unary()?)));
+        }
+        self.parse_union()
+    }
+
+    fn parse_union(&mut self) -> Result<Expr, XPathError> {
+        let mut lhs = self.parse_path()?;
+        while self.eat(&Token::Pipe) {
+            let rhs = self.parse_path()?;
+            lhs = Expr::Binary(BinaryOp::Union, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    /// Parses a location path, or a primary expression followed by a path.
+    fn parse_path(&mut self) -> Result<Expr, XPathError> {
+        let is_primary = match (self.peek(), self.peek_at(1)) {
+            (Some(Token::LParen | Token::Literal(_) | Token::Number(_)), _) => true,
+            (Some(Token::Name(name)), Some(Token::LParen)) => !is_node_type(name),
+            _ => false,
+        };
+
+        if !is_primary {
+            return self.parse_location_path();
+        }
+
+        let primary = self.parse_primary()?;
+        let predicates = self.parse_predicates()?;
+        let steps = if matches!(self.peek(), Some(Token::Slash | Token::DoubleSlash)) {
+            self.parse_relative_steps(Vec::new(), true)?
+        } else {
+            Vec::new()
+        };
+
+        if predicates.is_empty() && steps.is_empty() {
+            return Ok(primary);
+        }
+
+        Ok(Expr::Filter {
+            primary: Box::new(primary),
+            predicates,
+            steps,
+        })
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, XPathError> {
+        let token = self.peek().cloned().ok_or_else(|| self.error())?;
+        self.cursor += 1;
+
+        match token {
+            Token::LParen => {
+                let expr = self.parse_or()?;
+                self.expect(&Token::RParen)?;
+                Ok(expr)
+            }
+            Token::Literal(literal) => Ok(Expr::Literal(literal)),
+            Token::Number(number) => Ok(Expr::Number(number)),
+            Token::Name(name) => {
+                self.expect(&Token::LParen)?;
+                let mut args = Vec::new();
+                if !self.eat(&Token::RParen) {
+                    loop {
+                        args.push(self.parse_or()?);
+                        if self.eat(&Token::RParen) {
+                            break;
+                        }
+                        self.expect(&Token::Comma)?;
+                    }
+                }
+                Ok(Expr::Function(name, args))
+            }
+            _ => {
+                self.cursor -= 1;
+                Err(self.error())
+            }
+        }
+    }
+
+    fn parse_location_path(&mut self) -> Result<Expr, XPathError> {
+        let mut steps = Vec::new();
+        let absolute = match self.peek() {
+            Some(Token::Slash) => {
+                self.cursor += 1;
+                if !self.starts_step() {
+                    // `/` alone is the document
+                    return Ok(Expr::Path { absolute: true, steps });
+                }
+                true
+            }
+            Some(Token::DoubleSlash) => {
+                self.cursor += 1;
+                steps.push(descendant_or_self());
+                true
+            }
+            _ => false,
+        };
+
+        steps.push(self.parse_step()?);
+        let steps = self.parse_relative_steps(steps, false)?;
+        Ok(Expr::Path { absolute, steps })
+    }
+
+    /// Parses `/step` and `//step` while they continue.
+    /// If `required` is true, there must be at least one.
+    fn parse_relative_steps(&mut self, mut steps: Vec<Step>, required: bool) -> Result<Vec<Step>, XPathError> {
+        let mut required = required;
+        loop {
+            match self.peek() {
+                Some(Token::Slash) => self.cursor += 1,
+                Some(Token::DoubleSlash) => {
+                    self.cursor += 1;
+                    steps.push(descendant_or_self());
+                }
+                _ if required => return Err(self.error()),
+                _ => return Ok(steps),
+            }
+
+            steps.push(self.parse_step()?);
+            required = false;
+        }
+    }
+
+    fn starts_step(&self) -> bool {
+        matches!(
+            self.peek(),
+            Some(Token::Dot | Token::DoubleDot | Token::At | Token::Star | Token::Name(_))
+        )
+    }
+
+    fn parse_step(&mut self) -> Result<Step, XPathError> {
+        if self.eat(&Token::Dot) {
+            return Ok(Step {
+                axis: Axis::SelfNode,
+                test: NodeTest::Node,
+                predicates: Vec::new(),
+            });
+        }
+
+        if self.eat(&Token::DoubleDot) {
+            return Ok(Step {
+                axis: Axis::Parent,
+                test: NodeTest::Node,
+                predicates: Vec::new(),
+            });
+        }
+
+        let axis = if self.eat(&Token::At) {
+            Axis::Attribute
+        } else if let (Some(Token::Name(name)), Some(Token::DoubleColon)) = (self.peek(), self.peek_at(1)) {
+            let axis = Axis::from_name(name).ok_or(XPathError::UnknownAxis(self.offset()))?;
+            self.cursor += 2;
+            axis
+        } else {
+            Axis::Child
+        };
+
+        let test = match self.peek().cloned() {
+            Some(Token::Star) => {
+                self.cursor += 1;
+                NodeTest::Any
+            }
+            Some(Token::Name(name)) => {
+                self.cursor += 1;
+                if is_node_type(&name) && self.peek() == Some(&Token::LParen) {
+                    self.cursor += 1;
+                    if name == "processing-instruction" {
+                        // the target is allowed, but never matches anyway
+                        if let Some(Token::Literal(_)) = self.peek() {
+                            self.cursor += 1;
+                        }
+                    }
+                    self.expect(&Token::RParen)?;
+                    match name.as_str() {
+                        "text" => NodeTest::Text,
+                        "comment" => NodeTest::Comment,
+                        "node" => NodeTest::Node,
+                        _ => NodeTest::ProcessingInstruction,
+                    }
+                } else {
+                    NodeTest::Name(name)
+                }
+            }
+            _ => return Err(self.error()),
+        };
+
+        let predicates = self.parse_predicates()?;
+        Ok(Step { axis, test, predicates })
+    }
+
+    fn parse_predicates(&mut self) -> Result<Vec<Expr>, XPathError> {
+        let mut predicates = Vec::new();
+        while self.eat(&Token::LBracket) {
+            predicates.push(self.parse_or()?);
+            self.expect(&Token::RBracket)?;
+        }
+        Ok(predicates)
+    }
+}
+
+fn is_node_type(name: &str) -> bool {
+    matches!(name, "text" | "comment" | "node" | "processing-instruction")
+}
+
+/// The step `//` stands for.
+fn descendant_or_self() -> Step {
+    Step {
+        axis: Axis::DescendantOrSelf,
+        test: NodeTest::Node,
+        predicates: Vec::new(),
+    }
+}